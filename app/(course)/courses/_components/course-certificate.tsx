@@ -0,0 +1,91 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { invoke } from "@tauri-apps/api/core";
+import { Award, Download } from "lucide-react";
+import { useEffect, useState } from "react";
+import toast from "react-hot-toast";
+
+interface Certificate {
+    id: string;
+    code: string;
+    courseId: string;
+    studentName: string;
+    issuedAt: string;
+    signature: string;
+}
+
+interface CourseCertificateProps {
+    courseId: string;
+}
+
+export const CourseCertificate = ({
+    courseId
+}: CourseCertificateProps) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+    const [certificate, setCertificate] = useState<Certificate | null>(null);
+    const [studentName, setStudentName] = useState("");
+    const [isLoading, setIsLoading] = useState(false);
+
+    useEffect(() => {
+        invoke<Certificate[]>("get_certificates", { userId })
+            .then(certificates => setCertificate(
+                certificates.find(certificate => certificate.courseId === courseId) ?? null
+            ))
+            .catch(err => toast.error(err));
+    }, [courseId])
+
+    const issue = () => {
+        setIsLoading(true);
+        invoke<Certificate>("issue_certificate", {
+            userId,
+            courseId,
+            studentName
+        }).then(setCertificate)
+        .catch(err => toast.error(err))
+        .finally(() => setIsLoading(false));
+    }
+
+    const download = () => {
+        setIsLoading(true);
+        invoke<string>("export_certificate", { userId, courseId })
+            .then(path => toast.success(`Đã lưu chứng nhận tại ${path}`))
+            .catch(err => toast.error(err))
+            .finally(() => setIsLoading(false));
+    }
+
+    if (certificate) {
+        return (
+            <div className="mt-6 flex flex-col gap-y-2">
+                <p className="text-xs text-slate-500">
+                    Mã xác minh: <span className="font-mono">{certificate.code}</span>
+                </p>
+                {!certificate.signature && (
+                    <p className="text-xs text-slate-500">
+                        Chứng nhận đang chờ giảng viên ký, hãy tải lại sau.
+                    </p>
+                )}
+                <Button onClick={download} disabled={isLoading || !certificate.signature} size="sm" variant="success">
+                    Tải chứng nhận
+                    <Download className="h-4 w-4 ml-2" />
+                </Button>
+            </div>
+        )
+    }
+
+    return (
+        <div className="mt-6 flex flex-col gap-y-2">
+            <Input
+                value={studentName}
+                onChange={e => setStudentName(e.target.value)}
+                placeholder="Họ tên in trên chứng nhận"
+                disabled={isLoading}
+            />
+            <Button onClick={issue} disabled={isLoading || !studentName.trim()} size="sm" variant="success">
+                Nhận chứng nhận
+                <Award className="h-4 w-4 ml-2" />
+            </Button>
+        </div>
+    )
+}
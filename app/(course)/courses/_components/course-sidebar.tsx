@@ -1,18 +1,26 @@
 "use client";
 
-import { Chapter, Course, Purchase, UserProgress } from "@prisma/client"
+import { Chapter, Course, UserProgress } from "@prisma/client"
 import { useRouter } from "next/navigation";
 import { CourseSidebarItem } from "./course-sidebar-item";
 import { CourseProgress } from "@/components/course-progress";
+import { CourseCertificate } from "./course-certificate";
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { Loader2 } from "lucide-react";
 import toast from "react-hot-toast";
 
+interface Entitlement {
+    source: "purchase" | "bundle" | "subscription";
+    grantedAt: string;
+    expiresAt: string | null;
+}
+
 interface CourseSidebarProps {
     course: Course & {
         chapters: (Chapter & {
             userProgress: UserProgress[] | null;
+            availableAt?: string | null;
         })[]
     };
     progressCount: number;
@@ -25,22 +33,22 @@ export const CourseSidebar = ({
     // // const { userId } = auth();
     const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
     const router = useRouter();
-    const [purchase, setPurchase] = useState<Purchase | null>();
+    const [entitlement, setEntitlement] = useState<Entitlement | null>();
     const [isLoading, setIsLoading] = useState(true);
 
     useEffect(() => {
-        async function fetchPurchase() {
-            invoke<Purchase | null>("get_purchase", {
+        async function fetchEntitlement() {
+            invoke<Entitlement | null>("get_entitlement", {
                 userId,
                 courseId: course.id
-            }).then(fetchedPurchase => {
-                setPurchase(fetchedPurchase);
+            }).then(fetchedEntitlement => {
+                setEntitlement(fetchedEntitlement);
                 setIsLoading(false);
             })
             .catch(err => toast.error(err));
         }
 
-        fetchPurchase();
+        fetchEntitlement();
     }, [course.id])
 
     if (!userId) {
@@ -58,12 +66,15 @@ export const CourseSidebar = ({
                     <h1 className="font-semibold">
                         {course.title}
                     </h1>
-                    {purchase && (
+                    {entitlement && (
                         <div className="mt-10">
                             <CourseProgress
                                 variant="success"
                                 value={progressCount}
                             />
+                            {progressCount === 100 && (
+                                <CourseCertificate courseId={course.id} />
+                            )}
                         </div>
                     )}
                 </div>
@@ -75,7 +86,7 @@ export const CourseSidebar = ({
                             label={chapter.title}
                             isCompleted={!!chapter.userProgress?.[0]?.isCompleted}
                             courseId={course.id}
-                            isLocked={!chapter.isFree && !purchase}
+                            isLocked={(!chapter.isFree && !entitlement) || !!chapter.availableAt}
                         />
                     ))}
                 </div>
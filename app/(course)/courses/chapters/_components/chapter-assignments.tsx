@@ -0,0 +1,203 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Separator } from "@/components/ui/separator";
+import { Textarea } from "@/components/ui/textarea";
+import { invoke } from "@tauri-apps/api/core";
+import { ClipboardList, File, Loader2, Upload } from "lucide-react";
+import { CldUploadWidget } from "next-cloudinary";
+import { useEffect, useState } from "react";
+import toast from "react-hot-toast";
+
+interface Submission {
+    id: string;
+    fileUrl: string | null;
+    fileName: string | null;
+    answer: string | null;
+    score: number | null;
+    feedback: string | null;
+    submittedAt: string;
+}
+
+interface AssignmentWithSubmission {
+    id: string;
+    title: string;
+    instructions: string | null;
+    dueAt: string | null;
+    maxScore: number;
+    submission: Submission | null;
+}
+
+interface ChapterAssignmentsProps {
+    chapterId: string;
+}
+
+const isPastDue = (assignment: AssignmentWithSubmission) =>
+    !!assignment.dueAt && new Date(assignment.dueAt + "Z") < new Date();
+
+const AssignmentCard = ({
+    assignment,
+    onSubmitted
+}: {
+    assignment: AssignmentWithSubmission;
+    onSubmitted: (submission: Submission) => void;
+}) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+    const { submission } = assignment;
+    const [answer, setAnswer] = useState(submission?.answer ?? "");
+    const [file, setFile] = useState<{ url: string; name: string } | null>(
+        submission?.fileUrl ? { url: submission.fileUrl, name: submission.fileName ?? "" } : null
+    );
+    const [isSubmitting, setIsSubmitting] = useState(false);
+    const closed = isPastDue(assignment);
+
+    const submit = () => {
+        setIsSubmitting(true);
+        invoke<Submission>("submit_assignment", {
+            userId,
+            assignmentId: assignment.id,
+            submission: {
+                fileUrl: file?.url ?? null,
+                fileName: file?.name ?? null,
+                answer: answer || null
+            }
+        }).then(submission => {
+            toast.success("Đã nộp bài");
+            onSubmitted(submission);
+        }).catch(err => toast.error(err.message ?? err))
+        .finally(() => setIsSubmitting(false));
+    }
+
+    return (
+        <div className="border bg-slate-100 rounded-md p-4 space-y-2">
+            <div className="flex items-center justify-between">
+                <p className="font-medium">{assignment.title}</p>
+                <p className="text-xs text-slate-500">
+                    {assignment.dueAt
+                        ? `Hạn nộp: ${new Date(assignment.dueAt + "Z").toLocaleString()}`
+                        : "Không có hạn nộp"}
+                </p>
+            </div>
+            {assignment.instructions && (
+                <p className="text-sm whitespace-pre-wrap">{assignment.instructions}</p>
+            )}
+            {submission?.score != null && (
+                <div className="p-3 bg-emerald-100 border border-emerald-200 text-emerald-800 rounded-md text-sm">
+                    <p className="font-medium">
+                        Điểm: {submission.score}/{assignment.maxScore}
+                    </p>
+                    {submission.feedback && (
+                        <p className="whitespace-pre-wrap">{submission.feedback}</p>
+                    )}
+                </div>
+            )}
+            {submission && submission.score == null && (
+                <p className="text-xs text-slate-500 italic">
+                    Đã nộp lúc {new Date(submission.submittedAt + "Z").toLocaleString()}, đang chờ chấm điểm
+                </p>
+            )}
+            {file && (
+                <a
+                    href={file.url}
+                    target="_blank"
+                    className="flex items-center p-2 bg-sky-200 border text-sky-700 rounded-md text-sm hover:underline"
+                >
+                    <File className="h-4 w-4 mr-2" />
+                    <span className="line-clamp-1">{file.name || file.url}</span>
+                </a>
+            )}
+            {!closed && (
+                <>
+                    <Textarea
+                        disabled={isSubmitting}
+                        placeholder="Câu trả lời của bạn"
+                        value={answer}
+                        onChange={e => setAnswer(e.target.value)}
+                    />
+                    <div className="flex items-center gap-x-2">
+                        <CldUploadWidget
+                            options={{
+                                clientAllowedFormats: ["pdf", "docx", "png", "jpg"]
+                            }}
+                            uploadPreset="ml_default"
+                            onSuccess={(result) => {
+                                if (typeof result.info === "object") {
+                                    setFile({
+                                        url: result.info.url,
+                                        name: `${result.info.original_filename}.${result.info.format}`
+                                    });
+                                }
+                            }}
+                            onQueuesEnd={(result, { widget }) => {
+                                widget.close();
+                            }}
+                        >
+                            {({ open }) => (
+                                <Button
+                                    disabled={isSubmitting}
+                                    onClick={() => open()}
+                                    variant="ghost"
+                                    size="sm"
+                                >
+                                    <Upload className="h-4 w-4 mr-2" />
+                                    {file ? "Đổi tệp" : "Đính kèm tệp"}
+                                </Button>
+                            )}
+                        </CldUploadWidget>
+                        <Button
+                            disabled={isSubmitting || (!answer.trim() && !file)}
+                            onClick={submit}
+                            size="sm"
+                        >
+                            {isSubmitting && <Loader2 className="h-4 w-4 mr-2 animate-spin" />}
+                            {submission ? "Nộp lại" : "Nộp bài"}
+                        </Button>
+                    </div>
+                </>
+            )}
+            {closed && !submission && (
+                <p className="text-xs text-rose-600 italic">Đã hết hạn nộp bài</p>
+            )}
+        </div>
+    )
+}
+
+export const ChapterAssignments = ({
+    chapterId
+}: ChapterAssignmentsProps) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+    const [assignments, setAssignments] = useState<AssignmentWithSubmission[]>([]);
+
+    useEffect(() => {
+        invoke<AssignmentWithSubmission[]>("get_chapter_assignments", {
+            userId,
+            chapterId
+        }).then(setAssignments)
+        .catch(err => toast.error(err));
+    }, [chapterId])
+
+    if (assignments.length === 0) {
+        return null;
+    }
+
+    return (
+        <>
+            <Separator />
+            <div className="p-4 space-y-4">
+                <div className="flex items-center gap-x-2 font-medium">
+                    <ClipboardList className="h-5 w-5" />
+                    Bài tập
+                </div>
+                {assignments.map(assignment => (
+                    <AssignmentCard
+                        key={assignment.id}
+                        assignment={assignment}
+                        onSubmitted={submission => setAssignments(assignments.map(item =>
+                            item.id === assignment.id ? { ...item, submission } : item
+                        ))}
+                    />
+                ))}
+            </div>
+        </>
+    )
+}
@@ -0,0 +1,254 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Separator } from "@/components/ui/separator";
+import { Textarea } from "@/components/ui/textarea";
+import { cn } from "@/lib/utils";
+import { invoke } from "@tauri-apps/api/core";
+import { Bot, Loader2, MessageCircle, Pin } from "lucide-react";
+import { useCallback, useEffect, useState } from "react";
+import toast from "react-hot-toast";
+
+export interface CommentView {
+    id: string;
+    userId: string;
+    parentId: string | null;
+    body: string;
+    isPinned: boolean;
+    createdAt: string;
+    editedAt: string | null;
+    deletedAt: string | null;
+    byTeacher: boolean;
+}
+
+export interface Thread extends CommentView {
+    replies: CommentView[];
+}
+
+interface ChapterDiscussionProps {
+    chapterId: string;
+}
+
+const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+
+const Comment = ({
+    comment,
+    onReply,
+    onChanged
+}: {
+    comment: CommentView;
+    onReply?: () => void;
+    onChanged: () => void;
+}) => {
+    const [isEditting, setIsEditting] = useState(false);
+    const [body, setBody] = useState(comment.body);
+    const isOwn = comment.userId === userId && !comment.deletedAt;
+
+    const save = () => {
+        invoke("edit_comment", {
+            userId,
+            commentId: comment.id,
+            body
+        }).then(() => {
+            setIsEditting(false);
+            onChanged();
+        }).catch(err => toast.error(err.message ?? err));
+    }
+
+    const remove = () => {
+        invoke("delete_comment", {
+            userId,
+            commentId: comment.id
+        }).then(onChanged)
+        .catch(err => toast.error(err));
+    }
+
+    return (
+        <div className={cn(
+            "rounded-md p-3 text-sm",
+            comment.byTeacher ? "bg-sky-100 border border-sky-200" : "bg-slate-100"
+        )}>
+            <div className="flex items-center gap-x-2 text-xs text-slate-500">
+                {comment.isPinned && <Pin className="h-3 w-3 text-emerald-700" />}
+                <span>{comment.byTeacher ? "Giảng viên" : "Học viên"}</span>
+                <span>{new Date(comment.createdAt + "Z").toLocaleString()}</span>
+                {comment.editedAt && <span>(đã sửa)</span>}
+            </div>
+            {comment.deletedAt ? (
+                <p className="italic text-slate-500">Bình luận đã bị xóa</p>
+            ) : isEditting ? (
+                <div className="space-y-2 mt-1">
+                    <Textarea value={body} onChange={e => setBody(e.target.value)} />
+                    <div className="flex gap-x-2">
+                        <Button size="sm" onClick={save} disabled={!body.trim()}>Lưu</Button>
+                        <Button size="sm" variant="ghost" onClick={() => setIsEditting(false)}>Hủy</Button>
+                    </div>
+                </div>
+            ) : (
+                <p className="whitespace-pre-wrap mt-1">{comment.body}</p>
+            )}
+            {!isEditting && (
+                <div className="flex gap-x-3 mt-1 text-xs">
+                    {onReply && (
+                        <button className="hover:underline" onClick={onReply}>Trả lời</button>
+                    )}
+                    {isOwn && (
+                        <>
+                            <button className="hover:underline" onClick={() => setIsEditting(true)}>Sửa</button>
+                            <button className="hover:underline text-rose-600" onClick={remove}>Xóa</button>
+                        </>
+                    )}
+                </div>
+            )}
+        </div>
+    )
+}
+
+export const ChapterDiscussion = ({
+    chapterId
+}: ChapterDiscussionProps) => {
+    const [threads, setThreads] = useState<Thread[]>([]);
+    const [body, setBody] = useState("");
+    const [replyTo, setReplyTo] = useState<string | null>(null);
+    const [reply, setReply] = useState("");
+    const [isPosting, setIsPosting] = useState(false);
+    const [tutorAnswer, setTutorAnswer] = useState<string | null>(null);
+    const [isAsking, setIsAsking] = useState(false);
+
+    const refresh = useCallback(() => {
+        invoke<Thread[]>("get_chapter_comments", { userId, chapterId })
+            .then(setThreads)
+            .catch(err => toast.error(err));
+    }, [chapterId])
+
+    useEffect(refresh, [refresh])
+
+    const post = (text: string, parentId: string | null) => {
+        setIsPosting(true);
+        return invoke("post_comment", {
+            userId,
+            chapterId,
+            comment: { body: text, parentId }
+        }).then(() => {
+            refresh();
+            return true;
+        }).catch(err => {
+            toast.error(err.message ?? err);
+            return false;
+        }).finally(() => setIsPosting(false));
+    }
+
+    const askTutor = async () => {
+        setIsAsking(true);
+        setTutorAnswer(null);
+        try {
+            const prompt = await invoke<string>("get_tutor_prompt", {
+                userId,
+                chapterId,
+                question: body
+            });
+            setTutorAnswer(await invoke<string>("generate_text", {
+                prompt,
+                sampleLen: 400,
+                style: false
+            }));
+        } catch (err) {
+            toast.error(String(err));
+        } finally {
+            setIsAsking(false);
+        }
+    }
+
+    return (
+        <>
+            <Separator />
+            <div className="p-4 space-y-4">
+                <div className="flex items-center gap-x-2 font-medium">
+                    <MessageCircle className="h-5 w-5" />
+                    Thảo luận
+                </div>
+                <Textarea
+                    disabled={isPosting || isAsking}
+                    placeholder="Đặt câu hỏi cho giảng viên"
+                    value={body}
+                    onChange={e => setBody(e.target.value)}
+                />
+                <div className="flex items-center gap-x-2">
+                    <Button
+                        variant="outline"
+                        size="sm"
+                        disabled={isAsking || !body.trim()}
+                        onClick={askTutor}
+                    >
+                        {isAsking ? <Loader2 className="h-4 w-4 mr-2 animate-spin" /> : <Bot className="h-4 w-4 mr-2" />}
+                        Hỏi gia sư AI trước
+                    </Button>
+                    <Button
+                        size="sm"
+                        disabled={isPosting || !body.trim()}
+                        onClick={() => post(body, null).then(ok => {
+                            if (ok) {
+                                setBody("");
+                                setTutorAnswer(null);
+                            }
+                        })}
+                    >
+                        Gửi câu hỏi
+                    </Button>
+                </div>
+                {tutorAnswer && (
+                    <div className="p-3 rounded-md border border-dashed text-sm">
+                        <p className="text-xs text-slate-500 mb-1">
+                            Gia sư AI trả lời. Nếu chưa rõ, hãy gửi câu hỏi cho giảng viên.
+                        </p>
+                        <p className="whitespace-pre-wrap">{tutorAnswer}</p>
+                    </div>
+                )}
+                {threads.length === 0 && (
+                    <p className="text-sm text-slate-500 italic">Chưa có câu hỏi nào</p>
+                )}
+                {threads.map(thread => (
+                    <div key={thread.id} className="space-y-2">
+                        <Comment
+                            comment={thread}
+                            onReply={thread.deletedAt ? undefined : () => setReplyTo(thread.id)}
+                            onChanged={refresh}
+                        />
+                        <div className="pl-6 space-y-2">
+                            {thread.replies.map(reply => (
+                                <Comment key={reply.id} comment={reply} onChanged={refresh} />
+                            ))}
+                            {replyTo === thread.id && (
+                                <div className="space-y-2">
+                                    <Textarea
+                                        disabled={isPosting}
+                                        placeholder="Câu trả lời của bạn"
+                                        value={reply}
+                                        onChange={e => setReply(e.target.value)}
+                                    />
+                                    <div className="flex gap-x-2">
+                                        <Button
+                                            size="sm"
+                                            disabled={isPosting || !reply.trim()}
+                                            onClick={() => post(reply, thread.id).then(ok => {
+                                                if (ok) {
+                                                    setReply("");
+                                                    setReplyTo(null);
+                                                }
+                                            })}
+                                        >
+                                            Trả lời
+                                        </Button>
+                                        <Button size="sm" variant="ghost" onClick={() => setReplyTo(null)}>
+                                            Hủy
+                                        </Button>
+                                    </div>
+                                </div>
+                            )}
+                        </div>
+                    </div>
+                ))}
+            </div>
+        </>
+    )
+}
@@ -0,0 +1,187 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Separator } from "@/components/ui/separator";
+import { Textarea } from "@/components/ui/textarea";
+import { invoke } from "@tauri-apps/api/core";
+import { Download, Highlighter, NotebookPen, Search, Trash } from "lucide-react";
+import { useCallback, useEffect, useState } from "react";
+import toast from "react-hot-toast";
+
+export interface Note {
+    id: string;
+    chapterId: string | null;
+    attachmentId: string | null;
+    page: number | null;
+    highlight: string | null;
+    body: string | null;
+    createdAt: string;
+    updatedAt: string;
+}
+
+interface NoteWithSource extends Note {
+    courseId: string;
+    courseTitle: string;
+    chapterTitle: string | null;
+    attachmentName: string | null;
+}
+
+interface ChapterNotesProps {
+    courseId: string;
+    chapterId: string;
+}
+
+const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+
+export const ChapterNotes = ({
+    courseId,
+    chapterId
+}: ChapterNotesProps) => {
+    const [notes, setNotes] = useState<Note[]>([]);
+    const [highlight, setHighlight] = useState<string | null>(null);
+    const [body, setBody] = useState("");
+    const [isSaving, setIsSaving] = useState(false);
+    const [query, setQuery] = useState("");
+    const [results, setResults] = useState<NoteWithSource[] | null>(null);
+
+    const refresh = useCallback(() => {
+        invoke<Note[]>("get_chapter_notes", { userId, chapterId })
+            .then(setNotes)
+            .catch(err => toast.error(err));
+    }, [chapterId])
+
+    useEffect(refresh, [refresh])
+
+    const quoteSelection = () => {
+        const selected = window.getSelection()?.toString().trim();
+        if (!selected) {
+            toast.error("Hãy bôi đen một đoạn trong bài học trước");
+            return;
+        }
+        setHighlight(selected);
+    }
+
+    const save = () => {
+        setIsSaving(true);
+        invoke("create_note", {
+            userId,
+            note: { chapterId, attachmentId: null, page: null, highlight, body }
+        }).then(() => {
+            setHighlight(null);
+            setBody("");
+            refresh();
+        }).catch(err => toast.error(err.message ?? err))
+        .finally(() => setIsSaving(false));
+    }
+
+    const remove = (noteId: string) => {
+        invoke("delete_note", { userId, noteId })
+            .then(refresh)
+            .catch(err => toast.error(err));
+    }
+
+    const search = () => {
+        invoke<NoteWithSource[]>("search_notes", { userId, query, courseId })
+            .then(setResults)
+            .catch(err => toast.error(err));
+    }
+
+    const onExport = () => {
+        invoke<string>("export_course_notes", {
+            userId,
+            courseId
+        }).then((markdown) => {
+            const url = URL.createObjectURL(new Blob([markdown], { type: "text/markdown" }));
+            const link = document.createElement("a");
+            link.href = url;
+            link.download = `ghi-chu-${courseId}.md`;
+            link.click();
+            URL.revokeObjectURL(url);
+        }).catch(err => toast.error(err));
+    }
+
+    return (
+        <>
+            <Separator />
+            <div className="p-4 space-y-4">
+                <div className="flex items-center justify-between">
+                    <div className="flex items-center gap-x-2 font-medium">
+                        <NotebookPen className="h-5 w-5" />
+                        Ghi chú của tôi
+                    </div>
+                    <Button onClick={onExport} variant="ghost" size="sm">
+                        <Download className="h-4 w-4 mr-2" />
+                        Markdown
+                    </Button>
+                </div>
+                {highlight && (
+                    <blockquote className="border-l-4 border-amber-300 bg-amber-50 p-2 text-sm italic">
+                        {highlight}
+                    </blockquote>
+                )}
+                <Textarea
+                    disabled={isSaving}
+                    placeholder="Viết ghi chú cho bài học này"
+                    value={body}
+                    onChange={e => setBody(e.target.value)}
+                />
+                <div className="flex items-center gap-x-2">
+                    <Button variant="outline" size="sm" onClick={quoteSelection}>
+                        <Highlighter className="h-4 w-4 mr-2" />
+                        Trích đoạn đã chọn
+                    </Button>
+                    <Button
+                        size="sm"
+                        disabled={isSaving || (!body.trim() && !highlight)}
+                        onClick={save}
+                    >
+                        Lưu ghi chú
+                    </Button>
+                </div>
+                {notes.map(note => (
+                    <div key={note.id} className="flex items-start gap-x-2 rounded-md bg-slate-100 p-3 text-sm">
+                        <div className="flex-1 space-y-1">
+                            {note.highlight && (
+                                <p className="border-l-4 border-amber-300 pl-2 italic">{note.highlight}</p>
+                            )}
+                            {note.body && <p className="whitespace-pre-wrap">{note.body}</p>}
+                            <p className="text-xs text-slate-500">
+                                {new Date(note.updatedAt + "Z").toLocaleString()}
+                            </p>
+                        </div>
+                        <Button size="sm" variant="ghost" onClick={() => remove(note.id)}>
+                            <Trash className="h-4 w-4" />
+                        </Button>
+                    </div>
+                ))}
+                <div className="flex items-center gap-x-2">
+                    <Input
+                        placeholder="Tìm trong ghi chú của khóa học"
+                        value={query}
+                        onChange={e => setQuery(e.target.value)}
+                        onKeyDown={e => e.key === "Enter" && search()}
+                    />
+                    <Button size="sm" variant="outline" onClick={search}>
+                        <Search className="h-4 w-4" />
+                    </Button>
+                </div>
+                {results && (
+                    results.length === 0 ? (
+                        <p className="text-sm text-slate-500 italic">Không tìm thấy ghi chú nào</p>
+                    ) : results.map(note => (
+                        <div key={note.id} className="rounded-md border p-3 text-sm space-y-1">
+                            <p className="text-xs text-slate-500">
+                                {note.chapterTitle ?? `${note.attachmentName}${note.page ? `, trang ${note.page}` : ""}`}
+                            </p>
+                            {note.highlight && (
+                                <p className="border-l-4 border-amber-300 pl-2 italic">{note.highlight}</p>
+                            )}
+                            {note.body && <p className="whitespace-pre-wrap">{note.body}</p>}
+                        </div>
+                    ))
+                )}
+            </div>
+        </>
+    )
+}
@@ -1,6 +1,7 @@
 "use client";
 
 import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
 import { formatPrice } from "@/lib/format";
 import { Purchase } from "@prisma/client";
 import { invoke } from "@tauri-apps/api/core";
@@ -8,6 +9,11 @@ import { useRouter } from "next/navigation";
 import { useState } from "react";
 import toast from "react-hot-toast";
 
+interface CheckoutSession {
+    purchase: Purchase;
+    url: string | null;
+}
+
 interface CourseEnrollButtonProps {
     price: number;
     courseId: string;
@@ -19,14 +25,19 @@ export const CourseEnrollButton = ({
 }: CourseEnrollButtonProps) => {
     const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
     const [isLoading, setIsLoading] = useState(false);
+    const [couponCode, setCouponCode] = useState("");
     const router = useRouter();
 
     const onClick = async () => {
         setIsLoading(true);
-        invoke<Purchase>("course_checkout", {
+        invoke<CheckoutSession>("course_checkout", {
             userId,
-            courseId
-        }).then(() => {
+            courseId,
+            couponCode: couponCode.trim() || null
+        }).then(({ url }) => {
+            if (url) {
+                return window.location.assign(url);
+            }
             toast.success("Mua khóa học thành công");
             return router.push("/");
         }).catch(err => {
@@ -35,13 +46,22 @@ export const CourseEnrollButton = ({
     }
 
     return (
-        <Button
-            onClick={onClick}
-            disabled={isLoading}
-            className="w-full md:w-auto"
-            size="sm"
-        >
-            Enroll for {formatPrice(price)}
-        </Button>
+        <div className="flex w-full flex-col gap-2 md:w-auto md:flex-row">
+            <Input
+                value={couponCode}
+                onChange={(e) => setCouponCode(e.target.value)}
+                disabled={isLoading}
+                placeholder="Coupon code"
+                className="h-9 md:w-40"
+            />
+            <Button
+                onClick={onClick}
+                disabled={isLoading}
+                className="w-full md:w-auto"
+                size="sm"
+            >
+                Enroll for {formatPrice(price)}
+            </Button>
+        </div>
     )
 }
\ No newline at end of file
@@ -7,6 +7,12 @@ import { useConfettiStore } from "@/hooks/use-confetti-store";
 import { invoke } from "@tauri-apps/api/core";
 import { CldVideoPlayer } from "next-cloudinary";
 import 'next-cloudinary/dist/cld-video-player.css';
+import { useEffect, useRef } from "react";
+
+// Heartbeats are sent every 10 seconds while the video plays, plus one
+// whenever playback pauses or jumps. Keep `DEFAULT_HEARTBEAT_INTERVAL_SECS`
+// in the service above this so the throttle has something to merge.
+const HEARTBEAT_INTERVAL_MS = 10_000;
 
 interface VideoPlayerProps {
     videoId: string;
@@ -15,6 +21,7 @@ interface VideoPlayerProps {
     nextChapterId?: string;
     isLocked: boolean;
     completeOnEnd: boolean;
+    lastPosition?: number;
 };
 
 export const VideoPlayer = ({
@@ -23,11 +30,83 @@ export const VideoPlayer = ({
     chapterId,
     nextChapterId,
     isLocked,
-    completeOnEnd
+    completeOnEnd,
+    lastPosition
 }: VideoPlayerProps) => {
     const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
     const router = useRouter();
     const confetti = useConfettiStore();
+    const videoRef = useRef<HTMLVideoElement | null>(null);
+    const rangeStart = useRef<number | null>(null);
+    const playedUntil = useRef(0);
+    const lastReport = useRef(0);
+
+    const report = (video: HTMLVideoElement, end: number) => {
+        const start = rangeStart.current;
+        rangeStart.current = video.paused ? null : end;
+        lastReport.current = Date.now();
+        if (start === null || end <= start) {
+            return;
+        }
+        invoke("report_watch_progress", {
+            userId,
+            courseId,
+            chapterId,
+            heartbeat: {
+                startSecond: start,
+                endSecond: end,
+                position: end,
+                duration: Math.floor(video.duration) || null
+            }
+        }).catch(err => console.error(err));
+    }
+
+    useEffect(() => {
+        const video = videoRef.current;
+        if (!video || isLocked) {
+            return;
+        }
+
+        const onLoaded = () => {
+            if (lastPosition && lastPosition < video.duration - 5) {
+                video.currentTime = lastPosition;
+            }
+        };
+        const onPlay = () => {
+            rangeStart.current = Math.floor(video.currentTime);
+        };
+        const onTimeUpdate = () => {
+            playedUntil.current = Math.floor(video.currentTime);
+            if (Date.now() - lastReport.current >= HEARTBEAT_INTERVAL_MS) {
+                report(video, playedUntil.current);
+            }
+        };
+        const onSeeking = () => {
+            report(video, playedUntil.current);
+            rangeStart.current = null;
+        };
+        const onSeeked = () => {
+            if (!video.paused) {
+                rangeStart.current = Math.floor(video.currentTime);
+            }
+        };
+        const onPause = () => report(video, Math.floor(video.currentTime));
+
+        video.addEventListener("loadedmetadata", onLoaded);
+        video.addEventListener("play", onPlay);
+        video.addEventListener("timeupdate", onTimeUpdate);
+        video.addEventListener("seeking", onSeeking);
+        video.addEventListener("seeked", onSeeked);
+        video.addEventListener("pause", onPause);
+        return () => {
+            video.removeEventListener("loadedmetadata", onLoaded);
+            video.removeEventListener("play", onPlay);
+            video.removeEventListener("timeupdate", onTimeUpdate);
+            video.removeEventListener("seeking", onSeeking);
+            video.removeEventListener("seeked", onSeeked);
+            video.removeEventListener("pause", onPause);
+        };
+    }, [chapterId, isLocked]);
 
     const onEnd = async () => {
         if (completeOnEnd) {
@@ -68,6 +147,7 @@ export const VideoPlayer = ({
 
                 <CldVideoPlayer
                     src={videoId!}
+                    videoRef={videoRef}
                     onEnded={onEnd}
                     autoPlay
                 />
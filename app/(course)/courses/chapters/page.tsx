@@ -7,21 +7,32 @@ import { VideoPlayer } from "./_components/video-player";
 import { CourseEnrollButton } from "./_components/course-enroll-button";
 import { Separator } from "@/components/ui/separator";
 import { Preview } from "@/components/preview";
-import { File, Loader2 } from "lucide-react";
+import { BookOpen, File, Loader2 } from "lucide-react";
+import Link from "next/link";
 import { CourseProgressButton } from "./_components/course-progress-button";
+import { ChapterAssignments } from "./_components/chapter-assignments";
+import { ChapterDiscussion } from "./_components/chapter-discussion";
+import { ChapterNotes } from "./_components/chapter-notes";
 import { useEffect, useState } from "react";
-import { Attachment, Chapter, Course, Purchase, UserProgress } from "@prisma/client";
+import { Attachment, Chapter, Course, UserProgress } from "@prisma/client";
 import CourseLayout from "../_components/course-layout";
 import { invoke } from "@tauri-apps/api/core";
 import toast from "react-hot-toast";
 
+interface Entitlement {
+    source: "purchase" | "bundle" | "subscription";
+    grantedAt: string;
+    expiresAt: string | null;
+}
+
 interface ChapterDetails {
     chapter: Chapter | null;
     coursePrice: { price: number | null } | null;
     attachments: Attachment[] | null;
     nextChapter: Chapter | null,
-    userProgress: UserProgress | null,
-    purchase: Purchase | null;
+    userProgress: (UserProgress & { lastPosition: number }) | null,
+    entitlement: Entitlement | null;
+    availableAt: string | null;
 }
 
 const ChapterIdPage = () => {
@@ -95,16 +106,16 @@ const ChapterIdPage = () => {
             attachments,
             nextChapter,
             userProgress,
-            purchase
+            entitlement,
+            availableAt
         } = chapterDetails;
 
         if (!chapter || !coursePrice) {
             return router.push("/");
         }
 
-        const isLocked = !chapter.isFree && !purchase;
-        const completeOnEnd = !!purchase && !userProgress?.isCompleted;
-        console.log(purchase)
+        const isLocked = (!chapter.isFree && !entitlement) || !!availableAt;
+        const completeOnEnd = !!entitlement && !userProgress?.isCompleted;
         return ( 
             <CourseLayout course={course} progressCount={progressCount}>
                 <div>
@@ -114,12 +125,18 @@ const ChapterIdPage = () => {
                             label="You already completed this chapter."
                         />
                     )}
-                    {isLocked && (
+                    {isLocked && !availableAt && (
                         <Banner
                             variant="warning"
                             label="You need to purchase this course to watch this chapter."
                         />
                     )}
+                    {availableAt && (
+                        <Banner
+                            variant="warning"
+                            label={`This chapter unlocks on ${new Date(availableAt + "Z").toLocaleString()}.`}
+                        />
+                    )}
                     <div className="flex flex-col max-w-4xl mx-auto pb-20">
                         <div className="p-4">
                             <VideoPlayer
@@ -129,6 +146,7 @@ const ChapterIdPage = () => {
                                 nextChapterId={nextChapter?.id}
                                 isLocked={isLocked}
                                 completeOnEnd={completeOnEnd}
+                                lastPosition={userProgress?.lastPosition}
                             />
                         </div>
                         <div>
@@ -136,7 +154,7 @@ const ChapterIdPage = () => {
                                 <h2 className="text-2xl font-semibold mb-2">
                                     {chapter.title}
                                 </h2>
-                                {purchase ? (
+                                {entitlement ? (
                                     <CourseProgressButton
                                         chapterId={params.chapterId}
                                         courseId={params.courseId}
@@ -159,21 +177,42 @@ const ChapterIdPage = () => {
                                     <Separator />
                                     <div className="p-4">
                                         {attachments.map((attachment) => (
-                                            <a
-                                                href={attachment.url}
-                                                target="_blank"
-                                                key={attachment.id}
-                                                className="flex items-center p-3 w-full bg-sky-200 border text-sky-700 rounded-md hover:underline"
-                                            >
-                                                <File />
-                                                <p className="line-clamp-1">
-                                                    {attachment.name}
-                                                </p>
-                                            </a>
+                                            <div key={attachment.id} className="flex items-center gap-x-2">
+                                                <a
+                                                    href={attachment.url}
+                                                    target="_blank"
+                                                    className="flex items-center p-3 w-full bg-sky-200 border text-sky-700 rounded-md hover:underline"
+                                                >
+                                                    <File />
+                                                    <p className="line-clamp-1">
+                                                        {attachment.name}
+                                                    </p>
+                                                </a>
+                                                {attachment.name.toLowerCase().endsWith(".pdf") && (
+                                                    <Link
+                                                        href={`/pdf?${new URLSearchParams({
+                                                            attachmentId: attachment.id,
+                                                            url: attachment.url,
+                                                            name: attachment.name
+                                                        })}`}
+                                                        className="flex items-center p-3 shrink-0 border rounded-md text-sm hover:bg-slate-100"
+                                                    >
+                                                        <BookOpen className="h-4 w-4 mr-2" />
+                                                        Đọc và ghi chú
+                                                    </Link>
+                                                )}
+                                            </div>
                                         ))}
                                     </div>
                                 </>
                             )}
+                            {!isLocked && (
+                                <>
+                                    <ChapterAssignments chapterId={params.chapterId} />
+                                    <ChapterNotes courseId={params.courseId} chapterId={params.chapterId} />
+                                    <ChapterDiscussion chapterId={params.chapterId} />
+                                </>
+                            )}
                         </div>
                     </div>
                 </div>
@@ -0,0 +1,41 @@
+import { IconBadge } from "@/components/icon-badgs"
+import { Layers } from "lucide-react";
+import Link from "next/link";
+
+export interface ReviewStats {
+    due: number;
+    reviewedToday: number;
+    daily: { date: string; reviews: number }[];
+}
+
+export const ReviewCard = ({
+    stats
+}: {
+    stats: ReviewStats
+}) => {
+    const most = Math.max(1, ...stats.daily.map(day => day.reviews));
+
+    return (
+        <Link href="/flashcards" className="border rounded-md flex items-center gap-x-2 p-3 hover:bg-slate-50">
+            <IconBadge icon={Layers} />
+            <div className="flex-1">
+                <p className="font-medium">
+                    Ôn tập hôm nay
+                </p>
+                <p className="text-gray-500 text-sm">
+                    {stats.reviewedToday} đã ôn · {stats.due} cần ôn
+                </p>
+            </div>
+            <div className="flex items-end gap-x-1 h-8">
+                {stats.daily.map(day => (
+                    <div
+                        key={day.date}
+                        title={`${day.date}: ${day.reviews}`}
+                        className="w-1.5 bg-sky-700 rounded-sm"
+                        style={{ height: `${Math.max(2, (day.reviews / most) * 100)}%` }}
+                    />
+                ))}
+            </div>
+        </Link>
+    )
+}
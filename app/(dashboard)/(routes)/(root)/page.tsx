@@ -4,6 +4,7 @@ import "./page_root.css"
 import { CheckCircle, Clock } from "lucide-react";
 import { CoursesList } from "@/components/courses-list";
 import { InfoCard } from "./_components/info-card";
+import { ReviewCard, ReviewStats } from "./_components/review-card";
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import toast from "react-hot-toast";
@@ -33,6 +34,7 @@ export default function Dashboard() {
         completedCourses: CourseWithProgressWithCategory[]
       }>({ coursesInProgress: [], completedCourses: [] });
   const [isLoading, setIsLoading] = useState(true);
+  const [reviewStats, setReviewStats] = useState<ReviewStats | null>(null);
 
   useEffect(() => {
     async function fetchDashboardCourses() {
@@ -47,12 +49,15 @@ export default function Dashboard() {
       }).catch(err => toast.error(err));
     }
   fetchDashboardCourses();
+  invoke<ReviewStats>('get_review_stats', { userId })
+    .then(setReviewStats)
+    .catch(err => toast.error(err));
 }, []);
 
   if (coursesInProgress && completedCourses) {
     return (
       <div className="p-6 space-y-4">
-        <div className="grid grid-cols-1 sm:grid-cols-2 gap-4">
+        <div className="grid grid-cols-1 sm:grid-cols-3 gap-4">
           <InfoCard
             icon={Clock}
             label="Đang tham gia học"
@@ -64,6 +69,7 @@ export default function Dashboard() {
             numberOfItems={completedCourses.length}
             variant="success"
           />
+          {reviewStats && <ReviewCard stats={reviewStats} />}
         </div>
         {isLoading ? (
           <div className="flex items-center justify-center h-screen mt-10">
@@ -0,0 +1,166 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Combobox } from "@/components/ui/combobox";
+import { Textarea } from "@/components/ui/textarea";
+import { invoke } from "@tauri-apps/api/core";
+import { Loader2, Sparkles, Trash } from "lucide-react";
+import { useCallback, useEffect, useState } from "react";
+import toast from "react-hot-toast";
+
+export interface Flashcard {
+    id: string;
+    deckId: string;
+    chapterId: string | null;
+    front: string;
+    back: string;
+    isGenerated: boolean;
+    ease: number;
+    interval: number;
+    repetitions: number;
+    dueAt: string;
+    lastReviewedAt: string | null;
+}
+
+interface DeckEditorProps {
+    deckId: string;
+    courseId: string;
+    onChanged: () => void;
+}
+
+const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+
+export const DeckEditor = ({
+    deckId,
+    courseId,
+    onChanged
+}: DeckEditorProps) => {
+    const [cards, setCards] = useState<Flashcard[]>([]);
+    const [chapters, setChapters] = useState<{ id: string; title: string }[]>([]);
+    const [chapterId, setChapterId] = useState("");
+    const [front, setFront] = useState("");
+    const [back, setBack] = useState("");
+    const [isGenerating, setIsGenerating] = useState(false);
+
+    const refresh = useCallback(() => {
+        invoke<Flashcard[]>("get_deck_cards", { userId, deckId })
+            .then(setCards)
+            .catch(err => toast.error(err));
+    }, [deckId])
+
+    useEffect(refresh, [refresh])
+
+    useEffect(() => {
+        invoke<{ chapters: { id: string; title: string }[] }>("get_course_with_chapters_with_progress", {
+            userId,
+            courseId
+        }).then(course => setChapters(course.chapters))
+        .catch(err => toast.error(err));
+    }, [courseId])
+
+    const changed = () => {
+        refresh();
+        onChanged();
+    }
+
+    const add = () => {
+        invoke("create_card", {
+            userId,
+            deckId,
+            card: { front, back, chapterId: chapterId || null }
+        }).then(() => {
+            setFront("");
+            setBack("");
+            changed();
+        }).catch(err => toast.error(err.message ?? err));
+    }
+
+    const remove = (cardId: string) => {
+        invoke("delete_card", { userId, cardId })
+            .then(changed)
+            .catch(err => toast.error(err));
+    }
+
+    const generate = async () => {
+        setIsGenerating(true);
+        try {
+            const prompt = await invoke<string>("get_flashcard_prompt", {
+                userId,
+                deckId,
+                chapterId
+            });
+            const text = await invoke<string>("generate_flashcard_text", {
+                prompt,
+                sampleLen: 600
+            });
+            const added = await invoke<Flashcard[]>("add_generated_cards", {
+                userId,
+                deckId,
+                chapterId,
+                text
+            });
+            toast.success(`Đã tạo ${added.length} thẻ`);
+            changed();
+        } catch (err) {
+            toast.error((err as { message?: string }).message ?? String(err));
+        } finally {
+            setIsGenerating(false);
+        }
+    }
+
+    return (
+        <div className="space-y-4">
+            <div className="border bg-slate-100 rounded-md p-4 space-y-2">
+                <Combobox
+                    options={chapters.map(chapter => ({ label: chapter.title, value: chapter.id }))}
+                    value={chapterId}
+                    onChange={setChapterId}
+                />
+                <Textarea
+                    placeholder="Mặt trước: câu hỏi"
+                    value={front}
+                    onChange={e => setFront(e.target.value)}
+                />
+                <Textarea
+                    placeholder="Mặt sau: câu trả lời"
+                    value={back}
+                    onChange={e => setBack(e.target.value)}
+                />
+                <div className="flex gap-x-2">
+                    <Button size="sm" disabled={!front.trim() || !back.trim()} onClick={add}>
+                        Thêm thẻ
+                    </Button>
+                    <Button
+                        size="sm"
+                        variant="outline"
+                        disabled={!chapterId || isGenerating}
+                        onClick={generate}
+                    >
+                        {isGenerating
+                            ? <Loader2 className="h-4 w-4 mr-2 animate-spin" />
+                            : <Sparkles className="h-4 w-4 mr-2" />}
+                        Tạo thẻ từ bài học và ghi chú
+                    </Button>
+                </div>
+            </div>
+            {cards.length === 0 && (
+                <p className="text-sm text-slate-500 italic">Bộ thẻ chưa có thẻ nào</p>
+            )}
+            {cards.map(card => (
+                <div key={card.id} className="flex items-start gap-x-2 border rounded-md p-3 text-sm">
+                    <div className="flex-1 space-y-1">
+                        <p className="font-medium whitespace-pre-wrap">{card.front}</p>
+                        <p className="whitespace-pre-wrap text-slate-600">{card.back}</p>
+                        <p className="text-xs text-slate-500">
+                            {card.isGenerated && "AI · "}
+                            Ôn lại: {new Date(card.dueAt + "Z").toLocaleDateString()}
+                        </p>
+                    </div>
+                    <Button size="sm" variant="ghost" onClick={() => remove(card.id)}>
+                        <Trash className="h-4 w-4" />
+                    </Button>
+                </div>
+            ))}
+        </div>
+    )
+}
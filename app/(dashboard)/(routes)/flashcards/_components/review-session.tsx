@@ -0,0 +1,99 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { invoke } from "@tauri-apps/api/core";
+import { Loader2 } from "lucide-react";
+import { useCallback, useEffect, useState } from "react";
+import toast from "react-hot-toast";
+import { Flashcard } from "./deck-editor";
+
+interface ReviewSessionProps {
+    deckId: string | null;
+    onReviewed: () => void;
+}
+
+const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+
+// Điểm theo thang SM-2, từ 0 (quên hẳn) đến 5 (nhớ ngay)
+const grades = [
+    { grade: 1, label: "Quên", className: "bg-rose-600 hover:bg-rose-700" },
+    { grade: 3, label: "Khó", className: "bg-amber-600 hover:bg-amber-700" },
+    { grade: 4, label: "Nhớ", className: "bg-sky-700 hover:bg-sky-800" },
+    { grade: 5, label: "Dễ", className: "bg-emerald-600 hover:bg-emerald-700" },
+];
+
+export const ReviewSession = ({
+    deckId,
+    onReviewed
+}: ReviewSessionProps) => {
+    const [cards, setCards] = useState<Flashcard[]>([]);
+    const [isLoading, setIsLoading] = useState(true);
+    const [showBack, setShowBack] = useState(false);
+    const [isSubmitting, setIsSubmitting] = useState(false);
+
+    const refresh = useCallback(() => {
+        invoke<Flashcard[]>("get_due_cards", { userId, deckId })
+            .then(setCards)
+            .catch(err => toast.error(err))
+            .finally(() => setIsLoading(false));
+    }, [deckId])
+
+    useEffect(refresh, [refresh])
+
+    const review = (grade: number) => {
+        setIsSubmitting(true);
+        invoke("review_card", {
+            userId,
+            cardId: cards[0].id,
+            grade
+        }).then(() => {
+            setShowBack(false);
+            setCards(cards.slice(1));
+            onReviewed();
+        }).catch(err => toast.error(err.message ?? err))
+        .finally(() => setIsSubmitting(false));
+    }
+
+    if (isLoading) {
+        return <Loader2 className="w-6 h-6 animate-spin" />
+    }
+
+    if (cards.length === 0) {
+        return (
+            <div className="border rounded-md p-6 text-center text-sm text-slate-500">
+                Không còn thẻ nào cần ôn hôm nay
+            </div>
+        )
+    }
+
+    const card = cards[0];
+
+    return (
+        <div className="border rounded-md p-6 space-y-4">
+            <p className="text-xs text-slate-500">Còn {cards.length} thẻ</p>
+            <p className="text-lg font-medium whitespace-pre-wrap">{card.front}</p>
+            {showBack ? (
+                <>
+                    <p className="border-t pt-4 whitespace-pre-wrap">{card.back}</p>
+                    <div className="flex flex-wrap gap-2">
+                        {grades.map(({ grade, label, className }) => (
+                            <Button
+                                key={grade}
+                                size="sm"
+                                className={className}
+                                disabled={isSubmitting}
+                                onClick={() => review(grade)}
+                            >
+                                {label}
+                            </Button>
+                        ))}
+                    </div>
+                </>
+            ) : (
+                <Button size="sm" variant="outline" onClick={() => setShowBack(true)}>
+                    Hiện đáp án
+                </Button>
+            )}
+        </div>
+    )
+}
@@ -0,0 +1,162 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Combobox } from "@/components/ui/combobox";
+import { Input } from "@/components/ui/input";
+import { cn } from "@/lib/utils";
+import { invoke } from "@tauri-apps/api/core";
+import { Layers, Trash } from "lucide-react";
+import { useCallback, useEffect, useState } from "react";
+import toast from "react-hot-toast";
+import { DeckEditor } from "./_components/deck-editor";
+import { ReviewSession } from "./_components/review-session";
+
+interface DeckSummary {
+    id: string;
+    courseId: string;
+    title: string;
+    courseTitle: string;
+    cards: number;
+    due: number;
+}
+
+interface DashboardCourse {
+    id: string;
+    title: string;
+}
+
+const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+
+const FlashcardsPage = () => {
+    const [decks, setDecks] = useState<DeckSummary[]>([]);
+    const [courses, setCourses] = useState<DashboardCourse[]>([]);
+    const [courseId, setCourseId] = useState("");
+    const [title, setTitle] = useState("");
+    const [selected, setSelected] = useState<DeckSummary | null>(null);
+    const [mode, setMode] = useState<"review" | "edit">("review");
+
+    const refresh = useCallback(() => {
+        invoke<DeckSummary[]>("get_decks", { userId })
+            .then(setDecks)
+            .catch(err => toast.error(err));
+    }, [])
+
+    useEffect(() => {
+        refresh();
+        invoke<{
+            coursesInProgress: DashboardCourse[],
+            completedCourses: DashboardCourse[]
+        }>("get_dashboard_courses", { userId })
+            .then(({ coursesInProgress, completedCourses }) => setCourses([...coursesInProgress, ...completedCourses]))
+            .catch(err => toast.error(err));
+    }, [refresh])
+
+    const createDeck = () => {
+        invoke<DeckSummary>("create_deck", { userId, courseId, title })
+            .then(() => {
+                setTitle("");
+                refresh();
+            }).catch(err => toast.error(err.message ?? err));
+    }
+
+    const deleteDeck = (deckId: string) => {
+        invoke("delete_deck", { userId, deckId })
+            .then(() => {
+                setSelected(null);
+                refresh();
+            }).catch(err => toast.error(err));
+    }
+
+    return (
+        <div className="p-6 space-y-6">
+            <h1 className="text-2xl font-medium">Ôn tập với thẻ ghi nhớ</h1>
+            <div className="grid grid-cols-1 md:grid-cols-3 gap-6">
+                <div className="space-y-4">
+                    <div className="border bg-slate-100 rounded-md p-4 space-y-2">
+                        <p className="font-medium">Bộ thẻ mới</p>
+                        <Combobox
+                            options={courses.map(course => ({ label: course.title, value: course.id }))}
+                            value={courseId}
+                            onChange={setCourseId}
+                        />
+                        <Input
+                            placeholder="Tên bộ thẻ"
+                            value={title}
+                            onChange={e => setTitle(e.target.value)}
+                        />
+                        <Button size="sm" disabled={!courseId || !title.trim()} onClick={createDeck}>
+                            Tạo bộ thẻ
+                        </Button>
+                    </div>
+                    <button
+                        onClick={() => setSelected(null)}
+                        className={cn(
+                            "w-full text-left border rounded-md p-3 text-sm",
+                            !selected && "border-sky-700 bg-sky-50"
+                        )}
+                    >
+                        Tất cả bộ thẻ
+                    </button>
+                    {decks.map(deck => (
+                        <div
+                            key={deck.id}
+                            className={cn(
+                                "flex items-center gap-x-2 border rounded-md p-3 text-sm",
+                                selected?.id === deck.id && "border-sky-700 bg-sky-50"
+                            )}
+                        >
+                            <button className="flex-1 text-left" onClick={() => setSelected(deck)}>
+                                <p className="font-medium flex items-center gap-x-2">
+                                    <Layers className="h-4 w-4" />
+                                    {deck.title}
+                                </p>
+                                <p className="text-xs text-slate-500">
+                                    {deck.courseTitle} · {deck.cards} thẻ · {deck.due} cần ôn
+                                </p>
+                            </button>
+                            <Button size="sm" variant="ghost" onClick={() => deleteDeck(deck.id)}>
+                                <Trash className="h-4 w-4" />
+                            </Button>
+                        </div>
+                    ))}
+                </div>
+                <div className="md:col-span-2 space-y-4">
+                    {selected && (
+                        <div className="flex gap-x-2">
+                            <Button
+                                size="sm"
+                                variant={mode === "review" ? "default" : "outline"}
+                                onClick={() => setMode("review")}
+                            >
+                                Ôn tập
+                            </Button>
+                            <Button
+                                size="sm"
+                                variant={mode === "edit" ? "default" : "outline"}
+                                onClick={() => setMode("edit")}
+                            >
+                                Soạn thẻ
+                            </Button>
+                        </div>
+                    )}
+                    {selected && mode === "edit" ? (
+                        <DeckEditor
+                            key={selected.id}
+                            deckId={selected.id}
+                            courseId={selected.courseId}
+                            onChanged={refresh}
+                        />
+                    ) : (
+                        <ReviewSession
+                            key={selected?.id ?? "all"}
+                            deckId={selected?.id ?? null}
+                            onReviewed={refresh}
+                        />
+                    )}
+                </div>
+            </div>
+        </div>
+    );
+}
+
+export default FlashcardsPage;
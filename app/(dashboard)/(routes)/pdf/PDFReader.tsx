@@ -10,9 +10,13 @@ import {
   Copy,
   Trash2,
   X,
-  Eye
+  Eye,
+  Highlighter,
+  NotebookPen
 } from 'lucide-react';
 import {toast} from 'react-hot-toast';
+import { invoke } from "@tauri-apps/api/core";
+import { useSearchParams } from "next/navigation";
 import ChatbotClient2 from "./ChatbotClient";
 
 // Thiết lập worker cho PDF.js
@@ -28,6 +32,17 @@ interface PdfHistory {
   scale: number;
 }
 
+// Ghi chú theo trang, lưu trong cơ sở dữ liệu khi mở tài liệu của khóa học
+interface PageNote {
+  id: string;
+  page: number | null;
+  highlight: string | null;
+  body: string | null;
+  updatedAt: string;
+}
+
+const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+
 // Component chính
 const PdfViewer: React.FC = () => {
   // Refs
@@ -45,6 +60,15 @@ const PdfViewer: React.FC = () => {
   const [showHistory, setShowHistory] = useState(false);
   const [isLoading, setIsLoading] = useState(false);
 
+  // Tài liệu của khóa học được mở từ trang bài học
+  const searchParams = useSearchParams();
+  const attachmentId = searchParams.get("attachmentId");
+  const attachmentUrl = searchParams.get("url");
+  const attachmentName = searchParams.get("name");
+  const [notes, setNotes] = useState<PageNote[]>([]);
+  const [highlight, setHighlight] = useState<string | null>(null);
+  const [noteBody, setNoteBody] = useState("");
+
   // Hàm quản lý lịch sử PDF
   const managePdfHistory = useCallback((newPdf: Omit<PdfHistory, 'id'>) => {
     const id = Date.now().toString();
@@ -91,6 +115,65 @@ const PdfViewer: React.FC = () => {
     }
   }, []);
 
+  // Mở tài liệu của khóa học và tải ghi chú của nó
+  const refreshNotes = useCallback(() => {
+    if (!attachmentId) return;
+    invoke<PageNote[]>("get_attachment_notes", { userId, attachmentId })
+      .then(setNotes)
+      .catch(err => toast.error(err));
+  }, [attachmentId]);
+
+  useEffect(() => {
+    if (!attachmentId || !attachmentUrl) return;
+    setIsLoading(true);
+    pdfjsLib.getDocument(attachmentUrl).promise
+      .then(loadedPdf => {
+        setPdf(loadedPdf);
+        setNumPages(loadedPdf.numPages);
+        setCurrentPage(1);
+        setFileName(attachmentName);
+      })
+      .catch(error => {
+        console.error("Error loading attachment:", error);
+        toast.error('Mở tài liệu thất bại');
+      })
+      .finally(() => setIsLoading(false));
+    refreshNotes();
+  }, [attachmentId, attachmentUrl, attachmentName, refreshNotes]);
+
+  // Lấy đoạn văn bản đang bôi đen trên trang làm trích dẫn
+  const quoteSelection = useCallback(() => {
+    const selected = window.getSelection()?.toString().trim();
+    if (!selected) {
+      toast.error('Hãy bôi đen một đoạn trong trang trước');
+      return;
+    }
+    setHighlight(selected);
+  }, []);
+
+  const saveNote = useCallback(() => {
+    invoke("create_note", {
+      userId,
+      note: {
+        chapterId: null,
+        attachmentId,
+        page: currentPage,
+        highlight,
+        body: noteBody
+      }
+    }).then(() => {
+      setHighlight(null);
+      setNoteBody("");
+      refreshNotes();
+    }).catch(err => toast.error(err.message ?? err));
+  }, [attachmentId, currentPage, highlight, noteBody, refreshNotes]);
+
+  const deleteNote = useCallback((noteId: string) => {
+    invoke("delete_note", { userId, noteId })
+      .then(refreshNotes)
+      .catch(err => toast.error(err));
+  }, [refreshNotes]);
+
   // Render trang PDF
   const renderPage = useCallback(async (pageNumber: number) => {
     if (!pdf || !canvasRef.current || !textLayerRef.current) {
@@ -376,6 +459,64 @@ const PdfViewer: React.FC = () => {
           </div>
         )}
       </div>
+      {/* Notes */}
+      {attachmentId && (
+        <div className="max-w-6xl mx-auto mt-6 bg-white rounded-3xl shadow-xl border border-teal-200 p-6 space-y-4">
+          <div className="flex items-center space-x-2 text-teal-600 font-semibold">
+            <NotebookPen className="w-5 h-5" />
+            <span>Ghi chú trang {currentPage}</span>
+          </div>
+          {highlight && (
+            <blockquote className="border-l-4 border-amber-300 bg-amber-50 p-2 text-sm italic">
+              {highlight}
+            </blockquote>
+          )}
+          <textarea
+            className="w-full border border-teal-200 rounded-md p-2 text-sm"
+            placeholder="Viết ghi chú cho trang này"
+            value={noteBody}
+            onChange={e => setNoteBody(e.target.value)}
+          />
+          <div className="flex space-x-2">
+            <button
+              onClick={quoteSelection}
+              className="flex items-center space-x-2 px-3 py-2 rounded-md border border-teal-300 text-teal-600 hover:bg-teal-50 text-sm"
+            >
+              <Highlighter className="w-4 h-4" />
+              <span>Trích đoạn đã chọn</span>
+            </button>
+            <button
+              onClick={saveNote}
+              disabled={!noteBody.trim() && !highlight}
+              className="px-3 py-2 rounded-md bg-teal-400 text-white hover:bg-teal-500 text-sm disabled:opacity-50"
+            >
+              Lưu ghi chú
+            </button>
+          </div>
+          <ul className="divide-y divide-gray-200">
+            {notes.map(note => (
+              <li key={note.id} className="py-3 flex justify-between items-start text-sm">
+                <button
+                  onClick={() => note.page && setCurrentPage(note.page)}
+                  className="text-left space-y-1"
+                >
+                  <span className="text-xs text-teal-500">Trang {note.page}</span>
+                  {note.highlight && (
+                    <p className="border-l-4 border-amber-300 pl-2 italic">{note.highlight}</p>
+                  )}
+                  {note.body && <p className="whitespace-pre-wrap">{note.body}</p>}
+                </button>
+                <button
+                  onClick={() => deleteNote(note.id)}
+                  className="text-red-500 hover:bg-red-100 p-2 rounded-md transition-colors"
+                >
+                  <Trash2 className="w-4 h-4" />
+                </button>
+              </li>
+            ))}
+          </ul>
+        </div>
+      )}
       <ChatbotClient2 />
     </div>
   );
@@ -15,6 +15,7 @@ import { CategoryItem } from "./category-item";
 
 interface CategoriesProps {
     items: Category[];
+    counts?: Record<string, number>;
 }
 
 const iconMap: Record<Category["name"], IconType> = {
@@ -27,7 +28,7 @@ const iconMap: Record<Category["name"], IconType> = {
     "Engineering": FcEngineering
 }
 
-export const Categories = ({ items }: CategoriesProps) => {
+export const Categories = ({ items, counts }: CategoriesProps) => {
     return (
         <div className="flex items-center gap-x-2 overflow-x-auto pb-2">
             {items.map((item) =>(
@@ -36,6 +37,7 @@ export const Categories = ({ items }: CategoriesProps) => {
                     label={item.name}
                     icon={iconMap[item.name]}
                     value={item.id}
+                    count={counts?.[item.id]}
                 />
             ))}
         </div>
@@ -9,12 +9,14 @@ interface CategoryItemProps {
     label: string;
     value?: string;
     icon?: IconType;
+    count?: number;
 };
 
 export const CategoryItem = ({
     label,
     value,
     icon: Icon,
+    count,
 }: CategoryItemProps) => {
     const pathname = usePathname();
     const router = useRouter();
@@ -22,6 +24,7 @@ export const CategoryItem = ({
 
     const currentCategoryId = searchParams.get("categoryId");
     const currentTitle = searchParams.get("title");
+    const currentPrice = searchParams.get("price");
 
     const isSelected = currentCategoryId === value;
 
@@ -30,6 +33,7 @@ export const CategoryItem = ({
             url: pathname,
             query: {
                 title: currentTitle,
+                price: currentPrice,
                 categoryId: isSelected ? null : value
             }
         }, { skipNull: true, skipEmptyString: true });
@@ -50,6 +54,9 @@ export const CategoryItem = ({
             <div className="truncate">
                 {label}
             </div>
+            {count !== undefined && (
+                <span className="text-xs text-slate-500">({count})</span>
+            )}
         </button>
     )
 }
@@ -3,7 +3,8 @@
 import { Categories } from "./_components/categories";
 
 
-import { redirect, useSearchParams } from "next/navigation";
+import { redirect, usePathname, useRouter, useSearchParams } from "next/navigation";
+import queryString from "query-string";
 import { CoursesList } from "@/components/courses-list";
 import { useState, useEffect } from "react";
 
@@ -17,6 +18,27 @@ type CourseWithProgressWithCategory = Course & {
     progress: number | null
 };
 
+type PriceRange = "free" | "under200k" | "under500k" | "under1m" | "over1m";
+
+type SearchResults = {
+    courses: CourseWithProgressWithCategory[];
+    total: number;
+    page: number;
+    perPage: number;
+    facets: {
+        categories: { id: string; name: string; count: number }[];
+        prices: { range: PriceRange; min: number; max: number | null; count: number }[];
+    };
+};
+
+const priceLabels: Record<PriceRange, string> = {
+    free: "Miễn phí",
+    under200k: "Dưới 200.000₫",
+    under500k: "200.000₫ - 500.000₫",
+    under1m: "500.000₫ - 1.000.000₫",
+    over1m: "Trên 1.000.000₫",
+};
+
 const SquareLoader = () => (
   <motion.div 
     className="loader grid grid-cols-4 gap-2 w-24 h-24"
@@ -52,8 +74,10 @@ const SquareLoader = () => (
 const SearchPage = () => {
   const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
   const searchParams = useSearchParams();
+  const pathname = usePathname();
+  const router = useRouter();
   const [categories, setCategories] = useState<Category[]>([]);
-  const [courses, setCourses] = useState<CourseWithProgressWithCategory[]>([]);
+  const [results, setResults] = useState<SearchResults | null>(null);
   const [isLoading, setIsLoading] = useState(true);
 
   useEffect(() => {
@@ -62,6 +86,8 @@ const SearchPage = () => {
         // Các tham số tìm kiếm từ URL
         const title = searchParams.get("title")?.toString();
         const categoryId = searchParams.get("categoryId")?.toString();
+        const price = searchParams.get("price")?.toString();
+        const page = Number(searchParams.get("page") ?? 1);
   
         const fetchedCategories = await invoke<Category[]>("get_categories");
         console.log("Danh mục đã tải:", fetchedCategories);
@@ -69,15 +95,14 @@ const SearchPage = () => {
   
         console.log("Tham số tìm kiếm:", { title, categoryId });
   
-        const coursesRes = await invoke<CourseWithProgressWithCategory[]>("get_search", {
+        const searchRes = await invoke<SearchResults>("get_search", {
           userId,
-          title,
-          categoryId,
+          query: { text: title, categoryId, price, page },
         });
   
-        console.log("Các khóa học đã tải:", coursesRes);
+        console.log("Các khóa học đã tải:", searchRes);
   
-        setCourses(coursesRes);
+        setResults(searchRes);
         setIsLoading(false);
       } catch (err) {
         console.error("Lỗi khi tải dữ liệu:", err);
@@ -89,6 +114,26 @@ const SearchPage = () => {
     fetchCategoryAndCourses();
   }, [searchParams]);
 
+  const updateQuery = (changes: Record<string, string | number | null>) => {
+    const url = queryString.stringifyUrl({
+      url: pathname,
+      query: {
+        title: searchParams.get("title"),
+        categoryId: searchParams.get("categoryId"),
+        price: searchParams.get("price"),
+        ...changes,
+      }
+    }, { skipNull: true, skipEmptyString: true });
+
+    router.push(url);
+  };
+
+  const courses = results?.courses ?? [];
+  const total = results?.total ?? 0;
+  const page = results?.page ?? 1;
+  const pages = results ? Math.max(1, Math.ceil(total / results.perPage)) : 1;
+  const currentPrice = searchParams.get("price");
+
   if (!userId) {
     return redirect("/");
   }
@@ -118,7 +163,28 @@ const SearchPage = () => {
           transition={{ duration: 0.5, delay: 0.2 }}
         >
           <h2 className="text-2xl font-bold text-pastel-dark mb-4">Danh mục khóa học</h2>
-          <Categories items={categories} />
+          <Categories
+            items={categories}
+            counts={Object.fromEntries(
+              (results?.facets.categories ?? []).map((facet) => [facet.id, facet.count])
+            )}
+          />
+          <div className="flex items-center gap-x-2 overflow-x-auto pt-2">
+            {results?.facets.prices.map((facet) => (
+              <button
+                key={facet.range}
+                type="button"
+                onClick={() => updateQuery({ price: currentPrice === facet.range ? null : facet.range, page: null })}
+                className={`py-1 px-3 text-sm border rounded-full transition-all ${
+                  currentPrice === facet.range
+                    ? "border-sky-700 bg-sky-200/20 text-sky-800"
+                    : "border-slate-200 hover:border-sky-700"
+                }`}
+              >
+                {priceLabels[facet.range]} ({facet.count})
+              </button>
+            ))}
+          </div>
         </motion.div>
 
         {/* Phần Danh Sách Khóa Học */}
@@ -129,10 +195,35 @@ const SearchPage = () => {
           transition={{ duration: 0.5, delay: 0.4 }}
         >
           <h2 className="text-2xl font-bold text-pastel-dark mb-4">
-            Có {courses.length} khóa học phù hợp!
+            Có {total} khóa học phù hợp!
           </h2>
           {courses.length > 0 ? (
-            <CoursesList items={courses} />
+            <>
+              <CoursesList items={courses} />
+              {pages > 1 && (
+                <div className="flex justify-center items-center gap-x-4 mt-8">
+                  <button
+                    type="button"
+                    disabled={page <= 1}
+                    onClick={() => updateQuery({ page: page - 1 })}
+                    className="py-2 px-4 text-sm border border-slate-200 rounded-full disabled:opacity-50"
+                  >
+                    Trang trước
+                  </button>
+                  <span className="text-sm text-pastel-dark">
+                    Trang {page} / {pages}
+                  </span>
+                  <button
+                    type="button"
+                    disabled={page >= pages}
+                    onClick={() => updateQuery({ page: page + 1 })}
+                    className="py-2 px-4 text-sm border border-slate-200 rounded-full disabled:opacity-50"
+                  >
+                    Trang sau
+                  </button>
+                </div>
+              )}
+            </>
           ) : (
             <div className="text-center py-12 bg-white rounded-lg shadow-sm">
               <p className="text-gray-500 text-lg">Không có khóa học nào phù hợp với tiêu chí tìm kiếm của bạn.</p>
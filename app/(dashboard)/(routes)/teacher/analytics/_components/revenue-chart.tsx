@@ -0,0 +1,54 @@
+"use client";
+
+import {
+    Bar,
+    BarChart,
+    ResponsiveContainer,
+    Tooltip,
+    XAxis,
+    YAxis
+} from "recharts";
+
+import { Card } from "@/components/ui/card";
+
+interface RevenueChartProps {
+    data: {
+        period: string;
+        revenue: number;
+        sales: number;
+        refunds: number;
+    }[];
+}
+
+export const RevenueChart = ({
+    data
+}: RevenueChartProps) => {
+    return (
+        <Card>
+            <ResponsiveContainer width="100%" height={350}>
+                <BarChart data={data}>
+                    <XAxis
+                        dataKey="period"
+                        stroke="#888888"
+                        fontSize={12}
+                        tickLine={false}
+                        axisLine={false}
+                    />
+                    <YAxis
+                        stroke="#888888"
+                        fontSize={12}
+                        tickLine={false}
+                        axisLine={false}
+                        tickFormatter={(value) => `${value}₫`}
+                    />
+                    <Tooltip />
+                    <Bar
+                        dataKey="revenue"
+                        fill="#0369a1"
+                        radius={[4, 4, 0, 0]}
+                    />
+                </BarChart>
+            </ResponsiveContainer>
+        </Card>
+    )
+}
@@ -4,34 +4,64 @@ import { redirect } from "next/navigation";
 
 import { DataCard } from "./_components/data-card";
 import { Chart } from "./_components/chart";
+import { RevenueChart } from "./_components/revenue-chart";
+import { Button } from "@/components/ui/button";
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import toast from "react-hot-toast";
 import { Loader2 } from "lucide-react";
 
+type Interval = "day" | "week" | "month";
+
 type TeacherAnalytics =  {
-    data: { name: string; total: number; }[];
+    data: { name: string; total: number; sales: number; }[];
     totalRevenue: number;
     totalSales: number;
+    totalRefunds: number;
+    refundedAmount: number;
+    activeLearners: number;
+    series: { period: string; revenue: number; sales: number; refunds: number; }[];
 }
 
+const INTERVALS: { interval: Interval; label: string; days: number }[] = [
+    { interval: "day", label: "30 ngày", days: 30 },
+    { interval: "week", label: "12 tuần", days: 84 },
+    { interval: "month", label: "12 tháng", days: 365 },
+];
+
 const AnalyticsPage = () => {
     // const { userId } = auth();
     const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
     const [isLoading, setIsLoading] = useState(true);
-    const [analytics, setAnalytics] = useState<TeacherAnalytics>({data: [], totalRevenue: 0, totalSales: 0});
+    const [interval, setSelectedInterval] = useState<Interval>("day");
+    const [analytics, setAnalytics] = useState<TeacherAnalytics>({
+        data: [],
+        totalRevenue: 0,
+        totalSales: 0,
+        totalRefunds: 0,
+        refundedAmount: 0,
+        activeLearners: 0,
+        series: [],
+    });
 
     useEffect(() => {
         async function fetchAnalytics() {
+            const days = INTERVALS.find((item) => item.interval === interval)!.days;
+            const from = new Date(Date.now() - days * 24 * 60 * 60 * 1000);
             invoke<TeacherAnalytics>("get_teacher_analytics", {
-                userId
+                userId,
+                range: {
+                    from: from.toISOString().slice(0, 19),
+                    to: null,
+                    interval,
+                }
             }).then(analytic => {
                 setAnalytics(analytic);
                 setIsLoading(false);
             }).catch(err => toast.error(err));
         }
         fetchAnalytics();
-    }, [])
+    }, [interval])
 
     if (!userId) {
         return redirect("/");
@@ -41,7 +71,19 @@ const AnalyticsPage = () => {
         <>
         {!isLoading ? ( 
         <div className="p-6">
-            <div className="grid grid-cols-1 md:grid-cols-2 gap-4 mb-4">
+            <div className="flex gap-x-2 mb-4">
+                {INTERVALS.map((item) => (
+                    <Button
+                        key={item.interval}
+                        size="sm"
+                        variant={item.interval === interval ? "default" : "outline"}
+                        onClick={() => setSelectedInterval(item.interval)}
+                    >
+                        {item.label}
+                    </Button>
+                ))}
+            </div>
+            <div className="grid grid-cols-1 md:grid-cols-4 gap-4 mb-4">
                 <DataCard
                     label="Tổng doanh thu"
                     value={analytics.totalRevenue}
@@ -51,10 +93,24 @@ const AnalyticsPage = () => {
                     label="Tổng số đơn hàng"
                     value={analytics.totalSales}
                 />
+                <DataCard
+                    label="Hoàn tiền"
+                    value={analytics.refundedAmount}
+                    shouldFormat
+                />
+                <DataCard
+                    label="Học viên hoạt động"
+                    value={analytics.activeLearners}
+                />
+            </div>
+            <div className="grid grid-cols-1 gap-4">
+                <RevenueChart
+                    data={analytics.series}
+                />
+                <Chart
+                    data={analytics.data}
+                />
             </div>
-            <Chart
-                data={analytics.data}
-            />
         </div>
 
         ) : (
@@ -0,0 +1,51 @@
+"use client";
+
+import { invoke } from "@tauri-apps/api/core";
+import { MessageCircle } from "lucide-react";
+import Link from "next/link";
+import { useEffect, useState } from "react";
+import toast from "react-hot-toast";
+
+interface UnreadComments {
+    courseId: string;
+    courseTitle: string;
+    unread: number;
+}
+
+export const UnreadDiscussions = () => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+    const [courses, setCourses] = useState<UnreadComments[]>([]);
+
+    useEffect(() => {
+        invoke<UnreadComments[]>("get_unread_comments", { userId })
+            .then(setCourses)
+            .catch(err => toast.error(err));
+    }, [])
+
+    if (courses.length === 0) {
+        return null;
+    }
+
+    return (
+        <div className="mb-6 border bg-slate-100 rounded-md p-4">
+            <div className="font-medium flex items-center gap-x-2 mb-2">
+                <MessageCircle className="h-4 w-4" />
+                Thảo luận chưa đọc
+            </div>
+            <div className="space-y-1">
+                {courses.map(course => (
+                    <Link
+                        key={course.courseId}
+                        href={`/teacher/discussions?courseId=${course.courseId}`}
+                        className="flex items-center justify-between text-sm hover:underline"
+                    >
+                        <span className="line-clamp-1">{course.courseTitle}</span>
+                        <span className="ml-2 rounded-full bg-sky-700 text-white text-xs px-2">
+                            {course.unread}
+                        </span>
+                    </Link>
+                ))}
+            </div>
+        </div>
+    )
+}
@@ -61,7 +61,7 @@ export const ChapterAccessForm = ({
             toast.success("Chapter updated");
             toggleEdit();
             setIsFree(values.isFree);
-        }).catch(err => toast.error(err));
+        }).catch(err => toast.error(err.message ?? err));
     }
 
     return (
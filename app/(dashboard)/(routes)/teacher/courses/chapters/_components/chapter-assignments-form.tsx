@@ -0,0 +1,173 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Textarea } from "@/components/ui/textarea";
+import { invoke } from "@tauri-apps/api/core";
+import { ClipboardList, Loader2, PlusCircle, X } from "lucide-react";
+import { useEffect, useState } from "react";
+import toast from "react-hot-toast";
+
+interface Assignment {
+    id: string;
+    title: string;
+    instructions: string | null;
+    dueAt: string | null;
+    maxScore: number;
+}
+
+interface ChapterAssignmentsFormProps {
+    courseId: string;
+    chapterId: string;
+};
+
+export const ChapterAssignmentsForm = ({
+    courseId,
+    chapterId
+}: ChapterAssignmentsFormProps) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+    const [assignments, setAssignments] = useState<Assignment[]>([]);
+    const [isCreating, setIsCreating] = useState(false);
+    const [isSubmitting, setIsSubmitting] = useState(false);
+    const [deletingId, setDeletingId] = useState<string | null>(null);
+    const [title, setTitle] = useState("");
+    const [instructions, setInstructions] = useState("");
+    const [dueAt, setDueAt] = useState("");
+    const [maxScore, setMaxScore] = useState("10");
+
+    useEffect(() => {
+        invoke<Assignment[]>("get_teacher_assignments", {
+            userId,
+            courseId,
+            chapterId
+        }).then(setAssignments)
+        .catch(err => toast.error(err));
+    }, [courseId, chapterId])
+
+    const onCreate = () => {
+        setIsSubmitting(true);
+        invoke<Assignment>("create_assignment", {
+            userId,
+            courseId,
+            chapterId,
+            assignment: {
+                title,
+                instructions: instructions || null,
+                // The date picker gives local time, the backend stores UTC
+                dueAt: dueAt ? new Date(dueAt).toISOString().slice(0, 19) : null,
+                maxScore: Number(maxScore)
+            }
+        }).then(assignment => {
+            toast.success("Đã tạo bài tập");
+            setAssignments([...assignments, assignment]);
+            setTitle("");
+            setInstructions("");
+            setDueAt("");
+            setIsCreating(false);
+        }).catch(err => toast.error(err.message ?? err))
+        .finally(() => setIsSubmitting(false));
+    }
+
+    const onDelete = (id: string) => {
+        setDeletingId(id);
+        invoke("delete_assignment", {
+            userId,
+            courseId,
+            assignmentId: id
+        }).then(() => {
+            toast.success("Đã xóa bài tập");
+            setAssignments(assignments.filter(assignment => assignment.id !== id));
+        }).catch(err => toast.error(err))
+        .finally(() => setDeletingId(null));
+    }
+
+    return (
+        <div className="mt-6 border bg-slate-100 rounded-md p-4">
+            <div className="font-medium flex items-center justify-between">
+                Bài tập
+                <Button onClick={() => setIsCreating(current => !current)} variant="ghost">
+                    {isCreating ? (
+                        <>Hủy</>
+                    ) : (
+                        <>
+                        <PlusCircle className="h-4 w-4 mr-2"/>
+                        Thêm bài tập
+                        </>
+                    )}
+                </Button>
+            </div>
+            {isCreating && (
+                <div className="space-y-2 mt-4">
+                    <Input
+                        disabled={isSubmitting}
+                        placeholder="Tên bài tập"
+                        value={title}
+                        onChange={e => setTitle(e.target.value)}
+                    />
+                    <Textarea
+                        disabled={isSubmitting}
+                        placeholder="Hướng dẫn làm bài"
+                        value={instructions}
+                        onChange={e => setInstructions(e.target.value)}
+                    />
+                    <div className="flex gap-x-2">
+                        <Input
+                            disabled={isSubmitting}
+                            type="datetime-local"
+                            value={dueAt}
+                            onChange={e => setDueAt(e.target.value)}
+                        />
+                        <Input
+                            disabled={isSubmitting}
+                            type="number"
+                            min={1}
+                            placeholder="Điểm tối đa"
+                            value={maxScore}
+                            onChange={e => setMaxScore(e.target.value)}
+                        />
+                    </div>
+                    <Button
+                        disabled={isSubmitting || !title.trim()}
+                        onClick={onCreate}
+                    >
+                        Lưu
+                    </Button>
+                </div>
+            )}
+            {!isCreating && assignments.length === 0 && (
+                <p className="text-sm mt-2 text-slate-500 italic">
+                    Chưa có bài tập
+                </p>
+            )}
+            {assignments.length > 0 && (
+                <div className="space-y-2 mt-2">
+                    {assignments.map(assignment => (
+                        <div
+                            key={assignment.id}
+                            className="flex items-center p-3 w-full bg-sky-100 border-sky-200 border text-sky-700 rounded-md"
+                        >
+                            <ClipboardList className="h-4 w-4 mr-2 flex-shrink-0" />
+                            <div className="text-xs">
+                                <p className="font-medium line-clamp-1">{assignment.title}</p>
+                                <p>
+                                    Điểm tối đa {assignment.maxScore}
+                                    {assignment.dueAt && `, hạn nộp ${new Date(assignment.dueAt + "Z").toLocaleString()}`}
+                                </p>
+                            </div>
+                            {deletingId === assignment.id ? (
+                                <Loader2 className="ml-auto h-4 w-4 animate-spin" />
+                            ) : (
+                                <button
+                                    className="ml-auto hover:opacity-75 transition"
+                                    onClick={() => onDelete(assignment.id)}
+                                >
+                                    <X className="h-4 w-4" />
+                                </button>
+                            )}
+                        </div>
+                    ))}
+                </div>
+            )}
+        </div>
+    )
+}
@@ -62,7 +62,7 @@ export const ChapterDescriptionForm = ({
             toast.success("Chapter updated");
             toggleEdit();
             setDescription(values.description);
-        }).catch(err => toast.error(err));
+        }).catch(err => toast.error(err.message ?? err));
     }
 
     return (
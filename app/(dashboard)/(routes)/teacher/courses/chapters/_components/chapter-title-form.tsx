@@ -60,7 +60,7 @@ export const ChapterTitleForm = ({
             toast.success("Chapter updated");
             toggleEdit();
             setTitle(values.title);
-        }).catch(err => toast.error(err));
+        }).catch(err => toast.error(err.message ?? err));
     }
 
     return (
@@ -49,7 +49,7 @@ export const ChapterVideoForm = ({
                         }).then(() => {
                             toast.success("Chapter updated");
                             setVideoId(result.info.public_id);
-                        }).catch(err => toast.error(err))
+                        }).catch(err => toast.error(err.message ?? err))
                         .finally(() => setIsEditting(false));
                     }}
                     onQueuesEnd={(result, { widget }) => {
@@ -2,13 +2,14 @@
 
 import { IconBadge } from "@/components/icon-badgs";
 
-import { ArrowLeft, Eye, LayoutDashboard, Loader2, Video } from "lucide-react";
+import { ArrowLeft, ClipboardList, Eye, LayoutDashboard, Loader2, Video } from "lucide-react";
 import Link from "next/link";
 import { redirect, useSearchParams } from "next/navigation";
 import { ChapterTitleForm } from "./_components/chapter-title-form";
 import { ChapterDescriptionForm } from "./_components/chapter-description-form";
 import { ChapterAccessForm } from "./_components/chapter-access-form";
 import { ChapterVideoForm } from "./_components/chapter-video-form";
+import { ChapterAssignmentsForm } from "./_components/chapter-assignments-form";
 import { Banner } from "@/components/banner";
 import { ChapterActions } from "./_components/chapter-actions";
 import { useEffect, useState } from "react";
@@ -145,6 +146,16 @@ const ChapterIdPage = () => {
                                 courseId={courseId!}
                                 chapterId={chapterId!}
                             />
+                            <div className="flex items-center gap-x-2 mt-6">
+                                <IconBadge icon={ClipboardList} />
+                                <h2 className="text-xl">
+                                    Assignments
+                                </h2>
+                            </div>
+                            <ChapterAssignmentsForm
+                                courseId={courseId!}
+                                chapterId={chapterId!}
+                            />
                         </div>
                     </div>
                 </div>
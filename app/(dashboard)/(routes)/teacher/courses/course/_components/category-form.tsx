@@ -37,6 +37,7 @@ export const CategoryForm = ({
     courseId,
     options
 }: CategoryFormProps) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
     const [isEditting, setIsEditting] = useState(false);
     const [categoryId, setCategoryId] = useState(initialData?.categoryId || "");
 
@@ -55,13 +56,14 @@ export const CategoryForm = ({
         console.log(courseId);
 
         invoke("update_course", {
+            userId,
             courseId,
             updates: values
         }).then(() => {
             toast.success("Khóa học đã được cập nhật");
             toggleEdit();
             setCategoryId(values.categoryId);
-        }).catch(err => toast.error(err));
+        }).catch(err => toast.error(err.message ?? err));
     }
 
     const selectedOption = options.find((option) => option.value === categoryId);
@@ -35,6 +35,7 @@ export const DescriptionForm = ({
     initialData,
     courseId
 }: DescriptionFormProps) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
     const [isEditting, setIsEditting] = useState(false);
     const [description, setDescription] = useState(initialData?.description || "");
 
@@ -53,13 +54,14 @@ export const DescriptionForm = ({
         console.log(courseId);
 
         invoke("update_course", {
+            userId,
             courseId,
             updates: values
         }).then(() => {
             toast.success("Course updated");
             toggleEdit();
             setDescription(values.description);
-        }).catch(err => toast.error(err));
+        }).catch(err => toast.error(err.message ?? err));
     }
 
     return (
@@ -0,0 +1,101 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Download, Loader2 } from "lucide-react";
+import { useEffect, useState } from "react";
+import toast from "react-hot-toast";
+
+import { invoke } from "@tauri-apps/api/core";
+
+interface ChapterEngagement {
+    chapterId: string;
+    title: string;
+    position: number;
+    reached: number;
+    completed: number;
+    completionRate: number;
+    dropOffRate: number | null;
+    medianSecondsToComplete: number | null;
+    watchSeconds: number;
+}
+
+interface CourseEngagement {
+    courseId: string;
+    learners: number;
+    chapters: ChapterEngagement[];
+}
+
+interface EngagementPanelProps {
+    courseId: string;
+};
+
+const percent = (value: number | null) =>
+    value === null ? "-" : `${Math.round(value * 100)}%`;
+
+const minutes = (seconds: number | null) =>
+    seconds === null ? "-" : `${Math.round(seconds / 60)} phút`;
+
+export const EngagementPanel = ({
+    courseId
+}: EngagementPanelProps) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+    const [engagement, setEngagement] = useState<CourseEngagement>();
+
+    useEffect(() => {
+        invoke<CourseEngagement>("get_course_engagement", {
+            userId,
+            courseId
+        }).then(setEngagement)
+        .catch(err => toast.error(err));
+    }, [courseId]);
+
+    const onExport = () => {
+        invoke<string>("export_course_engagement", {
+            userId,
+            courseId
+        }).then((csv) => {
+            const url = URL.createObjectURL(new Blob([csv], { type: "text/csv" }));
+            const link = document.createElement("a");
+            link.href = url;
+            link.download = `engagement-${courseId}.csv`;
+            link.click();
+            URL.revokeObjectURL(url);
+        }).catch(err => toast.error(err));
+    }
+
+    return (
+        <div className="mt-6 border bg-slate-100 rounded-md p-4">
+            <div className="font-medium flex items-center justify-between">
+                Học viên: {engagement?.learners ?? "-"}
+                <Button onClick={onExport} variant="ghost" disabled={!engagement}>
+                    <Download className="h-4 w-4 mr-2" />
+                    CSV
+                </Button>
+            </div>
+            {!engagement ? (
+                <Loader2 className="h-4 w-4 animate-spin" />
+            ) : (
+                <table className="w-full text-sm mt-2">
+                    <thead>
+                        <tr className="text-left text-slate-500">
+                            <th>Chương</th>
+                            <th>Hoàn thành</th>
+                            <th>Bỏ dở</th>
+                            <th>Thời gian</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {engagement.chapters.map((chapter) => (
+                            <tr key={chapter.chapterId}>
+                                <td className="line-clamp-1">{chapter.title}</td>
+                                <td>{percent(chapter.completionRate)} ({chapter.completed}/{chapter.reached})</td>
+                                <td>{percent(chapter.dropOffRate)}</td>
+                                <td>{minutes(chapter.medianSecondsToComplete)}</td>
+                            </tr>
+                        ))}
+                    </tbody>
+                </table>
+            )}
+        </div>
+    )
+}
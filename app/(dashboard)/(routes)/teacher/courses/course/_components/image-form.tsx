@@ -30,6 +30,7 @@ export const ImageForm = ({
     initialData,
     courseId
 }: ImageFormProps) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
     const [isEditting, setIsEditting] = useState(false);
     const [imageUrl, setImageUrl] = useState(initialData?.imageUrl || "");
 
@@ -48,13 +49,14 @@ export const ImageForm = ({
                     }}
                     onSuccess={(result) => {
                         invoke("update_course", {
+                            userId,
                             courseId,
                             updates: {"imageUrl": result.info.url}
                         }).then(() => {
                             toast.success("Course updated");
                             setImageUrl(result.info.url);
                             setIsEditting(false);
-                        }).catch(err => toast.error(err));
+                        }).catch(err => toast.error(err.message ?? err));
 
                     }}
                     onQueuesEnd={(result, { widget }) => {
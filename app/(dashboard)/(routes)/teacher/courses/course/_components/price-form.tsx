@@ -34,6 +34,7 @@ export const PriceForm = ({
     initialData,
     courseId
 }: PriceFormProps) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
     const [isEditting, setIsEditting] = useState(false);
     const [price, setPrice] = useState(initialData?.price || undefined);
 
@@ -52,13 +53,14 @@ export const PriceForm = ({
         console.log(courseId);
 
         invoke("update_course", {
+            userId,
             courseId,
             updates: values
         }).then(() => {
             toast.success("Course updated");
             toggleEdit();
             setPrice(values.price);
-        }).catch(err => toast.error(err));
+        }).catch(err => toast.error(err.message ?? err));
     }
 
     return (
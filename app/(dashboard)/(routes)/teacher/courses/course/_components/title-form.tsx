@@ -35,6 +35,7 @@ export const TitleForm = ({
     initialData,
     courseId
 }: TitleFormProps) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
     const [isEditting, setIsEditting] = useState(false);
     const [title, setTitle] = useState(initialData?.title || "");
 
@@ -51,13 +52,14 @@ export const TitleForm = ({
         console.log(courseId);
 
         invoke("update_course", {
+            userId,
             courseId,
             updates: values
         }).then(() => {
             toast.success("Course updated");
             toggleEdit();
             setTitle(values.title);
-        }).catch(err => toast.error(err));
+        }).catch(err => toast.error(err.message ?? err));
     }
 
     return (
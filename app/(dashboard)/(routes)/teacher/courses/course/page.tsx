@@ -2,7 +2,7 @@
 
 import { IconBadge } from "@/components/icon-badgs";
 
-import { CircleDollarSign, File, LayoutDashboard, ListChecks, Loader2 } from "lucide-react";
+import { BarChart3, CircleDollarSign, File, LayoutDashboard, ListChecks, Loader2 } from "lucide-react";
 import { redirect, useSearchParams } from "next/navigation";
 import { TitleForm } from "./_components/title-form";
 import { DescriptionForm } from "./_components/description-form";
@@ -11,6 +11,7 @@ import { CategoryForm } from "./_components/category-form";
 import { PriceForm } from "./_components/price-form";
 import { AttachmentForm } from "./_components/attachment-form";
 import { ChaptersForm } from "./_components/chapters-form";
+import { EngagementPanel } from "./_components/engagement-panel";
 import { Banner } from "@/components/banner";
 import { Actions } from "./_components/actions";
 import { useEffect, useState } from "react";
@@ -159,6 +160,17 @@ const CourseIdPage = () => {
                                     courseId={course.id}
                                 />
                             </div>
+                            <div>
+                                <div className="flex items-center gap-x-2">
+                                    <IconBadge icon={BarChart3}/>
+                                    <h2 className="text-xl">
+                                        Mức độ tương tác
+                                    </h2>
+                                </div>
+                                <EngagementPanel
+                                    courseId={course.id}
+                                />
+                            </div>
                         </div>
                     </div>
                 </div>
@@ -2,6 +2,7 @@
 
 import { DataTable } from "./_components/data-table";
 import { columns } from "./_components/columns";
+import { UnreadDiscussions } from "./_components/unread-discussions";
 
 import { redirect } from "next/navigation";
 import { useEffect, useState } from "react";
@@ -41,6 +42,7 @@ const CoursesPage = () => {
                 <Loader2 className="w-4 h-4 animate-spin" />
             ) : (
                 <div className="p-6">
+                    <UnreadDiscussions />
                     <DataTable columns={columns} data={courses} />
                 </div>
             )}
@@ -0,0 +1,171 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Textarea } from "@/components/ui/textarea";
+import { cn } from "@/lib/utils";
+import { invoke } from "@tauri-apps/api/core";
+import { Loader2, Pin, PinOff, Trash } from "lucide-react";
+import { useSearchParams } from "next/navigation";
+import { useCallback, useEffect, useState } from "react";
+import toast from "react-hot-toast";
+
+interface CommentView {
+    id: string;
+    userId: string;
+    body: string;
+    isPinned: boolean;
+    createdAt: string;
+    editedAt: string | null;
+    deletedAt: string | null;
+    byTeacher: boolean;
+}
+
+interface Thread extends CommentView {
+    replies: CommentView[];
+}
+
+interface ChapterThreads {
+    chapterId: string;
+    chapterTitle: string;
+    threads: Thread[];
+}
+
+const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+
+const ModeratedComment = ({
+    comment,
+    onChanged
+}: {
+    comment: CommentView;
+    onChanged: () => void;
+}) => {
+    const pin = () => {
+        invoke("pin_comment", {
+            userId,
+            commentId: comment.id,
+            pinned: !comment.isPinned
+        }).then(onChanged)
+        .catch(err => toast.error(err));
+    }
+
+    const remove = () => {
+        invoke("delete_comment", {
+            userId,
+            commentId: comment.id
+        }).then(() => {
+            toast.success("Đã xóa bình luận");
+            onChanged();
+        }).catch(err => toast.error(err));
+    }
+
+    return (
+        <div className={cn(
+            "flex items-start gap-x-2 rounded-md p-3 text-sm",
+            comment.byTeacher ? "bg-sky-100 border border-sky-200" : "bg-white border"
+        )}>
+            <div className="flex-1">
+                <p className="text-xs text-slate-500">
+                    {comment.byTeacher ? "Bạn" : comment.userId} · {new Date(comment.createdAt + "Z").toLocaleString()}
+                    {comment.editedAt && " (đã sửa)"}
+                </p>
+                {comment.deletedAt ? (
+                    <p className="italic text-slate-500">Bình luận đã bị xóa</p>
+                ) : (
+                    <p className="whitespace-pre-wrap">{comment.body}</p>
+                )}
+            </div>
+            {!comment.deletedAt && (
+                <>
+                    <Button size="sm" variant="ghost" onClick={pin}>
+                        {comment.isPinned ? <PinOff className="h-4 w-4" /> : <Pin className="h-4 w-4" />}
+                    </Button>
+                    <Button size="sm" variant="ghost" onClick={remove}>
+                        <Trash className="h-4 w-4" />
+                    </Button>
+                </>
+            )}
+        </div>
+    )
+}
+
+const DiscussionsPage = () => {
+    const searchParams = useSearchParams();
+    const courseId = searchParams.get("courseId");
+    const [chapters, setChapters] = useState<ChapterThreads[]>([]);
+    const [isLoading, setIsLoading] = useState(true);
+    const [replies, setReplies] = useState<Record<string, string>>({});
+
+    const refresh = useCallback(() => {
+        invoke<ChapterThreads[]>("get_course_discussions", { userId, courseId })
+            .then(setChapters)
+            .catch(err => toast.error(err))
+            .finally(() => setIsLoading(false));
+    }, [courseId])
+
+    useEffect(() => {
+        refresh();
+        invoke("mark_discussions_read", { userId, courseId })
+            .catch(err => toast.error(err));
+    }, [courseId, refresh])
+
+    const reply = (chapterId: string, threadId: string) => {
+        invoke("post_comment", {
+            userId,
+            chapterId,
+            comment: { body: replies[threadId], parentId: threadId }
+        }).then(() => {
+            setReplies({ ...replies, [threadId]: "" });
+            refresh();
+        }).catch(err => toast.error(err.message ?? err));
+    }
+
+    if (isLoading) {
+        return (
+            <div className="p-6">
+                <Loader2 className="w-8 h-8 animate-spin"/>
+            </div>
+        )
+    }
+
+    return (
+        <div className="p-6 space-y-6">
+            <h1 className="text-2xl font-medium">Thảo luận</h1>
+            {chapters.length === 0 && (
+                <p className="text-sm text-slate-500 italic">Chưa có câu hỏi nào</p>
+            )}
+            {chapters.map(chapter => (
+                <div key={chapter.chapterId} className="space-y-4">
+                    <h2 className="text-lg font-medium">{chapter.chapterTitle}</h2>
+                    {chapter.threads.map(thread => (
+                        <div key={thread.id} className="border bg-slate-100 rounded-md p-4 space-y-2">
+                            <ModeratedComment comment={thread} onChanged={refresh} />
+                            <div className="pl-6 space-y-2">
+                                {thread.replies.map(reply => (
+                                    <ModeratedComment key={reply.id} comment={reply} onChanged={refresh} />
+                                ))}
+                                {!thread.deletedAt && (
+                                    <div className="flex items-end gap-x-2">
+                                        <Textarea
+                                            placeholder="Trả lời học viên"
+                                            value={replies[thread.id] ?? ""}
+                                            onChange={e => setReplies({ ...replies, [thread.id]: e.target.value })}
+                                        />
+                                        <Button
+                                            size="sm"
+                                            disabled={!replies[thread.id]?.trim()}
+                                            onClick={() => reply(chapter.chapterId, thread.id)}
+                                        >
+                                            Trả lời
+                                        </Button>
+                                    </div>
+                                )}
+                            </div>
+                        </div>
+                    ))}
+                </div>
+            ))}
+        </div>
+    );
+}
+
+export default DiscussionsPage;
@@ -0,0 +1,142 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Textarea } from "@/components/ui/textarea";
+import { invoke } from "@tauri-apps/api/core";
+import { File, Loader2 } from "lucide-react";
+import { useEffect, useState } from "react";
+import toast from "react-hot-toast";
+
+interface SubmissionToGrade {
+    id: string;
+    userId: string;
+    fileUrl: string | null;
+    fileName: string | null;
+    answer: string | null;
+    submittedAt: string;
+    assignmentTitle: string;
+    maxScore: number;
+    dueAt: string | null;
+    chapterTitle: string;
+    courseTitle: string;
+}
+
+const GradeForm = ({
+    submission,
+    onGraded
+}: {
+    submission: SubmissionToGrade;
+    onGraded: () => void;
+}) => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+    const [score, setScore] = useState("");
+    const [feedback, setFeedback] = useState("");
+    const [isSubmitting, setIsSubmitting] = useState(false);
+
+    const grade = () => {
+        setIsSubmitting(true);
+        invoke("grade_submission", {
+            userId,
+            submissionId: submission.id,
+            grade: {
+                score: Number(score),
+                feedback: feedback || null
+            }
+        }).then(() => {
+            toast.success("Đã chấm điểm");
+            onGraded();
+        }).catch(err => toast.error(err.message ?? err))
+        .finally(() => setIsSubmitting(false));
+    }
+
+    return (
+        <div className="border bg-slate-100 rounded-md p-4 space-y-2">
+            <div>
+                <p className="font-medium">{submission.assignmentTitle}</p>
+                <p className="text-xs text-slate-500">
+                    {submission.courseTitle} / {submission.chapterTitle}, nộp lúc {new Date(submission.submittedAt + "Z").toLocaleString()}
+                </p>
+            </div>
+            {submission.answer && (
+                <p className="text-sm whitespace-pre-wrap">{submission.answer}</p>
+            )}
+            {submission.fileUrl && (
+                <a
+                    href={submission.fileUrl}
+                    target="_blank"
+                    className="flex items-center p-2 bg-sky-200 border text-sky-700 rounded-md text-sm hover:underline"
+                >
+                    <File className="h-4 w-4 mr-2" />
+                    <span className="line-clamp-1">{submission.fileName ?? submission.fileUrl}</span>
+                </a>
+            )}
+            <div className="flex items-center gap-x-2">
+                <Input
+                    className="w-32"
+                    disabled={isSubmitting}
+                    type="number"
+                    min={0}
+                    max={submission.maxScore}
+                    placeholder={`Điểm / ${submission.maxScore}`}
+                    value={score}
+                    onChange={e => setScore(e.target.value)}
+                />
+                <Button
+                    disabled={isSubmitting || score === ""}
+                    onClick={grade}
+                >
+                    {isSubmitting && <Loader2 className="h-4 w-4 mr-2 animate-spin" />}
+                    Chấm điểm
+                </Button>
+            </div>
+            <Textarea
+                disabled={isSubmitting}
+                placeholder="Nhận xét"
+                value={feedback}
+                onChange={e => setFeedback(e.target.value)}
+            />
+        </div>
+    )
+}
+
+const GradingPage = () => {
+    const userId = "user_2n3IHnfFLi6yuQ5GZrtiNlbuMM2";
+    const [submissions, setSubmissions] = useState<SubmissionToGrade[]>([]);
+    const [isLoading, setIsLoading] = useState(true);
+
+    useEffect(() => {
+        invoke<SubmissionToGrade[]>("get_ungraded_submissions", { userId })
+            .then(setSubmissions)
+            .catch(err => toast.error(err))
+            .finally(() => setIsLoading(false));
+    }, [])
+
+    if (isLoading) {
+        return (
+            <div className="p-6">
+                <Loader2 className="w-8 h-8 animate-spin"/>
+            </div>
+        )
+    }
+
+    return (
+        <div className="p-6 space-y-4">
+            <h1 className="text-2xl font-medium">Bài nộp chờ chấm</h1>
+            {submissions.length === 0 && (
+                <p className="text-sm text-slate-500 italic">
+                    Không có bài nộp nào đang chờ chấm
+                </p>
+            )}
+            {submissions.map(submission => (
+                <GradeForm
+                    key={submission.id}
+                    submission={submission}
+                    onGraded={() => setSubmissions(submissions.filter(item => item.id !== submission.id))}
+                />
+            ))}
+        </div>
+    );
+}
+
+export default GradingPage;
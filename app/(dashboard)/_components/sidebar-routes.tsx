@@ -1,7 +1,7 @@
 "use client";
 
 import React from 'react';
-import { BarChart, BotMessageSquare, Compass, Layout, List, BookUser, Presentation } from "lucide-react";
+import { BarChart, BotMessageSquare, ClipboardCheck, Compass, Layers, Layout, List, BookUser, Presentation } from "lucide-react";
 import { SidebarItem } from "./sidebar-item";
 import { usePathname } from "next/navigation";
 import './sidebar.css'; // Import CSS
@@ -17,6 +17,11 @@ const guestRoutes = [
         label: "Thế giới khóa học",
         href: '/search'
     },
+    {
+        icon: Layers,
+        label: "Ôn tập",
+        href: "/flashcards"
+    },
     {
         icon: BotMessageSquare,
         label: "Chatbot",
@@ -50,6 +55,11 @@ const teacherRoutes = [
         label: "Phân tích",
         href: "/teacher/analytics"
     },
+    {
+        icon: ClipboardCheck,
+        label: "Chấm bài",
+        href: "/teacher/grading"
+    },
 ];
 
 export const SidebarRoutes = ({ isDark }: { isDark: boolean }) => { // Thêm isDark vào prop
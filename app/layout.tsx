@@ -4,6 +4,7 @@ import "./globals.css";
 import { ToastProvider } from "@/components/providers/toaster-provider";
 
 import { ConfettiProvider } from "@/components/providers/confetti-provider";
+import { StartupGate } from "@/components/providers/startup-gate";
 
 const geistSans = localFont({
   src: "./fonts/GeistVF.woff",
@@ -33,7 +34,9 @@ export default function RootLayout({
         >
           <ConfettiProvider/>
           <ToastProvider/>
-          {children}
+          <StartupGate>
+            {children}
+          </StartupGate>
         </body>
       </html>
   );
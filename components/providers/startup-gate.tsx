@@ -0,0 +1,245 @@
+"use client";
+
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+type Problem = { key: string; message: string };
+
+type StartupStatus = {
+    stage: "ready" | "locked" | "noKeystore" | "failed";
+    configPath: string | null;
+    keystorePath: string | null;
+    problems: Problem[];
+};
+
+const ProblemList = ({ problems }: { problems: Problem[] }) => (
+    <ul className="space-y-2 mb-4">
+        {problems.map((problem) => (
+            <li key={problem.key + problem.message} className="text-sm">
+                <code className="text-sky-700">{problem.key}</code>
+                <span className="text-slate-700">: {problem.message}</span>
+            </li>
+        ))}
+    </ul>
+);
+
+const Field = ({
+    label,
+    value,
+    onChange,
+    type = "text",
+}: {
+    label: string;
+    value: string;
+    onChange: (value: string) => void;
+    type?: string;
+}) => (
+    <label className="block text-sm text-slate-700 mb-3">
+        {label}
+        <input
+            type={type}
+            value={value}
+            onChange={(e) => onChange(e.target.value)}
+            className="mt-1 w-full rounded-md border border-slate-300 px-3 py-2 text-sm"
+        />
+    </label>
+);
+
+// Mở khóa kho bí mật, tạo kho lần đầu hoặc hiển thị lỗi cấu hình trước khi vào ứng dụng
+export const StartupGate = ({ children }: { children: React.ReactNode }) => {
+    const [status, setStatus] = useState<StartupStatus | null>(null);
+    const [problems, setProblems] = useState<Problem[]>([]);
+    const [isSubmitting, setIsSubmitting] = useState(false);
+    const [diagnosticsPath, setDiagnosticsPath] = useState<string | null>(null);
+
+    const [passphrase, setPassphrase] = useState("");
+    const [confirmation, setConfirmation] = useState("");
+    const [databaseUrl, setDatabaseUrl] = useState("");
+    const [signerToken, setSignerToken] = useState("");
+    const [stripeRelayToken, setStripeRelayToken] = useState("");
+    const [stripeWebhookSecret, setStripeWebhookSecret] = useState("");
+    const [certificateSigningKey, setCertificateSigningKey] = useState("");
+
+    useEffect(() => {
+        invoke<StartupStatus>("get_startup_status")
+            .then(setStatus)
+            .catch((err) => console.error("Không thể kiểm tra cấu hình:", err));
+    }, []);
+
+    const submit = async (command: string, args: Record<string, unknown>) => {
+        setIsSubmitting(true);
+        setProblems([]);
+        try {
+            setStatus(await invoke<StartupStatus>(command, args));
+            setPassphrase("");
+            setConfirmation("");
+        } catch (err) {
+            setProblems(err as Problem[]);
+        } finally {
+            setIsSubmitting(false);
+        }
+    };
+
+    const exportDiagnostics = () => {
+        invoke<string>("export_diagnostics")
+            .then(setDiagnosticsPath)
+            .catch((err) => console.error("Không thể xuất thông tin chẩn đoán:", err));
+    };
+
+    const unlock = (e: React.FormEvent) => {
+        e.preventDefault();
+        submit("unlock_keystore", { passphrase });
+    };
+
+    const create = (e: React.FormEvent) => {
+        e.preventDefault();
+        if (passphrase !== confirmation) {
+            setProblems([{ key: "keystore", message: "Mật khẩu nhập lại không khớp" }]);
+            return;
+        }
+        const optional = (value: string) => (value.trim() ? value.trim() : null);
+        submit("create_keystore", {
+            passphrase,
+            secrets: {
+                databaseUrl: databaseUrl.trim(),
+                cloudinarySignerToken: optional(signerToken),
+                stripeRelayToken: optional(stripeRelayToken),
+                stripeWebhookSecret: optional(stripeWebhookSecret),
+                certificateSigningKey: optional(certificateSigningKey),
+            },
+        });
+    };
+
+    if (!status) {
+        return null;
+    }
+
+    if (status.stage === "ready") {
+        return <>{children}</>;
+    }
+
+    return (
+        <div className="min-h-screen flex items-center justify-center bg-slate-50 p-6">
+            <div className="max-w-xl w-full bg-white rounded-lg shadow-sm border border-slate-200 p-6">
+                {status.stage === "failed" && (
+                    <>
+                        <h1 className="text-xl font-bold text-slate-800 mb-2">
+                            Không thể khởi động ứng dụng
+                        </h1>
+                        <p className="text-sm text-slate-600 mb-4">
+                            Cấu hình chưa đúng. Hãy sửa các mục dưới đây rồi mở lại ứng dụng.
+                        </p>
+                        <ProblemList problems={status.problems} />
+                        {status.configPath && (
+                            <p className="text-xs text-slate-500 mb-4">
+                                Tệp cấu hình: <code>{status.configPath}</code>
+                            </p>
+                        )}
+                        <button
+                            type="button"
+                            onClick={exportDiagnostics}
+                            className="rounded-md border border-slate-300 px-4 py-2 text-sm text-slate-700"
+                        >
+                            Xuất thông tin chẩn đoán
+                        </button>
+                        {diagnosticsPath && (
+                            <p className="text-xs text-slate-500 mt-2">
+                                Đã lưu tại <code>{diagnosticsPath}</code>. Hãy gửi tệp này cho bộ phận hỗ trợ.
+                            </p>
+                        )}
+                    </>
+                )}
+
+                {status.stage === "locked" && (
+                    <form onSubmit={unlock}>
+                        <h1 className="text-xl font-bold text-slate-800 mb-2">
+                            Mở khóa ứng dụng
+                        </h1>
+                        <p className="text-sm text-slate-600 mb-4">
+                            Nhập mật khẩu đã đặt khi cài đặt để mở kho bí mật.
+                        </p>
+                        <Field
+                            label="Mật khẩu"
+                            type="password"
+                            value={passphrase}
+                            onChange={setPassphrase}
+                        />
+                        <ProblemList problems={problems} />
+                        <button
+                            type="submit"
+                            disabled={isSubmitting || !passphrase}
+                            className="rounded-md bg-sky-700 px-4 py-2 text-sm text-white disabled:opacity-50"
+                        >
+                            Mở khóa
+                        </button>
+                    </form>
+                )}
+
+                {status.stage === "noKeystore" && (
+                    <form onSubmit={create}>
+                        <h1 className="text-xl font-bold text-slate-800 mb-2">
+                            Thiết lập ứng dụng
+                        </h1>
+                        <p className="text-sm text-slate-600 mb-4">
+                            Các thông tin bí mật được mã hóa trên máy này bằng mật khẩu bạn chọn.
+                        </p>
+                        <Field
+                            label="Địa chỉ cơ sở dữ liệu"
+                            value={databaseUrl}
+                            onChange={setDatabaseUrl}
+                        />
+                        <Field
+                            label="Mã ký yêu cầu Cloudinary"
+                            type="password"
+                            value={signerToken}
+                            onChange={setSignerToken}
+                        />
+                        <Field
+                            label="Mã chuyển tiếp thanh toán Stripe (không bắt buộc)"
+                            type="password"
+                            value={stripeRelayToken}
+                            onChange={setStripeRelayToken}
+                        />
+                        <Field
+                            label="Khóa webhook Stripe (không bắt buộc)"
+                            type="password"
+                            value={stripeWebhookSecret}
+                            onChange={setStripeWebhookSecret}
+                        />
+                        <Field
+                            label="Khóa ký chứng nhận (chỉ máy của giảng viên cấp chứng nhận)"
+                            type="password"
+                            value={certificateSigningKey}
+                            onChange={setCertificateSigningKey}
+                        />
+                        <Field
+                            label="Mật khẩu"
+                            type="password"
+                            value={passphrase}
+                            onChange={setPassphrase}
+                        />
+                        <Field
+                            label="Nhập lại mật khẩu"
+                            type="password"
+                            value={confirmation}
+                            onChange={setConfirmation}
+                        />
+                        <ProblemList problems={problems} />
+                        <button
+                            type="submit"
+                            disabled={isSubmitting || !passphrase || !databaseUrl}
+                            className="rounded-md bg-sky-700 px-4 py-2 text-sm text-white disabled:opacity-50"
+                        >
+                            Lưu và tiếp tục
+                        </button>
+                        {status.keystorePath && (
+                            <p className="text-xs text-slate-500 mt-4">
+                                Kho bí mật: <code>{status.keystorePath}</code>
+                            </p>
+                        )}
+                    </form>
+                )}
+            </div>
+        </div>
+    );
+};
@@ -16,18 +16,20 @@ export const SearchInput = () => {
     const pathname = usePathname();
 
     const currentCategoryId = searchParams.get("categoryId");
+    const currentPrice = searchParams.get("price");
 
     useEffect(() => {
         const url = queryString.stringifyUrl({
             url: pathname,
             query: {
                 categoryId: currentCategoryId,
+                price: currentPrice,
                 title: debouncedValue
             }
         }, { skipEmptyString: true, skipNull: true })
 
         router.push(url);
-    }, [debouncedValue, currentCategoryId, router, pathname]);
+    }, [debouncedValue, currentCategoryId, currentPrice, router, pathname]);
 
     return (
         <div className="relative">
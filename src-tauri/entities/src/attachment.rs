@@ -31,6 +31,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Course,
+    #[sea_orm(has_many = "super::note::Entity")]
+    Note,
 }
 
 impl Related<super::course::Entity> for Entity {
@@ -39,4 +41,10 @@ impl Related<super::course::Entity> for Entity {
     }
 }
 
+impl Related<super::note::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Note.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
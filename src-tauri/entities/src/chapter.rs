@@ -26,10 +26,18 @@ pub struct Model {
     pub created_at: DateTime,
     #[sea_orm(column_name = "updatedAt")]
     pub updated_at: DateTime,
+    #[sea_orm(column_name = "publishAt", nullable)]
+    pub publish_at: Option<DateTime>,
+    #[sea_orm(column_name = "unpublishAt", nullable)]
+    pub unpublish_at: Option<DateTime>,
+    #[sea_orm(column_name = "dripDays", nullable)]
+    pub drip_days: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::assignment::Entity")]
+    Assignment,
     #[sea_orm(
         belongs_to = "super::course::Entity",
         from = "Column::CourseId",
@@ -38,8 +46,38 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Course,
+    #[sea_orm(has_one = "super::chapter_draft::Entity")]
+    ChapterDraft,
+    #[sea_orm(has_many = "super::comment::Entity")]
+    Comment,
+    #[sea_orm(has_many = "super::course_search_word::Entity")]
+    CourseSearchWord,
+    #[sea_orm(has_many = "super::flashcard::Entity")]
+    Flashcard,
+    #[sea_orm(has_many = "super::note::Entity")]
+    Note,
     #[sea_orm(has_many = "super::user_progress::Entity")]
     UserProgress,
+    #[sea_orm(has_many = "super::watch_event::Entity")]
+    WatchEvent,
+}
+
+impl Related<super::assignment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Assignment.def()
+    }
+}
+
+impl Related<super::chapter_draft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ChapterDraft.def()
+    }
+}
+
+impl Related<super::comment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Comment.def()
+    }
 }
 
 impl Related<super::course::Entity> for Entity {
@@ -48,10 +86,34 @@ impl Related<super::course::Entity> for Entity {
     }
 }
 
+impl Related<super::course_search_word::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CourseSearchWord.def()
+    }
+}
+
+impl Related<super::flashcard::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Flashcard.def()
+    }
+}
+
+impl Related<super::note::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Note.def()
+    }
+}
+
 impl Related<super::user_progress::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::UserProgress.def()
     }
 }
 
+impl Related<super::watch_event::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WatchEvent.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
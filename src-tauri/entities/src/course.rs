@@ -27,6 +27,10 @@ pub struct Model {
     pub created_at: DateTime,
     #[sea_orm(column_name = "updatedAt")]
     pub updated_at: DateTime,
+    #[sea_orm(column_name = "publishAt", nullable)]
+    pub publish_at: Option<DateTime>,
+    #[sea_orm(column_name = "unpublishAt", nullable)]
+    pub unpublish_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -41,8 +45,26 @@ pub enum Relation {
         on_delete = "SetNull"
     )]
     Category,
+    #[sea_orm(has_many = "super::certificate::Entity")]
+    Certificate,
     #[sea_orm(has_many = "super::chapter::Entity")]
     Chapter,
+    #[sea_orm(has_many = "super::chapter_draft::Entity")]
+    ChapterDraft,
+    #[sea_orm(has_many = "super::coupon::Entity")]
+    Coupon,
+    #[sea_orm(has_one = "super::course_draft::Entity")]
+    CourseDraft,
+    #[sea_orm(has_many = "super::course_revision::Entity")]
+    CourseRevision,
+    #[sea_orm(has_many = "super::course_search_word::Entity")]
+    CourseSearchWord,
+    #[sea_orm(has_many = "super::deck::Entity")]
+    Deck,
+    #[sea_orm(has_many = "super::discussion_read::Entity")]
+    DiscussionRead,
+    #[sea_orm(has_many = "super::product_course::Entity")]
+    ProductCourse,
     #[sea_orm(has_many = "super::purchase::Entity")]
     Purchase,
 }
@@ -59,16 +81,79 @@ impl Related<super::category::Entity> for Entity {
     }
 }
 
+impl Related<super::certificate::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Certificate.def()
+    }
+}
+
 impl Related<super::chapter::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Chapter.def()
     }
 }
 
+impl Related<super::chapter_draft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ChapterDraft.def()
+    }
+}
+
+impl Related<super::coupon::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Coupon.def()
+    }
+}
+
+impl Related<super::course_draft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CourseDraft.def()
+    }
+}
+
+impl Related<super::course_revision::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CourseRevision.def()
+    }
+}
+
+impl Related<super::course_search_word::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CourseSearchWord.def()
+    }
+}
+
+impl Related<super::deck::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Deck.def()
+    }
+}
+
+impl Related<super::discussion_read::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DiscussionRead.def()
+    }
+}
+
+impl Related<super::product_course::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProductCourse.def()
+    }
+}
+
 impl Related<super::purchase::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Purchase.def()
     }
 }
 
+impl Related<super::product::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::product_course::Relation::Product.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::product_course::Relation::Course.def().rev())
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
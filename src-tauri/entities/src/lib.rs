@@ -2,10 +2,31 @@
 
 pub mod prelude;
 
+pub mod assignment;
 pub mod attachment;
 pub mod category;
+pub mod certificate;
 pub mod chapter;
+pub mod chapter_draft;
+pub mod comment;
+pub mod coupon;
 pub mod course;
+pub mod course_draft;
+pub mod course_revision;
+pub mod course_search_word;
+pub mod deck;
+pub mod discussion_read;
+pub mod flashcard;
+pub mod flashcard_review;
+pub mod note;
+pub mod product;
+pub mod product_course;
+pub mod product_payment;
+pub mod product_purchase;
 pub mod purchase;
+pub mod refunded_sale;
+pub mod sea_orm_active_enums;
 pub mod stripe_customer;
+pub mod submission;
 pub mod user_progress;
+pub mod watch_event;
@@ -1,9 +1,29 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.1
 
+pub use super::assignment::Entity as Assignment;
 pub use super::attachment::Entity as Attachment;
 pub use super::category::Entity as Category;
+pub use super::certificate::Entity as Certificate;
 pub use super::chapter::Entity as Chapter;
+pub use super::chapter_draft::Entity as ChapterDraft;
+pub use super::comment::Entity as Comment;
+pub use super::coupon::Entity as Coupon;
 pub use super::course::Entity as Course;
+pub use super::course_draft::Entity as CourseDraft;
+pub use super::course_revision::Entity as CourseRevision;
+pub use super::course_search_word::Entity as CourseSearchWord;
+pub use super::deck::Entity as Deck;
+pub use super::discussion_read::Entity as DiscussionRead;
+pub use super::flashcard::Entity as Flashcard;
+pub use super::flashcard_review::Entity as FlashcardReview;
+pub use super::note::Entity as Note;
+pub use super::product::Entity as Product;
+pub use super::product_course::Entity as ProductCourse;
+pub use super::product_payment::Entity as ProductPayment;
+pub use super::product_purchase::Entity as ProductPurchase;
 pub use super::purchase::Entity as Purchase;
+pub use super::refunded_sale::Entity as RefundedSale;
 pub use super::stripe_customer::Entity as StripeCustomer;
+pub use super::submission::Entity as Submission;
 pub use super::user_progress::Entity as UserProgress;
+pub use super::watch_event::Entity as WatchEvent;
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.1
 
+use super::sea_orm_active_enums::PurchaseStatus;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +18,28 @@ pub struct Model {
     pub created_at: DateTime,
     #[sea_orm(column_name = "updatedAt")]
     pub updated_at: DateTime,
+    pub status: PurchaseStatus,
+    #[sea_orm(
+        column_name = "stripeSessionId",
+        column_type = "Text",
+        nullable,
+        unique
+    )]
+    pub stripe_session_id: Option<String>,
+    pub amount: i32,
+    #[sea_orm(column_type = "Text")]
+    pub currency: String,
+    #[sea_orm(column_name = "paidAt")]
+    pub paid_at: Option<DateTime>,
+    #[sea_orm(column_name = "refundedAt")]
+    pub refunded_at: Option<DateTime>,
+    #[sea_orm(column_name = "revokedAt")]
+    pub revoked_at: Option<DateTime>,
+    #[sea_orm(column_name = "stripePaymentIntentId", column_type = "Text", nullable)]
+    pub stripe_payment_intent_id: Option<String>,
+    pub discount: i32,
+    #[sea_orm(column_name = "couponId", column_type = "Text", nullable)]
+    pub coupon_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -29,6 +52,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Course,
+    #[sea_orm(has_many = "super::refunded_sale::Entity")]
+    RefundedSale,
 }
 
 impl Related<super::course::Entity> for Entity {
@@ -37,4 +62,10 @@ impl Related<super::course::Entity> for Entity {
     }
 }
 
+impl Related<super::refunded_sale::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RefundedSale.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
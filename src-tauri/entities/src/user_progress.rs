@@ -19,6 +19,15 @@ pub struct Model {
     pub created_at: DateTime,
     #[sea_orm(column_name = "updatedAt")]
     pub updated_at: DateTime,
+    #[sea_orm(column_name = "watchedSeconds")]
+    pub watched_seconds: i32,
+    #[sea_orm(column_name = "lastPosition")]
+    pub last_position: i32,
+    pub duration: Option<i32>,
+    #[sea_orm(column_name = "watchedRanges", column_type = "Text")]
+    pub watched_ranges: String,
+    #[sea_orm(column_name = "completedAt")]
+    pub completed_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,46 @@
+pub use sea_orm_migration::prelude::*;
+
+mod m20241201_000001_create_table;
+mod m20241215_000001_create_course_revision;
+mod m20241220_000001_add_publish_schedule;
+mod m20241222_000001_add_purchase_payment;
+mod m20241224_000001_add_purchase_lifecycle;
+mod m20241226_000001_create_coupon;
+mod m20241228_000001_create_product;
+mod m20241230_000001_create_watch_event;
+mod m20250102_000001_add_watch_progress;
+mod m20250105_000001_create_certificate;
+mod m20250108_000001_create_assignment;
+mod m20250110_000001_create_comment;
+mod m20250112_000001_create_note;
+mod m20250114_000001_create_flashcard;
+mod m20250116_000001_create_search_index;
+mod m20250118_000001_create_refunded_sale;
+mod m20250120_000001_create_product_payment;
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20241201_000001_create_table::Migration),
+            Box::new(m20241215_000001_create_course_revision::Migration),
+            Box::new(m20241220_000001_add_publish_schedule::Migration),
+            Box::new(m20241222_000001_add_purchase_payment::Migration),
+            Box::new(m20241224_000001_add_purchase_lifecycle::Migration),
+            Box::new(m20241226_000001_create_coupon::Migration),
+            Box::new(m20241228_000001_create_product::Migration),
+            Box::new(m20241230_000001_create_watch_event::Migration),
+            Box::new(m20250102_000001_add_watch_progress::Migration),
+            Box::new(m20250105_000001_create_certificate::Migration),
+            Box::new(m20250108_000001_create_assignment::Migration),
+            Box::new(m20250110_000001_create_comment::Migration),
+            Box::new(m20250112_000001_create_note::Migration),
+            Box::new(m20250114_000001_create_flashcard::Migration),
+            Box::new(m20250116_000001_create_search_index::Migration),
+            Box::new(m20250118_000001_create_refunded_sale::Migration),
+            Box::new(m20250120_000001_create_product_payment::Migration),
+        ]
+    }
+}
@@ -0,0 +1,496 @@
+use sea_orm_migration::prelude::*;
+
+// Mirrors `prisma/schema.prisma`. Table, column, index and constraint names
+// follow Prisma's naming so the migration can be applied on top of a database
+// that was created with `prisma db push`.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Category::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Category::Id).text().not_null().primary_key())
+                    .col(ColumnDef::new(Category::Name).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("Category_name_key")
+                    .table(Category::Table)
+                    .col(Category::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Course::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Course::Id).text().not_null().primary_key())
+                    .col(ColumnDef::new(Course::UserId).text().not_null())
+                    .col(ColumnDef::new(Course::Title).text().not_null())
+                    .col(ColumnDef::new(Course::Description).text())
+                    .col(ColumnDef::new(Course::ImageUrl).text())
+                    .col(ColumnDef::new(Course::Price).integer())
+                    .col(
+                        ColumnDef::new(Course::IsPublished)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(Course::CategoryId).text())
+                    .col(
+                        ColumnDef::new(Course::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Course::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Course_categoryId_fkey")
+                            .from(Course::Table, Course::CategoryId)
+                            .to(Category::Table, Category::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("Course_categoryId_idx")
+                    .table(Course::Table)
+                    .col(Course::CategoryId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Attachment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Attachment::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Attachment::Name).text().not_null())
+                    .col(ColumnDef::new(Attachment::Url).text().not_null())
+                    .col(ColumnDef::new(Attachment::CourseId).text().not_null())
+                    .col(
+                        ColumnDef::new(Attachment::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Attachment::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Attachment_courseId_fkey")
+                            .from(Attachment::Table, Attachment::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("Attachment_courseId_idx")
+                    .table(Attachment::Table)
+                    .col(Attachment::CourseId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Chapter::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Chapter::Id).text().not_null().primary_key())
+                    .col(ColumnDef::new(Chapter::Title).text().not_null())
+                    .col(ColumnDef::new(Chapter::Description).text())
+                    .col(ColumnDef::new(Chapter::VideoId).text())
+                    .col(ColumnDef::new(Chapter::Position).integer().not_null())
+                    .col(
+                        ColumnDef::new(Chapter::IsPublished)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(Chapter::IsFree)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(Chapter::CourseId).text().not_null())
+                    .col(
+                        ColumnDef::new(Chapter::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Chapter::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Chapter_courseId_fkey")
+                            .from(Chapter::Table, Chapter::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("Chapter_courseId_idx")
+                    .table(Chapter::Table)
+                    .col(Chapter::CourseId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserProgress::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserProgress::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserProgress::UserId).text().not_null())
+                    .col(ColumnDef::new(UserProgress::ChapterId).text().not_null())
+                    .col(
+                        ColumnDef::new(UserProgress::IsCompleted)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(UserProgress::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(UserProgress::UpdatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("UserProgress_chapterId_fkey")
+                            .from(UserProgress::Table, UserProgress::ChapterId)
+                            .to(Chapter::Table, Chapter::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("UserProgress_userId_chapterId_key")
+                    .table(UserProgress::Table)
+                    .col(UserProgress::UserId)
+                    .col(UserProgress::ChapterId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("UserProgress_chapterId_idx")
+                    .table(UserProgress::Table)
+                    .col(UserProgress::ChapterId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Purchase::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Purchase::Id).text().not_null().primary_key())
+                    .col(ColumnDef::new(Purchase::UserId).text().not_null())
+                    .col(ColumnDef::new(Purchase::CourseId).text().not_null())
+                    .col(
+                        ColumnDef::new(Purchase::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Purchase::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Purchase_courseId_fkey")
+                            .from(Purchase::Table, Purchase::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("Purchase_userId_courseId_key")
+                    .table(Purchase::Table)
+                    .col(Purchase::UserId)
+                    .col(Purchase::CourseId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("Purchase_courseId_idx")
+                    .table(Purchase::Table)
+                    .col(Purchase::CourseId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(StripeCustomer::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(StripeCustomer::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(StripeCustomer::UserId).text().not_null())
+                    .col(
+                        ColumnDef::new(StripeCustomer::StripeCustomerId)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(StripeCustomer::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(StripeCustomer::UpdatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("StripeCustomer_userId_key")
+                    .table(StripeCustomer::Table)
+                    .col(StripeCustomer::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("StripeCustomer_stripeCustomerId_key")
+                    .table(StripeCustomer::Table)
+                    .col(StripeCustomer::StripeCustomerId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StripeCustomer::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Purchase::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(UserProgress::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Chapter::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Attachment::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Course::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Category::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Category {
+    #[sea_orm(iden = "Category")]
+    Table,
+    Id,
+    Name,
+}
+
+#[derive(DeriveIden)]
+enum Course {
+    #[sea_orm(iden = "Course")]
+    Table,
+    Id,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    Title,
+    Description,
+    #[sea_orm(iden = "imageUrl")]
+    ImageUrl,
+    Price,
+    #[sea_orm(iden = "isPublished")]
+    IsPublished,
+    #[sea_orm(iden = "categoryId")]
+    CategoryId,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Attachment {
+    #[sea_orm(iden = "Attachment")]
+    Table,
+    Id,
+    Name,
+    Url,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Chapter {
+    #[sea_orm(iden = "Chapter")]
+    Table,
+    Id,
+    Title,
+    Description,
+    #[sea_orm(iden = "videoId")]
+    VideoId,
+    Position,
+    #[sea_orm(iden = "isPublished")]
+    IsPublished,
+    #[sea_orm(iden = "isFree")]
+    IsFree,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum UserProgress {
+    #[sea_orm(iden = "UserProgress")]
+    Table,
+    Id,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "chapterId")]
+    ChapterId,
+    #[sea_orm(iden = "isCompleted")]
+    IsCompleted,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Purchase {
+    #[sea_orm(iden = "Purchase")]
+    Table,
+    Id,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+#[allow(clippy::enum_variant_names)]
+enum StripeCustomer {
+    #[sea_orm(iden = "StripeCustomer")]
+    Table,
+    Id,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "stripeCustomerId")]
+    StripeCustomerId,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
@@ -0,0 +1,213 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CourseDraft::Table)
+                    .col(
+                        ColumnDef::new(CourseDraft::CourseId)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CourseDraft::Data).text().not_null())
+                    .col(
+                        ColumnDef::new(CourseDraft::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(CourseDraft::UpdatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("CourseDraft_courseId_fkey")
+                            .from(CourseDraft::Table, CourseDraft::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ChapterDraft::Table)
+                    .col(
+                        ColumnDef::new(ChapterDraft::ChapterId)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ChapterDraft::CourseId).text().not_null())
+                    .col(ColumnDef::new(ChapterDraft::Data).text().not_null())
+                    .col(
+                        ColumnDef::new(ChapterDraft::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ChapterDraft::UpdatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("ChapterDraft_chapterId_fkey")
+                            .from(ChapterDraft::Table, ChapterDraft::ChapterId)
+                            .to(Chapter::Table, Chapter::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("ChapterDraft_courseId_fkey")
+                            .from(ChapterDraft::Table, ChapterDraft::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("ChapterDraft_courseId_idx")
+                    .table(ChapterDraft::Table)
+                    .col(ChapterDraft::CourseId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(CourseRevision::Table)
+                    .col(
+                        ColumnDef::new(CourseRevision::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CourseRevision::CourseId).text().not_null())
+                    .col(ColumnDef::new(CourseRevision::Version).integer().not_null())
+                    .col(ColumnDef::new(CourseRevision::Snapshot).text().not_null())
+                    .col(ColumnDef::new(CourseRevision::UserId).text().not_null())
+                    .col(
+                        ColumnDef::new(CourseRevision::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("CourseRevision_courseId_fkey")
+                            .from(CourseRevision::Table, CourseRevision::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("CourseRevision_courseId_version_key")
+                    .table(CourseRevision::Table)
+                    .col(CourseRevision::CourseId)
+                    .col(CourseRevision::Version)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CourseRevision::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ChapterDraft::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(CourseDraft::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Course {
+    #[sea_orm(iden = "Course")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Chapter {
+    #[sea_orm(iden = "Chapter")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum CourseDraft {
+    #[sea_orm(iden = "CourseDraft")]
+    Table,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    Data,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ChapterDraft {
+    #[sea_orm(iden = "ChapterDraft")]
+    Table,
+    #[sea_orm(iden = "chapterId")]
+    ChapterId,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    Data,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum CourseRevision {
+    #[sea_orm(iden = "CourseRevision")]
+    Table,
+    Id,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    Version,
+    Snapshot,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+}
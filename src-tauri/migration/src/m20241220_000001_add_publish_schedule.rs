@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// SQLite only accepts one column per ALTER TABLE, so every column gets its own
+// statement.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Course::PublishAt, Course::UnpublishAt] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Course::Table)
+                        .add_column(ColumnDef::new(column).timestamp().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        for column in [Chapter::PublishAt, Chapter::UnpublishAt] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Chapter::Table)
+                        .add_column(ColumnDef::new(column).timestamp().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Chapter::Table)
+                    .add_column(ColumnDef::new(Chapter::DripDays).integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Chapter::DripDays, Chapter::UnpublishAt, Chapter::PublishAt] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Chapter::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        for column in [Course::UnpublishAt, Course::PublishAt] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Course::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Course {
+    #[sea_orm(iden = "Course")]
+    Table,
+    #[sea_orm(iden = "publishAt")]
+    PublishAt,
+    #[sea_orm(iden = "unpublishAt")]
+    UnpublishAt,
+}
+
+#[derive(DeriveIden)]
+enum Chapter {
+    #[sea_orm(iden = "Chapter")]
+    Table,
+    #[sea_orm(iden = "publishAt")]
+    PublishAt,
+    #[sea_orm(iden = "unpublishAt")]
+    UnpublishAt,
+    #[sea_orm(iden = "dripDays")]
+    DripDays,
+}
@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Purchases made before checkout went through Stripe were granted straight
+// away, so existing rows are marked as paid.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Purchase::Table)
+                    .add_column(
+                        ColumnDef::new(Purchase::Status)
+                            .text()
+                            .not_null()
+                            .default("paid"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Purchase::Table)
+                    .add_column(ColumnDef::new(Purchase::StripeSessionId).text().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Purchase_stripeSessionId_key")
+                    .table(Purchase::Table)
+                    .col(Purchase::StripeSessionId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("Purchase_stripeSessionId_key")
+                    .table(Purchase::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [Purchase::StripeSessionId, Purchase::Status] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Purchase::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Purchase {
+    #[sea_orm(iden = "Purchase")]
+    Table,
+    Status,
+    #[sea_orm(iden = "stripeSessionId")]
+    StripeSessionId,
+}
@@ -0,0 +1,103 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Existing paid purchases are backfilled with the current course price and
+// treated as paid when they were created, which is the best record we have.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            ColumnDef::new(Purchase::Amount)
+                .integer()
+                .not_null()
+                .default(0)
+                .to_owned(),
+            ColumnDef::new(Purchase::Currency)
+                .text()
+                .not_null()
+                .default("vnd")
+                .to_owned(),
+            ColumnDef::new(Purchase::PaidAt)
+                .timestamp()
+                .null()
+                .to_owned(),
+            ColumnDef::new(Purchase::RefundedAt)
+                .timestamp()
+                .null()
+                .to_owned(),
+            ColumnDef::new(Purchase::RevokedAt)
+                .timestamp()
+                .null()
+                .to_owned(),
+            ColumnDef::new(Purchase::StripePaymentIntentId)
+                .text()
+                .null()
+                .to_owned(),
+        ];
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Purchase::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "Purchase"
+                SET "amount" = COALESCE(
+                        (SELECT CAST("price" AS INTEGER) FROM "Course" WHERE "Course"."id" = "Purchase"."courseId"),
+                        0
+                    ),
+                    "paidAt" = "createdAt"
+                WHERE "status" = 'paid'"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Purchase::StripePaymentIntentId,
+            Purchase::RevokedAt,
+            Purchase::RefundedAt,
+            Purchase::PaidAt,
+            Purchase::Currency,
+            Purchase::Amount,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Purchase::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Purchase {
+    #[sea_orm(iden = "Purchase")]
+    Table,
+    Amount,
+    Currency,
+    #[sea_orm(iden = "paidAt")]
+    PaidAt,
+    #[sea_orm(iden = "refundedAt")]
+    RefundedAt,
+    #[sea_orm(iden = "revokedAt")]
+    RevokedAt,
+    #[sea_orm(iden = "stripePaymentIntentId")]
+    StripePaymentIntentId,
+}
@@ -0,0 +1,167 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Purchases keep the discount they were given even if the coupon is deleted
+// later, so `Purchase.couponId` is a plain column rather than a foreign key.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Coupon::Table)
+                    .col(ColumnDef::new(Coupon::Id).text().not_null().primary_key())
+                    .col(ColumnDef::new(Coupon::Code).text().not_null())
+                    .col(ColumnDef::new(Coupon::UserId).text().not_null())
+                    .col(ColumnDef::new(Coupon::CourseId).text().null())
+                    .col(ColumnDef::new(Coupon::Kind).text().not_null())
+                    .col(ColumnDef::new(Coupon::Value).integer().not_null())
+                    .col(ColumnDef::new(Coupon::MaxRedemptions).integer().null())
+                    .col(
+                        ColumnDef::new(Coupon::Redemptions)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Coupon::ExpiresAt).timestamp().null())
+                    .col(
+                        ColumnDef::new(Coupon::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Coupon::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Coupon_courseId_fkey")
+                            .from(Coupon::Table, Coupon::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Coupon_code_key")
+                    .table(Coupon::Table)
+                    .col(Coupon::Code)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Coupon_userId_idx")
+                    .table(Coupon::Table)
+                    .col(Coupon::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Purchase::Table)
+                    .add_column(
+                        ColumnDef::new(Purchase::Discount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Purchase::Table)
+                    .add_column(ColumnDef::new(Purchase::CouponId).text().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Purchase_couponId_idx")
+                    .table(Purchase::Table)
+                    .col(Purchase::CouponId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("Purchase_couponId_idx")
+                    .table(Purchase::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [Purchase::CouponId, Purchase::Discount] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Purchase::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(Coupon::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Course {
+    #[sea_orm(iden = "Course")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Purchase {
+    #[sea_orm(iden = "Purchase")]
+    Table,
+    Discount,
+    #[sea_orm(iden = "couponId")]
+    CouponId,
+}
+
+#[derive(DeriveIden)]
+enum Coupon {
+    #[sea_orm(iden = "Coupon")]
+    Table,
+    Id,
+    Code,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    Kind,
+    Value,
+    #[sea_orm(iden = "maxRedemptions")]
+    MaxRedemptions,
+    Redemptions,
+    #[sea_orm(iden = "expiresAt")]
+    ExpiresAt,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
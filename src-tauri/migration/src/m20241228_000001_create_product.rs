@@ -0,0 +1,241 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Product::Table)
+                    .col(ColumnDef::new(Product::Id).text().not_null().primary_key())
+                    .col(ColumnDef::new(Product::UserId).text().not_null())
+                    .col(ColumnDef::new(Product::Kind).text().not_null())
+                    .col(ColumnDef::new(Product::Title).text().not_null())
+                    .col(ColumnDef::new(Product::Description).text().null())
+                    .col(ColumnDef::new(Product::Price).integer().not_null())
+                    .col(ColumnDef::new(Product::DurationDays).integer().null())
+                    .col(
+                        ColumnDef::new(Product::IsPublished)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(Product::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Product::UpdatedAt).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Product_userId_idx")
+                    .table(Product::Table)
+                    .col(Product::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProductCourse::Table)
+                    .col(ColumnDef::new(ProductCourse::ProductId).text().not_null())
+                    .col(ColumnDef::new(ProductCourse::CourseId).text().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(ProductCourse::ProductId)
+                            .col(ProductCourse::CourseId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("ProductCourse_productId_fkey")
+                            .from(ProductCourse::Table, ProductCourse::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("ProductCourse_courseId_fkey")
+                            .from(ProductCourse::Table, ProductCourse::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("ProductCourse_courseId_idx")
+                    .table(ProductCourse::Table)
+                    .col(ProductCourse::CourseId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProductPurchase::Table)
+                    .col(
+                        ColumnDef::new(ProductPurchase::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ProductPurchase::UserId).text().not_null())
+                    .col(ColumnDef::new(ProductPurchase::ProductId).text().not_null())
+                    .col(ColumnDef::new(ProductPurchase::Status).text().not_null())
+                    .col(ColumnDef::new(ProductPurchase::Amount).integer().not_null())
+                    .col(ColumnDef::new(ProductPurchase::Currency).text().not_null())
+                    .col(
+                        ColumnDef::new(ProductPurchase::StripeSessionId)
+                            .text()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ProductPurchase::StripePaymentIntentId)
+                            .text()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(ProductPurchase::PaidAt).timestamp().null())
+                    .col(ColumnDef::new(ProductPurchase::ExpiresAt).timestamp().null())
+                    .col(
+                        ColumnDef::new(ProductPurchase::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ProductPurchase::UpdatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("ProductPurchase_productId_fkey")
+                            .from(ProductPurchase::Table, ProductPurchase::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("ProductPurchase_userId_productId_key")
+                    .table(ProductPurchase::Table)
+                    .col(ProductPurchase::UserId)
+                    .col(ProductPurchase::ProductId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("ProductPurchase_stripeSessionId_key")
+                    .table(ProductPurchase::Table)
+                    .col(ProductPurchase::StripeSessionId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProductPurchase::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ProductCourse::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Product::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Course {
+    #[sea_orm(iden = "Course")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Product {
+    #[sea_orm(iden = "Product")]
+    Table,
+    Id,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    Kind,
+    Title,
+    Description,
+    Price,
+    #[sea_orm(iden = "durationDays")]
+    DurationDays,
+    #[sea_orm(iden = "isPublished")]
+    IsPublished,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ProductCourse {
+    #[sea_orm(iden = "ProductCourse")]
+    Table,
+    #[sea_orm(iden = "productId")]
+    ProductId,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+}
+
+#[derive(DeriveIden)]
+enum ProductPurchase {
+    #[sea_orm(iden = "ProductPurchase")]
+    Table,
+    Id,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "productId")]
+    ProductId,
+    Status,
+    Amount,
+    Currency,
+    #[sea_orm(iden = "stripeSessionId")]
+    StripeSessionId,
+    #[sea_orm(iden = "stripePaymentIntentId")]
+    StripePaymentIntentId,
+    #[sea_orm(iden = "paidAt")]
+    PaidAt,
+    #[sea_orm(iden = "expiresAt")]
+    ExpiresAt,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
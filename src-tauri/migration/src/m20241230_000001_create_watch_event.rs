@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WatchEvent::Table)
+                    .col(
+                        ColumnDef::new(WatchEvent::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(WatchEvent::UserId).text().not_null())
+                    .col(ColumnDef::new(WatchEvent::ChapterId).text().not_null())
+                    .col(ColumnDef::new(WatchEvent::StartSecond).integer().not_null())
+                    .col(ColumnDef::new(WatchEvent::EndSecond).integer().not_null())
+                    .col(
+                        ColumnDef::new(WatchEvent::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("WatchEvent_chapterId_fkey")
+                            .from(WatchEvent::Table, WatchEvent::ChapterId)
+                            .to(Chapter::Table, Chapter::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("WatchEvent_chapterId_userId_idx")
+                    .table(WatchEvent::Table)
+                    .col(WatchEvent::ChapterId)
+                    .col(WatchEvent::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WatchEvent::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Chapter {
+    #[sea_orm(iden = "Chapter")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WatchEvent {
+    #[sea_orm(iden = "WatchEvent")]
+    Table,
+    Id,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "chapterId")]
+    ChapterId,
+    #[sea_orm(iden = "startSecond")]
+    StartSecond,
+    #[sea_orm(iden = "endSecond")]
+    EndSecond,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+}
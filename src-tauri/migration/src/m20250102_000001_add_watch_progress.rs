@@ -0,0 +1,93 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// `watchedRanges` holds the merged `[start, end]` second ranges a student has
+// watched, as JSON. Chapters already completed are taken to have been
+// completed at their last update.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            ColumnDef::new(UserProgress::WatchedSeconds)
+                .integer()
+                .not_null()
+                .default(0)
+                .to_owned(),
+            ColumnDef::new(UserProgress::LastPosition)
+                .integer()
+                .not_null()
+                .default(0)
+                .to_owned(),
+            ColumnDef::new(UserProgress::Duration)
+                .integer()
+                .null()
+                .to_owned(),
+            ColumnDef::new(UserProgress::WatchedRanges)
+                .text()
+                .not_null()
+                .default("[]")
+                .to_owned(),
+            ColumnDef::new(UserProgress::CompletedAt)
+                .timestamp()
+                .null()
+                .to_owned(),
+        ];
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(UserProgress::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "UserProgress" SET "completedAt" = "updatedAt" WHERE "isCompleted""#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            UserProgress::CompletedAt,
+            UserProgress::WatchedRanges,
+            UserProgress::Duration,
+            UserProgress::LastPosition,
+            UserProgress::WatchedSeconds,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(UserProgress::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserProgress {
+    #[sea_orm(iden = "UserProgress")]
+    Table,
+    #[sea_orm(iden = "watchedSeconds")]
+    WatchedSeconds,
+    #[sea_orm(iden = "lastPosition")]
+    LastPosition,
+    Duration,
+    #[sea_orm(iden = "watchedRanges")]
+    WatchedRanges,
+    #[sea_orm(iden = "completedAt")]
+    CompletedAt,
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Certificate::Table)
+                    .col(
+                        ColumnDef::new(Certificate::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Certificate::Code)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Certificate::UserId).text().not_null())
+                    .col(ColumnDef::new(Certificate::CourseId).text().not_null())
+                    .col(ColumnDef::new(Certificate::StudentName).text().not_null())
+                    .col(ColumnDef::new(Certificate::CourseTitle).text().not_null())
+                    .col(ColumnDef::new(Certificate::TeacherName).text().not_null())
+                    .col(ColumnDef::new(Certificate::IssuedAt).timestamp().not_null())
+                    .col(ColumnDef::new(Certificate::Signature).text().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Certificate_courseId_fkey")
+                            .from(Certificate::Table, Certificate::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Certificate_userId_courseId_key")
+                    .table(Certificate::Table)
+                    .col(Certificate::UserId)
+                    .col(Certificate::CourseId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Certificate::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Course {
+    #[sea_orm(iden = "Course")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Certificate {
+    #[sea_orm(iden = "Certificate")]
+    Table,
+    Id,
+    Code,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    #[sea_orm(iden = "studentName")]
+    StudentName,
+    #[sea_orm(iden = "courseTitle")]
+    CourseTitle,
+    #[sea_orm(iden = "teacherName")]
+    TeacherName,
+    #[sea_orm(iden = "issuedAt")]
+    IssuedAt,
+    Signature,
+}
@@ -0,0 +1,157 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Assignment::Table)
+                    .col(
+                        ColumnDef::new(Assignment::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Assignment::ChapterId).text().not_null())
+                    .col(ColumnDef::new(Assignment::Title).text().not_null())
+                    .col(ColumnDef::new(Assignment::Instructions).text())
+                    .col(ColumnDef::new(Assignment::DueAt).timestamp())
+                    .col(ColumnDef::new(Assignment::MaxScore).integer().not_null())
+                    .col(
+                        ColumnDef::new(Assignment::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Assignment::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Assignment_chapterId_fkey")
+                            .from(Assignment::Table, Assignment::ChapterId)
+                            .to(Chapter::Table, Chapter::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Assignment_chapterId_idx")
+                    .table(Assignment::Table)
+                    .col(Assignment::ChapterId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Submission::Table)
+                    .col(
+                        ColumnDef::new(Submission::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Submission::AssignmentId).text().not_null())
+                    .col(ColumnDef::new(Submission::UserId).text().not_null())
+                    .col(ColumnDef::new(Submission::FileUrl).text())
+                    .col(ColumnDef::new(Submission::FileName).text())
+                    .col(ColumnDef::new(Submission::Answer).text())
+                    .col(ColumnDef::new(Submission::Score).integer())
+                    .col(ColumnDef::new(Submission::Feedback).text())
+                    .col(
+                        ColumnDef::new(Submission::SubmittedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Submission::GradedAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Submission_assignmentId_fkey")
+                            .from(Submission::Table, Submission::AssignmentId)
+                            .to(Assignment::Table, Assignment::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Submission_assignmentId_userId_key")
+                    .table(Submission::Table)
+                    .col(Submission::AssignmentId)
+                    .col(Submission::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Submission::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Assignment::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Chapter {
+    #[sea_orm(iden = "Chapter")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Assignment {
+    #[sea_orm(iden = "Assignment")]
+    Table,
+    Id,
+    #[sea_orm(iden = "chapterId")]
+    ChapterId,
+    Title,
+    Instructions,
+    #[sea_orm(iden = "dueAt")]
+    DueAt,
+    #[sea_orm(iden = "maxScore")]
+    MaxScore,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Submission {
+    #[sea_orm(iden = "Submission")]
+    Table,
+    Id,
+    #[sea_orm(iden = "assignmentId")]
+    AssignmentId,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "fileUrl")]
+    FileUrl,
+    #[sea_orm(iden = "fileName")]
+    FileName,
+    Answer,
+    Score,
+    Feedback,
+    #[sea_orm(iden = "submittedAt")]
+    SubmittedAt,
+    #[sea_orm(iden = "gradedAt")]
+    GradedAt,
+}
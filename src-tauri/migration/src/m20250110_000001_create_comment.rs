@@ -0,0 +1,162 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Comment::Table)
+                    .col(ColumnDef::new(Comment::Id).text().not_null().primary_key())
+                    .col(ColumnDef::new(Comment::ChapterId).text().not_null())
+                    .col(ColumnDef::new(Comment::UserId).text().not_null())
+                    .col(ColumnDef::new(Comment::ParentId).text())
+                    .col(ColumnDef::new(Comment::Body).text().not_null())
+                    .col(
+                        ColumnDef::new(Comment::IsPinned)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(Comment::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Comment::EditedAt).timestamp())
+                    .col(ColumnDef::new(Comment::DeletedAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Comment_chapterId_fkey")
+                            .from(Comment::Table, Comment::ChapterId)
+                            .to(Chapter::Table, Chapter::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Comment_parentId_fkey")
+                            .from(Comment::Table, Comment::ParentId)
+                            .to(Comment::Table, Comment::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Comment_chapterId_createdAt_idx")
+                    .table(Comment::Table)
+                    .col(Comment::ChapterId)
+                    .col(Comment::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(DiscussionRead::Table)
+                    .col(
+                        ColumnDef::new(DiscussionRead::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(DiscussionRead::UserId).text().not_null())
+                    .col(ColumnDef::new(DiscussionRead::CourseId).text().not_null())
+                    .col(
+                        ColumnDef::new(DiscussionRead::ReadAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("DiscussionRead_courseId_fkey")
+                            .from(DiscussionRead::Table, DiscussionRead::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("DiscussionRead_userId_courseId_key")
+                    .table(DiscussionRead::Table)
+                    .col(DiscussionRead::UserId)
+                    .col(DiscussionRead::CourseId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DiscussionRead::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Comment::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Course {
+    #[sea_orm(iden = "Course")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Chapter {
+    #[sea_orm(iden = "Chapter")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Comment {
+    #[sea_orm(iden = "Comment")]
+    Table,
+    Id,
+    #[sea_orm(iden = "chapterId")]
+    ChapterId,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "parentId")]
+    ParentId,
+    Body,
+    #[sea_orm(iden = "isPinned")]
+    IsPinned,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "editedAt")]
+    EditedAt,
+    #[sea_orm(iden = "deletedAt")]
+    DeletedAt,
+}
+
+#[derive(DeriveIden)]
+enum DiscussionRead {
+    #[sea_orm(iden = "DiscussionRead")]
+    Table,
+    Id,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    #[sea_orm(iden = "readAt")]
+    ReadAt,
+}
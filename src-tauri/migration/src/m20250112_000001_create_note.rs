@@ -0,0 +1,110 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Note::Table)
+                    .col(ColumnDef::new(Note::Id).text().not_null().primary_key())
+                    .col(ColumnDef::new(Note::UserId).text().not_null())
+                    .col(ColumnDef::new(Note::ChapterId).text())
+                    .col(ColumnDef::new(Note::AttachmentId).text())
+                    .col(ColumnDef::new(Note::Page).integer())
+                    .col(ColumnDef::new(Note::Highlight).text())
+                    .col(ColumnDef::new(Note::Body).text())
+                    .col(
+                        ColumnDef::new(Note::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Note::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Note_chapterId_fkey")
+                            .from(Note::Table, Note::ChapterId)
+                            .to(Chapter::Table, Chapter::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Note_attachmentId_fkey")
+                            .from(Note::Table, Note::AttachmentId)
+                            .to(Attachment::Table, Attachment::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Note_userId_chapterId_idx")
+                    .table(Note::Table)
+                    .col(Note::UserId)
+                    .col(Note::ChapterId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Note_userId_attachmentId_page_idx")
+                    .table(Note::Table)
+                    .col(Note::UserId)
+                    .col(Note::AttachmentId)
+                    .col(Note::Page)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Note::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Chapter {
+    #[sea_orm(iden = "Chapter")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Attachment {
+    #[sea_orm(iden = "Attachment")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Note {
+    #[sea_orm(iden = "Note")]
+    Table,
+    Id,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "chapterId")]
+    ChapterId,
+    #[sea_orm(iden = "attachmentId")]
+    AttachmentId,
+    Page,
+    Highlight,
+    Body,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
@@ -0,0 +1,252 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Deck::Table)
+                    .col(ColumnDef::new(Deck::Id).text().not_null().primary_key())
+                    .col(ColumnDef::new(Deck::UserId).text().not_null())
+                    .col(ColumnDef::new(Deck::CourseId).text().not_null())
+                    .col(ColumnDef::new(Deck::Title).text().not_null())
+                    .col(
+                        ColumnDef::new(Deck::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Deck::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Deck_courseId_fkey")
+                            .from(Deck::Table, Deck::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Deck_userId_courseId_idx")
+                    .table(Deck::Table)
+                    .col(Deck::UserId)
+                    .col(Deck::CourseId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Flashcard::Table)
+                    .col(
+                        ColumnDef::new(Flashcard::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Flashcard::DeckId).text().not_null())
+                    .col(ColumnDef::new(Flashcard::ChapterId).text())
+                    .col(ColumnDef::new(Flashcard::Front).text().not_null())
+                    .col(ColumnDef::new(Flashcard::Back).text().not_null())
+                    .col(
+                        ColumnDef::new(Flashcard::IsGenerated)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(Flashcard::Ease)
+                            .integer()
+                            .not_null()
+                            .default(2500),
+                    )
+                    .col(
+                        ColumnDef::new(Flashcard::Interval)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Flashcard::Repetitions)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Flashcard::DueAt).timestamp().not_null())
+                    .col(ColumnDef::new(Flashcard::LastReviewedAt).timestamp())
+                    .col(
+                        ColumnDef::new(Flashcard::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Flashcard::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Flashcard_deckId_fkey")
+                            .from(Flashcard::Table, Flashcard::DeckId)
+                            .to(Deck::Table, Deck::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("Flashcard_chapterId_fkey")
+                            .from(Flashcard::Table, Flashcard::ChapterId)
+                            .to(Chapter::Table, Chapter::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("Flashcard_deckId_dueAt_idx")
+                    .table(Flashcard::Table)
+                    .col(Flashcard::DeckId)
+                    .col(Flashcard::DueAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FlashcardReview::Table)
+                    .col(
+                        ColumnDef::new(FlashcardReview::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(FlashcardReview::FlashcardId)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FlashcardReview::UserId).text().not_null())
+                    .col(ColumnDef::new(FlashcardReview::Grade).integer().not_null())
+                    .col(
+                        ColumnDef::new(FlashcardReview::ReviewedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FlashcardReview_flashcardId_fkey")
+                            .from(FlashcardReview::Table, FlashcardReview::FlashcardId)
+                            .to(Flashcard::Table, Flashcard::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("FlashcardReview_userId_reviewedAt_idx")
+                    .table(FlashcardReview::Table)
+                    .col(FlashcardReview::UserId)
+                    .col(FlashcardReview::ReviewedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FlashcardReview::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Flashcard::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Deck::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Course {
+    #[sea_orm(iden = "Course")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Chapter {
+    #[sea_orm(iden = "Chapter")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Deck {
+    #[sea_orm(iden = "Deck")]
+    Table,
+    Id,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    Title,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Flashcard {
+    #[sea_orm(iden = "Flashcard")]
+    Table,
+    Id,
+    #[sea_orm(iden = "deckId")]
+    DeckId,
+    #[sea_orm(iden = "chapterId")]
+    ChapterId,
+    Front,
+    Back,
+    #[sea_orm(iden = "isGenerated")]
+    IsGenerated,
+    Ease,
+    Interval,
+    Repetitions,
+    #[sea_orm(iden = "dueAt")]
+    DueAt,
+    #[sea_orm(iden = "lastReviewedAt")]
+    LastReviewedAt,
+    #[sea_orm(iden = "createdAt")]
+    CreatedAt,
+    #[sea_orm(iden = "updatedAt")]
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum FlashcardReview {
+    #[sea_orm(iden = "FlashcardReview")]
+    Table,
+    Id,
+    #[sea_orm(iden = "flashcardId")]
+    FlashcardId,
+    #[sea_orm(iden = "userId")]
+    UserId,
+    Grade,
+    #[sea_orm(iden = "reviewedAt")]
+    ReviewedAt,
+}
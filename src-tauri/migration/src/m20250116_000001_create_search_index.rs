@@ -0,0 +1,127 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// `CourseSearchWord` holds every normalized word of a course's title,
+// description and category, and of its chapter titles, so search only reads
+// the courses whose words match. `Note.searchText` holds the normalized words
+// of a note. Both are filled in by the app, which also catches up on rows
+// written before this migration.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CourseSearchWord::Table)
+                    .col(
+                        ColumnDef::new(CourseSearchWord::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CourseSearchWord::CourseId).text().not_null())
+                    .col(ColumnDef::new(CourseSearchWord::ChapterId).text())
+                    .col(ColumnDef::new(CourseSearchWord::Field).text().not_null())
+                    .col(ColumnDef::new(CourseSearchWord::Word).text().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("CourseSearchWord_courseId_fkey")
+                            .from(CourseSearchWord::Table, CourseSearchWord::CourseId)
+                            .to(Course::Table, Course::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("CourseSearchWord_chapterId_fkey")
+                            .from(CourseSearchWord::Table, CourseSearchWord::ChapterId)
+                            .to(Chapter::Table, Chapter::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("CourseSearchWord_word_idx")
+                    .table(CourseSearchWord::Table)
+                    .col(CourseSearchWord::Word)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("CourseSearchWord_courseId_idx")
+                    .table(CourseSearchWord::Table)
+                    .col(CourseSearchWord::CourseId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Note::Table)
+                    .add_column(ColumnDef::new(Note::SearchText).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Note::Table)
+                    .drop_column(Note::SearchText)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(CourseSearchWord::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Course {
+    #[sea_orm(iden = "Course")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Chapter {
+    #[sea_orm(iden = "Chapter")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Note {
+    #[sea_orm(iden = "Note")]
+    Table,
+    #[sea_orm(iden = "searchText")]
+    SearchText,
+}
+
+#[derive(DeriveIden)]
+enum CourseSearchWord {
+    #[sea_orm(iden = "CourseSearchWord")]
+    Table,
+    Id,
+    #[sea_orm(iden = "courseId")]
+    CourseId,
+    #[sea_orm(iden = "chapterId")]
+    ChapterId,
+    Field,
+    Word,
+}
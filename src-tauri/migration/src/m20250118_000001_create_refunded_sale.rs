@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RefundedSale::Table)
+                    .col(
+                        ColumnDef::new(RefundedSale::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RefundedSale::PurchaseId).text().not_null())
+                    .col(ColumnDef::new(RefundedSale::Amount).integer().not_null())
+                    .col(ColumnDef::new(RefundedSale::Discount).integer().not_null())
+                    .col(ColumnDef::new(RefundedSale::CouponId).text())
+                    .col(ColumnDef::new(RefundedSale::Currency).text().not_null())
+                    .col(ColumnDef::new(RefundedSale::PaidAt).timestamp().not_null())
+                    .col(
+                        ColumnDef::new(RefundedSale::RefundedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RefundedSale::StripePaymentIntentId).text())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("RefundedSale_purchaseId_fkey")
+                            .from(RefundedSale::Table, RefundedSale::PurchaseId)
+                            .to(Purchase::Table, Purchase::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("RefundedSale_purchaseId_idx")
+                    .table(RefundedSale::Table)
+                    .col(RefundedSale::PurchaseId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RefundedSale::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Purchase {
+    #[sea_orm(iden = "Purchase")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum RefundedSale {
+    #[sea_orm(iden = "RefundedSale")]
+    Table,
+    Id,
+    #[sea_orm(iden = "purchaseId")]
+    PurchaseId,
+    Amount,
+    Discount,
+    #[sea_orm(iden = "couponId")]
+    CouponId,
+    Currency,
+    #[sea_orm(iden = "paidAt")]
+    PaidAt,
+    #[sea_orm(iden = "refundedAt")]
+    RefundedAt,
+    #[sea_orm(iden = "stripePaymentIntentId")]
+    StripePaymentIntentId,
+}
@@ -0,0 +1,107 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// A product purchase is one row however often a subscription is renewed, so
+// every payment for it is kept here. Existing purchases are backfilled with
+// their last payment, and refunds with when the purchase was last updated.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProductPayment::Table)
+                    .col(
+                        ColumnDef::new(ProductPayment::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ProductPayment::ProductPurchaseId)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ProductPayment::Amount).integer().not_null())
+                    .col(ColumnDef::new(ProductPayment::Currency).text().not_null())
+                    .col(
+                        ColumnDef::new(ProductPayment::PaidAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ProductPayment::RefundedAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(ProductPayment::StripePaymentIntentId).text())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("ProductPayment_productPurchaseId_fkey")
+                            .from(ProductPayment::Table, ProductPayment::ProductPurchaseId)
+                            .to(ProductPurchase::Table, ProductPurchase::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("ProductPayment_productPurchaseId_idx")
+                    .table(ProductPayment::Table)
+                    .col(ProductPayment::ProductPurchaseId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"INSERT INTO "ProductPayment"
+                    ("id", "productPurchaseId", "amount", "currency", "paidAt", "refundedAt", "stripePaymentIntentId")
+                SELECT "id", "id", "amount", "currency", "paidAt",
+                    CASE WHEN "status" = 'refunded' THEN "updatedAt" END,
+                    "stripePaymentIntentId"
+                FROM "ProductPurchase"
+                WHERE "paidAt" IS NOT NULL"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProductPayment::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProductPurchase {
+    #[sea_orm(iden = "ProductPurchase")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum ProductPayment {
+    #[sea_orm(iden = "ProductPayment")]
+    Table,
+    Id,
+    #[sea_orm(iden = "productPurchaseId")]
+    ProductPurchaseId,
+    Amount,
+    Currency,
+    #[sea_orm(iden = "paidAt")]
+    PaidAt,
+    #[sea_orm(iden = "refundedAt")]
+    RefundedAt,
+    #[sea_orm(iden = "stripePaymentIntentId")]
+    StripePaymentIntentId,
+}
@@ -0,0 +1,474 @@
+use std::collections::HashMap;
+
+use ::entities::{prelude::*, *};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::{
+    chrono::{NaiveDateTime, Utc},
+    Uuid,
+};
+
+use crate::{
+    entitlements,
+    media::{self, CloudinaryConfig, MediaKind},
+    validation::{UpdateError, Validator},
+};
+
+pub struct Assignments;
+
+const MAX_SCORE_LIMIT: i32 = 1000;
+
+/// Homework a teacher sets in a chapter. Students hand it in before `due_at`,
+/// if set, and are graded out of `max_score`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NewAssignment {
+    pub title: String,
+    pub instructions: Option<String>,
+    pub due_at: Option<NaiveDateTime>,
+    pub max_score: i32,
+}
+
+/// What a student hands in: a file uploaded the same way as course
+/// attachments, a written answer, or both.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NewSubmission {
+    pub file_url: Option<String>,
+    pub file_name: Option<String>,
+    pub answer: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Grade {
+    pub score: i32,
+    pub feedback: Option<String>,
+}
+
+/// An assignment with the student's own submission, if they handed one in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignmentWithSubmission {
+    #[serde(flatten)]
+    pub assignment: assignment::Model,
+    pub submission: Option<submission::Model>,
+}
+
+/// A submission waiting for a grade, with what the teacher needs to grade it
+/// from one list.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmissionToGrade {
+    #[serde(flatten)]
+    pub submission: submission::Model,
+    pub assignment_title: String,
+    pub max_score: i32,
+    pub due_at: Option<NaiveDateTime>,
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub course_id: String,
+    pub course_title: String,
+}
+
+impl Assignments {
+    pub async fn create(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+        chapter_id: String,
+        assignment: NewAssignment,
+    ) -> Result<assignment::Model, UpdateError> {
+        owned_chapter(db, &user_id, &course_id, &chapter_id).await?;
+        validate(&assignment, None)?;
+
+        let now = Utc::now().naive_utc();
+        Ok(assignment::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            chapter_id: Set(chapter_id),
+            title: Set(assignment.title.trim().to_string()),
+            instructions: Set(assignment.instructions),
+            due_at: Set(assignment.due_at),
+            max_score: Set(assignment.max_score),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await?)
+    }
+
+    /// Changes an assignment. Grades already given are kept, so lowering
+    /// `max_score` below one of them is refused.
+    pub async fn update(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+        assignment_id: String,
+        updates: NewAssignment,
+    ) -> Result<assignment::Model, UpdateError> {
+        let assignment = owned_assignment(db, &user_id, &course_id, &assignment_id).await?;
+        validate(&updates, Some(&assignment))?;
+
+        let highest_score = Submission::find()
+            .select_only()
+            .column_as(submission::Column::Score.max(), "score")
+            .filter(submission::Column::AssignmentId.eq(assignment_id))
+            .into_tuple::<Option<i32>>()
+            .one(db)
+            .await?
+            .flatten();
+        if highest_score.is_some_and(|score| score > updates.max_score) {
+            let mut validator = Validator::default();
+            validator.error(
+                "maxScore",
+                "Maximum score is lower than a grade already given",
+            );
+            validator.finish()?;
+        }
+
+        let mut assignment: assignment::ActiveModel = assignment.into();
+        assignment.title = Set(updates.title.trim().to_string());
+        assignment.instructions = Set(updates.instructions);
+        assignment.due_at = Set(updates.due_at);
+        assignment.max_score = Set(updates.max_score);
+        assignment.updated_at = Set(Utc::now().naive_utc());
+        Ok(assignment.update(db).await?)
+    }
+
+    /// Deletes an assignment with its submissions and their files.
+    pub async fn delete(
+        db: &DbConn,
+        cloudinary: &CloudinaryConfig,
+        user_id: String,
+        course_id: String,
+        assignment_id: String,
+    ) -> Result<(), DbErr> {
+        let assignment = owned_assignment(db, &user_id, &course_id, &assignment_id).await?;
+        let files = Submission::find()
+            .select_only()
+            .column(submission::Column::FileUrl)
+            .filter(submission::Column::AssignmentId.eq(assignment_id))
+            .into_tuple::<Option<String>>()
+            .all(db)
+            .await?;
+
+        assignment.delete(db).await?;
+        for url in files.into_iter().flatten() {
+            cloudinary
+                .destroy(MediaKind::Image, media::public_id(&url))
+                .await;
+        }
+        Ok(())
+    }
+
+    /// The assignments in a chapter, for the teacher editing it.
+    pub async fn list_for_teacher(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+        chapter_id: String,
+    ) -> Result<Vec<assignment::Model>, DbErr> {
+        owned_chapter(db, &user_id, &course_id, &chapter_id).await?;
+        Assignment::find()
+            .filter(assignment::Column::ChapterId.eq(chapter_id))
+            .order_by_asc(assignment::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// The assignments in a chapter with what the student handed in, once
+    /// they can open the chapter.
+    pub async fn list(
+        db: &DbConn,
+        user_id: String,
+        chapter_id: String,
+    ) -> Result<Vec<AssignmentWithSubmission>, DbErr> {
+        let chapter = entitlements::accessible_chapter(db, &user_id, &chapter_id).await?;
+        with_submissions(db, &user_id, vec![chapter.id]).await
+    }
+
+    /// Hands in `submission`, replacing any earlier one until the due date.
+    /// A new submission goes back to the teacher for grading.
+    pub async fn submit(
+        db: &DbConn,
+        cloudinary: &CloudinaryConfig,
+        user_id: String,
+        assignment_id: String,
+        submission: NewSubmission,
+    ) -> Result<submission::Model, UpdateError> {
+        let assignment = Assignment::find_by_id(assignment_id.clone())
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find assignment".into()))?;
+        entitlements::accessible_chapter(db, &user_id, &assignment.chapter_id).await?;
+
+        let now = Utc::now().naive_utc();
+        if assignment.due_at.is_some_and(|due_at| due_at < now) {
+            return Err(DbErr::Custom("The due date has passed".into()).into());
+        }
+
+        let file_url = non_empty(submission.file_url);
+        let answer = non_empty(submission.answer);
+        if file_url.is_none() && answer.is_none() {
+            let mut validator = Validator::default();
+            validator.error("answer", "Attach a file or write an answer");
+            validator.finish()?;
+        }
+        let file_name = file_url.as_ref().map(|url| {
+            non_empty(submission.file_name)
+                .unwrap_or_else(|| url.rsplit('/').next().unwrap_or_default().to_string())
+        });
+
+        let existing = Submission::find()
+            .filter(submission::Column::AssignmentId.eq(assignment_id.clone()))
+            .filter(submission::Column::UserId.eq(user_id.clone()))
+            .one(db)
+            .await?;
+        let replaced_file = existing
+            .as_ref()
+            .and_then(|existing| existing.file_url.clone())
+            .filter(|url| Some(url) != file_url.as_ref());
+
+        let saved = submission::ActiveModel {
+            id: Set(existing.as_ref().map_or_else(
+                || Uuid::new_v4().to_string(),
+                |existing| existing.id.clone(),
+            )),
+            assignment_id: Set(assignment_id),
+            user_id: Set(user_id),
+            file_url: Set(file_url),
+            file_name: Set(file_name),
+            answer: Set(answer),
+            score: Set(None),
+            feedback: Set(None),
+            submitted_at: Set(now),
+            graded_at: Set(None),
+        };
+        let saved = match existing {
+            Some(_) => saved.update(db).await?,
+            None => saved.insert(db).await?,
+        };
+
+        if let Some(url) = replaced_file {
+            cloudinary
+                .destroy(MediaKind::Image, media::public_id(&url))
+                .await;
+        }
+        Ok(saved)
+    }
+
+    /// Submissions in the teacher's courses still waiting for a grade, oldest
+    /// first.
+    pub async fn ungraded(db: &DbConn, user_id: String) -> Result<Vec<SubmissionToGrade>, DbErr> {
+        let courses: HashMap<String, course::Model> = Course::find()
+            .filter(course::Column::UserId.eq(user_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|course| (course.id.clone(), course))
+            .collect();
+        let chapters: HashMap<String, chapter::Model> = Chapter::find()
+            .filter(chapter::Column::CourseId.is_in(courses.keys().cloned()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|chapter| (chapter.id.clone(), chapter))
+            .collect();
+        let assignments: HashMap<String, assignment::Model> = Assignment::find()
+            .filter(assignment::Column::ChapterId.is_in(chapters.keys().cloned()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|assignment| (assignment.id.clone(), assignment))
+            .collect();
+        let submissions = Submission::find()
+            .filter(submission::Column::AssignmentId.is_in(assignments.keys().cloned()))
+            .filter(submission::Column::Score.is_null())
+            .order_by_asc(submission::Column::SubmittedAt)
+            .all(db)
+            .await?;
+
+        Ok(submissions
+            .into_iter()
+            .filter_map(|submission| {
+                let assignment = assignments.get(&submission.assignment_id)?;
+                let chapter = chapters.get(&assignment.chapter_id)?;
+                let course = courses.get(&chapter.course_id)?;
+                Some(SubmissionToGrade {
+                    assignment_title: assignment.title.clone(),
+                    max_score: assignment.max_score,
+                    due_at: assignment.due_at,
+                    chapter_id: chapter.id.clone(),
+                    chapter_title: chapter.title.clone(),
+                    course_id: course.id.clone(),
+                    course_title: course.title.clone(),
+                    submission,
+                })
+            })
+            .collect())
+    }
+
+    /// Grades a submission in one of the teacher's courses. Grading again
+    /// replaces the earlier grade.
+    pub async fn grade(
+        db: &DbConn,
+        user_id: String,
+        submission_id: String,
+        grade: Grade,
+    ) -> Result<submission::Model, UpdateError> {
+        let not_found = || DbErr::RecordNotFound("Cannot find submission".into());
+        let (submission, assignment) = Submission::find_by_id(submission_id)
+            .find_also_related(Assignment)
+            .one(db)
+            .await?
+            .ok_or_else(not_found)?;
+        let assignment = assignment.ok_or_else(not_found)?;
+        let owned = Chapter::find_by_id(assignment.chapter_id.clone())
+            .inner_join(Course)
+            .filter(course::Column::UserId.eq(user_id))
+            .one(db)
+            .await?;
+        if owned.is_none() {
+            return Err(not_found().into());
+        }
+
+        let mut validator = Validator::default();
+        if !(0..=assignment.max_score).contains(&grade.score) {
+            validator.error(
+                "score",
+                &format!("Score must be between 0 and {}", assignment.max_score),
+            );
+        }
+        validator.finish()?;
+
+        let mut submission: submission::ActiveModel = submission.into();
+        submission.score = Set(Some(grade.score));
+        submission.feedback = Set(non_empty(grade.feedback));
+        submission.graded_at = Set(Some(Utc::now().naive_utc()));
+        Ok(submission.update(db).await?)
+    }
+
+    /// Every assignment in the published chapters of a course with the
+    /// student's submission and grade, in chapter order.
+    pub async fn grades(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<Vec<AssignmentWithSubmission>, DbErr> {
+        let chapter_ids = Chapter::find()
+            .select_only()
+            .column(chapter::Column::Id)
+            .filter(chapter::Column::CourseId.eq(course_id))
+            .filter(chapter::Column::IsPublished.eq(true))
+            .order_by_asc(chapter::Column::Position)
+            .into_tuple::<String>()
+            .all(db)
+            .await?;
+        with_submissions(db, &user_id, chapter_ids).await
+    }
+}
+
+/// The assignments in `chapter_ids`, in that order, each with the student's
+/// submission.
+async fn with_submissions(
+    db: &DbConn,
+    user_id: &str,
+    chapter_ids: Vec<String>,
+) -> Result<Vec<AssignmentWithSubmission>, DbErr> {
+    let mut assignments = Assignment::find()
+        .filter(assignment::Column::ChapterId.is_in(chapter_ids.clone()))
+        .order_by_asc(assignment::Column::CreatedAt)
+        .all(db)
+        .await?;
+    let position: HashMap<&String, usize> = chapter_ids.iter().zip(0..).collect();
+    assignments.sort_by_key(|assignment| position.get(&assignment.chapter_id).copied());
+
+    let mut submissions: HashMap<String, submission::Model> = Submission::find()
+        .filter(submission::Column::UserId.eq(user_id))
+        .filter(
+            submission::Column::AssignmentId
+                .is_in(assignments.iter().map(|assignment| assignment.id.clone())),
+        )
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|submission| (submission.assignment_id.clone(), submission))
+        .collect();
+
+    Ok(assignments
+        .into_iter()
+        .map(|assignment| AssignmentWithSubmission {
+            submission: submissions.remove(&assignment.id),
+            assignment,
+        })
+        .collect())
+}
+
+async fn owned_chapter(
+    db: &DbConn,
+    user_id: &str,
+    course_id: &str,
+    chapter_id: &str,
+) -> Result<chapter::Model, DbErr> {
+    let owned_course = Course::find_by_id(course_id)
+        .filter(course::Column::UserId.eq(user_id))
+        .one(db)
+        .await?;
+    if owned_course.is_none() {
+        return Err(DbErr::RecordNotFound("Cannot find course".into()));
+    }
+
+    Chapter::find_by_id(chapter_id)
+        .filter(chapter::Column::CourseId.eq(course_id))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find chapter".into()))
+}
+
+async fn owned_assignment(
+    db: &DbConn,
+    user_id: &str,
+    course_id: &str,
+    assignment_id: &str,
+) -> Result<assignment::Model, DbErr> {
+    let not_found = || DbErr::RecordNotFound("Cannot find assignment".into());
+    let assignment = Assignment::find_by_id(assignment_id)
+        .one(db)
+        .await?
+        .ok_or_else(not_found)?;
+    owned_chapter(db, user_id, course_id, &assignment.chapter_id)
+        .await
+        .map_err(|_| not_found())?;
+    Ok(assignment)
+}
+
+/// Checks `assignment`. A due date in the past is only refused when it
+/// changes, so an assignment that is over can still be edited.
+fn validate(
+    assignment: &NewAssignment,
+    current: Option<&assignment::Model>,
+) -> Result<(), UpdateError> {
+    let mut validator = Validator::default();
+    validator.title("title", &Some(assignment.title.clone()));
+    if !(1..=MAX_SCORE_LIMIT).contains(&assignment.max_score) {
+        validator.error(
+            "maxScore",
+            &format!("Maximum score must be between 1 and {}", MAX_SCORE_LIMIT),
+        );
+    }
+    let due_at_changed = current.map_or(true, |current| current.due_at != assignment.due_at);
+    if due_at_changed
+        && assignment
+            .due_at
+            .is_some_and(|due_at| due_at <= Utc::now().naive_utc())
+    {
+        validator.error("dueAt", "Due date must be in the future");
+    }
+    validator.finish()
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
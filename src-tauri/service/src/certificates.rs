@@ -0,0 +1,321 @@
+use ::entities::{prelude::*, *};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Timelike;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::{
+    chrono::{NaiveDateTime, Utc},
+    Uuid,
+};
+
+use crate::{entitlements, loaders};
+
+pub struct Certificates;
+
+// Bumped if the signed fields ever change, so old signatures stop matching
+// instead of being checked against the wrong bytes.
+const PAYLOAD_VERSION: u32 = 1;
+
+/// How certificates are issued and checked. Every install knows the public
+/// key, but only the issuer's install holds the signing key: the others record
+/// certificates for it to sign with [`Certificates::sign_pending`].
+#[derive(Debug, Clone)]
+pub struct CertificateSettings {
+    /// Hex ed25519 public key certificates are checked against.
+    pub public_key: String,
+    /// Hex ed25519 private key, the 32 byte seed.
+    pub signing_key: Option<String>,
+    /// Printed on every certificate as the one who awards it.
+    pub teacher_name: String,
+}
+
+/// The part of a certificate its signature covers: everything printed on it
+/// plus who it was issued to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificatePayload {
+    pub version: u32,
+    pub code: String,
+    pub user_id: String,
+    pub course_id: String,
+    pub student_name: String,
+    pub course_title: String,
+    pub teacher_name: String,
+    pub issued_at: NaiveDateTime,
+}
+
+impl From<&certificate::Model> for CertificatePayload {
+    fn from(certificate: &certificate::Model) -> Self {
+        CertificatePayload {
+            version: PAYLOAD_VERSION,
+            code: certificate.code.clone(),
+            user_id: certificate.user_id.clone(),
+            course_id: certificate.course_id.clone(),
+            student_name: certificate.student_name.clone(),
+            course_title: certificate.course_title.clone(),
+            teacher_name: certificate.teacher_name.clone(),
+            issued_at: certificate.issued_at,
+        }
+    }
+}
+
+/// Outcome of [`Certificates::verify`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum Verification {
+    Valid {
+        certificate: CertificatePayload,
+    },
+    /// The token reads as a certificate but was not signed by the issuer, or
+    /// was changed after.
+    Tampered,
+    /// The token is not a certificate at all.
+    Malformed,
+}
+
+impl CertificatePayload {
+    /// Fields are serialized in declaration order, so the same certificate
+    /// always signs the same bytes.
+    fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Payload serializes")
+    }
+}
+
+/// Checks that `public_key` can verify certificates and, when given, that
+/// `signing_key` is its private half.
+pub fn check_keys(public_key: &str, signing_key: Option<&str>) -> Result<(), String> {
+    let public_key = verifying_key(public_key)?;
+    if let Some(signing_key) = signing_key {
+        if self::signing_key(signing_key)?.verifying_key() != public_key {
+            return Err("The signing key does not belong to the public key".into());
+        }
+    }
+    Ok(())
+}
+
+impl Certificates {
+    /// Issues the certificate for a course the student has finished, or
+    /// returns the one they already have. Names are copied into the
+    /// certificate so renaming the course later does not change it. The
+    /// certificate is only signed right away on the issuer's install;
+    /// elsewhere its signature stays empty until the issuer signs it.
+    pub async fn issue(
+        db: &DbConn,
+        settings: &CertificateSettings,
+        user_id: String,
+        student_name: String,
+        course_id: String,
+    ) -> Result<certificate::Model, DbErr> {
+        if let Some(existing) = Self::get(db, user_id.clone(), course_id.clone()).await? {
+            return Ok(existing);
+        }
+
+        let now = Utc::now().naive_utc();
+        let course = Course::find_by_id(course_id.clone())
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find course".into()))?;
+        check_finished(db, &user_id, &course_id, now).await?;
+
+        let student_name = student_name.trim().to_string();
+        if student_name.is_empty() {
+            return Err(DbErr::Custom(
+                "Enter the name to print on the certificate".into(),
+            ));
+        }
+
+        let payload = CertificatePayload {
+            version: PAYLOAD_VERSION,
+            code: new_code(),
+            user_id,
+            course_id,
+            student_name,
+            course_title: course.title,
+            teacher_name: settings.teacher_name.clone(),
+            // Stored timestamps lose anything below a second on some
+            // databases, which would break the signature
+            issued_at: now.with_nanosecond(0).unwrap_or(now),
+        };
+        let signature = match &settings.signing_key {
+            Some(key) => sign(&payload, key)?,
+            None => String::new(),
+        };
+
+        certificate::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            code: Set(payload.code),
+            user_id: Set(payload.user_id),
+            course_id: Set(payload.course_id),
+            student_name: Set(payload.student_name),
+            course_title: Set(payload.course_title),
+            teacher_name: Set(payload.teacher_name),
+            issued_at: Set(payload.issued_at),
+            signature: Set(signature),
+        }
+        .insert(db)
+        .await
+    }
+
+    /// Signs the certificates other installs recorded, on the install that
+    /// holds the signing key. Anyone who can reach the database can record a
+    /// certificate, so each student's progress is checked again first and
+    /// the issuer's own teacher name is the one signed. Returns how many were
+    /// signed.
+    pub async fn sign_pending(db: &DbConn, settings: &CertificateSettings) -> Result<usize, DbErr> {
+        let Some(key) = &settings.signing_key else {
+            return Ok(0);
+        };
+
+        let now = Utc::now().naive_utc();
+        let pending = Certificate::find()
+            .filter(certificate::Column::Signature.eq(""))
+            .all(db)
+            .await?;
+        let mut signed = 0;
+        for certificate in pending {
+            if let Err(err) =
+                check_finished(db, &certificate.user_id, &certificate.course_id, now).await
+            {
+                tracing::debug!("Not signing certificate {}: {:?}", certificate.code, err);
+                continue;
+            }
+
+            let mut payload = CertificatePayload::from(&certificate);
+            payload.teacher_name = settings.teacher_name.clone();
+            let signature = sign(&payload, key)?;
+            certificate::ActiveModel {
+                teacher_name: Set(payload.teacher_name),
+                signature: Set(signature),
+                ..certificate.into()
+            }
+            .update(db)
+            .await?;
+            signed += 1;
+        }
+
+        Ok(signed)
+    }
+
+    pub async fn get(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<Option<certificate::Model>, DbErr> {
+        Certificate::find()
+            .filter(certificate::Column::UserId.eq(user_id))
+            .filter(certificate::Column::CourseId.eq(course_id))
+            .one(db)
+            .await
+    }
+
+    /// Every certificate the student has earned, newest first.
+    pub async fn list(db: &DbConn, user_id: String) -> Result<Vec<certificate::Model>, DbErr> {
+        Certificate::find()
+            .filter(certificate::Column::UserId.eq(user_id))
+            .order_by_desc(certificate::Column::IssuedAt)
+            .all(db)
+            .await
+    }
+
+    /// What gets printed on the certificate for anyone to check it: the
+    /// signed payload and its signature. `None` until the issuer signed it.
+    pub fn token(certificate: &certificate::Model) -> Option<String> {
+        if certificate.signature.is_empty() {
+            return None;
+        }
+        let payload = CertificatePayload::from(certificate).to_bytes();
+        Some(format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(payload),
+            certificate.signature
+        ))
+    }
+
+    /// Checks a [`Certificates::token`] against the issuer's public key.
+    /// Everything needed is in the token, so this works without the database
+    /// or a network connection.
+    pub fn verify(public_key: &str, token: &str) -> Result<Verification, DbErr> {
+        let public_key = verifying_key(public_key).map_err(DbErr::Custom)?;
+
+        let Some((payload, signature)) = token.trim().split_once('.') else {
+            return Ok(Verification::Malformed);
+        };
+        let (Ok(payload), Ok(signature)) = (
+            URL_SAFE_NO_PAD.decode(payload),
+            URL_SAFE_NO_PAD.decode(signature),
+        ) else {
+            return Ok(Verification::Malformed);
+        };
+        let Ok(signature) = Signature::from_slice(&signature) else {
+            return Ok(Verification::Malformed);
+        };
+
+        if public_key.verify_strict(&payload, &signature).is_err() {
+            return Ok(Verification::Tampered);
+        }
+        match serde_json::from_slice::<CertificatePayload>(&payload) {
+            Ok(certificate) if certificate.version == PAYLOAD_VERSION => {
+                Ok(Verification::Valid { certificate })
+            }
+            _ => Ok(Verification::Malformed),
+        }
+    }
+}
+
+// Certificates are only for students who can still open the course and have
+// finished every published chapter and assignment in it.
+async fn check_finished(
+    db: &DbConn,
+    user_id: &str,
+    course_id: &str,
+    now: NaiveDateTime,
+) -> Result<(), DbErr> {
+    if entitlements::entitlement(db, user_id, course_id, now)
+        .await?
+        .is_none()
+    {
+        return Err(DbErr::RecordNotFound("Cannot find course".into()));
+    }
+
+    let chapters = loaders::published_chapters(db, &[course_id.to_string()])
+        .await?
+        .remove(course_id)
+        .unwrap_or_default();
+    let chapter_ids: Vec<String> = chapters.into_iter().map(|chapter| chapter.id).collect();
+    let items = loaders::ProgressItems::load(db, user_id, &chapter_ids).await?;
+    if items.percent(&chapter_ids) < 100 {
+        return Err(DbErr::Custom(
+            "Finish every chapter and assignment to get a certificate".into(),
+        ));
+    }
+    Ok(())
+}
+
+// Base64 ed25519 signature of the payload.
+fn sign(payload: &CertificatePayload, key: &str) -> Result<String, DbErr> {
+    let key = signing_key(key).map_err(DbErr::Custom)?;
+    Ok(URL_SAFE_NO_PAD.encode(key.sign(&payload.to_bytes()).to_bytes()))
+}
+
+fn signing_key(key: &str) -> Result<SigningKey, String> {
+    let bytes: [u8; 32] = hex::decode(key.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("The certificate signing key is not a 32 byte hex key")?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+fn verifying_key(key: &str) -> Result<VerifyingKey, String> {
+    hex::decode(key.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or("The certificate public key is not a valid ed25519 key".into())
+}
+
+/// A code such as `3F9A-C210-7B4E`, short enough to read off a printout.
+fn new_code() -> String {
+    let hex = Uuid::new_v4().simple().to_string().to_uppercase();
+    format!("{}-{}-{}", &hex[0..4], &hex[4..8], &hex[8..12])
+}
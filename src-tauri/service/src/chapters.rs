@@ -1,11 +1,24 @@
-use std::env;
+use std::collections::HashMap;
 
 use ::entities::{prelude::*, *};
-use cloudinary::upload::Upload;
 use sea_orm::*;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use sqlx::types::{chrono::Utc, Uuid};
+use sqlx::types::{
+    chrono::{NaiveDateTime, Utc},
+    Uuid,
+};
+
+use crate::{
+    entitlements::{self, Entitlement},
+    media::{CloudinaryConfig, MediaKind},
+    progress::{self, ProgressUpdate, WatchHeartbeat, WatchSettings, WatchThrottle},
+    revisions, schedule, search,
+    validation::{UpdateError, Validator},
+};
+
+/// Heartbeats continuing the previous one within this many seconds extend
+/// its watch event instead of adding a new one.
+const WATCH_EVENT_WINDOW: i64 = 60;
 
 pub struct Chapters;
 
@@ -22,7 +35,11 @@ pub struct ChapterDetails {
     attachments: Option<Vec<attachment::Model>>,
     next_chapter: Option<chapter::Model>,
     user_progress: Option<user_progress::Model>,
-    purchase: Option<purchase::Model>,
+    /// How the student got access to the course, if they have it.
+    entitlement: Option<Entitlement>,
+    /// Set while the chapter is still dripping for this student. The video is
+    /// withheld until then.
+    available_at: Option<NaiveDateTime>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -32,6 +49,58 @@ pub struct ReorderData {
     position: i32,
 }
 
+/// Fields a teacher can change on a chapter. Missing fields are left untouched.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ChapterUpdate {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub video_id: Option<String>,
+    pub is_free: Option<bool>,
+}
+
+impl ChapterUpdate {
+    /// Reads an update as sent by the client. Unknown keys and values of the
+    /// wrong type become field errors.
+    pub fn from_json(value: serde_json::Value) -> Result<ChapterUpdate, UpdateError> {
+        let mut validator = Validator::default();
+        let mut fields = validator.object(value, &["title", "description", "videoId", "isFree"]);
+        let updates = ChapterUpdate {
+            title: validator.field(&mut fields, "title", "text"),
+            description: validator.field(&mut fields, "description", "text"),
+            video_id: validator.field(&mut fields, "videoId", "text"),
+            is_free: validator.field(&mut fields, "isFree", "true or false"),
+        };
+        validator.finish()?;
+        Ok(updates)
+    }
+
+    /// Layers `newer` on top of `self`, keeping fields `newer` leaves out.
+    pub(crate) fn merge(self, newer: ChapterUpdate) -> ChapterUpdate {
+        ChapterUpdate {
+            title: newer.title.or(self.title),
+            description: newer.description.or(self.description),
+            video_id: newer.video_id.or(self.video_id),
+            is_free: newer.is_free.or(self.is_free),
+        }
+    }
+
+    pub(crate) fn apply_to(&self, chapter: &mut chapter::Model) {
+        if let Some(title) = &self.title {
+            chapter.title = title.trim().to_string();
+        }
+        if let Some(description) = &self.description {
+            chapter.description = Some(description.clone());
+        }
+        if let Some(video_id) = &self.video_id {
+            chapter.video_id = Some(video_id.clone());
+        }
+        if let Some(is_free) = self.is_free {
+            chapter.is_free = is_free;
+        }
+    }
+}
+
 impl Chapters {
     pub async fn get(
         db: &DbConn,
@@ -39,13 +108,8 @@ impl Chapters {
         course_id: String,
         chapter_id: String,
     ) -> Result<ChapterDetails, DbErr> {
-        println!("1");
-        let purchase = purchase::Entity::find()
-            .filter(purchase::Column::UserId.eq(user_id.clone())) // Filter by userId
-            .filter(purchase::Column::CourseId.eq(course_id.clone())) // Filter by courseId
-            .one(db) // Retrieve one record
-            .await?;
-        println!("2");
+        let now = Utc::now().naive_utc();
+        let entitlement = entitlements::entitlement(db, &user_id, &course_id, now).await?;
         let course_price = Course::find_by_id(course_id.clone())
             .filter(course::Column::IsPublished.eq(true))
             .select_only()
@@ -53,22 +117,26 @@ impl Chapters {
             .into_model::<ChapterPrice>()
             .one(db)
             .await?;
-        println!("3");
         let chapter = Chapter::find_by_id(chapter_id.clone())
+            .filter(chapter::Column::CourseId.eq(course_id.clone()))
             .filter(chapter::Column::IsPublished.eq(true))
             .one(db)
             .await?;
-        println!("4");
         if chapter.is_none() || course_price.is_none() {
             return Err(DbErr::Custom("Chapter or course not found".into()));
         }
-        println!("5");
-        let chapter = chapter.unwrap();
-        println!("6");
+        let mut chapter = chapter.unwrap();
+        let granted_at = entitlement
+            .as_ref()
+            .map(|entitlement| entitlement.granted_at);
+        let available_at = schedule::locked_until(&chapter, granted_at, now);
+        if schedule::is_locked(&chapter, granted_at, now) {
+            chapter.video_id = None;
+        }
         let mut attachments: Option<Vec<attachment::Model>> = Some(Vec::new());
         let mut next_chapter: Option<chapter::Model> = None;
 
-        if purchase.is_some() {
+        if entitlement.is_some() {
             attachments = Some(
                 Attachment::find()
                     .filter(attachment::Column::CourseId.eq(course_id.clone()))
@@ -76,8 +144,7 @@ impl Chapters {
                     .await?,
             );
         }
-        println!("7");
-        if chapter.is_free || purchase.is_some() {
+        if chapter.is_free || entitlement.is_some() {
             next_chapter = Chapter::find()
                 .filter(chapter::Column::CourseId.eq(course_id.clone()))
                 .filter(chapter::Column::IsPublished.eq(true))
@@ -86,25 +153,25 @@ impl Chapters {
                 .one(db)
                 .await?;
         }
-        println!("8");
         let user_progress = UserProgress::find()
             .filter(user_progress::Column::ChapterId.eq(chapter_id.clone()))
             .filter(user_progress::Column::UserId.eq(user_id.clone()))
             .one(db)
             .await?;
-        println!("9");
         Ok(ChapterDetails {
             chapter: Some(chapter),
             course_price,
             attachments,
             next_chapter,
             user_progress,
-            purchase,
+            entitlement,
+            available_at,
         })
     }
 
     pub async fn delete(
         db: &DbConn,
+        cloudinary: &CloudinaryConfig,
         user_id: String,
         course_id: String,
         chapter_id: String,
@@ -130,17 +197,9 @@ impl Chapters {
         };
 
         if chapter.video_id.is_some() {
-            let api_key =
-                env::var("NEXT_PUBLIC_CLOUDINARY_API_KEY").expect("Cannot get Cloudinary api key");
-            let cloud_name = env::var("NEXT_PUBLIC_CLOUDINARY_CLOUD_NAME")
-                .expect("Cannot get Cloudinary cloud name");
-            let api_secret =
-                env::var("CLOUDINARY_API_SECRET").expect("Cannot get Cloudinary api secret");
-            let upload = Upload::new(api_key, cloud_name, api_secret);
-            let _ = upload
-                .destroy(chapter.clone().video_id.unwrap())
-                .await
-                .unwrap();
+            cloudinary
+                .destroy(MediaKind::Video, chapter.clone().video_id.unwrap())
+                .await;
         }
 
         chapter.delete(db).await?;
@@ -191,15 +250,7 @@ impl Chapters {
             _ => return Err(DbErr::RecordNotFound("Cannot find chapter".into())),
         };
 
-        if chapter.description.is_none() || chapter.video_id.is_none() {
-            return Err(DbErr::AttrNotSet("Missing required field".into()));
-        }
-
-        let mut chapter: chapter::ActiveModel = chapter.into();
-        chapter.is_published = Set(true);
-        chapter.update(db).await?;
-
-        Ok(())
+        publish_chapter(db, chapter).await
     }
 
     pub async fn unpublish(
@@ -223,63 +274,142 @@ impl Chapters {
             .one(db)
             .await?;
 
-        let mut chapter: chapter::ActiveModel = match chapter {
-            Some(chapter) => chapter.into(),
-            _ => return Err(DbErr::RecordNotFound("Cannot find chapter".into())),
-        };
-        chapter.is_published = Set(false);
-        chapter.update(db).await?;
-
-        let published_chapters_in_course = Chapter::find()
-            .filter(chapter::Column::CourseId.eq(course_id.clone()))
-            .filter(chapter::Column::IsPublished.eq(true))
-            .all(db)
-            .await?;
-
-        if published_chapters_in_course.len() == 0 {
-            let mut update_course: course::ActiveModel = Course::find_by_id(course_id.clone())
-                .one(db)
-                .await?
-                .unwrap()
-                .into();
-
-            update_course.is_published = Set(false);
-            update_course.update(db).await?;
+        match chapter {
+            Some(chapter) => unpublish_chapter(db, chapter).await,
+            _ => Err(DbErr::RecordNotFound("Cannot find chapter".into())),
         }
-
-        Ok(())
     }
 
+    /// Applies `update` to the student's progress in a chapter, creating it
+    /// on first use. Watching `settings.complete_at_percent` of the video
+    /// completes the chapter.
     pub async fn update_progress(
         db: &DbConn,
         user_id: String,
         chapter_id: String,
-        is_completed: bool,
-    ) -> Result<(), DbErr> {
-        if let Some(existing_progress) = UserProgress::find()
+        update: ProgressUpdate,
+        settings: &WatchSettings,
+    ) -> Result<user_progress::Model, DbErr> {
+        let now = Utc::now().naive_utc();
+        let existing = UserProgress::find()
             .filter(user_progress::Column::UserId.eq(user_id.clone()))
             .filter(user_progress::Column::ChapterId.eq(chapter_id.clone()))
             .one(db)
-            .await?
-        {
-            let mut existing_progress: user_progress::ActiveModel = existing_progress.into();
-            existing_progress.is_completed = Set(is_completed.into());
-            existing_progress.updated_at = Set(Utc::now().naive_utc());
-            existing_progress.update(db).await?;
-        } else {
-            let new_progress = user_progress::ActiveModel {
-                id: Set(Uuid::new_v4().to_string()),
-                user_id: Set(user_id.clone()),
-                chapter_id: Set(chapter_id.clone()),
-                is_completed: Set(is_completed.clone()),
-                created_at: Set(Utc::now().naive_utc()),
-                updated_at: Set(Utc::now().naive_utc()),
-                ..Default::default()
-            };
-            new_progress.insert(db).await?;
+            .await?;
+        let mut progress = existing.clone().unwrap_or(user_progress::Model {
+            id: Uuid::new_v4().to_string(),
+            user_id,
+            chapter_id,
+            is_completed: false,
+            created_at: now,
+            updated_at: now,
+            watched_seconds: 0,
+            last_position: 0,
+            duration: None,
+            watched_ranges: "[]".into(),
+            completed_at: None,
+        });
+        let was_completed = progress.is_completed;
+
+        if let Some(heartbeat) = update.heartbeat {
+            let duration = heartbeat.duration.or(progress.duration);
+            let end_second = duration.map_or(heartbeat.end_second, |duration| {
+                heartbeat.end_second.min(duration)
+            });
+            let mut ranges: Vec<[i32; 2]> =
+                serde_json::from_str(&progress.watched_ranges).unwrap_or_default();
+            if end_second > heartbeat.start_second {
+                progress::merge_range(&mut ranges, [heartbeat.start_second, end_second]);
+            }
+
+            progress.watched_seconds = progress::watched_seconds(&ranges);
+            progress.watched_ranges = serde_json::to_string(&ranges).unwrap();
+            progress.last_position = heartbeat.position;
+            progress.duration = duration;
+            if duration.is_some_and(|duration| {
+                i64::from(progress.watched_seconds) * 100
+                    >= i64::from(settings.complete_at_percent) * i64::from(duration)
+            }) {
+                progress.is_completed = true;
+            }
+        }
+        if let Some(is_completed) = update.is_completed {
+            progress.is_completed = is_completed;
         }
 
-        Ok(())
+        progress.completed_at = match (was_completed, progress.is_completed) {
+            (_, false) => None,
+            (false, true) => Some(now),
+            (true, true) => progress.completed_at.or(Some(now)),
+        };
+        progress.updated_at = now;
+
+        let progress = user_progress::ActiveModel::from(progress);
+        match existing {
+            Some(_) => progress.reset_all().update(db).await,
+            None => progress.reset_all().insert(db).await,
+        }
+    }
+
+    /// Takes a heartbeat from the video player: logs the watched range for
+    /// [`crate::Teacher::course_engagement`] and moves the student's progress
+    /// along, so they can resume from `heartbeat.position`. Heartbeats coming
+    /// faster than `throttle` allows are held back and merged, in which case
+    /// nothing is written yet and `None` is returned.
+    pub async fn report_watch(
+        db: &DbConn,
+        throttle: &WatchThrottle,
+        user_id: String,
+        course_id: String,
+        chapter_id: String,
+        heartbeat: WatchHeartbeat,
+        settings: &WatchSettings,
+    ) -> Result<Option<user_progress::Model>, DbErr> {
+        if !heartbeat.is_valid() {
+            return Err(DbErr::Custom("Invalid watch range".into()));
+        }
+
+        let now = Utc::now().naive_utc();
+        let Some(heartbeats) = throttle.offer(&user_id, &course_id, &chapter_id, heartbeat, now)
+        else {
+            return Ok(None);
+        };
+        write_watch(
+            db, user_id, course_id, chapter_id, heartbeats, settings, now,
+        )
+        .await
+        .map(Some)
+    }
+
+    /// Writes the heartbeats `throttle` has held back for longer than its
+    /// interval, and returns for how many chapters. Run it every few seconds
+    /// so the end of a viewing session is not lost.
+    pub async fn flush_watch(
+        db: &DbConn,
+        throttle: &WatchThrottle,
+        settings: &WatchSettings,
+        now: NaiveDateTime,
+    ) -> Result<usize, DbErr> {
+        let due = throttle.take_due(now);
+        let count = due.len();
+        for held in due {
+            match write_watch(
+                db,
+                held.user_id,
+                held.course_id,
+                held.chapter_id,
+                held.heartbeats,
+                settings,
+                now,
+            )
+            .await
+            {
+                // A chapter locked in the meantime only drops its own heartbeats.
+                Ok(_) | Err(DbErr::Custom(_)) | Err(DbErr::RecordNotFound(_)) => (),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(count)
     }
 
     pub async fn reorder(
@@ -298,24 +428,55 @@ impl Chapters {
             _ => return Err(DbErr::RecordNotFound("Cannot find course".into())),
         }
 
-        for item in list.iter() {
-            let mut update_chapter: chapter::ActiveModel = Chapter::find_by_id(item.id.clone())
-                .one(db)
-                .await?
-                .unwrap()
-                .into();
+        let txn = db.begin().await?;
 
-            update_chapter.position = Set(item.position.into());
-            update_chapter.update(db).await?;
-        }
-
-        let chapter = Chapter::find()
+        let chapters = Chapter::find()
             .filter(chapter::Column::CourseId.eq(course_id.clone()))
             .order_by_asc(chapter::Column::Position)
-            .all(db)
+            .all(&txn)
             .await?;
 
-        Ok(chapter)
+        let mut requested: HashMap<String, i32> = HashMap::new();
+        for item in list.iter() {
+            if !chapters.iter().any(|chapter| chapter.id == item.id) {
+                return Err(DbErr::RecordNotFound("Cannot find chapter".into()));
+            }
+            if requested.insert(item.id.clone(), item.position).is_some() {
+                return Err(DbErr::Custom("Duplicate chapter in reorder list".into()));
+            }
+        }
+
+        // Chapters missing from the list keep their current position. The sort is
+        // stable, so duplicate positions keep the existing order, and the result is
+        // renumbered to 1..n to close any gaps.
+        let mut ordered = chapters;
+        ordered.sort_by_key(|chapter| {
+            (
+                requested
+                    .get(&chapter.id)
+                    .copied()
+                    .unwrap_or(chapter.position),
+                !requested.contains_key(&chapter.id),
+            )
+        });
+
+        let mut res = Vec::with_capacity(ordered.len());
+        for (index, chapter) in ordered.into_iter().enumerate() {
+            let position = index as i32 + 1;
+            if chapter.position == position {
+                res.push(chapter);
+                continue;
+            }
+
+            let mut update_chapter: chapter::ActiveModel = chapter.into();
+            update_chapter.position = Set(position);
+            update_chapter.updated_at = Set(Utc::now().naive_utc());
+            res.push(update_chapter.update(&txn).await?);
+        }
+
+        txn.commit().await?;
+
+        Ok(res)
     }
 
     pub async fn create(
@@ -335,7 +496,7 @@ impl Chapters {
 
         let last_chapter = Chapter::find()
             .filter(chapter::Column::CourseId.eq(course_id.clone()))
-            .order_by_asc(chapter::Column::Position)
+            .order_by_desc(chapter::Column::Position)
             .one(db)
             .await?;
 
@@ -355,6 +516,7 @@ impl Chapters {
         };
 
         chapter.insert(db).await?;
+        search::index_course(db, &course_id).await?;
 
         let res = Chapter::find()
             .filter(chapter::Column::CourseId.eq(course_id.clone()))
@@ -367,67 +529,199 @@ impl Chapters {
 
     pub async fn update(
         db: &DbConn,
+        cloudinary: &CloudinaryConfig,
         user_id: String,
         course_id: String,
         chapter_id: String,
-        updates: std::collections::HashMap<String, Value>,
-    ) -> Result<(), DbErr> {
+        updates: ChapterUpdate,
+    ) -> Result<chapter::Model, UpdateError> {
         let owned_course = Course::find_by_id(course_id.clone())
             .filter(course::Column::UserId.eq(user_id.clone()))
             .one(db)
             .await?;
 
-        match owned_course {
-            Some(_) => (),
-            _ => return Err(DbErr::RecordNotFound("Cannot find course".into())),
-        }
+        let owned_course = match owned_course {
+            Some(course) => course,
+            _ => return Err(DbErr::RecordNotFound("Cannot find course".into()).into()),
+        };
 
-        let mut chapter: chapter::ActiveModel = Chapter::find_by_id(chapter_id.clone())
+        let chapter = Chapter::find_by_id(chapter_id.clone())
             .filter(chapter::Column::CourseId.eq(course_id.clone()))
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find chapter".into()))?;
+
+        let mut validator = Validator::default();
+        validator.title("title", &updates.title);
+        validator.finish()?;
+
+        // Students only see published chapters of published courses, so only
+        // those edits need to wait in a draft.
+        let draft = revisions::chapter_draft(db, &chapter.id).await?;
+        if (owned_course.is_published && chapter.is_published) || draft.is_some() {
+            let updates = match draft {
+                Some(draft) => draft.merge(updates),
+                None => updates,
+            };
+            revisions::save_chapter_draft(db, &chapter, &updates).await?;
+
+            let mut preview = chapter;
+            updates.apply_to(&mut preview);
+            return Ok(preview);
+        }
+
+        // Published revisions may still reference the old video, so it is only
+        // destroyed when the course has never been published.
+        if let (Some(_), Some(old_video_id)) = (&updates.video_id, &chapter.video_id) {
+            if !revisions::has_revisions(db, &course_id).await? {
+                cloudinary
+                    .destroy(MediaKind::Video, old_video_id.clone())
+                    .await;
+            }
+        }
+
+        let mut updated = chapter;
+        updates.apply_to(&mut updated);
+        updated.updated_at = Utc::now().naive_utc();
+
+        let updated = chapter::ActiveModel::from(updated)
+            .reset_all()
+            .update(db)
+            .await?;
+        search::index_course(db, &course_id).await?;
+        Ok(updated)
+    }
+}
+
+/// Publishes `chapter` if it has a description and a video, clearing any
+/// pending scheduled publish.
+pub(crate) async fn publish_chapter(db: &DbConn, chapter: chapter::Model) -> Result<(), DbErr> {
+    if chapter.description.is_none() || chapter.video_id.is_none() {
+        return Err(DbErr::AttrNotSet("Missing required field".into()));
+    }
+
+    let mut chapter: chapter::ActiveModel = chapter.into();
+    chapter.is_published = Set(true);
+    chapter.publish_at = Set(None);
+    chapter.update(db).await?;
+
+    Ok(())
+}
+
+/// Hides `chapter` from students and clears any pending scheduled unpublish.
+/// A course left without published chapters is unpublished as well.
+pub(crate) async fn unpublish_chapter(db: &DbConn, chapter: chapter::Model) -> Result<(), DbErr> {
+    let course_id = chapter.course_id.clone();
+    let mut chapter: chapter::ActiveModel = chapter.into();
+    chapter.is_published = Set(false);
+    chapter.unpublish_at = Set(None);
+    chapter.update(db).await?;
+
+    let published_chapters_in_course = Chapter::find()
+        .filter(chapter::Column::CourseId.eq(course_id.clone()))
+        .filter(chapter::Column::IsPublished.eq(true))
+        .all(db)
+        .await?;
+
+    if published_chapters_in_course.is_empty() {
+        let mut update_course: course::ActiveModel = Course::find_by_id(course_id.clone())
             .one(db)
             .await?
             .unwrap()
             .into();
 
-        for (key, value) in updates.iter() {
-            match key.as_str() {
-                "isFree" => {
-                    if let Some(is_free) = value.as_bool() {
-                        chapter.is_free = Set(is_free);
-                    }
-                }
-                "description" => {
-                    if let Some(description) = value.as_str() {
-                        chapter.description = Set(Some(description.to_string()));
-                    }
-                }
-                "videoId" => {
-                    if let Some(video_id) = value.as_str() {
-                        let old_video_id = chapter.video_id.unwrap();
-                        if let Some(old_video_id) = old_video_id {
-                            let api_key = env::var("NEXT_PUBLIC_CLOUDINARY_API_KEY")
-                                .expect("Cannot get Cloudinary api key");
-                            let cloud_name = env::var("NEXT_PUBLIC_CLOUDINARY_CLOUD_NAME")
-                                .expect("Cannot get Cloudinary cloud name");
-                            let api_secret = env::var("CLOUDINARY_API_SECRET")
-                                .expect("Cannot get Cloudinary api secret");
-                            let upload = Upload::new(api_key, cloud_name, api_secret);
-                            let _ = upload.destroy(old_video_id).await.unwrap();
-                        }
-
-                        chapter.video_id = Set(Some(video_id.to_string()));
-                    }
-                }
-                "title" => {
-                    if let Some(title) = value.as_str() {
-                        chapter.title = Set(title.to_string());
-                    }
-                }
-                _ => continue,
+        update_course.is_published = Set(false);
+        update_course.update(db).await?;
+    }
+
+    Ok(())
+}
+
+async fn write_watch(
+    db: &DbConn,
+    user_id: String,
+    course_id: String,
+    chapter_id: String,
+    heartbeats: Vec<WatchHeartbeat>,
+    settings: &WatchSettings,
+    now: NaiveDateTime,
+) -> Result<user_progress::Model, DbErr> {
+    let chapter = Chapter::find_by_id(chapter_id.clone())
+        .filter(chapter::Column::CourseId.eq(course_id.clone()))
+        .filter(chapter::Column::IsPublished.eq(true))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find chapter".into()))?;
+
+    let entitlement = entitlements::entitlement(db, &user_id, &course_id, now).await?;
+    let granted_at = entitlement
+        .as_ref()
+        .map(|entitlement| entitlement.granted_at);
+    if schedule::is_locked(&chapter, granted_at, now) {
+        return Err(DbErr::Custom("Chapter is locked".into()));
+    }
+
+    let mut progress = None;
+    for heartbeat in heartbeats {
+        record_watch_event(db, &user_id, &chapter_id, &heartbeat, now).await?;
+        progress = Some(
+            Chapters::update_progress(
+                db,
+                user_id.clone(),
+                chapter_id.clone(),
+                ProgressUpdate {
+                    is_completed: None,
+                    heartbeat: Some(heartbeat),
+                },
+                settings,
+            )
+            .await?,
+        );
+    }
+    progress.ok_or(DbErr::Custom("Invalid watch range".into()))
+}
+
+// Playing straight through sends a heartbeat every few seconds; those are
+// folded into one event so the log grows with viewing sessions rather than
+// with heartbeats.
+async fn record_watch_event(
+    db: &DbConn,
+    user_id: &str,
+    chapter_id: &str,
+    heartbeat: &WatchHeartbeat,
+    now: NaiveDateTime,
+) -> Result<(), DbErr> {
+    let latest = WatchEvent::find()
+        .filter(watch_event::Column::UserId.eq(user_id))
+        .filter(watch_event::Column::ChapterId.eq(chapter_id))
+        .order_by_desc(watch_event::Column::CreatedAt)
+        .one(db)
+        .await?;
+
+    match latest {
+        Some(latest)
+            if (now - latest.created_at).num_seconds() < WATCH_EVENT_WINDOW
+                && (latest.start_second..=latest.end_second).contains(&heartbeat.start_second) =>
+        {
+            if heartbeat.end_second > latest.end_second {
+                let mut latest: watch_event::ActiveModel = latest.into();
+                latest.end_second = Set(heartbeat.end_second);
+                latest.update(db).await?;
             }
         }
-
-        chapter.update(db).await?;
-        Ok(())
+        _ => {
+            watch_event::ActiveModel {
+                id: Set(Uuid::new_v4().to_string()),
+                user_id: Set(user_id.to_string()),
+                chapter_id: Set(chapter_id.to_string()),
+                start_second: Set(heartbeat.start_second),
+                end_second: Set(heartbeat.end_second),
+                created_at: Set(now),
+            }
+            .insert(db)
+            .await?;
+        }
     }
+
+    Ok(())
 }
@@ -0,0 +1,236 @@
+use ::entities::{prelude::*, sea_orm_active_enums::CouponKind, *};
+use sea_orm::{sea_query::Expr, *};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{
+    chrono::{NaiveDateTime, Utc},
+    Uuid,
+};
+
+use crate::validation::{UpdateError, Validator};
+
+pub struct Coupons;
+
+const CODE_MAX_LEN: usize = 32;
+
+/// A promotion a teacher can hand out. Without `course_id` it applies to every
+/// course the teacher owns. `value` is a percentage for
+/// [`CouponKind::Percent`] and an amount in đồng for [`CouponKind::Fixed`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NewCoupon {
+    pub code: String,
+    pub course_id: Option<String>,
+    pub kind: CouponKind,
+    pub value: i32,
+    pub max_redemptions: Option<i32>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// What a student pays for a course once their coupon, if any, is applied.
+pub(crate) struct Quote {
+    pub(crate) amount: i32,
+    pub(crate) discount: i32,
+    pub(crate) coupon_id: Option<String>,
+}
+
+impl Coupons {
+    pub async fn create(
+        db: &DbConn,
+        user_id: String,
+        coupon: NewCoupon,
+    ) -> Result<coupon::Model, UpdateError> {
+        if let Some(course_id) = &coupon.course_id {
+            let owned_course = Course::find_by_id(course_id.clone())
+                .filter(course::Column::UserId.eq(user_id.clone()))
+                .one(db)
+                .await?;
+
+            if owned_course.is_none() {
+                return Err(DbErr::RecordNotFound("Cannot find course".into()).into());
+            }
+        }
+
+        let code = normalize(&coupon.code);
+        let mut validator = Validator::default();
+        if code.is_empty()
+            || code.chars().count() > CODE_MAX_LEN
+            || !code
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            validator.error(
+                "code",
+                &format!(
+                    "Code must be 1 to {} letters, digits, dashes or underscores",
+                    CODE_MAX_LEN
+                ),
+            );
+        } else if Coupon::find()
+            .filter(coupon::Column::Code.eq(code.clone()))
+            .one(db)
+            .await?
+            .is_some()
+        {
+            validator.error("code", "Code is already in use");
+        }
+        match coupon.kind {
+            CouponKind::Percent if !(1..=100).contains(&coupon.value) => {
+                validator.error("value", "Percentage must be between 1 and 100")
+            }
+            CouponKind::Fixed if coupon.value <= 0 => {
+                validator.error("value", "Amount must be positive")
+            }
+            _ => (),
+        }
+        if coupon.max_redemptions.is_some_and(|max| max < 1) {
+            validator.error("maxRedemptions", "Coupon must be redeemable at least once");
+        }
+        if coupon
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now().naive_utc())
+        {
+            validator.error("expiresAt", "Expiry must be in the future");
+        }
+        validator.finish()?;
+
+        Ok(coupon::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            code: Set(code),
+            user_id: Set(user_id),
+            course_id: Set(coupon.course_id),
+            kind: Set(coupon.kind),
+            value: Set(coupon.value),
+            max_redemptions: Set(coupon.max_redemptions),
+            redemptions: Set(0),
+            expires_at: Set(coupon.expires_at),
+            created_at: Set(Utc::now().naive_utc()),
+            updated_at: Set(Utc::now().naive_utc()),
+        }
+        .insert(db)
+        .await?)
+    }
+
+    pub async fn list(db: &DbConn, user_id: String) -> Result<Vec<coupon::Model>, DbErr> {
+        Coupon::find()
+            .filter(coupon::Column::UserId.eq(user_id))
+            .order_by_desc(coupon::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// Deletes a coupon. Purchases made with it keep their discount.
+    pub async fn delete(db: &DbConn, user_id: String, coupon_id: String) -> Result<(), DbErr> {
+        let res = Coupon::delete_many()
+            .filter(coupon::Column::Id.eq(coupon_id))
+            .filter(coupon::Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbErr::RecordNotFound("Cannot find coupon".into()));
+        }
+
+        Ok(())
+    }
+}
+
+fn normalize(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// The price of `course` with `code` applied. Coupons only work on courses of
+/// the teacher who created them.
+pub(crate) async fn quote(
+    db: &DbConn,
+    course: &course::Model,
+    code: Option<&str>,
+) -> Result<Quote, DbErr> {
+    let price = course.price.unwrap_or(0).max(0);
+    let Some(code) = code.map(normalize).filter(|code| !code.is_empty()) else {
+        return Ok(Quote {
+            amount: price,
+            discount: 0,
+            coupon_id: None,
+        });
+    };
+
+    let coupon = Coupon::find()
+        .filter(coupon::Column::Code.eq(code))
+        .filter(coupon::Column::UserId.eq(course.user_id.clone()))
+        .one(db)
+        .await?
+        .filter(|coupon| {
+            coupon
+                .course_id
+                .as_ref()
+                .map_or(true, |course_id| *course_id == course.id)
+        })
+        .ok_or(DbErr::Custom("Invalid coupon code".into()))?;
+
+    if coupon
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now().naive_utc())
+    {
+        return Err(DbErr::Custom("Coupon has expired".into()));
+    }
+    if coupon
+        .max_redemptions
+        .is_some_and(|max| coupon.redemptions >= max)
+    {
+        return Err(DbErr::Custom("Coupon has been fully redeemed".into()));
+    }
+
+    let discount = match coupon.kind {
+        CouponKind::Percent => (i64::from(price) * i64::from(coupon.value) / 100) as i32,
+        CouponKind::Fixed => coupon.value,
+    }
+    .min(price);
+
+    Ok(Quote {
+        amount: price - discount,
+        discount,
+        coupon_id: Some(coupon.id),
+    })
+}
+
+/// Counts a paid purchase against its coupon's redemption limit. Returns
+/// `false` when other students used up the coupon after this one was quoted,
+/// in which case the discount must not be granted. A coupon deleted since
+/// still counts as redeemed, as purchases made with it keep their discount.
+pub(crate) async fn redeem<C: ConnectionTrait>(
+    db: &C,
+    purchase: &purchase::Model,
+) -> Result<bool, DbErr> {
+    let Some(coupon_id) = &purchase.coupon_id else {
+        return Ok(true);
+    };
+
+    let res = Coupon::update_many()
+        .col_expr(
+            coupon::Column::Redemptions,
+            Expr::col(coupon::Column::Redemptions).add(1),
+        )
+        .col_expr(
+            coupon::Column::UpdatedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(coupon::Column::Id.eq(coupon_id.clone()))
+        .filter(
+            Condition::any()
+                .add(coupon::Column::MaxRedemptions.is_null())
+                .add(
+                    Expr::col(coupon::Column::Redemptions)
+                        .lt(Expr::col(coupon::Column::MaxRedemptions)),
+                ),
+        )
+        .exec(db)
+        .await?;
+    if res.rows_affected > 0 {
+        return Ok(true);
+    }
+
+    Ok(Coupon::find_by_id(coupon_id.clone())
+        .one(db)
+        .await?
+        .is_none())
+}
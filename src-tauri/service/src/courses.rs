@@ -1,12 +1,17 @@
-use std::env;
-
 use ::entities::{prelude::*, *};
 use sea_orm::*;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use sqlx::types::{chrono::Utc, Uuid};
-
-use cloudinary::upload::Upload;
+use sqlx::types::{
+    chrono::{NaiveDateTime, Utc},
+    Uuid,
+};
+
+use crate::{
+    entitlements, loaders,
+    media::{CloudinaryConfig, MediaKind},
+    revisions, schedule, search,
+    validation::{UpdateError, Validator},
+};
 
 pub struct Courses;
 
@@ -29,6 +34,9 @@ pub struct ChapterWithProgress {
     #[serde(flatten)]
     chapter: chapter::Model,
     user_progress: Vec<user_progress::Model>,
+    /// Set while the chapter is still dripping for this user. The video is
+    /// withheld until then.
+    available_at: Option<NaiveDateTime>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -39,6 +47,67 @@ pub struct CourseWithChapters {
     chapters: Vec<chapter::Model>,
 }
 
+/// Fields a teacher can change on a course. Missing fields are left untouched.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct CourseUpdate {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub price: Option<i32>,
+    pub category_id: Option<String>,
+}
+
+impl CourseUpdate {
+    /// Reads an update as sent by the client. Unknown keys and values of the
+    /// wrong type, such as a string price, become field errors.
+    pub fn from_json(value: serde_json::Value) -> Result<CourseUpdate, UpdateError> {
+        let mut validator = Validator::default();
+        let mut fields = validator.object(
+            value,
+            &["title", "description", "imageUrl", "price", "categoryId"],
+        );
+        let updates = CourseUpdate {
+            title: validator.field(&mut fields, "title", "text"),
+            description: validator.field(&mut fields, "description", "text"),
+            image_url: validator.field(&mut fields, "imageUrl", "text"),
+            price: validator.field(&mut fields, "price", "a whole number"),
+            category_id: validator.field(&mut fields, "categoryId", "text"),
+        };
+        validator.finish()?;
+        Ok(updates)
+    }
+
+    /// Layers `newer` on top of `self`, keeping fields `newer` leaves out.
+    pub(crate) fn merge(self, newer: CourseUpdate) -> CourseUpdate {
+        CourseUpdate {
+            title: newer.title.or(self.title),
+            description: newer.description.or(self.description),
+            image_url: newer.image_url.or(self.image_url),
+            price: newer.price.or(self.price),
+            category_id: newer.category_id.or(self.category_id),
+        }
+    }
+
+    pub(crate) fn apply_to(&self, course: &mut course::Model) {
+        if let Some(title) = &self.title {
+            course.title = title.trim().to_string();
+        }
+        if let Some(description) = &self.description {
+            course.description = Some(description.clone());
+        }
+        if let Some(image_url) = &self.image_url {
+            course.image_url = Some(image_url.clone());
+        }
+        if let Some(price) = self.price {
+            course.price = Some(price);
+        }
+        if let Some(category_id) = &self.category_id {
+            course.category_id = Some(category_id.clone());
+        }
+    }
+}
+
 impl Courses {
     pub async fn create(
         db: &DbConn,
@@ -55,80 +124,77 @@ impl Courses {
         };
 
         let new_course: course::Model = new_course.insert(db).await?;
+        search::index_course(db, &new_course.id).await?;
         Ok(new_course)
     }
 
     pub async fn update(
         db: &DbConn,
+        cloudinary: &CloudinaryConfig,
+        user_id: String,
         course_id: String,
-        updates: std::collections::HashMap<String, Value>,
-    ) -> Result<(), DbErr> {
-        let mut course: course::ActiveModel =
-            Course::find_by_id(course_id).one(db).await?.unwrap().into();
-
-        for (key, value) in updates.iter() {
-            match key.as_str() {
-                "categoryId" => {
-                    if let Some(category_id) = value.as_str() {
-                        course.category_id = Set(Some(category_id.to_string()));
-                    }
-                }
-                "description" => {
-                    if let Some(description) = value.as_str() {
-                        course.description = Set(Some(description.to_string()));
-                    }
-                }
-                "imageUrl" => {
-                    if let Some(image_url) = value.as_str() {
-                        match course.image_url.unwrap() {
-                            Some(url) => {
-                                let pid: &str =
-                                    url.split("/").last().unwrap().split(".").next().unwrap();
-
-                                let api_key = env::var("NEXT_PUBLIC_CLOUDINARY_API_KEY")
-                                    .expect("Cannot get Cloudinary api key");
-                                let cloud_name = env::var("NEXT_PUBLIC_CLOUDINARY_CLOUD_NAME")
-                                    .expect("Cannot get Cloudinary cloud name");
-                                let api_secret = env::var("CLOUDINARY_API_SECRET")
-                                    .expect("Cannot get Cloudinary api secret");
-                                let upload = Upload::new(api_key, cloud_name, api_secret);
-                                let _ = upload.destroy(pid).await.unwrap();
-                            }
-                            _ => {}
-                        }
-                        course.image_url = Set(Some(image_url.to_string()));
-                    }
-                }
-                "title" => {
-                    if let Some(title) = value.as_str() {
-                        course.title = Set(title.to_string());
-                    }
-                }
-                "price" => {
-                    if let Some(price) = value.as_i64() {
-                        course.price = Set(Some(price as i32));
-                    }
-                }
-                _ => continue,
+        updates: CourseUpdate,
+    ) -> Result<course::Model, UpdateError> {
+        let course = Course::find_by_id(course_id)
+            .filter(course::Column::UserId.eq(user_id))
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find course".into()))?;
+
+        let mut validator = Validator::default();
+        validator.title("title", &updates.title);
+        if matches!(updates.price, Some(price) if price < 0) {
+            validator.error("price", "Price must not be negative");
+        }
+        if let Some(category_id) = updates.category_id.clone() {
+            if Category::find_by_id(category_id).one(db).await?.is_none() {
+                validator.error("categoryId", "Category does not exist");
             }
         }
+        validator.finish()?;
+
+        // While the course is live, edits are kept as a draft so students keep
+        // seeing the published version until the teacher publishes the draft.
+        let draft = revisions::course_draft(db, &course.id).await?;
+        if course.is_published || draft.is_some() {
+            let updates = match draft {
+                Some(draft) => draft.merge(updates),
+                None => updates,
+            };
+            revisions::save_course_draft(db, &course.id, &updates).await?;
+
+            let mut preview = course;
+            updates.apply_to(&mut preview);
+            return Ok(preview);
+        }
 
-        course.update(db).await?;
-        Ok(())
+        // Published revisions may still reference the old image, so it is only
+        // destroyed when the course has never been published.
+        if let (Some(_), Some(url)) = (&updates.image_url, &course.image_url) {
+            if !revisions::has_revisions(db, &course.id).await? {
+                let pid: &str = url.split("/").last().unwrap().split(".").next().unwrap();
+
+                cloudinary.destroy(MediaKind::Image, pid).await;
+            }
+        }
+
+        let mut updated = course;
+        updates.apply_to(&mut updated);
+        updated.updated_at = Utc::now().naive_utc();
+
+        let updated = course::ActiveModel::from(updated)
+            .reset_all()
+            .update(db)
+            .await?;
+        search::index_course(db, &updated.id).await?;
+        Ok(updated)
     }
 
     pub async fn list(db: &DbConn, user_id: String) -> Result<Vec<course::Model>, DbErr> {
-        println!("Lmao");
-        let courses = Course::find()
+        Course::find()
             .filter(course::Column::UserId.eq(user_id))
             .all(db)
-            .await?;
-
-        for course in courses.iter() {
-            println!("{:?}", course);
-        }
-
-        Ok(courses)
+            .await
     }
 
     pub async fn add_attachment(
@@ -167,6 +233,7 @@ impl Courses {
 
     pub async fn remove_attachment(
         db: &DbConn,
+        cloudinary: &CloudinaryConfig,
         user_id: String,
         attachment_id: String,
         course_id: String,
@@ -195,14 +262,7 @@ impl Courses {
             .next()
             .unwrap();
 
-        let api_key =
-            env::var("NEXT_PUBLIC_CLOUDINARY_API_KEY").expect("Cannot get Cloudinary api key");
-        let cloud_name = env::var("NEXT_PUBLIC_CLOUDINARY_CLOUD_NAME")
-            .expect("Cannot get Cloudinary cloud name");
-        let api_secret =
-            env::var("CLOUDINARY_API_SECRET").expect("Cannot get Cloudinary api secret");
-        let upload = Upload::new(api_key, cloud_name, api_secret);
-        let _ = upload.destroy(pid).await.unwrap();
+        cloudinary.destroy(MediaKind::Image, pid).await;
 
         delete_attachment.delete(db).await?;
 
@@ -213,7 +273,12 @@ impl Courses {
         Ok(res)
     }
 
-    pub async fn delete(db: &DbConn, user_id: String, course_id: String) -> Result<(), DbErr> {
+    pub async fn delete(
+        db: &DbConn,
+        cloudinary: &CloudinaryConfig,
+        user_id: String,
+        course_id: String,
+    ) -> Result<(), DbErr> {
         let delete_course = Course::find_by_id(course_id.clone())
             .filter(course::Column::UserId.eq(user_id.clone()))
             .one(db)
@@ -232,14 +297,7 @@ impl Courses {
         for chapter in chapters.iter() {
             match chapter.video_id.clone() {
                 Some(pid) => {
-                    let api_key = env::var("NEXT_PUBLIC_CLOUDINARY_API_KEY")
-                        .expect("Cannot get Cloudinary api key");
-                    let cloud_name = env::var("NEXT_PUBLIC_CLOUDINARY_CLOUD_NAME")
-                        .expect("Cannot get Cloudinary cloud name");
-                    let api_secret = env::var("CLOUDINARY_API_SECRET")
-                        .expect("Cannot get Cloudinary api secret");
-                    let upload = Upload::new(api_key, cloud_name, api_secret);
-                    let _ = upload.destroy(pid).await.unwrap();
+                    cloudinary.destroy(MediaKind::Video, pid).await;
                 }
                 _ => {}
             }
@@ -256,16 +314,10 @@ impl Courses {
             .one(db)
             .await?;
 
-        match course.clone() {
-            Some(_) => (),
-            None => return Err(DbErr::Custom("Cannot find course".into())),
-        };
-
-        let mut course: course::ActiveModel = course.unwrap().into();
-        course.is_published = Set(false);
-        course.update(db).await?;
-
-        Ok(())
+        match course {
+            Some(course) => unpublish_course(db, course).await,
+            None => Err(DbErr::Custom("Cannot find course".into())),
+        }
     }
 
     pub async fn publish(db: &DbConn, user_id: String, course_id: String) -> Result<(), DbErr> {
@@ -274,31 +326,10 @@ impl Courses {
             .one(db)
             .await?;
 
-        let publish_course = match course.clone() {
-            Some(course) => course,
-            None => return Err(DbErr::Custom("Cannot find course".into())),
-        };
-
-        let chapters = chapter::Entity::find()
-            .filter(chapter::Column::CourseId.eq(course_id.clone()))
-            .all(db)
-            .await?;
-
-        let has_published_chapter = chapters.iter().any(|chapter| chapter.is_published);
-
-        if !(publish_course.description.is_some()
-            && publish_course.image_url.is_some()
-            && publish_course.category_id.is_some()
-            && has_published_chapter)
-        {
-            return Err(DbErr::Custom("Missing required fields".into()));
+        match course {
+            Some(course) => publish_course(db, course).await,
+            None => Err(DbErr::Custom("Cannot find course".into())),
         }
-
-        let mut course: course::ActiveModel = course.unwrap().into();
-        course.is_published = Set(true);
-        course.update(db).await?;
-
-        Ok(())
     }
 
     pub async fn get(db: &DbConn, course_id: String) -> Result<CourseWithChapters, DbErr> {
@@ -332,6 +363,11 @@ impl Courses {
             None => return Err(DbErr::Custom("Cannot find course".into())),
         };
 
+        let now = Utc::now().naive_utc();
+        let granted_at = entitlements::entitlement(db, &user_id, &course_id, now)
+            .await?
+            .map(|entitlement| entitlement.granted_at);
+
         // Step 2: Find related chapters for this course, ordered by position, and include userProgress
         let chapters_with_progress: Vec<ChapterWithProgress> = chapter::Entity::find()
             .filter(chapter::Column::CourseId.eq(course_id.clone()))
@@ -341,17 +377,23 @@ impl Courses {
             .all(db)
             .await?
             .into_iter()
-            .filter_map(|(chapter, progress)| {
+            .map(|(mut chapter, progress)| {
                 // Filter userProgress by user_id
                 let user_progress: Vec<user_progress::Model> = progress
                     .into_iter()
                     .filter(|up| up.user_id == user_id)
                     .collect();
 
-                Some(ChapterWithProgress {
+                let available_at = schedule::locked_until(&chapter, granted_at, now);
+                if schedule::is_locked(&chapter, granted_at, now) {
+                    chapter.video_id = None;
+                }
+
+                ChapterWithProgress {
                     chapter,
                     user_progress,
-                })
+                    available_at,
+                }
             })
             .collect();
 
@@ -380,15 +422,12 @@ impl Courses {
             .map(|chapter| chapter.id)
             .collect();
 
-        let valid_completed_chapters = UserProgress::find()
-            .filter(user_progress::Column::UserId.eq(user_id.clone()))
-            .filter(user_progress::Column::ChapterId.is_in(published_chapter_ids.clone()))
-            .filter(user_progress::Column::IsCompleted.eq(true))
-            .count(db)
-            .await?;
+        // Graded assignments count alongside completed chapters
+        let items = loaders::ProgressItems::load(db, &user_id, &published_chapter_ids).await?;
+        let (done, total) = items.tally(&published_chapter_ids);
 
-        let progress_percentage = if !published_chapter_ids.is_empty() {
-            (valid_completed_chapters as f32 / published_chapter_ids.len() as f32) * 100.0
+        let progress_percentage = if total > 0 {
+            (done as f32 / total as f32) * 100.0
         } else {
             0.0
         };
@@ -398,3 +437,47 @@ impl Courses {
         Ok(progress_percentage)
     }
 }
+
+/// Publishes `course` once it has every field students need and at least one
+/// published chapter, and records the published content as a new revision.
+/// Any pending scheduled publish is cleared.
+pub(crate) async fn publish_course(db: &DbConn, course: course::Model) -> Result<(), DbErr> {
+    let chapters = chapter::Entity::find()
+        .filter(chapter::Column::CourseId.eq(course.id.clone()))
+        .all(db)
+        .await?;
+
+    let has_published_chapter = chapters.iter().any(|chapter| chapter.is_published);
+
+    if !(course.description.is_some()
+        && course.image_url.is_some()
+        && course.category_id.is_some()
+        && has_published_chapter)
+    {
+        return Err(DbErr::Custom("Missing required fields".into()));
+    }
+
+    let txn = db.begin().await?;
+
+    let user_id = course.user_id.clone();
+    let course_id = course.id.clone();
+    let mut course: course::ActiveModel = course.into();
+    course.is_published = Set(true);
+    course.publish_at = Set(None);
+    course.update(&txn).await?;
+    revisions::record(&txn, &user_id, &course_id).await?;
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+/// Hides `course` from students and clears any pending scheduled unpublish.
+pub(crate) async fn unpublish_course(db: &DbConn, course: course::Model) -> Result<(), DbErr> {
+    let mut course: course::ActiveModel = course.into();
+    course.is_published = Set(false);
+    course.unpublish_at = Set(None);
+    course.update(db).await?;
+
+    Ok(())
+}
@@ -0,0 +1,449 @@
+use std::collections::HashMap;
+
+use ::entities::{prelude::*, *};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    *,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{chrono::Utc, Uuid};
+
+use crate::{
+    entitlements,
+    validation::{UpdateError, Validator},
+};
+
+pub struct Discussions;
+
+pub const COMMENT_MAX_LEN: usize = 5000;
+
+// The local model has a small context window, so only the start of a long
+// chapter description goes into a tutor prompt.
+const TUTOR_CONTEXT_LEN: usize = 2000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NewComment {
+    pub body: String,
+    /// The comment being answered. Threads are one level deep, so a reply to
+    /// a reply joins the same thread.
+    pub parent_id: Option<String>,
+}
+
+/// A comment as shown in a thread. Deleted comments keep their place when
+/// they have replies, with the body removed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentView {
+    #[serde(flatten)]
+    pub comment: comment::Model,
+    /// Whether the course teacher wrote it.
+    pub by_teacher: bool,
+}
+
+/// A question and its replies. Pinned replies, the accepted answers, come
+/// first.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Thread {
+    #[serde(flatten)]
+    pub comment: CommentView,
+    pub replies: Vec<CommentView>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterThreads {
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub threads: Vec<Thread>,
+}
+
+/// Comments from students the teacher has not read yet, in one course.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, FromQueryResult)]
+#[serde(rename_all = "camelCase")]
+pub struct UnreadComments {
+    pub course_id: String,
+    pub course_title: String,
+    pub unread: i64,
+}
+
+impl Discussions {
+    /// The threads of a chapter, pinned ones first and then newest first.
+    pub async fn list(
+        db: &DbConn,
+        user_id: String,
+        chapter_id: String,
+    ) -> Result<Vec<Thread>, DbErr> {
+        let (_, course) = participant(db, &user_id, &chapter_id).await?;
+        let comments = Comment::find()
+            .filter(comment::Column::ChapterId.eq(chapter_id))
+            .order_by_asc(comment::Column::CreatedAt)
+            .all(db)
+            .await?;
+        Ok(threads(comments, &course.user_id))
+    }
+
+    /// Every thread in one of the teacher's courses, by chapter, for
+    /// moderating them in one place.
+    pub async fn for_course(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<Vec<ChapterThreads>, DbErr> {
+        let course = owned_course(db, &user_id, &course_id).await?;
+        let chapters = Chapter::find()
+            .filter(chapter::Column::CourseId.eq(course_id))
+            .order_by_asc(chapter::Column::Position)
+            .all(db)
+            .await?;
+        let mut comments: HashMap<String, Vec<comment::Model>> = HashMap::new();
+        for comment in Comment::find()
+            .filter(comment::Column::ChapterId.is_in(chapters.iter().map(|c| c.id.clone())))
+            .order_by_asc(comment::Column::CreatedAt)
+            .all(db)
+            .await?
+        {
+            comments
+                .entry(comment.chapter_id.clone())
+                .or_default()
+                .push(comment);
+        }
+
+        Ok(chapters
+            .into_iter()
+            .filter_map(|chapter| {
+                let threads = threads(comments.remove(&chapter.id)?, &course.user_id);
+                (!threads.is_empty()).then_some(ChapterThreads {
+                    chapter_id: chapter.id,
+                    chapter_title: chapter.title,
+                    threads,
+                })
+            })
+            .collect())
+    }
+
+    /// Posts a question in a chapter, or a reply to one.
+    pub async fn post(
+        db: &DbConn,
+        user_id: String,
+        chapter_id: String,
+        comment: NewComment,
+    ) -> Result<comment::Model, UpdateError> {
+        participant(db, &user_id, &chapter_id).await?;
+        validate(&comment.body)?;
+
+        let parent_id = match comment.parent_id {
+            Some(parent_id) => {
+                let parent = Comment::find_by_id(parent_id)
+                    .filter(comment::Column::ChapterId.eq(chapter_id.clone()))
+                    .filter(comment::Column::DeletedAt.is_null())
+                    .one(db)
+                    .await?
+                    .ok_or(DbErr::RecordNotFound("Cannot find comment".into()))?;
+                Some(parent.parent_id.unwrap_or(parent.id))
+            }
+            None => None,
+        };
+
+        Ok(comment::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            chapter_id: Set(chapter_id),
+            user_id: Set(user_id),
+            parent_id: Set(parent_id),
+            body: Set(comment.body.trim().to_string()),
+            is_pinned: Set(false),
+            created_at: Set(Utc::now().naive_utc()),
+            edited_at: Set(None),
+            deleted_at: Set(None),
+        }
+        .insert(db)
+        .await?)
+    }
+
+    /// Changes the body of one of the user's own comments.
+    pub async fn edit(
+        db: &DbConn,
+        user_id: String,
+        comment_id: String,
+        body: String,
+    ) -> Result<comment::Model, UpdateError> {
+        let comment = Comment::find_by_id(comment_id)
+            .filter(comment::Column::UserId.eq(user_id.clone()))
+            .filter(comment::Column::DeletedAt.is_null())
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find comment".into()))?;
+        participant(db, &user_id, &comment.chapter_id).await?;
+        validate(&body)?;
+
+        let mut comment: comment::ActiveModel = comment.into();
+        comment.body = Set(body.trim().to_string());
+        comment.edited_at = Set(Some(Utc::now().naive_utc()));
+        Ok(comment.update(db).await?)
+    }
+
+    /// Deletes a comment. Authors can delete their own and teachers any in
+    /// their courses. The row is kept so the replies stay in their thread.
+    pub async fn delete(db: &DbConn, user_id: String, comment_id: String) -> Result<(), DbErr> {
+        let (comment, course) = comment_in_course(db, &comment_id).await?;
+        if comment.user_id != user_id && course.user_id != user_id {
+            return Err(DbErr::RecordNotFound("Cannot find comment".into()));
+        }
+
+        let mut comment: comment::ActiveModel = comment.into();
+        comment.is_pinned = Set(false);
+        comment.deleted_at = Set(Some(Utc::now().naive_utc()));
+        comment.update(db).await?;
+        Ok(())
+    }
+
+    /// Pins or unpins a comment in one of the teacher's courses. A pinned
+    /// thread stays at the top of the chapter; a pinned reply is the answer
+    /// to its thread, so pinning one unpins the others.
+    pub async fn pin(
+        db: &DbConn,
+        user_id: String,
+        comment_id: String,
+        pinned: bool,
+    ) -> Result<comment::Model, DbErr> {
+        let (comment, course) = comment_in_course(db, &comment_id).await?;
+        if course.user_id != user_id {
+            return Err(DbErr::RecordNotFound("Cannot find comment".into()));
+        }
+
+        let txn = db.begin().await?;
+        if let (true, Some(parent_id)) = (pinned, &comment.parent_id) {
+            Comment::update_many()
+                .col_expr(comment::Column::IsPinned, Expr::value(false))
+                .filter(comment::Column::ParentId.eq(parent_id.clone()))
+                .exec(&txn)
+                .await?;
+        }
+        let mut comment: comment::ActiveModel = comment.into();
+        comment.is_pinned = Set(pinned);
+        let comment = comment.update(&txn).await?;
+        txn.commit().await?;
+        Ok(comment)
+    }
+
+    /// Unread student comments in each of the teacher's courses that have
+    /// any, counted since the teacher last opened the course's discussions.
+    pub async fn unread(db: &DbConn, user_id: String) -> Result<Vec<UnreadComments>, DbErr> {
+        let teacher_id = user_id.clone();
+        Comment::find()
+            .select_only()
+            .column_as(course::Column::Id, "course_id")
+            .column_as(course::Column::Title, "course_title")
+            .column_as(comment::Column::Id.count(), "unread")
+            .inner_join(Chapter)
+            .join(JoinType::InnerJoin, chapter::Relation::Course.def())
+            .join(
+                JoinType::LeftJoin,
+                course::Relation::DiscussionRead
+                    .def()
+                    .on_condition(move |_, read| {
+                        Condition::all().add(
+                            Expr::col((read, discussion_read::Column::UserId))
+                                .eq(teacher_id.clone()),
+                        )
+                    }),
+            )
+            .filter(course::Column::UserId.eq(user_id.clone()))
+            .filter(comment::Column::UserId.ne(user_id))
+            .filter(comment::Column::DeletedAt.is_null())
+            .filter(
+                Condition::any()
+                    .add(discussion_read::Column::ReadAt.is_null())
+                    .add(
+                        Expr::col((Comment, comment::Column::CreatedAt))
+                            .gt(Expr::col((DiscussionRead, discussion_read::Column::ReadAt))),
+                    ),
+            )
+            .group_by(course::Column::Id)
+            .group_by(course::Column::Title)
+            .order_by_asc(course::Column::Title)
+            .into_model::<UnreadComments>()
+            .all(db)
+            .await
+    }
+
+    /// Marks every comment in the course as read by the teacher.
+    pub async fn mark_read(db: &DbConn, user_id: String, course_id: String) -> Result<(), DbErr> {
+        owned_course(db, &user_id, &course_id).await?;
+        DiscussionRead::insert(discussion_read::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            user_id: Set(user_id),
+            course_id: Set(course_id),
+            read_at: Set(Utc::now().naive_utc()),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                discussion_read::Column::UserId,
+                discussion_read::Column::CourseId,
+            ])
+            .update_column(discussion_read::Column::ReadAt)
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+        Ok(())
+    }
+
+    /// A prompt for the AI tutor that puts `question` in the context of the
+    /// chapter, so students can try the tutor before asking the teacher.
+    pub async fn tutor_prompt(
+        db: &DbConn,
+        user_id: String,
+        chapter_id: String,
+        question: String,
+    ) -> Result<String, DbErr> {
+        let (chapter, course) = participant(db, &user_id, &chapter_id).await?;
+        let description = plain_text(chapter.description.as_deref().unwrap_or_default());
+        let context = match description.char_indices().nth(TUTOR_CONTEXT_LEN) {
+            Some((end, _)) => format!("{}…", &description[..end]),
+            None => description,
+        };
+
+        let mut prompt = format!(
+            "Khóa học \"{}\", bài \"{}\".",
+            course.title.trim(),
+            chapter.title.trim()
+        );
+        if !context.is_empty() {
+            prompt.push_str(&format!("\nNội dung bài học: {}", context));
+        }
+        prompt.push_str(&format!("\nCâu hỏi của học viên: {}", question.trim()));
+        Ok(prompt)
+    }
+}
+
+/// The chapter and its course, if the user can take part in the chapter's
+/// discussion: the course teacher, or a student who can open the chapter.
+async fn participant(
+    db: &DbConn,
+    user_id: &str,
+    chapter_id: &str,
+) -> Result<(chapter::Model, course::Model), DbErr> {
+    let not_found = || DbErr::RecordNotFound("Cannot find chapter".into());
+    let (chapter, course) = Chapter::find_by_id(chapter_id)
+        .find_also_related(Course)
+        .one(db)
+        .await?
+        .ok_or_else(not_found)?;
+    let course = course.ok_or_else(not_found)?;
+    if course.user_id != user_id {
+        entitlements::accessible_chapter(db, user_id, chapter_id).await?;
+    }
+    Ok((chapter, course))
+}
+
+async fn comment_in_course(
+    db: &DbConn,
+    comment_id: &str,
+) -> Result<(comment::Model, course::Model), DbErr> {
+    let not_found = || DbErr::RecordNotFound("Cannot find comment".into());
+    let comment = Comment::find_by_id(comment_id)
+        .filter(comment::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(not_found)?;
+    let course = Course::find()
+        .inner_join(Chapter)
+        .filter(chapter::Column::Id.eq(comment.chapter_id.clone()))
+        .one(db)
+        .await?
+        .ok_or_else(not_found)?;
+    Ok((comment, course))
+}
+
+async fn owned_course(db: &DbConn, user_id: &str, course_id: &str) -> Result<course::Model, DbErr> {
+    Course::find_by_id(course_id)
+        .filter(course::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find course".into()))
+}
+
+/// Groups `comments`, oldest first, into threads. Deleted replies are left
+/// out, and so are deleted questions nobody answered.
+fn threads(comments: Vec<comment::Model>, teacher_id: &str) -> Vec<Thread> {
+    let view = |mut comment: comment::Model| {
+        if comment.deleted_at.is_some() {
+            comment.body = String::new();
+        }
+        CommentView {
+            by_teacher: comment.user_id == teacher_id,
+            comment,
+        }
+    };
+
+    let (questions, replies): (Vec<_>, Vec<_>) = comments
+        .into_iter()
+        .partition(|comment| comment.parent_id.is_none());
+    let mut replies_to: HashMap<String, Vec<CommentView>> = HashMap::new();
+    for reply in replies {
+        if reply.deleted_at.is_none() {
+            let parent_id = reply.parent_id.clone().unwrap_or_default();
+            replies_to.entry(parent_id).or_default().push(view(reply));
+        }
+    }
+
+    let mut threads: Vec<Thread> = questions
+        .into_iter()
+        .rev()
+        .filter_map(|question| {
+            let mut replies = replies_to.remove(&question.id).unwrap_or_default();
+            if question.deleted_at.is_some() && replies.is_empty() {
+                return None;
+            }
+            replies.sort_by_key(|reply| !reply.comment.is_pinned);
+            Some(Thread {
+                comment: view(question),
+                replies,
+            })
+        })
+        .collect();
+    threads.sort_by_key(|thread| !thread.comment.comment.is_pinned);
+    threads
+}
+
+fn validate(body: &str) -> Result<(), UpdateError> {
+    let mut validator = Validator::default();
+    let len = body.trim().chars().count();
+    if len == 0 || len > COMMENT_MAX_LEN {
+        validator.error(
+            "body",
+            &format!(
+                "Comment must be between 1 and {} characters",
+                COMMENT_MAX_LEN
+            ),
+        );
+    }
+    validator.finish()
+}
+
+/// The text of a chapter description, which the editor saves as HTML.
+pub(crate) fn plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
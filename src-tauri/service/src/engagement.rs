@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet};
+
+use ::entities::{prelude::*, *};
+use sea_orm::{sea_query::Expr, *};
+use serde::Serialize;
+use sqlx::types::chrono::NaiveDateTime;
+
+use crate::Teacher;
+
+/// How students move through one chapter of a course.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterEngagement {
+    chapter_id: String,
+    title: String,
+    position: i32,
+    /// Students who opened the chapter, by watching it or recording progress.
+    reached: i64,
+    completed: i64,
+    /// Share of `reached` that completed the chapter.
+    completion_rate: f64,
+    /// Share of `reached` that never reached a later chapter. `None` for the
+    /// last chapter, where there is nowhere left to go.
+    drop_off_rate: Option<f64>,
+    /// Median time between first watching the chapter and completing it.
+    median_seconds_to_complete: Option<i64>,
+    watch_seconds: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseEngagement {
+    course_id: String,
+    /// Students who reached at least one chapter.
+    learners: i64,
+    chapters: Vec<ChapterEngagement>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct WatchTotals {
+    user_id: String,
+    chapter_id: String,
+    first_watched_at: NaiveDateTime,
+    seconds: i64,
+}
+
+impl Teacher {
+    /// Per-chapter engagement with one of the teacher's courses, following
+    /// its published chapters in order.
+    pub async fn course_engagement(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<CourseEngagement, DbErr> {
+        Course::find_by_id(course_id.clone())
+            .filter(course::Column::UserId.eq(user_id))
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find course".into()))?;
+
+        let chapters = Chapter::find()
+            .filter(chapter::Column::CourseId.eq(course_id.clone()))
+            .filter(chapter::Column::IsPublished.eq(true))
+            .order_by_asc(chapter::Column::Position)
+            .all(db)
+            .await?;
+        let chapter_ids = chapters.iter().map(|chapter| chapter.id.clone());
+
+        let progress = UserProgress::find()
+            .filter(user_progress::Column::ChapterId.is_in(chapter_ids.clone()))
+            .all(db)
+            .await?;
+        let watched = WatchEvent::find()
+            .select_only()
+            .column_as(watch_event::Column::UserId, "user_id")
+            .column_as(watch_event::Column::ChapterId, "chapter_id")
+            .column_as(watch_event::Column::CreatedAt.min(), "first_watched_at")
+            .column_as(
+                Expr::expr(
+                    Expr::col(watch_event::Column::EndSecond)
+                        .sub(Expr::col(watch_event::Column::StartSecond)),
+                )
+                .sum(),
+                "seconds",
+            )
+            .filter(watch_event::Column::ChapterId.is_in(chapter_ids))
+            .group_by(watch_event::Column::UserId)
+            .group_by(watch_event::Column::ChapterId)
+            .into_model::<WatchTotals>()
+            .all(db)
+            .await?;
+
+        // Everything below is keyed by (chapter, student).
+        let mut reached: HashMap<&str, HashSet<&str>> = HashMap::new();
+        let mut first_watched: HashMap<(&str, &str), NaiveDateTime> = HashMap::new();
+        let mut watch_seconds: HashMap<&str, i64> = HashMap::new();
+        for totals in &watched {
+            reached
+                .entry(&totals.chapter_id)
+                .or_default()
+                .insert(&totals.user_id);
+            first_watched.insert(
+                (&totals.chapter_id, &totals.user_id),
+                totals.first_watched_at,
+            );
+            *watch_seconds.entry(&totals.chapter_id).or_default() += totals.seconds;
+        }
+        for row in &progress {
+            reached
+                .entry(&row.chapter_id)
+                .or_default()
+                .insert(&row.user_id);
+        }
+
+        // The position of the furthest chapter each student reached.
+        let mut furthest: HashMap<&str, i32> = HashMap::new();
+        for chapter in &chapters {
+            for user_id in reached.get(chapter.id.as_str()).into_iter().flatten() {
+                furthest.insert(user_id, chapter.position);
+            }
+        }
+
+        let last_position = chapters.last().map(|chapter| chapter.position);
+        let chapters = chapters
+            .iter()
+            .map(|chapter| {
+                let students = reached.get(chapter.id.as_str());
+                let reached_count = students.map_or(0, |students| students.len()) as i64;
+
+                let mut durations: Vec<i64> = progress
+                    .iter()
+                    .filter(|row| row.chapter_id == chapter.id && row.is_completed)
+                    .map(|row| {
+                        let started_at = first_watched
+                            .get(&(row.chapter_id.as_str(), row.user_id.as_str()))
+                            .map_or(row.created_at, |watched| (*watched).min(row.created_at));
+                        let completed_at = row.completed_at.unwrap_or(row.updated_at);
+                        (completed_at - started_at).num_seconds().max(0)
+                    })
+                    .collect();
+                let completed = durations.len() as i64;
+                durations.sort_unstable();
+
+                let dropped = students.map_or(0, |students| {
+                    students
+                        .iter()
+                        .filter(|user_id| furthest[**user_id] == chapter.position)
+                        .count()
+                }) as i64;
+
+                ChapterEngagement {
+                    chapter_id: chapter.id.clone(),
+                    title: chapter.title.clone(),
+                    position: chapter.position,
+                    reached: reached_count,
+                    completed,
+                    completion_rate: rate(completed, reached_count),
+                    drop_off_rate: (Some(chapter.position) != last_position)
+                        .then(|| rate(dropped, reached_count)),
+                    median_seconds_to_complete: median(&durations),
+                    watch_seconds: watch_seconds.get(chapter.id.as_str()).copied().unwrap_or(0),
+                }
+            })
+            .collect();
+
+        Ok(CourseEngagement {
+            course_id,
+            learners: furthest.len() as i64,
+            chapters,
+        })
+    }
+
+    /// [`Teacher::course_engagement`] as CSV, one row per chapter.
+    pub async fn course_engagement_csv(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<String, DbErr> {
+        let engagement = Self::course_engagement(db, user_id, course_id).await?;
+
+        let mut csv = String::from(
+            "position,chapter,reached,completed,completionRate,dropOffRate,medianSecondsToComplete,watchSeconds\n",
+        );
+        for chapter in engagement.chapters {
+            csv.push_str(&format!(
+                "{},{},{},{},{:.4},{},{},{}\n",
+                chapter.position,
+                csv_field(&chapter.title),
+                chapter.reached,
+                chapter.completed,
+                chapter.completion_rate,
+                chapter
+                    .drop_off_rate
+                    .map(|rate| format!("{:.4}", rate))
+                    .unwrap_or_default(),
+                chapter
+                    .median_seconds_to_complete
+                    .map(|seconds| seconds.to_string())
+                    .unwrap_or_default(),
+                chapter.watch_seconds,
+            ));
+        }
+
+        Ok(csv)
+    }
+}
+
+fn rate(count: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+// `values` must be sorted.
+fn median(values: &[i64]) -> Option<i64> {
+    match values.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(values[len / 2]),
+        len => Some((values[len / 2 - 1] + values[len / 2]) / 2),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
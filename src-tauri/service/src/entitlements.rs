@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use ::entities::{
+    prelude::*,
+    sea_orm_active_enums::{ProductKind, PurchaseStatus},
+    *,
+};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+
+use crate::purchases;
+
+/// What gives a student access to a course.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EntitlementSource {
+    Purchase,
+    Bundle,
+    Subscription,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Entitlement {
+    pub source: EntitlementSource,
+    /// When access started. Dripped chapters count from here.
+    pub granted_at: NaiveDateTime,
+    /// When access ends, for subscriptions.
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// The student's access to `course_id` at `now`, if any. Every check of
+/// whether a student may open a course goes through here.
+pub(crate) async fn entitlement(
+    db: &DbConn,
+    user_id: &str,
+    course_id: &str,
+    now: NaiveDateTime,
+) -> Result<Option<Entitlement>, DbErr> {
+    Ok(grants(db, user_id, Some(course_id), now)
+        .await?
+        .remove(course_id))
+}
+
+/// A published chapter the student can open now: free, or in a course they
+/// have access to.
+pub(crate) async fn accessible_chapter(
+    db: &DbConn,
+    user_id: &str,
+    chapter_id: &str,
+) -> Result<chapter::Model, DbErr> {
+    let chapter = Chapter::find_by_id(chapter_id)
+        .filter(chapter::Column::IsPublished.eq(true))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find chapter".into()))?;
+    if !chapter.is_free
+        && entitlement(db, user_id, &chapter.course_id, Utc::now().naive_utc())
+            .await?
+            .is_none()
+    {
+        return Err(DbErr::RecordNotFound("Cannot find chapter".into()));
+    }
+    Ok(chapter)
+}
+
+/// Every course the student can open at `now`, keyed by course id.
+pub(crate) async fn entitlements(
+    db: &DbConn,
+    user_id: &str,
+    now: NaiveDateTime,
+) -> Result<HashMap<String, Entitlement>, DbErr> {
+    grants(db, user_id, None, now).await
+}
+
+/// Product purchases that currently give access: paid and, for
+/// subscriptions, not yet expired.
+pub(crate) fn product_grants_access(now: NaiveDateTime) -> Condition {
+    Condition::all()
+        .add(product_purchase::Column::Status.eq(PurchaseStatus::Paid))
+        .add(
+            Condition::any()
+                .add(product_purchase::Column::ExpiresAt.is_null())
+                .add(product_purchase::Column::ExpiresAt.gt(now)),
+        )
+}
+
+// A course bought on its own wins over a bundle, and a bundle over a
+// subscription, since those never run out.
+async fn grants(
+    db: &DbConn,
+    user_id: &str,
+    course_id: Option<&str>,
+    now: NaiveDateTime,
+) -> Result<HashMap<String, Entitlement>, DbErr> {
+    let mut granted = HashMap::new();
+
+    let purchases = Purchase::find()
+        .filter(purchase::Column::UserId.eq(user_id))
+        .filter(purchases::grants_access())
+        .apply_if(course_id, |query, course_id| {
+            query.filter(purchase::Column::CourseId.eq(course_id))
+        })
+        .all(db)
+        .await?;
+    for purchase in purchases {
+        granted.insert(
+            purchase.course_id,
+            Entitlement {
+                source: EntitlementSource::Purchase,
+                granted_at: purchase.paid_at.unwrap_or(purchase.created_at),
+                expires_at: None,
+            },
+        );
+    }
+
+    let products = ProductPurchase::find()
+        .filter(product_purchase::Column::UserId.eq(user_id))
+        .filter(product_grants_access(now))
+        .find_also_related(Product)
+        .all(db)
+        .await?;
+    let mut bundles = HashMap::new();
+    let mut subscriptions = HashMap::new();
+    for (purchase, product) in products {
+        let Some(product) = product else { continue };
+        let entitlement = Entitlement {
+            source: match product.kind {
+                ProductKind::Bundle => EntitlementSource::Bundle,
+                ProductKind::Subscription => EntitlementSource::Subscription,
+            },
+            granted_at: purchase.paid_at.unwrap_or(purchase.created_at),
+            expires_at: purchase.expires_at,
+        };
+        match product.kind {
+            ProductKind::Bundle => bundles.insert(product.id, entitlement),
+            ProductKind::Subscription => subscriptions.insert(product.user_id, entitlement),
+        };
+    }
+
+    if !bundles.is_empty() {
+        let bundled = ProductCourse::find()
+            .filter(product_course::Column::ProductId.is_in(bundles.keys().cloned()))
+            .apply_if(course_id, |query, course_id| {
+                query.filter(product_course::Column::CourseId.eq(course_id))
+            })
+            .all(db)
+            .await?;
+        for item in bundled {
+            granted
+                .entry(item.course_id)
+                .or_insert_with(|| bundles[&item.product_id].clone());
+        }
+    }
+
+    if !subscriptions.is_empty() {
+        let subscribed = Course::find()
+            .filter(course::Column::UserId.is_in(subscriptions.keys().cloned()))
+            .filter(course::Column::IsPublished.eq(true))
+            .apply_if(course_id, |query, course_id| {
+                query.filter(course::Column::Id.eq(course_id))
+            })
+            .all(db)
+            .await?;
+        for course in subscribed {
+            granted
+                .entry(course.id)
+                .or_insert_with(|| subscriptions[&course.user_id].clone());
+        }
+    }
+
+    Ok(granted)
+}
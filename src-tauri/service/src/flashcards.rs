@@ -0,0 +1,560 @@
+use std::collections::{HashMap, HashSet};
+
+use ::entities::{prelude::*, *};
+use chrono::{Duration, NaiveDate, NaiveTime};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::{chrono::Utc, Uuid};
+
+use crate::{
+    discussions, entitlements, search,
+    validation::{UpdateError, Validator},
+};
+
+pub struct Flashcards;
+
+pub const CARD_MAX_LEN: usize = 1000;
+
+/// How many cards the local model is asked for at a time.
+pub const GENERATED_CARDS: usize = 5;
+
+/// Days of review counts shown on the dashboard, today included.
+pub const REVIEW_STATS_DAYS: i64 = 7;
+
+// Ease is kept in thousandths, so 2500 is SM-2's starting factor of 2.5.
+const STARTING_EASE: i32 = 2500;
+const MIN_EASE: i32 = 1300;
+const MAX_EASE: i32 = 5000;
+
+/// The longest a card goes between reviews, however well it is known.
+pub const MAX_REVIEW_INTERVAL_DAYS: i32 = 365;
+
+// The local model has a small context window, so only the start of a long
+// chapter and its notes go into a prompt.
+const PROMPT_CONTEXT_LEN: usize = 2000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NewCard {
+    pub front: String,
+    pub back: String,
+    /// The chapter the card is about, in the deck's course.
+    pub chapter_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct CardUpdate {
+    pub front: String,
+    pub back: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeckSummary {
+    #[serde(flatten)]
+    pub deck: deck::Model,
+    pub course_title: String,
+    pub cards: i64,
+    /// Cards due for review now.
+    pub due: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyReviews {
+    pub date: NaiveDate,
+    pub reviews: i64,
+}
+
+/// The student's reviews for the dashboard. Days are UTC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewStats {
+    pub due: i64,
+    pub reviewed_today: i64,
+    /// The last `REVIEW_STATS_DAYS` days, oldest first, including days
+    /// without reviews.
+    pub daily: Vec<DailyReviews>,
+}
+
+impl Flashcards {
+    /// Starts a deck for a course the user teaches or can open.
+    pub async fn create_deck(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+        title: String,
+    ) -> Result<deck::Model, UpdateError> {
+        let mut validator = Validator::default();
+        validator.title("title", &Some(title.clone()));
+        validator.finish()?;
+        course_access(db, &user_id, &course_id).await?;
+
+        let now = Utc::now().naive_utc();
+        Ok(deck::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            user_id: Set(user_id),
+            course_id: Set(course_id),
+            title: Set(title.trim().to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await?)
+    }
+
+    /// The user's decks with how many cards they hold and how many are due.
+    pub async fn decks(db: &DbConn, user_id: String) -> Result<Vec<DeckSummary>, DbErr> {
+        let decks = Deck::find()
+            .find_also_related(Course)
+            .filter(deck::Column::UserId.eq(user_id))
+            .order_by_asc(deck::Column::CreatedAt)
+            .all(db)
+            .await?;
+        let now = Utc::now().naive_utc();
+        let mut counts: HashMap<String, (i64, i64)> = HashMap::new();
+        for card in Flashcard::find()
+            .filter(flashcard::Column::DeckId.is_in(decks.iter().map(|(d, _)| d.id.clone())))
+            .all(db)
+            .await?
+        {
+            let count = counts.entry(card.deck_id).or_default();
+            count.0 += 1;
+            if card.due_at <= now {
+                count.1 += 1;
+            }
+        }
+
+        Ok(decks
+            .into_iter()
+            .filter_map(|(deck, course)| {
+                let (cards, due) = counts.remove(&deck.id).unwrap_or_default();
+                Some(DeckSummary {
+                    course_title: course?.title,
+                    deck,
+                    cards,
+                    due,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn delete_deck(db: &DbConn, user_id: String, deck_id: String) -> Result<(), DbErr> {
+        own_deck(db, &user_id, &deck_id).await?.delete(db).await?;
+        Ok(())
+    }
+
+    /// Every card in one of the user's decks, oldest first.
+    pub async fn cards(
+        db: &DbConn,
+        user_id: String,
+        deck_id: String,
+    ) -> Result<Vec<flashcard::Model>, DbErr> {
+        own_deck(db, &user_id, &deck_id).await?;
+        Flashcard::find()
+            .filter(flashcard::Column::DeckId.eq(deck_id))
+            .order_by_asc(flashcard::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// Adds a card written by hand. New cards are due right away.
+    pub async fn create_card(
+        db: &DbConn,
+        user_id: String,
+        deck_id: String,
+        card: NewCard,
+    ) -> Result<flashcard::Model, UpdateError> {
+        let mut validator = Validator::default();
+        validate(&mut validator, &card.front, &card.back);
+        validator.finish()?;
+        let deck = own_deck(db, &user_id, &deck_id).await?;
+        if let Some(chapter_id) = &card.chapter_id {
+            deck_chapter(db, &user_id, &deck, chapter_id).await?;
+        }
+
+        Ok(
+            new_card(deck_id, card.chapter_id, &card.front, &card.back, false)
+                .insert(db)
+                .await?,
+        )
+    }
+
+    /// Rewrites a card. Its schedule is kept.
+    pub async fn update_card(
+        db: &DbConn,
+        user_id: String,
+        card_id: String,
+        update: CardUpdate,
+    ) -> Result<flashcard::Model, UpdateError> {
+        let mut validator = Validator::default();
+        validate(&mut validator, &update.front, &update.back);
+        validator.finish()?;
+        let card = own_card(db, &user_id, &card_id).await?;
+
+        let mut card: flashcard::ActiveModel = card.into();
+        card.front = Set(update.front.trim().to_string());
+        card.back = Set(update.back.trim().to_string());
+        card.updated_at = Set(Utc::now().naive_utc());
+        Ok(card.update(db).await?)
+    }
+
+    pub async fn delete_card(db: &DbConn, user_id: String, card_id: String) -> Result<(), DbErr> {
+        own_card(db, &user_id, &card_id).await?.delete(db).await?;
+        Ok(())
+    }
+
+    /// A prompt asking the local model for cards on a chapter, from its
+    /// description and the user's notes on it. The answer goes back through
+    /// `add_generated`.
+    pub async fn generation_prompt(
+        db: &DbConn,
+        user_id: String,
+        deck_id: String,
+        chapter_id: String,
+    ) -> Result<String, DbErr> {
+        let deck = own_deck(db, &user_id, &deck_id).await?;
+        let chapter = deck_chapter(db, &user_id, &deck, &chapter_id).await?;
+        let notes = Note::find()
+            .filter(note::Column::UserId.eq(user_id))
+            .filter(note::Column::ChapterId.eq(chapter_id))
+            .order_by_asc(note::Column::CreatedAt)
+            .all(db)
+            .await?;
+
+        let mut context =
+            discussions::plain_text(chapter.description.as_deref().unwrap_or_default());
+        for note in notes {
+            for text in [note.highlight, note.body].into_iter().flatten() {
+                context.push_str(&format!("\n- {}", text.trim()));
+            }
+        }
+        let context = match context.char_indices().nth(PROMPT_CONTEXT_LEN) {
+            Some((end, _)) => format!("{}…", &context[..end]),
+            None => context,
+        };
+
+        Ok(format!(
+            "Hãy tạo tối đa {} thẻ ghi nhớ để ôn tập bài \"{}\". Mỗi thẻ gồm một dòng \
+             bắt đầu bằng \"Hỏi:\" và một dòng bắt đầu bằng \"Đáp:\". Câu trả lời ngắn gọn, \
+             chỉ dựa vào nội dung sau.\n{}",
+            GENERATED_CARDS,
+            chapter.title.trim(),
+            context
+        ))
+    }
+
+    /// Turns the model's answer to `generation_prompt` into cards. Pairs the
+    /// model got wrong and questions already in the deck are skipped.
+    pub async fn add_generated(
+        db: &DbConn,
+        user_id: String,
+        deck_id: String,
+        chapter_id: String,
+        text: String,
+    ) -> Result<Vec<flashcard::Model>, UpdateError> {
+        let deck = own_deck(db, &user_id, &deck_id).await?;
+        deck_chapter(db, &user_id, &deck, &chapter_id).await?;
+        let mut fronts: HashSet<String> = Flashcard::find()
+            .filter(flashcard::Column::DeckId.eq(deck_id.clone()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|card| search::normalize(&card.front))
+            .collect();
+
+        let cards: Vec<flashcard::ActiveModel> = parse_cards(&text)
+            .into_iter()
+            .filter(|(front, back)| {
+                let mut validator = Validator::default();
+                validate(&mut validator, front, back);
+                validator.finish().is_ok() && fronts.insert(search::normalize(front))
+            })
+            .take(GENERATED_CARDS)
+            .map(|(front, back)| {
+                new_card(
+                    deck_id.clone(),
+                    Some(chapter_id.clone()),
+                    &front,
+                    &back,
+                    true,
+                )
+            })
+            .collect();
+        if cards.is_empty() {
+            let mut validator = Validator::default();
+            validator.error("text", "No new cards could be read from the answer");
+            validator.finish()?;
+        }
+
+        let txn = db.begin().await?;
+        let mut added = Vec::with_capacity(cards.len());
+        for card in cards {
+            added.push(card.insert(&txn).await?);
+        }
+        txn.commit().await?;
+        Ok(added)
+    }
+
+    /// Cards due for review now across the user's decks, or in one deck,
+    /// the longest overdue first.
+    pub async fn due(
+        db: &DbConn,
+        user_id: String,
+        deck_id: Option<String>,
+    ) -> Result<Vec<flashcard::Model>, DbErr> {
+        Flashcard::find()
+            .inner_join(Deck)
+            .filter(deck::Column::UserId.eq(user_id))
+            .apply_if(deck_id, |query, deck_id| {
+                query.filter(flashcard::Column::DeckId.eq(deck_id))
+            })
+            .filter(flashcard::Column::DueAt.lte(Utc::now().naive_utc()))
+            .order_by_asc(flashcard::Column::DueAt)
+            .all(db)
+            .await
+    }
+
+    /// Records a review with `grade` from 0 (forgotten) to 5 (perfect
+    /// recall) and schedules the card's next one. A card reviewed before it
+    /// is due keeps its schedule; the review only counts towards the stats.
+    pub async fn review(
+        db: &DbConn,
+        user_id: String,
+        card_id: String,
+        grade: i32,
+    ) -> Result<flashcard::Model, UpdateError> {
+        if !(0..=5).contains(&grade) {
+            let mut validator = Validator::default();
+            validator.error("grade", "Grade must be between 0 and 5");
+            validator.finish()?;
+        }
+        let card = own_card(db, &user_id, &card_id).await?;
+        let now = Utc::now().naive_utc();
+
+        let txn = db.begin().await?;
+        flashcard_review::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            flashcard_id: Set(card.id.clone()),
+            user_id: Set(user_id),
+            grade: Set(grade),
+            reviewed_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+        let due = card.due_at <= now;
+        let (ease, interval, repetitions) =
+            schedule(card.ease, card.interval, card.repetitions, grade);
+        let mut card: flashcard::ActiveModel = card.into();
+        if due {
+            card.ease = Set(ease);
+            card.interval = Set(interval);
+            card.repetitions = Set(repetitions);
+            card.due_at = Set(now + Duration::days(interval.into()));
+        }
+        card.last_reviewed_at = Set(Some(now));
+        card.updated_at = Set(now);
+        let card = card.update(&txn).await?;
+        txn.commit().await?;
+        Ok(card)
+    }
+
+    pub async fn review_stats(db: &DbConn, user_id: String) -> Result<ReviewStats, DbErr> {
+        let now = Utc::now().naive_utc();
+        let today = now.date();
+        let first_day = today - Duration::days(REVIEW_STATS_DAYS - 1);
+
+        let due = Flashcard::find()
+            .inner_join(Deck)
+            .filter(deck::Column::UserId.eq(user_id.clone()))
+            .filter(flashcard::Column::DueAt.lte(now))
+            .count(db)
+            .await? as i64;
+        let mut per_day: HashMap<NaiveDate, i64> = HashMap::new();
+        for review in FlashcardReview::find()
+            .filter(flashcard_review::Column::UserId.eq(user_id))
+            .filter(flashcard_review::Column::ReviewedAt.gte(first_day.and_time(NaiveTime::MIN)))
+            .all(db)
+            .await?
+        {
+            *per_day.entry(review.reviewed_at.date()).or_default() += 1;
+        }
+
+        let daily: Vec<DailyReviews> = first_day
+            .iter_days()
+            .take(REVIEW_STATS_DAYS as usize)
+            .map(|date| DailyReviews {
+                date,
+                reviews: per_day.get(&date).copied().unwrap_or_default(),
+            })
+            .collect();
+        Ok(ReviewStats {
+            due,
+            reviewed_today: per_day.get(&today).copied().unwrap_or_default(),
+            daily,
+        })
+    }
+}
+
+/// SM-2: the ease, interval in days and run of successful reviews after a
+/// review with `grade`. A grade under 3 starts the card over without
+/// touching its ease. Ease stops at `MAX_EASE` and the interval at
+/// `MAX_REVIEW_INTERVAL_DAYS`, so a card reviewed well for years never overflows.
+pub(crate) fn schedule(ease: i32, interval: i32, repetitions: i32, grade: i32) -> (i32, i32, i32) {
+    if grade < 3 {
+        return (ease, 1, 0);
+    }
+    let miss = 5 - grade;
+    let ease = (ease + 100 - miss * (80 + miss * 20)).clamp(MIN_EASE, MAX_EASE);
+    let interval = match repetitions {
+        0 => 1,
+        1 => 6,
+        _ => {
+            let next = i64::from(interval)
+                .saturating_mul(ease.into())
+                .saturating_add(500)
+                / 1000;
+            next.min(MAX_REVIEW_INTERVAL_DAYS.into()) as i32
+        }
+    };
+    (ease, interval, repetitions.saturating_add(1))
+}
+
+/// Question and answer pairs in the model's answer. Lines may be numbered
+/// or bulleted, and an answer may run over several lines.
+fn parse_cards(text: &str) -> Vec<(String, String)> {
+    const FRONT: [&str; 3] = ["hoi:", "cau hoi:", "q:"];
+    const BACK: [&str; 3] = ["dap:", "tra loi:", "a:"];
+    let strip = |line: &str, prefixes: &[&str]| {
+        let normalized = search::normalize(line);
+        prefixes.iter().find_map(|prefix| {
+            normalized.starts_with(prefix).then(|| {
+                line.chars()
+                    .skip(prefix.chars().count())
+                    .collect::<String>()
+            })
+        })
+    };
+
+    let mut cards = Vec::new();
+    let mut front: Option<String> = None;
+    let mut back: Option<String> = None;
+    for line in text.lines() {
+        let line = line
+            .trim()
+            .trim_start_matches(|c: char| c.is_ascii_digit() || ".)-*# ".contains(c));
+        if let Some(question) = strip(line, &FRONT) {
+            if let (Some(front), Some(back)) = (front.take(), back.take()) {
+                cards.push((front, back));
+            }
+            front = Some(question.trim().to_string());
+        } else if let Some(answer) = strip(line, &BACK) {
+            if front.is_some() {
+                back = Some(answer.trim().to_string());
+            }
+        } else if let (Some(back), false) = (back.as_mut(), line.is_empty()) {
+            back.push('\n');
+            back.push_str(line);
+        }
+    }
+    if let (Some(front), Some(back)) = (front, back) {
+        cards.push((front, back));
+    }
+    cards
+}
+
+fn new_card(
+    deck_id: String,
+    chapter_id: Option<String>,
+    front: &str,
+    back: &str,
+    is_generated: bool,
+) -> flashcard::ActiveModel {
+    let now = Utc::now().naive_utc();
+    flashcard::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        deck_id: Set(deck_id),
+        chapter_id: Set(chapter_id),
+        front: Set(front.trim().to_string()),
+        back: Set(back.trim().to_string()),
+        is_generated: Set(is_generated),
+        ease: Set(STARTING_EASE),
+        interval: Set(0),
+        repetitions: Set(0),
+        due_at: Set(now),
+        last_reviewed_at: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+}
+
+/// The course, if the user teaches it or has access to it.
+async fn course_access(
+    db: &DbConn,
+    user_id: &str,
+    course_id: &str,
+) -> Result<course::Model, DbErr> {
+    let not_found = || DbErr::RecordNotFound("Cannot find course".into());
+    let course = Course::find_by_id(course_id)
+        .one(db)
+        .await?
+        .ok_or_else(not_found)?;
+    if course.user_id != user_id
+        && entitlements::entitlement(db, user_id, course_id, Utc::now().naive_utc())
+            .await?
+            .is_none()
+    {
+        return Err(not_found());
+    }
+    Ok(course)
+}
+
+/// A chapter of the deck's course that the user can open.
+async fn deck_chapter(
+    db: &DbConn,
+    user_id: &str,
+    deck: &deck::Model,
+    chapter_id: &str,
+) -> Result<chapter::Model, DbErr> {
+    let not_found = || DbErr::RecordNotFound("Cannot find chapter".into());
+    let course = course_access(db, user_id, &deck.course_id).await?;
+    let chapter = Chapter::find_by_id(chapter_id)
+        .filter(chapter::Column::CourseId.eq(deck.course_id.clone()))
+        .one(db)
+        .await?
+        .ok_or_else(not_found)?;
+    if course.user_id != user_id {
+        entitlements::accessible_chapter(db, user_id, chapter_id).await?;
+    }
+    Ok(chapter)
+}
+
+async fn own_deck(db: &DbConn, user_id: &str, deck_id: &str) -> Result<deck::Model, DbErr> {
+    Deck::find_by_id(deck_id)
+        .filter(deck::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find deck".into()))
+}
+
+async fn own_card(db: &DbConn, user_id: &str, card_id: &str) -> Result<flashcard::Model, DbErr> {
+    Flashcard::find_by_id(card_id)
+        .inner_join(Deck)
+        .filter(deck::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find card".into()))
+}
+
+fn validate(validator: &mut Validator, front: &str, back: &str) {
+    for (field, text) in [("front", front), ("back", back)] {
+        let len = text.trim().chars().count();
+        if len == 0 || len > CARD_MAX_LEN {
+            validator.error(
+                field,
+                &format!("Must be between 1 and {} characters", CARD_MAX_LEN),
+            );
+        }
+    }
+}
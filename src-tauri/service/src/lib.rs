@@ -1,11 +1,67 @@
+mod assignments;
+mod certificates;
 mod chapters;
+mod coupons;
 mod courses;
+mod discussions;
+mod engagement;
+mod entitlements;
+mod flashcards;
+mod loaders;
+mod media;
+mod notes;
 mod other_routes;
+mod payments;
+mod products;
+mod progress;
+mod purchases;
+mod revisions;
+mod schedule;
+mod search;
 mod teacher;
+mod validation;
 
+pub use assignments::{
+    AssignmentWithSubmission, Assignments, Grade, NewAssignment, NewSubmission, SubmissionToGrade,
+};
+pub use certificates::{
+    check_keys as check_certificate_keys, CertificatePayload, CertificateSettings, Certificates,
+    Verification,
+};
 pub use chapters::*;
+pub use coupons::{Coupons, NewCoupon};
 pub use courses::*;
+pub use discussions::{
+    ChapterThreads, CommentView, Discussions, NewComment, Thread, UnreadComments, COMMENT_MAX_LEN,
+};
+pub use engagement::{ChapterEngagement, CourseEngagement};
+pub use entitlements::{Entitlement, EntitlementSource};
+pub use flashcards::{
+    CardUpdate, DailyReviews, DeckSummary, Flashcards, NewCard, ReviewStats, CARD_MAX_LEN,
+    GENERATED_CARDS, MAX_REVIEW_INTERVAL_DAYS, REVIEW_STATS_DAYS,
+};
+pub use media::{
+    sign as sign_media_request, CloudinaryConfig, MediaKind, SignRequest, SignResponse,
+    DEFAULT_CLOUDINARY_API_BASE,
+};
+pub use notes::{NewNote, NoteUpdate, NoteWithSource, Notes, NOTE_MAX_LEN};
 pub use other_routes::*;
+pub use payments::{
+    is_relayed as is_relayed_stripe_call, CheckoutSession, Payments, StripeConfig, WebhookError,
+};
+pub use products::{NewProduct, ProductCheckout, ProductWithCourses, Products};
+pub use progress::{
+    ProgressUpdate, WatchHeartbeat, WatchSettings, WatchThrottle, DEFAULT_COMPLETE_AT_PERCENT,
+    DEFAULT_HEARTBEAT_INTERVAL_SECS,
+};
+pub use purchases::Purchases;
+pub use revisions::{CourseSnapshot, RevisionChange, RevisionSummary, Revisions};
+pub use schedule::{ChapterSchedule, CourseSchedule, Schedule};
+pub use search::{
+    CategoryFacet, PriceFacet, PriceRange, SearchFacets, SearchQuery, DEFAULT_PER_PAGE,
+    MAX_PER_PAGE,
+};
 pub use teacher::*;
+pub use validation::{FieldError, UpdateError};
 
 pub use sea_orm;
@@ -0,0 +1,136 @@
+//! Batched loaders for pages that list many courses at once. Each one runs a
+//! fixed number of queries however many ids it is given, so a page costs the
+//! same number of round trips with 5 courses or 500.
+
+use std::collections::{HashMap, HashSet};
+
+use ::entities::{prelude::*, *};
+use sea_orm::{sea_query::Expr, *};
+
+// Keeps `IN (...)` lists under SQLite's limit on bound parameters.
+const CHUNK_SIZE: usize = 500;
+
+/// Published chapters of each of `course_ids`, in order.
+pub(crate) async fn published_chapters(
+    db: &DbConn,
+    course_ids: &[String],
+) -> Result<HashMap<String, Vec<chapter::Model>>, DbErr> {
+    let mut chapters: HashMap<String, Vec<chapter::Model>> = HashMap::new();
+    for ids in course_ids.chunks(CHUNK_SIZE) {
+        let loaded = Chapter::find()
+            .filter(chapter::Column::CourseId.is_in(ids.iter().cloned()))
+            .filter(chapter::Column::IsPublished.eq(true))
+            .order_by_asc(chapter::Column::Position)
+            .all(db)
+            .await?;
+        for chapter in loaded {
+            chapters
+                .entry(chapter.course_id.clone())
+                .or_default()
+                .push(chapter);
+        }
+    }
+    Ok(chapters)
+}
+
+/// Which of `chapter_ids` the student has completed.
+pub(crate) async fn completed_chapters(
+    db: &DbConn,
+    user_id: &str,
+    chapter_ids: &[String],
+) -> Result<HashSet<String>, DbErr> {
+    let mut completed = HashSet::new();
+    for ids in chapter_ids.chunks(CHUNK_SIZE) {
+        let loaded = UserProgress::find()
+            .select_only()
+            .column(user_progress::Column::ChapterId)
+            .filter(user_progress::Column::UserId.eq(user_id))
+            .filter(user_progress::Column::ChapterId.is_in(ids.iter().cloned()))
+            .filter(user_progress::Column::IsCompleted.eq(true))
+            .into_tuple::<String>()
+            .all(db)
+            .await?;
+        completed.extend(loaded);
+    }
+    Ok(completed)
+}
+
+/// What counts towards a student's progress in a course: each published
+/// chapter, done once completed, and each assignment in those chapters, done
+/// once its submission is graded.
+pub(crate) struct ProgressItems {
+    completed: HashSet<String>,
+    /// Assignments in each chapter, and whether they are graded.
+    assignments: HashMap<String, Vec<bool>>,
+}
+
+impl ProgressItems {
+    /// Loads the student's items in all of `chapter_ids` at once.
+    pub(crate) async fn load(
+        db: &DbConn,
+        user_id: &str,
+        chapter_ids: &[String],
+    ) -> Result<Self, DbErr> {
+        let completed = completed_chapters(db, user_id, chapter_ids).await?;
+        let mut assignments: HashMap<String, Vec<bool>> = HashMap::new();
+        for ids in chapter_ids.chunks(CHUNK_SIZE) {
+            let user_id = user_id.to_string();
+            let loaded = Assignment::find()
+                .select_only()
+                .column(assignment::Column::ChapterId)
+                .column_as(
+                    Expr::col((Submission, submission::Column::Score)).is_not_null(),
+                    "graded",
+                )
+                .join(
+                    JoinType::LeftJoin,
+                    assignment::Relation::Submission
+                        .def()
+                        .on_condition(move |_, submission| {
+                            Condition::all().add(
+                                Expr::col((submission, submission::Column::UserId))
+                                    .eq(user_id.clone()),
+                            )
+                        }),
+                )
+                .filter(assignment::Column::ChapterId.is_in(ids.iter().cloned()))
+                .into_tuple::<(String, bool)>()
+                .all(db)
+                .await?;
+            for (chapter_id, graded) in loaded {
+                assignments.entry(chapter_id).or_default().push(graded);
+            }
+        }
+        Ok(ProgressItems {
+            completed,
+            assignments,
+        })
+    }
+
+    /// How many items of a course with `chapter_ids` are done, out of how
+    /// many.
+    pub(crate) fn tally<'a>(
+        &self,
+        chapter_ids: impl IntoIterator<Item = &'a String>,
+    ) -> (usize, usize) {
+        chapter_ids.into_iter().fold((0, 0), |(done, total), id| {
+            let graded = self.assignments.get(id).map_or(&[][..], Vec::as_slice);
+            (
+                done + usize::from(self.completed.contains(id))
+                    + graded.iter().filter(|graded| **graded).count(),
+                total + 1 + graded.len(),
+            )
+        })
+    }
+
+    /// Share of the items of a course with `chapter_ids` that are done, as a
+    /// whole percentage. A course without chapters is at 0.
+    pub(crate) fn percent<'a>(&self, chapter_ids: impl IntoIterator<Item = &'a String>) -> i8 {
+        let (done, total) = self.tally(chapter_ids);
+        if total > 0 {
+            ((done as f32 / total as f32) * 100.0) as i8
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Deleting media from Cloudinary without the account's API secret.
+//!
+//! Cloudinary only accepts a destroy call signed with the API secret, and
+//! anything in the installed app can be read by whoever runs it. So the app
+//! asks a signer, a small service the school runs next to the database, to
+//! sign each destroy call for it and then sends the signed call to Cloudinary
+//! itself. The signer holds the secret and decides which installs it signs
+//! for through `signer_token`; it can be as little as checking the token and
+//! returning [`SignRequest::signature`].
+
+use reqwest::Client;
+use sea_orm::sqlx::types::chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+pub const DEFAULT_CLOUDINARY_API_BASE: &str = "https://api.cloudinary.com";
+
+/// Where course images and chapter videos are uploaded to. Uploads go straight
+/// from the browser; the app only needs this to delete media it no longer
+/// uses.
+#[derive(Debug, Clone)]
+pub struct CloudinaryConfig {
+    pub api_base: String,
+    pub api_key: String,
+    pub cloud_name: String,
+    pub signer_url: String,
+    pub signer_token: String,
+}
+
+/// Cloudinary keeps images and videos apart, and a destroy call has to name
+/// the right one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaKind {
+    Image,
+    Video,
+}
+
+impl MediaKind {
+    pub fn resource_type(self) -> &'static str {
+        match self {
+            MediaKind::Image => "image",
+            MediaKind::Video => "video",
+        }
+    }
+}
+
+/// What the app posts to the signer, with `signer_token` as bearer token.
+/// Signers should refuse timestamps more than a few minutes old, which
+/// Cloudinary does too.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SignRequest {
+    pub public_id: String,
+    pub resource_type: MediaKind,
+    pub timestamp: i64,
+}
+
+impl SignRequest {
+    /// Signature Cloudinary expects on the destroy call for this request.
+    pub fn signature(&self, api_secret: &str) -> String {
+        sign(
+            &[
+                ("public_id", &self.public_id),
+                ("timestamp", &self.timestamp.to_string()),
+            ],
+            api_secret,
+        )
+    }
+}
+
+/// The signer's answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignResponse {
+    pub signature: String,
+}
+
+/// Cloudinary's request signature: the parameters sorted by name, joined as a
+/// query string, followed by the API secret and hashed with SHA-1.
+pub fn sign(params: &[(&str, &str)], api_secret: &str) -> String {
+    let mut params = params.to_vec();
+    params.sort();
+    let query = params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut hasher = Sha1::new();
+    hasher.update(query.as_bytes());
+    hasher.update(api_secret.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Public id of an uploaded file, from its delivery URL: the last path
+/// segment without its extension.
+pub(crate) fn public_id(url: &str) -> &str {
+    let file = url.rsplit('/').next().unwrap_or(url);
+    file.split('.').next().unwrap_or(file)
+}
+
+impl CloudinaryConfig {
+    /// Deletes `public_id`. Media left behind only costs storage, so a failure
+    /// is logged rather than failing whatever the teacher was doing.
+    pub(crate) async fn destroy(&self, kind: MediaKind, public_id: impl Into<String>) {
+        let public_id = public_id.into();
+        if let Err(err) = self.try_destroy(kind, &public_id).await {
+            tracing::warn!("Cannot delete {} from Cloudinary: {}", public_id, err);
+        }
+    }
+
+    async fn try_destroy(&self, kind: MediaKind, public_id: &str) -> Result<(), String> {
+        let client = Client::new();
+        let request = SignRequest {
+            public_id: public_id.to_string(),
+            resource_type: kind,
+            timestamp: Utc::now().timestamp(),
+        };
+
+        let res = client
+            .post(&self.signer_url)
+            .bearer_auth(&self.signer_token)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|err| format!("cannot reach the signer: {}", err))?;
+        if !res.status().is_success() {
+            return Err(format!("the signer refused: {}", res.status()));
+        }
+        let signed: SignResponse = res
+            .json()
+            .await
+            .map_err(|err| format!("unexpected answer from the signer: {}", err))?;
+
+        let res = client
+            .post(format!(
+                "{}/v1_1/{}/{}/destroy",
+                self.api_base,
+                self.cloud_name,
+                kind.resource_type()
+            ))
+            .form(&[
+                ("public_id", public_id),
+                ("timestamp", &request.timestamp.to_string()),
+                ("api_key", &self.api_key),
+                ("signature", &signed.signature),
+            ])
+            .send()
+            .await
+            .map_err(|err| format!("cannot reach Cloudinary: {}", err))?;
+        if !res.status().is_success() {
+            return Err(format!("Cloudinary refused: {}", res.status()));
+        }
+        Ok(())
+    }
+}
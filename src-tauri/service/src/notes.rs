@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+
+use ::entities::{prelude::*, *};
+use sea_orm::{sea_query::Query, *};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{chrono::Utc, Uuid};
+
+use crate::{
+    entitlements, search,
+    validation::{UpdateError, Validator},
+};
+
+pub struct Notes;
+
+pub const NOTE_MAX_LEN: usize = 10000;
+
+/// A note on a chapter, or on a page of a course attachment.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NewNote {
+    pub chapter_id: Option<String>,
+    pub attachment_id: Option<String>,
+    /// The PDF page, counting from 1. Only for attachments.
+    pub page: Option<i32>,
+    /// The text the student highlighted.
+    pub highlight: Option<String>,
+    pub body: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NoteUpdate {
+    pub highlight: Option<String>,
+    pub body: Option<String>,
+}
+
+/// A note found by search, with where it was taken.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteWithSource {
+    #[serde(flatten)]
+    pub note: note::Model,
+    pub course_id: String,
+    pub course_title: String,
+    pub chapter_title: Option<String>,
+    pub attachment_name: Option<String>,
+}
+
+impl Notes {
+    pub async fn create(
+        db: &DbConn,
+        user_id: String,
+        note: NewNote,
+    ) -> Result<note::Model, UpdateError> {
+        let highlight = clean(note.highlight);
+        let body = clean(note.body);
+        let mut validator = Validator::default();
+        match (&note.chapter_id, &note.attachment_id) {
+            (Some(_), None) | (None, Some(_)) => {}
+            _ => validator.error(
+                "chapterId",
+                "A note belongs to either a chapter or an attachment",
+            ),
+        }
+        match note.page {
+            Some(_) if note.attachment_id.is_none() => {
+                validator.error("page", "Only attachment notes have a page")
+            }
+            Some(page) if page < 1 => validator.error("page", "Page must be at least 1"),
+            _ => {}
+        }
+        validate(&mut validator, &highlight, &body);
+        validator.finish()?;
+
+        if let Some(chapter_id) = &note.chapter_id {
+            chapter_source(db, &user_id, chapter_id).await?;
+        }
+        if let Some(attachment_id) = &note.attachment_id {
+            attachment_source(db, &user_id, attachment_id).await?;
+        }
+
+        let now = Utc::now().naive_utc();
+        Ok(note::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            user_id: Set(user_id),
+            chapter_id: Set(note.chapter_id),
+            attachment_id: Set(note.attachment_id),
+            page: Set(note.page),
+            search_text: Set(Some(search_text(&highlight, &body))),
+            highlight: Set(highlight),
+            body: Set(body),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await?)
+    }
+
+    /// Replaces the highlight and text of one of the user's notes.
+    pub async fn update(
+        db: &DbConn,
+        user_id: String,
+        note_id: String,
+        update: NoteUpdate,
+    ) -> Result<note::Model, UpdateError> {
+        let note = own_note(db, &user_id, &note_id).await?;
+        let highlight = clean(update.highlight);
+        let body = clean(update.body);
+        let mut validator = Validator::default();
+        validate(&mut validator, &highlight, &body);
+        validator.finish()?;
+
+        let mut note: note::ActiveModel = note.into();
+        note.search_text = Set(Some(search_text(&highlight, &body)));
+        note.highlight = Set(highlight);
+        note.body = Set(body);
+        note.updated_at = Set(Utc::now().naive_utc());
+        Ok(note.update(db).await?)
+    }
+
+    pub async fn delete(db: &DbConn, user_id: String, note_id: String) -> Result<(), DbErr> {
+        own_note(db, &user_id, &note_id).await?.delete(db).await?;
+        Ok(())
+    }
+
+    /// The user's notes on a chapter, oldest first.
+    pub async fn list_for_chapter(
+        db: &DbConn,
+        user_id: String,
+        chapter_id: String,
+    ) -> Result<Vec<note::Model>, DbErr> {
+        chapter_source(db, &user_id, &chapter_id).await?;
+        Note::find()
+            .filter(note::Column::UserId.eq(user_id))
+            .filter(note::Column::ChapterId.eq(chapter_id))
+            .order_by_asc(note::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// The user's notes on an attachment, by page.
+    pub async fn list_for_attachment(
+        db: &DbConn,
+        user_id: String,
+        attachment_id: String,
+    ) -> Result<Vec<note::Model>, DbErr> {
+        attachment_source(db, &user_id, &attachment_id).await?;
+        Note::find()
+            .filter(note::Column::UserId.eq(user_id))
+            .filter(note::Column::AttachmentId.eq(attachment_id))
+            .order_by_asc(note::Column::Page)
+            .order_by_asc(note::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// The user's notes whose highlight or text has every word of `query`,
+    /// ignoring accents and matching the start of words, newest first. An
+    /// empty query lists every note.
+    pub async fn search(
+        db: &DbConn,
+        user_id: String,
+        query: String,
+        course_id: Option<String>,
+    ) -> Result<Vec<NoteWithSource>, DbErr> {
+        let mut matching = Note::find().filter(note::Column::UserId.eq(user_id));
+        for term in search::tokenize(&query) {
+            matching = matching.filter(note::Column::SearchText.contains(format!(" {}", term)));
+        }
+        if let Some(course_id) = course_id {
+            matching = matching.filter(
+                Condition::any()
+                    .add(
+                        note::Column::ChapterId.in_subquery(
+                            Query::select()
+                                .column(chapter::Column::Id)
+                                .from(Chapter)
+                                .and_where(chapter::Column::CourseId.eq(course_id.clone()))
+                                .to_owned(),
+                        ),
+                    )
+                    .add(
+                        note::Column::AttachmentId.in_subquery(
+                            Query::select()
+                                .column(attachment::Column::Id)
+                                .from(Attachment)
+                                .and_where(attachment::Column::CourseId.eq(course_id))
+                                .to_owned(),
+                        ),
+                    ),
+            );
+        }
+        let notes = matching
+            .order_by_desc(note::Column::UpdatedAt)
+            .all(db)
+            .await?;
+        with_sources(db, notes).await
+    }
+
+    /// The user's notes on a course as Markdown: chapter notes in chapter
+    /// order, then attachment notes by page.
+    pub async fn export_markdown(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<String, DbErr> {
+        let course = Course::find_by_id(course_id.clone())
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find course".into()))?;
+        let chapters = Chapter::find()
+            .filter(chapter::Column::CourseId.eq(course_id.clone()))
+            .order_by_asc(chapter::Column::Position)
+            .all(db)
+            .await?;
+        let attachments = Attachment::find()
+            .filter(attachment::Column::CourseId.eq(course_id))
+            .order_by_asc(attachment::Column::CreatedAt)
+            .all(db)
+            .await?;
+
+        let mut by_source: HashMap<String, Vec<note::Model>> = HashMap::new();
+        for note in Note::find()
+            .filter(note::Column::UserId.eq(user_id))
+            .filter(
+                Condition::any()
+                    .add(note::Column::ChapterId.is_in(chapters.iter().map(|c| c.id.clone())))
+                    .add(
+                        note::Column::AttachmentId.is_in(attachments.iter().map(|a| a.id.clone())),
+                    ),
+            )
+            .order_by_asc(note::Column::Page)
+            .order_by_asc(note::Column::CreatedAt)
+            .all(db)
+            .await?
+        {
+            let source = note
+                .chapter_id
+                .clone()
+                .or(note.attachment_id.clone())
+                .unwrap_or_default();
+            by_source.entry(source).or_default().push(note);
+        }
+
+        let mut markdown = format!("# Ghi chú: {}\n", course.title.trim());
+        for chapter in chapters {
+            if let Some(notes) = by_source.remove(&chapter.id) {
+                markdown.push_str(&format!("\n## {}\n", chapter.title.trim()));
+                for note in notes {
+                    push_note(&mut markdown, &note);
+                }
+            }
+        }
+        for attachment in attachments {
+            if let Some(notes) = by_source.remove(&attachment.id) {
+                markdown.push_str(&format!("\n## Tài liệu: {}\n", attachment.name.trim()));
+                let mut page = None;
+                for note in notes {
+                    if note.page != page {
+                        page = note.page;
+                        if let Some(page) = page {
+                            markdown.push_str(&format!("\n### Trang {}\n", page));
+                        }
+                    }
+                    push_note(&mut markdown, &note);
+                }
+            }
+        }
+        Ok(markdown)
+    }
+}
+
+/// The chapter's course, if the user can take notes on the chapter: the
+/// course teacher, or a student who can open it.
+async fn chapter_source(db: &DbConn, user_id: &str, chapter_id: &str) -> Result<(), DbErr> {
+    let course = Course::find()
+        .inner_join(Chapter)
+        .filter(chapter::Column::Id.eq(chapter_id))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find chapter".into()))?;
+    if course.user_id != user_id {
+        entitlements::accessible_chapter(db, user_id, chapter_id).await?;
+    }
+    Ok(())
+}
+
+/// Whether the user can read the attachment: the course teacher, or a
+/// student with access to the course.
+async fn attachment_source(db: &DbConn, user_id: &str, attachment_id: &str) -> Result<(), DbErr> {
+    let not_found = || DbErr::RecordNotFound("Cannot find attachment".into());
+    let (_, course) = Attachment::find_by_id(attachment_id)
+        .find_also_related(Course)
+        .one(db)
+        .await?
+        .ok_or_else(not_found)?;
+    let course = course.ok_or_else(not_found)?;
+    if course.user_id != user_id
+        && entitlements::entitlement(db, user_id, &course.id, Utc::now().naive_utc())
+            .await?
+            .is_none()
+    {
+        return Err(not_found());
+    }
+    Ok(())
+}
+
+async fn own_note(db: &DbConn, user_id: &str, note_id: &str) -> Result<note::Model, DbErr> {
+    Note::find_by_id(note_id)
+        .filter(note::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find note".into()))
+}
+
+async fn with_sources(db: &DbConn, notes: Vec<note::Model>) -> Result<Vec<NoteWithSource>, DbErr> {
+    let chapters: HashMap<String, chapter::Model> = Chapter::find()
+        .filter(chapter::Column::Id.is_in(notes.iter().filter_map(|n| n.chapter_id.clone())))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|chapter| (chapter.id.clone(), chapter))
+        .collect();
+    let attachments: HashMap<String, attachment::Model> = Attachment::find()
+        .filter(attachment::Column::Id.is_in(notes.iter().filter_map(|n| n.attachment_id.clone())))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|attachment| (attachment.id.clone(), attachment))
+        .collect();
+    let courses: HashMap<String, String> = Course::find()
+        .filter(
+            course::Column::Id.is_in(
+                chapters
+                    .values()
+                    .map(|c| c.course_id.clone())
+                    .chain(attachments.values().map(|a| a.course_id.clone())),
+            ),
+        )
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|course| (course.id, course.title))
+        .collect();
+
+    Ok(notes
+        .into_iter()
+        .filter_map(|note| {
+            let chapter = note.chapter_id.as_ref().and_then(|id| chapters.get(id));
+            let attachment = note
+                .attachment_id
+                .as_ref()
+                .and_then(|id| attachments.get(id));
+            let course_id = chapter
+                .map(|c| c.course_id.clone())
+                .or(attachment.map(|a| a.course_id.clone()))?;
+            Some(NoteWithSource {
+                course_title: courses.get(&course_id)?.clone(),
+                course_id,
+                chapter_title: chapter.map(|c| c.title.clone()),
+                attachment_name: attachment.map(|a| a.name.clone()),
+                note,
+            })
+        })
+        .collect())
+}
+
+// The normalized words of a note, each after a space, so a search term
+// matches the start of a word wherever ` term` appears.
+fn search_text(highlight: &Option<String>, body: &Option<String>) -> String {
+    search::tokenize(&format!(
+        "{} {}",
+        highlight.as_deref().unwrap_or_default(),
+        body.as_deref().unwrap_or_default()
+    ))
+    .iter()
+    .map(|word| format!(" {}", word))
+    .collect()
+}
+
+/// Fills in the search text of notes written before notes had one.
+pub(crate) async fn index_missing(db: &DbConn) -> Result<usize, DbErr> {
+    let missing = Note::find()
+        .filter(note::Column::SearchText.is_null())
+        .all(db)
+        .await?;
+    let count = missing.len();
+    for note in missing {
+        let text = search_text(&note.highlight, &note.body);
+        let mut note: note::ActiveModel = note.into();
+        note.search_text = Set(Some(text));
+        note.update(db).await?;
+    }
+    Ok(count)
+}
+
+fn clean(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn validate(validator: &mut Validator, highlight: &Option<String>, body: &Option<String>) {
+    if highlight.is_none() && body.is_none() {
+        validator.error("body", "A note needs a highlight or some text");
+    }
+    for (field, text) in [("highlight", highlight), ("body", body)] {
+        if text
+            .as_ref()
+            .is_some_and(|t| t.chars().count() > NOTE_MAX_LEN)
+        {
+            validator.error(
+                field,
+                &format!("Must be at most {} characters", NOTE_MAX_LEN),
+            );
+        }
+    }
+}
+
+fn push_note(markdown: &mut String, note: &note::Model) {
+    markdown.push('\n');
+    if let Some(highlight) = &note.highlight {
+        for line in highlight.lines() {
+            markdown.push_str(&format!("> {}\n", line));
+        }
+        if note.body.is_some() {
+            markdown.push('\n');
+        }
+    }
+    if let Some(body) = &note.body {
+        markdown.push_str(body);
+        markdown.push('\n');
+    }
+}
@@ -1,7 +1,18 @@
-use ::entities::{prelude::*, *};
+use std::collections::HashMap;
+
+use ::entities::{prelude::*, sea_orm_active_enums::PurchaseStatus, *};
 use sea_orm::*;
 use serde::{Deserialize, Serialize};
-use sqlx::types::{chrono::Utc, Uuid};
+use sqlx::types::chrono::Utc;
+
+use crate::{
+    coupons,
+    entitlements::{self, Entitlement},
+    loaders, notes,
+    payments::{self, CheckoutSession, LineItem, StripeConfig},
+    purchases,
+    search::{self, Document, PriceRange, SearchFacets, SearchQuery},
+};
 
 pub struct OtherRoutes;
 
@@ -38,71 +49,76 @@ pub struct ChapterIdOnly {
     id: String,
 }
 
+/// One page of [`OtherRoutes::search`] results.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults {
+    courses: Vec<SearchCourseWithProgressWithCategory>,
+    /// Matching courses across all pages.
+    total: u64,
+    page: u64,
+    per_page: u64,
+    facets: SearchFacets,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ChapterOfCourse {
+    id: String,
+    course_id: String,
+}
+
 impl OtherRoutes {
     pub async fn dashboard_courses(
         db: &DbConn,
         user_id: String,
     ) -> Result<DashboardCourses, DbErr> {
-        // Step 1: Fetch purchases with related courses
-        let purchases_with_courses = Purchase::find()
-            .filter(purchase::Column::UserId.eq(user_id.clone()))
-            .find_with_related(Course)
+        // Step 1: Fetch every course the user has access to, with its category
+        let granted = entitlements::entitlements(db, &user_id, Utc::now().naive_utc()).await?;
+        let courses = Course::find()
+            .filter(course::Column::Id.is_in(granted.into_keys()))
+            .find_also_related(Category)
             .all(db)
             .await?;
 
-        // Step 2: Prepare the final result
+        // Step 2: Fetch the published chapters of all of them, and which of
+        // those and their assignments the user is done with
+        let course_ids: Vec<String> = courses
+            .iter()
+            .map(|(course, _)| course.id.clone())
+            .collect();
+        let mut chapters = loaders::published_chapters(db, &course_ids).await?;
+        let chapter_ids: Vec<String> = chapters
+            .values()
+            .flatten()
+            .map(|chapter| chapter.id.clone())
+            .collect();
+        let items = loaders::ProgressItems::load(db, &user_id, &chapter_ids).await?;
+
+        // Step 3: Prepare the final result
         let mut completed_courses = Vec::new();
         let mut courses_in_progress = Vec::new();
 
-        for (_purchase, courses) in purchases_with_courses {
-            for course in courses {
-                // Fetch the category for the course
-                let category = course.find_related(Category).one(db).await?;
-
-                // Fetch all chapters for the course
-                let chapters = Chapter::find()
-                    .filter(chapter::Column::CourseId.eq(course.id.clone()))
-                    .filter(chapter::Column::IsPublished.eq(true))
-                    .all(db)
-                    .await?;
-
-                // Calculate progress
-                let total_chapters = chapters.len() as f32;
-                let completed_chapters = user_progress::Entity::find()
-                    .filter(user_progress::Column::UserId.eq(user_id.clone()))
-                    .filter(
-                        user_progress::Column::ChapterId
-                            .is_in(chapters.iter().map(|ch| ch.id.clone())),
-                    )
-                    .filter(user_progress::Column::IsCompleted.eq(true))
-                    .all(db)
-                    .await?;
-                let completed_chapters_len = completed_chapters.len() as f32;
-
-                let progress = if total_chapters > 0.0 {
-                    Some(((completed_chapters_len / total_chapters) * 100.0) as i8)
-                } else {
-                    Some(0)
-                };
-
-                // Append course with category, chapters, and progress to result
-                match progress {
-                    Some(100) => {
-                        completed_courses.push(CourseWithProgressWithCategory {
-                            course,
-                            category,
-                            chapters,
-                            progress,
-                        });
-                    }
-                    _ => {
-                        courses_in_progress.push(CourseWithProgressWithCategory {
-                            course,
-                            category,
-                            chapters,
-                            progress,
-                        });
-                    }
+        for (course, category) in courses {
+            let chapters = chapters.remove(&course.id).unwrap_or_default();
+            let progress = Some(items.percent(chapters.iter().map(|chapter| &chapter.id)));
+
+            // Append course with category, chapters, and progress to result
+            match progress {
+                Some(100) => {
+                    completed_courses.push(CourseWithProgressWithCategory {
+                        course,
+                        category,
+                        chapters,
+                        progress,
+                    });
+                }
+                _ => {
+                    courses_in_progress.push(CourseWithProgressWithCategory {
+                        course,
+                        category,
+                        chapters,
+                        progress,
+                    });
                 }
             }
         }
@@ -121,12 +137,22 @@ impl OtherRoutes {
         let result = purchase::Entity::find()
             .filter(purchase::Column::UserId.eq(user_id)) // Filter by userId
             .filter(purchase::Column::CourseId.eq(course_id)) // Filter by courseId
+            .filter(purchases::grants_access())
             .one(db) // Retrieve one record
             .await
             .unwrap();
         Ok(result)
     }
 
+    /// How `user_id` got access to `course_id`, if they have it.
+    pub async fn entitlement(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<Option<Entitlement>, DbErr> {
+        entitlements::entitlement(db, &user_id, &course_id, Utc::now().naive_utc()).await
+    }
+
     pub async fn categories(db: &DatabaseConnection) -> Result<Vec<category::Model>, DbErr> {
         let result = Category::find()
             .order_by_asc(category::Column::Name)
@@ -137,119 +163,249 @@ impl OtherRoutes {
         Ok(result)
     }
 
+    /// Published courses matching `query`, best match first, or newest
+    /// first when there is no text to rank by.
     pub async fn search(
         db: &DatabaseConnection,
         user_id: String,
-        title: Option<String>,
-        category_id: Option<String>,
-    ) -> Result<Vec<SearchCourseWithProgressWithCategory>, DbErr> {
-        // Step 1: Fetch published courses with the specified filters
-        let mut courses_query = course::Entity::find()
-            .filter(course::Column::IsPublished.eq(true))
-            .order_by_desc(course::Column::CreatedAt);
-
-        if let Some(title) = title {
-            courses_query = courses_query.filter(course::Column::Title.contains(&title));
-        }
-        if let Some(category_id) = category_id {
-            courses_query = courses_query.filter(course::Column::CategoryId.eq(category_id));
-        }
-
-        let courses_with_relations = courses_query
-            .find_also_related(category::Entity)
-            .all(db)
-            .await
-            .unwrap();
-
-        // Step 2: Fetch chapters for each course and filter by isPublished
-        let mut result = Vec::new();
-
-        for (course, category) in courses_with_relations {
-            // Fetch only published chapters with id field
-            let chapters = chapter::Entity::find()
-                .filter(chapter::Column::CourseId.eq(course.id.clone()))
-                .filter(chapter::Column::IsPublished.eq(true))
-                .select_only()
-                .column(chapter::Column::Id)
-                .into_model::<ChapterIdOnly>()
+        query: SearchQuery,
+    ) -> Result<SearchResults, DbErr> {
+        let terms = query
+            .text
+            .as_deref()
+            .map(search::tokenize)
+            .unwrap_or_default();
+        let page = query.page();
+        let per_page = query.per_page();
+
+        // Step 1: Find the page. Without text the database filters, counts
+        // and pages the catalogue; with text only the courses the index
+        // matched are loaded and ranked. The sort is stable, so equally good
+        // matches stay newest first.
+        let (matches, total, facets) = if terms.is_empty() {
+            let facets = search::catalogue_facets(db, &query).await?;
+            let total = facets.total(&query);
+            let matches = course::Entity::find()
+                .filter(course::Column::IsPublished.eq(true))
+                .apply_if(query.category_id.clone(), |select, category_id| {
+                    select.filter(course::Column::CategoryId.eq(category_id))
+                })
+                .apply_if(query.price, |select, range| {
+                    select.filter(range.condition())
+                })
+                .order_by_desc(course::Column::CreatedAt)
+                .find_also_related(category::Entity)
+                .offset((page - 1) * per_page)
+                .limit(per_page)
                 .all(db)
-                .await
-                .unwrap();
-
-            // Check if there is a purchase by the user for this course
-            let purchase_exists = purchase::Entity::find()
-                .filter(purchase::Column::UserId.eq(user_id.clone()))
-                .filter(purchase::Column::CourseId.eq(course.id.clone()))
-                .one(db)
-                .await
-                .unwrap()
-                .is_some();
-
-            // Calculate progress if there is a purchase
-            let progress: Option<i8> = if purchase_exists {
-                // Calculate progress
-                let total_chapters = chapters.len() as f32;
-                let completed_chapters = user_progress::Entity::find()
-                    .filter(user_progress::Column::UserId.eq(user_id.clone()))
-                    .filter(
-                        user_progress::Column::ChapterId
-                            .is_in(chapters.iter().map(|ch| ch.id.clone())),
-                    )
-                    .filter(user_progress::Column::IsCompleted.eq(true))
-                    .all(db)
-                    .await
-                    .unwrap();
-
-                let completed_chapters_len = completed_chapters.len() as f32;
-
-                let res = if total_chapters > 0.0 {
-                    Some(((completed_chapters_len / total_chapters) * 100.0) as i8)
-                } else {
-                    Some(0)
-                };
-                res
-            } else {
-                None
-            };
-
-            // Add course data to result
-            result.push(SearchCourseWithProgressWithCategory {
-                course,
-                category,
-                chapters,
-                progress,
+                .await?;
+            (matches, total, facets)
+        } else {
+            let mut documents = search::matching_documents(db, &terms).await?;
+            let courses = course::Entity::find()
+                .filter(course::Column::Id.is_in(documents.keys().cloned()))
+                .order_by_desc(course::Column::CreatedAt)
+                .find_also_related(category::Entity)
+                .all(db)
+                .await?;
+            let mut matches: Vec<(f64, course::Model, Option<category::Model>)> = courses
+                .into_iter()
+                .filter_map(|(course, category)| {
+                    let words = documents.remove(&course.id).unwrap_or_default();
+                    let score = Document::new(&course.title, words).score(&terms)?;
+                    Some((score, course, category))
+                })
+                .collect();
+            matches.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+            let facets = search::facets(
+                &matches
+                    .iter()
+                    .map(|(_, course, category)| (course, category.as_ref()))
+                    .collect::<Vec<_>>(),
+                &query,
+            );
+
+            // Step 2: Apply the filters and cut out the requested page
+            matches.retain(|(_, course, _)| {
+                query.category_id.as_ref().map_or(true, |category_id| {
+                    course.category_id.as_ref() == Some(category_id)
+                }) && query
+                    .price
+                    .map_or(true, |range| PriceRange::of(course.price) == range)
             });
+            let total = matches.len() as u64;
+            let matches = matches
+                .into_iter()
+                .skip(((page - 1) * per_page) as usize)
+                .take(per_page as usize)
+                .map(|(_, course, category)| (course, category))
+                .collect();
+            (matches, total, facets)
+        };
+
+        // Step 3: Load the published chapters of the courses on the page
+        let chapters = chapter::Entity::find()
+            .select_only()
+            .column_as(chapter::Column::Id, "id")
+            .column_as(chapter::Column::CourseId, "course_id")
+            .filter(
+                chapter::Column::CourseId
+                    .is_in(matches.iter().map(|(course, _)| course.id.clone())),
+            )
+            .filter(chapter::Column::IsPublished.eq(true))
+            .order_by_asc(chapter::Column::Position)
+            .into_model::<ChapterOfCourse>()
+            .all(db)
+            .await?;
+        let mut chapters_by_course: HashMap<String, Vec<ChapterOfCourse>> = HashMap::new();
+        for chapter in chapters {
+            chapters_by_course
+                .entry(chapter.course_id.clone())
+                .or_default()
+                .push(chapter);
         }
+        let chapters_of = |course_id: &str| chapters_by_course.get(course_id).into_iter().flatten();
+
+        // Step 4: Fetch progress in every course on the page the user has
+        // access to at once
+        let granted = entitlements::entitlements(db, &user_id, Utc::now().naive_utc()).await?;
+        let chapter_ids: Vec<String> = matches
+            .iter()
+            .filter(|(course, _)| granted.contains_key(&course.id))
+            .flat_map(|(course, _)| chapters_of(&course.id).map(|chapter| chapter.id.clone()))
+            .collect();
+        let items = loaders::ProgressItems::load(db, &user_id, &chapter_ids).await?;
+
+        let courses = matches
+            .into_iter()
+            .map(|(course, category)| {
+                let chapters: Vec<ChapterIdOnly> = chapters_of(&course.id)
+                    .map(|chapter| ChapterIdOnly {
+                        id: chapter.id.clone(),
+                    })
+                    .collect();
+
+                // Calculate progress if the user has access to this course
+                let progress = granted
+                    .contains_key(&course.id)
+                    .then(|| items.percent(chapters.iter().map(|chapter| &chapter.id)));
+
+                SearchCourseWithProgressWithCategory {
+                    course,
+                    category,
+                    chapters,
+                    progress,
+                }
+            })
+            .collect();
+
+        Ok(SearchResults {
+            courses,
+            total,
+            page,
+            per_page,
+            facets,
+        })
+    }
 
-        Ok(result)
+    /// Rewrites the search index of one course. The app keeps it up to date
+    /// on its own writes; this is for courses written some other way.
+    pub async fn reindex_course(db: &DbConn, course_id: String) -> Result<(), DbErr> {
+        search::index_course(db, &course_id).await
+    }
+
+    /// Indexes the courses and notes written before search had an index, or
+    /// by an older version of the app, and returns how many. Run at startup.
+    pub async fn update_search_index(db: &DbConn) -> Result<usize, DbErr> {
+        Ok(search::index_missing(db).await? + notes::index_missing(db).await?)
     }
 
+    /// Starts buying `course_id`, with `coupon_code` taken off the price.
+    /// Paid courses open a Stripe Checkout Session and leave a pending
+    /// purchase that the webhook confirms once Stripe has taken the payment;
+    /// free courses, and courses a coupon makes free, are granted straight
+    /// away. A coupon is only counted once the purchase is paid; a payment
+    /// that arrives after the coupon ran out is refunded by the webhook.
     pub async fn checkout(
-        db: &DatabaseConnection,
+        db: &DbConn,
+        stripe: &StripeConfig,
         user_id: String,
         course_id: String,
-    ) -> Result<purchase::Model, DbErr> {
+        coupon_code: Option<String>,
+    ) -> Result<CheckoutSession, DbErr> {
+        let course = Course::find_by_id(course_id.clone())
+            .filter(course::Column::IsPublished.eq(true))
+            .one(db)
+            .await?
+            .ok_or(DbErr::Custom("Cannot find course".into()))?;
+
         let purchase = Purchase::find()
             .filter(purchase::Column::CourseId.eq(course_id.clone()))
             .filter(purchase::Column::UserId.eq(user_id.clone()))
             .one(db)
             .await?;
 
-        if let Some(_) = purchase {
-            return Err(DbErr::Custom("Already Purchased".to_string()));
+        match purchase.as_ref().map(|purchase| &purchase.status) {
+            Some(PurchaseStatus::Paid) => {
+                return Err(DbErr::Custom("Already Purchased".to_string()))
+            }
+            Some(PurchaseStatus::Revoked) => {
+                return Err(DbErr::Custom("Access to this course was revoked".into()))
+            }
+            _ => (),
         }
 
-        let new_purchase = purchase::ActiveModel {
-            id: Set(Uuid::new_v4().to_string()),
-            user_id: Set(user_id),
-            course_id: Set(course_id),
-            created_at: Set(Utc::now().naive_utc()),
-            updated_at: Set(Utc::now().naive_utc()),
-            ..Default::default()
-        };
+        let quote = coupons::quote(db, &course, coupon_code.as_deref()).await?;
+        if quote.amount == 0 {
+            let txn = db.begin().await?;
+            let purchase = payments::save_purchase(
+                &txn,
+                purchase,
+                &user_id,
+                &course_id,
+                &quote,
+                PurchaseStatus::Paid,
+                None,
+            )
+            .await?;
+            if !coupons::redeem(&txn, &purchase).await? {
+                return Err(DbErr::Custom("Coupon has been fully redeemed".into()));
+            }
+            txn.commit().await?;
+            return Ok(CheckoutSession {
+                purchase,
+                url: None,
+            });
+        }
 
-        let new_purchase = new_purchase.insert(db).await?;
+        // Stripe keeps a session payable for a day, so the one opened by an
+        // earlier attempt is closed before the student gets a new one.
+        if let Some(previous) = purchase
+            .as_ref()
+            .filter(|purchase| purchase.status == PurchaseStatus::Pending)
+            .and_then(|purchase| purchase.stripe_session_id.as_deref())
+        {
+            payments::expire_session(stripe, previous).await;
+        }
 
-        Ok(new_purchase)
+        let customer_id = payments::customer(db, stripe, &user_id).await?;
+        let item = LineItem::course(&course, quote.amount);
+        let session = payments::create_session(stripe, &customer_id, &user_id, &item).await?;
+        let purchase = payments::save_purchase(
+            db,
+            purchase,
+            &user_id,
+            &course_id,
+            &quote,
+            PurchaseStatus::Pending,
+            Some(session.id),
+        )
+        .await?;
+
+        Ok(CheckoutSession {
+            purchase,
+            url: session.url,
+        })
     }
 }
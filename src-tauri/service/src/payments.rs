@@ -0,0 +1,619 @@
+//! Taking payments through Stripe without the account's secret key.
+//!
+//! Stripe's secret key can do anything on the account, from reading every
+//! customer to paying out the balance, and anything in the installed app can
+//! be read by whoever runs it. So the app sends its Stripe API calls to a
+//! payments relay, a small service the school runs next to the database, with
+//! `relay_token` as bearer token. The relay checks the token, refuses any call
+//! [`is_relayed`] does not allow, and forwards the rest to Stripe with the
+//! secret key. Refunds stay possible for anyone holding a relay token, so a
+//! relay should only give tokens to teachers' installs, or hold refunds for
+//! approval.
+//!
+//! The webhook secret still ships with the app: it only lets Stripe events be
+//! checked, and a forged event has to reach the app's own webhook listener.
+
+use std::collections::HashMap;
+
+use ::entities::{prelude::*, sea_orm_active_enums::PurchaseStatus, *};
+use hmac::{Hmac, Mac};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use sqlx::types::{chrono::Utc, Uuid};
+
+use crate::{
+    coupons::{self, Quote},
+    products, purchases,
+};
+
+pub struct Payments;
+
+// Prices are stored in đồng. VND has no minor unit on Stripe, so the price is
+// also the amount Stripe charges.
+pub(crate) const CURRENCY: &str = "vnd";
+
+// Webhook events signed longer ago than this, in seconds, are rejected as
+// possible replays.
+const SIGNATURE_TOLERANCE: i64 = 300;
+
+/// How to reach Stripe. Calls go to the payments relay at `relay_url`, which
+/// answers like `https://api.stripe.com`; tests point it at a local mock
+/// server.
+#[derive(Debug, Clone)]
+pub struct StripeConfig {
+    pub relay_url: String,
+    pub relay_token: String,
+    pub webhook_secret: String,
+    /// Where Stripe sends the student back after checkout.
+    pub app_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutSession {
+    pub purchase: purchase::Model,
+    /// Stripe page to send the student to. `None` when the course is free and
+    /// the purchase was granted right away.
+    pub url: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum WebhookError {
+    /// The `Stripe-Signature` header is missing, stale or does not match.
+    Signature,
+    /// The event is malformed or refers to a checkout we never started.
+    Payload(String),
+    Db(DbErr),
+}
+
+impl From<DbErr> for WebhookError {
+    fn from(err: DbErr) -> Self {
+        WebhookError::Db(err)
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct StripeObject {
+    pub(crate) id: String,
+    pub(crate) url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Event {
+    #[serde(rename = "type")]
+    kind: String,
+    data: EventData,
+}
+
+#[derive(Deserialize)]
+struct EventData {
+    object: Value,
+}
+
+#[derive(Deserialize)]
+struct CompletedSession {
+    id: String,
+    #[serde(default)]
+    payment_status: String,
+    payment_intent: Option<String>,
+    /// What [`create_session`] sent along: `userId` and a `courseId` or
+    /// `productId`.
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+}
+
+impl CompletedSession {
+    fn metadata(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+}
+
+#[derive(Deserialize)]
+struct RefundedCharge {
+    payment_intent: Option<String>,
+    #[serde(default)]
+    refunded: bool,
+}
+
+impl Payments {
+    /// Verifies a Stripe webhook delivery and applies it, returning the
+    /// course purchase it changed. Paid checkouts grant the course or
+    /// product, expired or failed ones drop the pending purchase, and charges
+    /// fully refunded from the Stripe dashboard refund the purchase. Other
+    /// event types are acknowledged and ignored.
+    pub async fn handle_webhook(
+        db: &DbConn,
+        stripe: &StripeConfig,
+        payload: &str,
+        signature: &str,
+    ) -> Result<Option<purchase::Model>, WebhookError> {
+        verify_signature(
+            payload,
+            signature,
+            &stripe.webhook_secret,
+            Utc::now().timestamp(),
+        )?;
+
+        let event: Event =
+            serde_json::from_str(payload).map_err(|err| WebhookError::Payload(err.to_string()))?;
+        match event.kind.as_str() {
+            "checkout.session.completed"
+            | "checkout.session.async_payment_succeeded"
+            | "checkout.session.expired"
+            | "checkout.session.async_payment_failed" => {
+                let session = serde_json::from_value(event.data.object)
+                    .map_err(|err| WebhookError::Payload(err.to_string()))?;
+                apply_session(db, stripe, &event.kind, session).await
+            }
+            "charge.refunded" => {
+                let charge = serde_json::from_value(event.data.object)
+                    .map_err(|err| WebhookError::Payload(err.to_string()))?;
+                apply_refund(db, charge).await
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+async fn apply_session(
+    db: &DbConn,
+    stripe: &StripeConfig,
+    kind: &str,
+    session: CompletedSession,
+) -> Result<Option<purchase::Model>, WebhookError> {
+    // Checking out again points the purchase at the new session, while the
+    // earlier one may still be paid before it is expired. Sessions are matched
+    // on the student and course they were opened for when their id is no
+    // longer on record.
+    let purchase = match Purchase::find()
+        .filter(purchase::Column::StripeSessionId.eq(session.id.clone()))
+        .one(db)
+        .await?
+    {
+        Some(purchase) => Some(purchase),
+        None => match (session.metadata("userId"), session.metadata("courseId")) {
+            (Some(user_id), Some(course_id)) => {
+                Purchase::find()
+                    .filter(purchase::Column::UserId.eq(user_id))
+                    .filter(purchase::Column::CourseId.eq(course_id))
+                    .one(db)
+                    .await?
+            }
+            _ => None,
+        },
+    };
+    let Some(purchase) = purchase else {
+        return apply_product_session(db, stripe, kind, session).await;
+    };
+    let current = purchase.stripe_session_id.clone();
+    let superseded = current.as_deref() != Some(session.id.as_str());
+
+    match kind {
+        "checkout.session.expired" | "checkout.session.async_payment_failed" => {
+            // The purchase waits on a newer session now.
+            if superseded || purchase.status != PurchaseStatus::Pending {
+                return Ok(None);
+            }
+            // A student buying again after a refund gets the refunded
+            // purchase back if the new attempt falls through.
+            let refunded = RefundedSale::find()
+                .filter(refunded_sale::Column::PurchaseId.eq(purchase.id.clone()))
+                .order_by_desc(refunded_sale::Column::RefundedAt)
+                .one(db)
+                .await?;
+            match refunded {
+                Some(sale) => restore_refunded_sale(db, purchase, sale).await?,
+                None => {
+                    purchase.delete(db).await?;
+                }
+            }
+            Ok(None)
+        }
+        // Delayed payment methods complete the session before the money
+        // arrives; those are confirmed by `async_payment_succeeded`.
+        _ if session.payment_status != "paid" => Ok(None),
+        _ if purchase.status != PurchaseStatus::Pending => Ok(Some(purchase)),
+        _ => {
+            let now = Utc::now().naive_utc();
+            let txn = db.begin().await?;
+            let redeemed = coupons::redeem(&txn, &purchase).await?;
+            let purchase_id = purchase.id.clone();
+            let mut purchase: purchase::ActiveModel = purchase.into();
+            purchase.paid_at = Set(Some(now));
+            purchase.stripe_session_id = Set(Some(session.id.clone()));
+            purchase.stripe_payment_intent_id = Set(session.payment_intent.clone());
+            purchase.updated_at = Set(now);
+            let purchase = if redeemed {
+                purchase.status = Set(PurchaseStatus::Paid);
+                let purchase = purchase.update(&txn).await?;
+                txn.commit().await?;
+                purchase
+            } else {
+                // The coupon ran out while the student was paying, so the
+                // discounted payment is given back instead of granting the
+                // course.
+                txn.rollback().await?;
+                if let Some(payment_intent) = &session.payment_intent {
+                    refund(stripe, payment_intent, &purchase_id).await?;
+                }
+                purchase.status = Set(PurchaseStatus::Refunded);
+                purchase.refunded_at = Set(Some(now));
+                purchase.update(db).await?
+            };
+            if let Some(current) = current.filter(|_| superseded) {
+                expire_session(stripe, &current).await;
+            }
+            Ok(Some(purchase))
+        }
+    }
+}
+
+async fn apply_product_session(
+    db: &DbConn,
+    stripe: &StripeConfig,
+    kind: &str,
+    session: CompletedSession,
+) -> Result<Option<purchase::Model>, WebhookError> {
+    let purchase = match ProductPurchase::find()
+        .filter(product_purchase::Column::StripeSessionId.eq(session.id.clone()))
+        .one(db)
+        .await?
+    {
+        Some(purchase) => Some(purchase),
+        None => match (session.metadata("userId"), session.metadata("productId")) {
+            (Some(user_id), Some(product_id)) => {
+                ProductPurchase::find()
+                    .filter(product_purchase::Column::UserId.eq(user_id))
+                    .filter(product_purchase::Column::ProductId.eq(product_id))
+                    .one(db)
+                    .await?
+            }
+            _ => None,
+        },
+    };
+    let purchase = purchase.ok_or(WebhookError::Payload("Unknown checkout session".into()))?;
+    let current = purchase.stripe_session_id.clone();
+    let superseded = current.as_deref() != Some(session.id.as_str());
+    let paid = session.payment_status == "paid";
+
+    match kind {
+        "checkout.session.expired" | "checkout.session.async_payment_failed" if superseded => {}
+        _ => {
+            products::apply_session(db, kind, purchase, paid, session.payment_intent).await?;
+            if let Some(current) = current.filter(|_| superseded && paid) {
+                expire_session(stripe, &current).await;
+            }
+        }
+    }
+    Ok(None)
+}
+
+async fn apply_refund(
+    db: &DbConn,
+    charge: RefundedCharge,
+) -> Result<Option<purchase::Model>, WebhookError> {
+    // Partial refunds leave the student with access.
+    let Some(payment_intent) = charge.payment_intent.filter(|_| charge.refunded) else {
+        return Ok(None);
+    };
+
+    let purchase = Purchase::find()
+        .filter(purchase::Column::StripePaymentIntentId.eq(payment_intent.clone()))
+        .one(db)
+        .await?;
+
+    match purchase {
+        Some(purchase) if purchase.status == PurchaseStatus::Paid => {
+            Ok(Some(purchases::mark_refunded(db, purchase).await?))
+        }
+        Some(_) => Ok(None),
+        None => {
+            products::apply_refund(db, &payment_intent).await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Whether a payments relay should forward a POST to `/v1/{path}`: the
+/// calls the app makes, and nothing else.
+pub fn is_relayed(path: &str) -> bool {
+    match path.split('/').collect::<Vec<_>>()[..] {
+        ["customers"] | ["checkout", "sessions"] | ["refunds"] => true,
+        ["checkout", "sessions", session_id, "expire"] => !session_id.is_empty(),
+        _ => false,
+    }
+}
+
+async fn post(
+    stripe: &StripeConfig,
+    path: &str,
+    form: &[(String, String)],
+) -> Result<StripeObject, DbErr> {
+    let res = reqwest::Client::new()
+        .post(format!("{}/v1/{}", stripe.relay_url, path))
+        .bearer_auth(&stripe.relay_token)
+        .form(form)
+        .send()
+        .await
+        .map_err(|err| DbErr::Custom(format!("Cannot reach Stripe: {}", err)))?;
+
+    if !res.status().is_success() {
+        return Err(DbErr::Custom(format!(
+            "Stripe rejected the request: {}",
+            res.status()
+        )));
+    }
+
+    res.json()
+        .await
+        .map_err(|err| DbErr::Custom(format!("Unexpected Stripe response: {}", err)))
+}
+
+/// The Stripe customer id for `user_id`, created on first checkout.
+pub(crate) async fn customer(
+    db: &DbConn,
+    stripe: &StripeConfig,
+    user_id: &str,
+) -> Result<String, DbErr> {
+    let existing = StripeCustomer::find()
+        .filter(stripe_customer::Column::UserId.eq(user_id))
+        .one(db)
+        .await?;
+    if let Some(existing) = existing {
+        return Ok(existing.stripe_customer_id);
+    }
+
+    let created = post(
+        stripe,
+        "customers",
+        &[("metadata[userId]".into(), user_id.to_string())],
+    )
+    .await?;
+
+    stripe_customer::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        user_id: Set(user_id.to_string()),
+        stripe_customer_id: Set(created.id.clone()),
+        created_at: Set(Utc::now().naive_utc()),
+        updated_at: Set(Utc::now().naive_utc()),
+    }
+    .insert(db)
+    .await?;
+
+    Ok(created.id)
+}
+
+/// What a Checkout Session sells.
+pub(crate) struct LineItem<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) description: Option<&'a str>,
+    pub(crate) amount: i32,
+    /// Page the student comes back to, relative to the app url and already
+    /// carrying a query string.
+    pub(crate) return_to: String,
+    pub(crate) metadata: Vec<(&'static str, String)>,
+}
+
+impl<'a> LineItem<'a> {
+    pub(crate) fn course(course: &'a course::Model, amount: i32) -> Self {
+        LineItem {
+            name: &course.title,
+            description: course.description.as_deref(),
+            amount,
+            return_to: format!("/courses?courseId={}", course.id),
+            metadata: vec![("courseId", course.id.clone())],
+        }
+    }
+}
+
+/// Opens a Stripe Checkout Session charging `user_id` for `item`.
+pub(crate) async fn create_session(
+    stripe: &StripeConfig,
+    customer_id: &str,
+    user_id: &str,
+    item: &LineItem<'_>,
+) -> Result<StripeObject, DbErr> {
+    let mut form: Vec<(String, String)> = vec![
+        ("mode".into(), "payment".into()),
+        ("customer".into(), customer_id.to_string()),
+        ("line_items[0][quantity]".into(), "1".into()),
+        (
+            "line_items[0][price_data][currency]".into(),
+            CURRENCY.into(),
+        ),
+        (
+            "line_items[0][price_data][unit_amount]".into(),
+            item.amount.to_string(),
+        ),
+        (
+            "line_items[0][price_data][product_data][name]".into(),
+            item.name.to_string(),
+        ),
+        (
+            "success_url".into(),
+            format!("{}{}&success=1", stripe.app_url, item.return_to),
+        ),
+        (
+            "cancel_url".into(),
+            format!("{}{}&canceled=1", stripe.app_url, item.return_to),
+        ),
+        ("metadata[userId]".into(), user_id.to_string()),
+    ];
+    for (key, value) in &item.metadata {
+        form.push((format!("metadata[{}]", key), value.clone()));
+    }
+    if let Some(description) = item.description {
+        form.push((
+            "line_items[0][price_data][product_data][description]".into(),
+            description.to_string(),
+        ));
+    }
+
+    post(stripe, "checkout/sessions", &form).await
+}
+
+/// Closes an open Checkout Session so it can no longer be paid. Stripe
+/// refuses sessions that are already complete or expired, and either way
+/// nothing more can be paid on them, so a failure is only logged.
+pub(crate) async fn expire_session(stripe: &StripeConfig, session_id: &str) {
+    let path = format!("checkout/sessions/{}/expire", session_id);
+    if let Err(err) = post(stripe, &path, &[]).await {
+        tracing::warn!("Cannot expire checkout session {}: {}", session_id, err);
+    }
+}
+
+/// Refunds the full amount of a payment.
+pub(crate) async fn refund(
+    stripe: &StripeConfig,
+    payment_intent: &str,
+    purchase_id: &str,
+) -> Result<StripeObject, DbErr> {
+    post(
+        stripe,
+        "refunds",
+        &[
+            ("payment_intent".into(), payment_intent.to_string()),
+            ("metadata[purchaseId]".into(), purchase_id.to_string()),
+        ],
+    )
+    .await
+}
+
+/// Stores the purchase for a checkout at the quoted price, reusing the row of
+/// an earlier unfinished or refunded attempt so the one-purchase-per-course
+/// constraint holds. A refunded sale is moved to [`refunded_sale`] first, so
+/// it still counts as sold and refunded.
+pub(crate) async fn save_purchase<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    existing: Option<purchase::Model>,
+    user_id: &str,
+    course_id: &str,
+    quote: &Quote,
+    status: PurchaseStatus,
+    stripe_session_id: Option<String>,
+) -> Result<purchase::Model, DbErr> {
+    let paid_at = (status == PurchaseStatus::Paid).then(|| Utc::now().naive_utc());
+    match existing {
+        Some(existing) => {
+            let txn = db.begin().await?;
+            if let (Some(paid_at), Some(refunded_at)) = (existing.paid_at, existing.refunded_at) {
+                refunded_sale::ActiveModel {
+                    id: Set(Uuid::new_v4().to_string()),
+                    purchase_id: Set(existing.id.clone()),
+                    amount: Set(existing.amount),
+                    discount: Set(existing.discount),
+                    coupon_id: Set(existing.coupon_id.clone()),
+                    currency: Set(existing.currency.clone()),
+                    paid_at: Set(paid_at),
+                    refunded_at: Set(refunded_at),
+                    stripe_payment_intent_id: Set(existing.stripe_payment_intent_id.clone()),
+                }
+                .insert(&txn)
+                .await?;
+            }
+
+            let mut purchase: purchase::ActiveModel = existing.into();
+            purchase.status = Set(status);
+            purchase.stripe_session_id = Set(stripe_session_id);
+            purchase.amount = Set(quote.amount);
+            purchase.discount = Set(quote.discount);
+            purchase.coupon_id = Set(quote.coupon_id.clone());
+            purchase.currency = Set(CURRENCY.into());
+            purchase.paid_at = Set(paid_at);
+            purchase.refunded_at = Set(None);
+            purchase.stripe_payment_intent_id = Set(None);
+            purchase.updated_at = Set(Utc::now().naive_utc());
+            let purchase = purchase.update(&txn).await?;
+            txn.commit().await?;
+            Ok(purchase)
+        }
+        None => {
+            purchase::ActiveModel {
+                id: Set(Uuid::new_v4().to_string()),
+                user_id: Set(user_id.to_string()),
+                course_id: Set(course_id.to_string()),
+                status: Set(status),
+                stripe_session_id: Set(stripe_session_id),
+                amount: Set(quote.amount),
+                discount: Set(quote.discount),
+                coupon_id: Set(quote.coupon_id.clone()),
+                currency: Set(CURRENCY.into()),
+                paid_at: Set(paid_at),
+                refunded_at: Set(None),
+                revoked_at: Set(None),
+                stripe_payment_intent_id: Set(None),
+                created_at: Set(Utc::now().naive_utc()),
+                updated_at: Set(Utc::now().naive_utc()),
+            }
+            .insert(db)
+            .await
+        }
+    }
+}
+
+// Puts the last refunded sale back on the purchase, undoing
+// [`save_purchase`] for an attempt that was never paid.
+async fn restore_refunded_sale(
+    db: &DbConn,
+    purchase: purchase::Model,
+    sale: refunded_sale::Model,
+) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+    let mut purchase: purchase::ActiveModel = purchase.into();
+    purchase.status = Set(PurchaseStatus::Refunded);
+    purchase.stripe_session_id = Set(None);
+    purchase.amount = Set(sale.amount);
+    purchase.discount = Set(sale.discount);
+    purchase.coupon_id = Set(sale.coupon_id.clone());
+    purchase.currency = Set(sale.currency.clone());
+    purchase.paid_at = Set(Some(sale.paid_at));
+    purchase.refunded_at = Set(Some(sale.refunded_at));
+    purchase.stripe_payment_intent_id = Set(sale.stripe_payment_intent_id.clone());
+    purchase.updated_at = Set(Utc::now().naive_utc());
+    purchase.update(&txn).await?;
+    sale.delete(&txn).await?;
+    txn.commit().await
+}
+
+/// Checks a `Stripe-Signature` header (`t=<unix time>,v1=<hex hmac>,...`)
+/// against the raw request body.
+fn verify_signature(
+    payload: &str,
+    header: &str,
+    secret: &str,
+    now: i64,
+) -> Result<(), WebhookError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.push(value),
+            _ => (),
+        }
+    }
+
+    let timestamp = timestamp.ok_or(WebhookError::Signature)?;
+    if (now - timestamp).abs() > SIGNATURE_TOLERANCE {
+        return Err(WebhookError::Signature);
+    }
+
+    let signed_payload = format!("{}.{}", timestamp, payload);
+    let matches = signatures.into_iter().any(|signature| {
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(signed_payload.as_bytes());
+        mac.verify_slice(&signature).is_ok()
+    });
+
+    if matches {
+        Ok(())
+    } else {
+        Err(WebhookError::Signature)
+    }
+}
@@ -0,0 +1,415 @@
+use ::entities::{
+    prelude::*,
+    sea_orm_active_enums::{ProductKind, PurchaseStatus},
+    *,
+};
+use chrono::Duration;
+use sea_orm::{sea_query::Expr, *};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{chrono::Utc, Uuid};
+
+use crate::{
+    payments::{self, LineItem, StripeConfig, CURRENCY},
+    validation::{UpdateError, Validator},
+};
+
+pub struct Products;
+
+/// Something other than a single course that students can buy. A bundle
+/// grants the courses in `course_ids` for good; a subscription grants every
+/// published course of the teacher for `duration_days`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NewProduct {
+    pub kind: ProductKind,
+    pub title: String,
+    pub description: Option<String>,
+    pub price: i32,
+    pub duration_days: Option<i32>,
+    #[serde(default)]
+    pub course_ids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductWithCourses {
+    #[serde(flatten)]
+    pub product: product::Model,
+    pub course_ids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductCheckout {
+    pub purchase: product_purchase::Model,
+    /// Stripe page to send the student to. `None` when the product is free
+    /// and was granted right away.
+    pub url: Option<String>,
+}
+
+impl Products {
+    pub async fn create(
+        db: &DbConn,
+        user_id: String,
+        product: NewProduct,
+    ) -> Result<ProductWithCourses, UpdateError> {
+        let mut validator = Validator::default();
+        validator.title("title", &Some(product.title.clone()));
+        if product.price < 0 {
+            validator.error("price", "Price cannot be negative");
+        }
+        match product.kind {
+            ProductKind::Bundle => {
+                if product.duration_days.is_some() {
+                    validator.error("durationDays", "Bundles do not expire");
+                }
+                if product.course_ids.len() < 2 {
+                    validator.error("courseIds", "A bundle needs at least two courses");
+                }
+            }
+            ProductKind::Subscription => {
+                if product.duration_days.map_or(true, |days| days < 1) {
+                    validator.error("durationDays", "Subscriptions last at least one day");
+                }
+                if !product.course_ids.is_empty() {
+                    validator.error(
+                        "courseIds",
+                        "Subscriptions cover every course of the teacher",
+                    );
+                }
+            }
+        }
+        validator.finish()?;
+
+        let mut course_ids = product.course_ids.clone();
+        course_ids.sort();
+        course_ids.dedup();
+        let owned = Course::find()
+            .filter(course::Column::Id.is_in(course_ids.clone()))
+            .filter(course::Column::UserId.eq(user_id.clone()))
+            .count(db)
+            .await?;
+        if owned != course_ids.len() as u64 {
+            return Err(DbErr::RecordNotFound("Cannot find course".into()).into());
+        }
+
+        let txn = db.begin().await?;
+        let created = product::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            user_id: Set(user_id),
+            kind: Set(product.kind),
+            title: Set(product.title.trim().to_string()),
+            description: Set(product.description),
+            price: Set(product.price),
+            duration_days: Set(product.duration_days),
+            is_published: Set(false),
+            created_at: Set(Utc::now().naive_utc()),
+            updated_at: Set(Utc::now().naive_utc()),
+        }
+        .insert(&txn)
+        .await?;
+        for course_id in &course_ids {
+            product_course::ActiveModel {
+                product_id: Set(created.id.clone()),
+                course_id: Set(course_id.clone()),
+            }
+            .insert(&txn)
+            .await?;
+        }
+        txn.commit().await?;
+
+        Ok(ProductWithCourses {
+            product: created,
+            course_ids,
+        })
+    }
+
+    /// The teacher's products, newest first.
+    pub async fn list(db: &DbConn, user_id: String) -> Result<Vec<ProductWithCourses>, DbErr> {
+        let products = Product::find()
+            .filter(product::Column::UserId.eq(user_id))
+            .order_by_desc(product::Column::CreatedAt)
+            .find_with_related(ProductCourse)
+            .all(db)
+            .await?;
+
+        Ok(products
+            .into_iter()
+            .map(|(product, courses)| ProductWithCourses {
+                product,
+                course_ids: courses.into_iter().map(|course| course.course_id).collect(),
+            })
+            .collect())
+    }
+
+    /// Published products that give access to `course_id`: bundles that
+    /// include it and subscriptions of its teacher.
+    pub async fn for_course(db: &DbConn, course_id: String) -> Result<Vec<product::Model>, DbErr> {
+        let course = Course::find_by_id(course_id.clone())
+            .filter(course::Column::IsPublished.eq(true))
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find course".into()))?;
+
+        let bundled = ProductCourse::find()
+            .filter(product_course::Column::CourseId.eq(course_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|item| item.product_id);
+
+        Product::find()
+            .filter(product::Column::IsPublished.eq(true))
+            .filter(
+                Condition::any()
+                    .add(product::Column::Id.is_in(bundled))
+                    .add(
+                        Condition::all()
+                            .add(product::Column::Kind.eq(ProductKind::Subscription))
+                            .add(product::Column::UserId.eq(course.user_id)),
+                    ),
+            )
+            .order_by_asc(product::Column::Price)
+            .all(db)
+            .await
+    }
+
+    pub async fn set_published(
+        db: &DbConn,
+        user_id: String,
+        product_id: String,
+        is_published: bool,
+    ) -> Result<product::Model, DbErr> {
+        let product = owned_product(db, &user_id, &product_id).await?;
+
+        let mut product: product::ActiveModel = product.into();
+        product.is_published = Set(is_published);
+        product.updated_at = Set(Utc::now().naive_utc());
+        product.update(db).await
+    }
+
+    /// Deletes a product together with its purchases, so students lose the
+    /// access it gave them.
+    pub async fn delete(db: &DbConn, user_id: String, product_id: String) -> Result<(), DbErr> {
+        let product = owned_product(db, &user_id, &product_id).await?;
+        product.delete(db).await?;
+
+        Ok(())
+    }
+
+    /// Starts buying a product, like [`crate::OtherRoutes::checkout`] does for
+    /// a course. An active subscription can be bought again to extend it.
+    pub async fn checkout(
+        db: &DbConn,
+        stripe: &StripeConfig,
+        user_id: String,
+        product_id: String,
+    ) -> Result<ProductCheckout, DbErr> {
+        let product = Product::find_by_id(product_id.clone())
+            .filter(product::Column::IsPublished.eq(true))
+            .one(db)
+            .await?
+            .ok_or(DbErr::Custom("Cannot find product".into()))?;
+
+        let purchase = ProductPurchase::find()
+            .filter(product_purchase::Column::UserId.eq(user_id.clone()))
+            .filter(product_purchase::Column::ProductId.eq(product_id.clone()))
+            .one(db)
+            .await?;
+
+        match purchase.as_ref().map(|purchase| &purchase.status) {
+            Some(PurchaseStatus::Paid) if product.kind == ProductKind::Bundle => {
+                return Err(DbErr::Custom("Already Purchased".to_string()))
+            }
+            Some(PurchaseStatus::Revoked) => {
+                return Err(DbErr::Custom("Access to this product was revoked".into()))
+            }
+            _ => (),
+        }
+
+        let purchase = match purchase {
+            Some(purchase) => purchase,
+            None => {
+                product_purchase::ActiveModel {
+                    id: Set(Uuid::new_v4().to_string()),
+                    user_id: Set(user_id.clone()),
+                    product_id: Set(product_id.clone()),
+                    status: Set(PurchaseStatus::Pending),
+                    amount: Set(product.price),
+                    currency: Set(CURRENCY.into()),
+                    stripe_session_id: Set(None),
+                    stripe_payment_intent_id: Set(None),
+                    paid_at: Set(None),
+                    expires_at: Set(None),
+                    created_at: Set(Utc::now().naive_utc()),
+                    updated_at: Set(Utc::now().naive_utc()),
+                }
+                .insert(db)
+                .await?
+            }
+        };
+
+        if product.price <= 0 {
+            let purchase = confirm(db, &product, purchase, None).await?;
+            return Ok(ProductCheckout {
+                purchase,
+                url: None,
+            });
+        }
+
+        if let Some(previous) = purchase
+            .stripe_session_id
+            .as_deref()
+            .filter(|_| purchase.status == PurchaseStatus::Pending)
+        {
+            payments::expire_session(stripe, previous).await;
+        }
+
+        let customer_id = payments::customer(db, stripe, &user_id).await?;
+        let item = LineItem {
+            name: &product.title,
+            description: product.description.as_deref(),
+            amount: product.price,
+            return_to: format!("/?productId={}", product.id),
+            metadata: vec![("productId", product.id.clone())],
+        };
+        let session = payments::create_session(stripe, &customer_id, &user_id, &item).await?;
+
+        // A subscription being renewed stays paid until the new payment
+        // arrives, so the student keeps access in the meantime.
+        let mut purchase: product_purchase::ActiveModel = purchase.into();
+        if purchase.status.as_ref() != &PurchaseStatus::Paid {
+            purchase.status = Set(PurchaseStatus::Pending);
+        }
+        purchase.amount = Set(product.price);
+        purchase.stripe_session_id = Set(Some(session.id));
+        purchase.updated_at = Set(Utc::now().naive_utc());
+
+        Ok(ProductCheckout {
+            purchase: purchase.update(db).await?,
+            url: session.url,
+        })
+    }
+}
+
+async fn owned_product(
+    db: &DbConn,
+    user_id: &str,
+    product_id: &str,
+) -> Result<product::Model, DbErr> {
+    Product::find_by_id(product_id)
+        .filter(product::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find product".into()))
+}
+
+// Marks a product purchase as paid. Subscriptions run for another
+// `duration_days` from now, or from the end of the current period if it has
+// not run out yet. A renewal keeps the first `paid_at`, which dripped chapters
+// are released from, so chapters the subscriber already had stay open.
+async fn confirm(
+    db: &DbConn,
+    product: &product::Model,
+    purchase: product_purchase::Model,
+    payment_intent: Option<String>,
+) -> Result<product_purchase::Model, DbErr> {
+    let now = Utc::now().naive_utc();
+    let expires_at = product.duration_days.map(|days| {
+        let start = purchase
+            .expires_at
+            .filter(|expires_at| purchase.status == PurchaseStatus::Paid && *expires_at > now)
+            .unwrap_or(now);
+        start + Duration::days(days.into())
+    });
+
+    let paid_at = purchase
+        .paid_at
+        .filter(|_| purchase.status == PurchaseStatus::Paid)
+        .unwrap_or(now);
+
+    let txn = db.begin().await?;
+    product_payment::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        product_purchase_id: Set(purchase.id.clone()),
+        amount: Set(purchase.amount),
+        currency: Set(purchase.currency.clone()),
+        paid_at: Set(now),
+        refunded_at: Set(None),
+        stripe_payment_intent_id: Set(payment_intent.clone()),
+    }
+    .insert(&txn)
+    .await?;
+
+    let mut purchase: product_purchase::ActiveModel = purchase.into();
+    purchase.status = Set(PurchaseStatus::Paid);
+    purchase.paid_at = Set(Some(paid_at));
+    purchase.expires_at = Set(expires_at);
+    purchase.stripe_payment_intent_id = Set(payment_intent);
+    purchase.updated_at = Set(now);
+    let purchase = purchase.update(&txn).await?;
+
+    txn.commit().await?;
+    Ok(purchase)
+}
+
+/// Applies a Checkout Session event to the product purchase it was opened for.
+pub(crate) async fn apply_session(
+    db: &DbConn,
+    kind: &str,
+    purchase: product_purchase::Model,
+    paid: bool,
+    payment_intent: Option<String>,
+) -> Result<(), DbErr> {
+    match kind {
+        "checkout.session.expired" | "checkout.session.async_payment_failed" => {
+            if purchase.status == PurchaseStatus::Pending {
+                purchase.delete(db).await?;
+            }
+        }
+        // Each payment extends a subscription once, however often Stripe
+        // delivers the event.
+        _ if !paid
+            || (purchase.status == PurchaseStatus::Paid
+                && payment_intent.is_some()
+                && purchase.stripe_payment_intent_id == payment_intent) => {}
+        _ => {
+            let product = Product::find_by_id(purchase.product_id.clone())
+                .one(db)
+                .await?
+                .ok_or(DbErr::RecordNotFound("Cannot find product".into()))?;
+            confirm(db, &product, purchase, payment_intent).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Refunds the product purchase paid with `payment_intent`, if there is one.
+pub(crate) async fn apply_refund(db: &DbConn, payment_intent: &str) -> Result<(), DbErr> {
+    let purchase = ProductPurchase::find()
+        .filter(product_purchase::Column::StripePaymentIntentId.eq(payment_intent))
+        .filter(product_purchase::Column::Status.eq(PurchaseStatus::Paid))
+        .one(db)
+        .await?;
+
+    if let Some(purchase) = purchase {
+        let now = Utc::now().naive_utc();
+        let txn = db.begin().await?;
+        ProductPayment::update_many()
+            .col_expr(product_payment::Column::RefundedAt, Expr::value(now))
+            .filter(product_payment::Column::ProductPurchaseId.eq(purchase.id.clone()))
+            .filter(product_payment::Column::StripePaymentIntentId.eq(payment_intent))
+            .filter(product_payment::Column::RefundedAt.is_null())
+            .exec(&txn)
+            .await?;
+
+        let mut purchase: product_purchase::ActiveModel = purchase.into();
+        purchase.status = Set(PurchaseStatus::Refunded);
+        purchase.updated_at = Set(now);
+        purchase.update(&txn).await?;
+        txn.commit().await?;
+    }
+
+    Ok(())
+}
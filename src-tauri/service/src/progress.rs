@@ -0,0 +1,201 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+/// Share of a video a student has to watch before the chapter completes on
+/// its own, unless configured otherwise.
+pub const DEFAULT_COMPLETE_AT_PERCENT: u8 = 90;
+
+#[derive(Debug, Clone)]
+pub struct WatchSettings {
+    /// Percentage of the video, 1 to 100.
+    pub complete_at_percent: u8,
+}
+
+impl Default for WatchSettings {
+    fn default() -> Self {
+        WatchSettings {
+            complete_at_percent: DEFAULT_COMPLETE_AT_PERCENT,
+        }
+    }
+}
+
+/// Sent by the video player every few seconds while a chapter plays:
+/// `start_second..end_second` is what played since the previous heartbeat and
+/// `position` is where the player is now.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct WatchHeartbeat {
+    pub start_second: i32,
+    pub end_second: i32,
+    pub position: i32,
+    /// Length of the video, once the player knows it.
+    pub duration: Option<i32>,
+}
+
+impl WatchHeartbeat {
+    pub(crate) fn is_valid(&self) -> bool {
+        self.start_second >= 0
+            && self.end_second > self.start_second
+            && self.position >= 0
+            && self.duration.map_or(true, |duration| duration > 0)
+    }
+}
+
+/// Shortest time, in seconds, between two writes of a student's watching of
+/// one chapter. The player reports every 10 seconds, so about one heartbeat
+/// in three is written and the others are merged into it.
+pub const DEFAULT_HEARTBEAT_INTERVAL_SECS: i64 = 30;
+
+/// Holds back heartbeats that arrive sooner than `interval` after the last
+/// write for the same student and chapter. They are merged in memory and
+/// written together by the next heartbeat after the interval, or by
+/// [`crate::Chapters::flush_watch`].
+pub struct WatchThrottle {
+    interval: Duration,
+    held: Mutex<HashMap<(String, String), Held>>,
+}
+
+struct Held {
+    course_id: String,
+    written_at: Option<NaiveDateTime>,
+    ranges: Vec<[i32; 2]>,
+    position: i32,
+    duration: Option<i32>,
+}
+
+/// Heartbeats of one student and chapter that are due to be written.
+pub(crate) struct HeldWatch {
+    pub(crate) user_id: String,
+    pub(crate) course_id: String,
+    pub(crate) chapter_id: String,
+    pub(crate) heartbeats: Vec<WatchHeartbeat>,
+}
+
+impl Default for WatchThrottle {
+    fn default() -> Self {
+        WatchThrottle::new(Duration::seconds(DEFAULT_HEARTBEAT_INTERVAL_SECS))
+    }
+}
+
+impl WatchThrottle {
+    pub fn new(interval: Duration) -> Self {
+        WatchThrottle {
+            interval,
+            held: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes `heartbeat` and returns what has to be written now: nothing while
+    /// the interval since the last write runs, otherwise every range held so
+    /// far, merged with this one.
+    pub(crate) fn offer(
+        &self,
+        user_id: &str,
+        course_id: &str,
+        chapter_id: &str,
+        heartbeat: WatchHeartbeat,
+        now: NaiveDateTime,
+    ) -> Option<Vec<WatchHeartbeat>> {
+        let mut held = self.held.lock().unwrap();
+        let entry = held
+            .entry((user_id.to_string(), chapter_id.to_string()))
+            .or_insert_with(|| Held {
+                course_id: course_id.to_string(),
+                written_at: None,
+                ranges: Vec::new(),
+                position: 0,
+                duration: None,
+            });
+        entry.course_id = course_id.to_string();
+        entry.hold(heartbeat);
+
+        if entry
+            .written_at
+            .is_some_and(|written_at| now - written_at < self.interval)
+        {
+            return None;
+        }
+        entry.written_at = Some(now);
+        Some(entry.take())
+    }
+
+    /// Takes everything held for longer than the interval, and forgets
+    /// students who stopped watching.
+    pub(crate) fn take_due(&self, now: NaiveDateTime) -> Vec<HeldWatch> {
+        let mut held = self.held.lock().unwrap();
+        let mut due = Vec::new();
+        held.retain(|(user_id, chapter_id), entry| {
+            if entry
+                .written_at
+                .is_some_and(|written_at| now - written_at < self.interval)
+            {
+                return true;
+            }
+            if entry.ranges.is_empty() {
+                return false;
+            }
+            entry.written_at = Some(now);
+            due.push(HeldWatch {
+                user_id: user_id.clone(),
+                course_id: entry.course_id.clone(),
+                chapter_id: chapter_id.clone(),
+                heartbeats: entry.take(),
+            });
+            true
+        });
+        due
+    }
+}
+
+impl Held {
+    fn hold(&mut self, heartbeat: WatchHeartbeat) {
+        merge_range(
+            &mut self.ranges,
+            [heartbeat.start_second, heartbeat.end_second],
+        );
+        self.position = heartbeat.position;
+        self.duration = heartbeat.duration.or(self.duration);
+    }
+
+    // One heartbeat per stretch watched, all ending at the latest position.
+    fn take(&mut self) -> Vec<WatchHeartbeat> {
+        std::mem::take(&mut self.ranges)
+            .into_iter()
+            .map(|[start_second, end_second]| WatchHeartbeat {
+                start_second,
+                end_second,
+                position: self.position,
+                duration: self.duration,
+            })
+            .collect()
+    }
+}
+
+/// Changes to a student's progress in a chapter. A heartbeat can complete
+/// the chapter by itself; an explicit `is_completed` always wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgressUpdate {
+    pub is_completed: Option<bool>,
+    pub heartbeat: Option<WatchHeartbeat>,
+}
+
+/// Adds `range` to the sorted, disjoint `ranges`, joining any it overlaps or
+/// touches.
+pub(crate) fn merge_range(ranges: &mut Vec<[i32; 2]>, range: [i32; 2]) {
+    let mut merged = range;
+    ranges.retain(|existing| {
+        if existing[0] > merged[1] || existing[1] < merged[0] {
+            return true;
+        }
+        merged = [merged[0].min(existing[0]), merged[1].max(existing[1])];
+        false
+    });
+    let at = ranges.partition_point(|existing| existing[0] < merged[0]);
+    ranges.insert(at, merged);
+}
+
+pub(crate) fn watched_seconds(ranges: &[[i32; 2]]) -> i32 {
+    ranges.iter().map(|range| range[1] - range[0]).sum()
+}
@@ -0,0 +1,109 @@
+use ::entities::{prelude::*, sea_orm_active_enums::PurchaseStatus, *};
+use sea_orm::*;
+use sqlx::types::chrono::Utc;
+
+use crate::payments::{self, StripeConfig};
+
+pub struct Purchases;
+
+impl Purchases {
+    /// Every purchase of a course owned by `user_id`, newest first, whatever
+    /// its status.
+    pub async fn list(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<Vec<purchase::Model>, DbErr> {
+        let owned_course = Course::find_by_id(course_id.clone())
+            .filter(course::Column::UserId.eq(user_id))
+            .one(db)
+            .await?;
+
+        if owned_course.is_none() {
+            return Err(DbErr::RecordNotFound("Cannot find course".into()));
+        }
+
+        Purchase::find()
+            .filter(purchase::Column::CourseId.eq(course_id))
+            .order_by_desc(purchase::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// Gives the student their money back and removes their access. Purchases
+    /// paid through Stripe are refunded there first; free ones are only
+    /// marked as refunded.
+    pub async fn refund(
+        db: &DbConn,
+        stripe: &StripeConfig,
+        user_id: String,
+        purchase_id: String,
+    ) -> Result<purchase::Model, DbErr> {
+        let purchase = owned_purchase(db, &user_id, &purchase_id).await?;
+
+        if purchase.status != PurchaseStatus::Paid {
+            return Err(DbErr::Custom("Only paid purchases can be refunded".into()));
+        }
+
+        if purchase.amount > 0 {
+            let payment_intent = purchase
+                .stripe_payment_intent_id
+                .clone()
+                .ok_or(DbErr::Custom("Purchase was not paid through Stripe".into()))?;
+            payments::refund(stripe, &payment_intent, &purchase.id).await?;
+        }
+
+        mark_refunded(db, purchase).await
+    }
+
+    /// Takes away a student's access without refunding them. A revoked
+    /// student cannot buy the course again.
+    pub async fn revoke(
+        db: &DbConn,
+        user_id: String,
+        purchase_id: String,
+    ) -> Result<purchase::Model, DbErr> {
+        let purchase = owned_purchase(db, &user_id, &purchase_id).await?;
+
+        if purchase.status != PurchaseStatus::Paid {
+            return Err(DbErr::Custom("Only paid purchases can be revoked".into()));
+        }
+
+        let mut purchase: purchase::ActiveModel = purchase.into();
+        purchase.status = Set(PurchaseStatus::Revoked);
+        purchase.revoked_at = Set(Some(Utc::now().naive_utc()));
+        purchase.updated_at = Set(Utc::now().naive_utc());
+        purchase.update(db).await
+    }
+}
+
+/// Only paid purchases give access to a course; pending, refunded and revoked
+/// ones are kept for the record.
+pub(crate) fn grants_access() -> sea_query::SimpleExpr {
+    purchase::Column::Status.eq(PurchaseStatus::Paid)
+}
+
+pub(crate) async fn mark_refunded(
+    db: &DbConn,
+    purchase: purchase::Model,
+) -> Result<purchase::Model, DbErr> {
+    let mut purchase: purchase::ActiveModel = purchase.into();
+    purchase.status = Set(PurchaseStatus::Refunded);
+    purchase.refunded_at = Set(Some(Utc::now().naive_utc()));
+    purchase.updated_at = Set(Utc::now().naive_utc());
+    purchase.update(db).await
+}
+
+// A purchase of one of `user_id`'s courses.
+async fn owned_purchase(
+    db: &DbConn,
+    user_id: &str,
+    purchase_id: &str,
+) -> Result<purchase::Model, DbErr> {
+    Purchase::find_by_id(purchase_id)
+        .inner_join(Course)
+        .filter(course::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find purchase".into()))
+}
@@ -0,0 +1,466 @@
+use ::entities::{prelude::*, *};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::{
+    chrono::{NaiveDateTime, Utc},
+    Uuid,
+};
+
+use crate::{search, ChapterUpdate, CourseUpdate};
+
+pub struct Revisions;
+
+const COURSE_FIELDS: [&str; 5] = ["title", "description", "imageUrl", "price", "categoryId"];
+const CHAPTER_FIELDS: [&str; 6] = [
+    "title",
+    "description",
+    "videoId",
+    "isFree",
+    "position",
+    "isPublished",
+];
+
+/// The course and its chapters as students saw them at a given version.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseSnapshot {
+    pub course: course::Model,
+    pub chapters: Vec<chapter::Model>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionSummary {
+    id: String,
+    version: i32,
+    user_id: String,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+impl From<course_revision::Model> for RevisionSummary {
+    fn from(revision: course_revision::Model) -> Self {
+        Self {
+            id: revision.id,
+            version: revision.version,
+            user_id: revision.user_id,
+            created_at: revision.created_at,
+        }
+    }
+}
+
+impl Revisions {
+    /// Applies every pending course and chapter draft to the live rows and
+    /// records the result as a new revision, all in one transaction.
+    pub async fn publish_draft(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<course_revision::Model, DbErr> {
+        let course = owned_course(db, &user_id, &course_id).await?;
+
+        let txn = db.begin().await?;
+
+        let course_draft = CourseDraft::find_by_id(course_id.clone()).one(&txn).await?;
+        let chapter_drafts = ChapterDraft::find()
+            .filter(chapter_draft::Column::CourseId.eq(course_id.clone()))
+            .all(&txn)
+            .await?;
+
+        if course_draft.is_none() && chapter_drafts.is_empty() {
+            return Err(DbErr::Custom("No draft to publish".into()));
+        }
+
+        if let Some(draft) = course_draft {
+            let updates: CourseUpdate = parse_json(&draft.data)?;
+            let mut published = course;
+            updates.apply_to(&mut published);
+            published.updated_at = Utc::now().naive_utc();
+            course::ActiveModel::from(published)
+                .reset_all()
+                .update(&txn)
+                .await?;
+        }
+
+        for draft in chapter_drafts {
+            let chapter = Chapter::find_by_id(draft.chapter_id.clone())
+                .one(&txn)
+                .await?
+                .ok_or(DbErr::RecordNotFound("Cannot find chapter".into()))?;
+            let updates: ChapterUpdate = parse_json(&draft.data)?;
+            let mut published = chapter;
+            updates.apply_to(&mut published);
+            published.updated_at = Utc::now().naive_utc();
+            chapter::ActiveModel::from(published)
+                .reset_all()
+                .update(&txn)
+                .await?;
+        }
+
+        delete_drafts(&txn, &course_id).await?;
+        search::index_course(&txn, &course_id).await?;
+        let revision = record(&txn, &user_id, &course_id).await?;
+
+        txn.commit().await?;
+
+        Ok(revision)
+    }
+
+    pub async fn discard_draft(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<(), DbErr> {
+        owned_course(db, &user_id, &course_id).await?;
+        delete_drafts(db, &course_id).await
+    }
+
+    pub async fn history(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+    ) -> Result<Vec<RevisionSummary>, DbErr> {
+        owned_course(db, &user_id, &course_id).await?;
+
+        let revisions = CourseRevision::find()
+            .filter(course_revision::Column::CourseId.eq(course_id))
+            .order_by_desc(course_revision::Column::Version)
+            .all(db)
+            .await?;
+
+        Ok(revisions.into_iter().map(RevisionSummary::from).collect())
+    }
+
+    /// Lists what changed between two versions. Without `to`, the current
+    /// draft is compared against `from`.
+    pub async fn diff(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+        from: i32,
+        to: Option<i32>,
+    ) -> Result<Vec<RevisionChange>, DbErr> {
+        let course = owned_course(db, &user_id, &course_id).await?;
+
+        let before = snapshot(db, &course_id, from).await?;
+        let after = match to {
+            Some(version) => snapshot(db, &course_id, version).await?,
+            None => preview(db, course).await?,
+        };
+
+        Ok(diff_snapshots(&before, &after))
+    }
+
+    /// Restores the content of `version` on the live course and records it as
+    /// a new revision. Pending drafts are discarded. Chapters deleted since
+    /// `version` are not recreated.
+    pub async fn rollback(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+        version: i32,
+    ) -> Result<course_revision::Model, DbErr> {
+        let course = owned_course(db, &user_id, &course_id).await?;
+        let target = snapshot(db, &course_id, version).await?;
+
+        let txn = db.begin().await?;
+
+        let category_exists = match target.course.category_id.clone() {
+            Some(category_id) => Category::find_by_id(category_id).one(&txn).await?.is_some(),
+            None => true,
+        };
+
+        let mut restored: course::ActiveModel = course.into();
+        restored.title = Set(target.course.title);
+        restored.description = Set(target.course.description);
+        restored.image_url = Set(target.course.image_url);
+        restored.price = Set(target.course.price);
+        if category_exists {
+            restored.category_id = Set(target.course.category_id);
+        }
+        restored.updated_at = Set(Utc::now().naive_utc());
+        restored.update(&txn).await?;
+
+        for chapter in target.chapters {
+            let live = Chapter::find_by_id(chapter.id.clone())
+                .filter(chapter::Column::CourseId.eq(course_id.clone()))
+                .one(&txn)
+                .await?;
+
+            if let Some(live) = live {
+                let mut restored: chapter::ActiveModel = live.into();
+                restored.title = Set(chapter.title);
+                restored.description = Set(chapter.description);
+                restored.video_id = Set(chapter.video_id);
+                restored.is_free = Set(chapter.is_free);
+                restored.updated_at = Set(Utc::now().naive_utc());
+                restored.update(&txn).await?;
+            }
+        }
+
+        delete_drafts(&txn, &course_id).await?;
+        search::index_course(&txn, &course_id).await?;
+        let revision = record(&txn, &user_id, &course_id).await?;
+
+        txn.commit().await?;
+
+        Ok(revision)
+    }
+}
+
+async fn owned_course(db: &DbConn, user_id: &str, course_id: &str) -> Result<course::Model, DbErr> {
+    Course::find_by_id(course_id)
+        .filter(course::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find course".into()))
+}
+
+fn parse_json<T: for<'de> Deserialize<'de>>(data: &str) -> Result<T, DbErr> {
+    serde_json::from_str(data).map_err(|err| DbErr::Json(err.to_string()))
+}
+
+async fn delete_drafts<C: ConnectionTrait>(db: &C, course_id: &str) -> Result<(), DbErr> {
+    CourseDraft::delete_by_id(course_id).exec(db).await?;
+    ChapterDraft::delete_many()
+        .filter(chapter_draft::Column::CourseId.eq(course_id))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+async fn snapshot(db: &DbConn, course_id: &str, version: i32) -> Result<CourseSnapshot, DbErr> {
+    let revision = CourseRevision::find()
+        .filter(course_revision::Column::CourseId.eq(course_id))
+        .filter(course_revision::Column::Version.eq(version))
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find revision".into()))?;
+
+    parse_json(&revision.snapshot)
+}
+
+/// Snapshots the live course and its chapters as the next version.
+pub(crate) async fn record<C: ConnectionTrait>(
+    db: &C,
+    user_id: &str,
+    course_id: &str,
+) -> Result<course_revision::Model, DbErr> {
+    let course = Course::find_by_id(course_id)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Cannot find course".into()))?;
+    let chapters = Chapter::find()
+        .filter(chapter::Column::CourseId.eq(course_id))
+        .order_by_asc(chapter::Column::Position)
+        .all(db)
+        .await?;
+
+    let last = CourseRevision::find()
+        .filter(course_revision::Column::CourseId.eq(course_id))
+        .order_by_desc(course_revision::Column::Version)
+        .one(db)
+        .await?;
+    let version = last.map_or(1, |revision| revision.version + 1);
+
+    let snapshot = serde_json::to_string(&CourseSnapshot { course, chapters })
+        .map_err(|err| DbErr::Json(err.to_string()))?;
+
+    course_revision::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        course_id: Set(course_id.to_string()),
+        version: Set(version),
+        snapshot: Set(snapshot),
+        user_id: Set(user_id.to_string()),
+        created_at: Set(Utc::now().naive_utc()),
+    }
+    .insert(db)
+    .await
+}
+
+pub(crate) async fn has_revisions<C: ConnectionTrait>(
+    db: &C,
+    course_id: &str,
+) -> Result<bool, DbErr> {
+    let count = CourseRevision::find()
+        .filter(course_revision::Column::CourseId.eq(course_id))
+        .count(db)
+        .await?;
+
+    Ok(count > 0)
+}
+
+pub(crate) async fn course_draft<C: ConnectionTrait>(
+    db: &C,
+    course_id: &str,
+) -> Result<Option<CourseUpdate>, DbErr> {
+    match CourseDraft::find_by_id(course_id).one(db).await? {
+        Some(draft) => Ok(Some(parse_json(&draft.data)?)),
+        None => Ok(None),
+    }
+}
+
+pub(crate) async fn chapter_draft<C: ConnectionTrait>(
+    db: &C,
+    chapter_id: &str,
+) -> Result<Option<ChapterUpdate>, DbErr> {
+    match ChapterDraft::find_by_id(chapter_id).one(db).await? {
+        Some(draft) => Ok(Some(parse_json(&draft.data)?)),
+        None => Ok(None),
+    }
+}
+
+pub(crate) async fn save_course_draft<C: ConnectionTrait>(
+    db: &C,
+    course_id: &str,
+    updates: &CourseUpdate,
+) -> Result<(), DbErr> {
+    let data = serde_json::to_string(updates).map_err(|err| DbErr::Json(err.to_string()))?;
+
+    match CourseDraft::find_by_id(course_id).one(db).await? {
+        Some(draft) => {
+            let mut draft: course_draft::ActiveModel = draft.into();
+            draft.data = Set(data);
+            draft.updated_at = Set(Utc::now().naive_utc());
+            draft.update(db).await?;
+        }
+        None => {
+            course_draft::ActiveModel {
+                course_id: Set(course_id.to_string()),
+                data: Set(data),
+                created_at: Set(Utc::now().naive_utc()),
+                updated_at: Set(Utc::now().naive_utc()),
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn save_chapter_draft<C: ConnectionTrait>(
+    db: &C,
+    chapter: &chapter::Model,
+    updates: &ChapterUpdate,
+) -> Result<(), DbErr> {
+    let data = serde_json::to_string(updates).map_err(|err| DbErr::Json(err.to_string()))?;
+
+    match ChapterDraft::find_by_id(chapter.id.clone()).one(db).await? {
+        Some(draft) => {
+            let mut draft: chapter_draft::ActiveModel = draft.into();
+            draft.data = Set(data);
+            draft.updated_at = Set(Utc::now().naive_utc());
+            draft.update(db).await?;
+        }
+        None => {
+            chapter_draft::ActiveModel {
+                chapter_id: Set(chapter.id.clone()),
+                course_id: Set(chapter.course_id.clone()),
+                data: Set(data),
+                created_at: Set(Utc::now().naive_utc()),
+                updated_at: Set(Utc::now().naive_utc()),
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The course as it will look once the pending drafts are published.
+pub(crate) async fn preview<C: ConnectionTrait>(
+    db: &C,
+    mut course: course::Model,
+) -> Result<CourseSnapshot, DbErr> {
+    if let Some(updates) = course_draft(db, &course.id).await? {
+        updates.apply_to(&mut course);
+    }
+
+    let mut chapters = Chapter::find()
+        .filter(chapter::Column::CourseId.eq(course.id.clone()))
+        .order_by_asc(chapter::Column::Position)
+        .all(db)
+        .await?;
+    let drafts = ChapterDraft::find()
+        .filter(chapter_draft::Column::CourseId.eq(course.id.clone()))
+        .all(db)
+        .await?;
+    for draft in drafts {
+        if let Some(chapter) = chapters.iter_mut().find(|c| c.id == draft.chapter_id) {
+            let updates: ChapterUpdate = parse_json(&draft.data)?;
+            updates.apply_to(chapter);
+        }
+    }
+
+    Ok(CourseSnapshot { course, chapters })
+}
+
+fn diff_fields(
+    prefix: &str,
+    fields: &[&str],
+    before: &Value,
+    after: &Value,
+) -> Vec<RevisionChange> {
+    fields
+        .iter()
+        .filter(|field| before[**field] != after[**field])
+        .map(|field| RevisionChange {
+            field: format!("{}{}", prefix, field),
+            before: before[*field].clone(),
+            after: after[*field].clone(),
+        })
+        .collect()
+}
+
+fn to_value<T: Serialize>(model: &T) -> Value {
+    serde_json::to_value(model).unwrap_or(Value::Null)
+}
+
+fn diff_snapshots(before: &CourseSnapshot, after: &CourseSnapshot) -> Vec<RevisionChange> {
+    let mut changes = diff_fields(
+        "",
+        &COURSE_FIELDS,
+        &to_value(&before.course),
+        &to_value(&after.course),
+    );
+
+    for old in before.chapters.iter() {
+        let prefix = format!("chapters.{}.", old.id);
+        match after.chapters.iter().find(|new| new.id == old.id) {
+            Some(new) => changes.extend(diff_fields(
+                &prefix,
+                &CHAPTER_FIELDS,
+                &to_value(old),
+                &to_value(new),
+            )),
+            None => changes.push(RevisionChange {
+                field: format!("chapters.{}", old.id),
+                before: Value::String(old.title.clone()),
+                after: Value::Null,
+            }),
+        }
+    }
+
+    for new in after.chapters.iter() {
+        if !before.chapters.iter().any(|old| old.id == new.id) {
+            changes.push(RevisionChange {
+                field: format!("chapters.{}", new.id),
+                before: Value::Null,
+                after: Value::String(new.title.clone()),
+            });
+        }
+    }
+
+    changes
+}
@@ -0,0 +1,201 @@
+use ::entities::{prelude::*, *};
+use chrono::Duration;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+
+use crate::{
+    chapters, courses,
+    validation::{UpdateError, Validator},
+};
+
+pub struct Schedule;
+
+/// When a course goes live and when it is taken down again. Both times are
+/// UTC; `None` clears the corresponding schedule.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct CourseSchedule {
+    pub publish_at: Option<NaiveDateTime>,
+    pub unpublish_at: Option<NaiveDateTime>,
+}
+
+/// Like [`CourseSchedule`], plus `drip_days`: how many days after buying the
+/// course a student has to wait before the chapter unlocks.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ChapterSchedule {
+    pub publish_at: Option<NaiveDateTime>,
+    pub unpublish_at: Option<NaiveDateTime>,
+    pub drip_days: Option<i32>,
+}
+
+impl Schedule {
+    pub async fn set_course(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+        schedule: CourseSchedule,
+    ) -> Result<course::Model, UpdateError> {
+        let course = Course::find_by_id(course_id.clone())
+            .filter(course::Column::UserId.eq(user_id.clone()))
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find course".into()))?;
+
+        let mut validator = Validator::default();
+        validate_times(
+            &mut validator,
+            course.is_published,
+            schedule.publish_at,
+            schedule.unpublish_at,
+        );
+        validator.finish()?;
+
+        let mut course: course::ActiveModel = course.into();
+        course.publish_at = Set(schedule.publish_at);
+        course.unpublish_at = Set(schedule.unpublish_at);
+        course.updated_at = Set(Utc::now().naive_utc());
+
+        Ok(course.update(db).await?)
+    }
+
+    pub async fn set_chapter(
+        db: &DbConn,
+        user_id: String,
+        course_id: String,
+        chapter_id: String,
+        schedule: ChapterSchedule,
+    ) -> Result<chapter::Model, UpdateError> {
+        let owned_course = Course::find_by_id(course_id.clone())
+            .filter(course::Column::UserId.eq(user_id.clone()))
+            .one(db)
+            .await?;
+
+        if owned_course.is_none() {
+            return Err(DbErr::RecordNotFound("Cannot find course".into()).into());
+        }
+
+        let chapter = Chapter::find_by_id(chapter_id.clone())
+            .filter(chapter::Column::CourseId.eq(course_id.clone()))
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find chapter".into()))?;
+
+        let mut validator = Validator::default();
+        validate_times(
+            &mut validator,
+            chapter.is_published,
+            schedule.publish_at,
+            schedule.unpublish_at,
+        );
+        if schedule.drip_days.is_some_and(|days| days < 0) {
+            validator.error("dripDays", "Drip days cannot be negative");
+        }
+        validator.finish()?;
+
+        let mut chapter: chapter::ActiveModel = chapter.into();
+        chapter.publish_at = Set(schedule.publish_at);
+        chapter.unpublish_at = Set(schedule.unpublish_at);
+        chapter.drip_days = Set(schedule.drip_days);
+        chapter.updated_at = Set(Utc::now().naive_utc());
+
+        Ok(chapter.update(db).await?)
+    }
+
+    /// Applies every scheduled publish and unpublish due at `now` and returns
+    /// how many were applied. Chapters go first so a course scheduled together
+    /// with its chapters finds them published. Anything still missing required
+    /// fields stays scheduled and is retried on the next run.
+    pub async fn apply_due(db: &DbConn, now: NaiveDateTime) -> Result<u64, DbErr> {
+        let mut applied = 0;
+
+        let due_chapters = Chapter::find()
+            .filter(chapter::Column::IsPublished.eq(false))
+            .filter(chapter::Column::PublishAt.lte(now))
+            .all(db)
+            .await?;
+        for chapter in due_chapters {
+            match chapters::publish_chapter(db, chapter).await {
+                Ok(()) => applied += 1,
+                Err(DbErr::AttrNotSet(_)) => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        let due_courses = Course::find()
+            .filter(course::Column::IsPublished.eq(false))
+            .filter(course::Column::PublishAt.lte(now))
+            .all(db)
+            .await?;
+        for course in due_courses {
+            match courses::publish_course(db, course).await {
+                Ok(()) => applied += 1,
+                Err(DbErr::Custom(_)) => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        let due_chapters = Chapter::find()
+            .filter(chapter::Column::UnpublishAt.lte(now))
+            .all(db)
+            .await?;
+        for chapter in due_chapters {
+            chapters::unpublish_chapter(db, chapter).await?;
+            applied += 1;
+        }
+
+        let due_courses = Course::find()
+            .filter(course::Column::UnpublishAt.lte(now))
+            .all(db)
+            .await?;
+        for course in due_courses {
+            courses::unpublish_course(db, course).await?;
+            applied += 1;
+        }
+
+        Ok(applied)
+    }
+}
+
+fn validate_times(
+    validator: &mut Validator,
+    is_published: bool,
+    publish_at: Option<NaiveDateTime>,
+    unpublish_at: Option<NaiveDateTime>,
+) {
+    if publish_at.is_some() && is_published {
+        validator.error("publishAt", "Already published");
+    }
+    if let (Some(publish_at), Some(unpublish_at)) = (publish_at, unpublish_at) {
+        if unpublish_at <= publish_at {
+            validator.error("unpublishAt", "Unpublish time must be after publish time");
+        }
+    }
+}
+
+/// When a dripped chapter unlocks for a student who was given access at
+/// `granted_at`, or `None` if it is already available at `now`. Free chapters
+/// are never dripped, and without access there is nothing to drip from.
+pub(crate) fn locked_until(
+    chapter: &chapter::Model,
+    granted_at: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    if chapter.is_free {
+        return None;
+    }
+
+    let release = granted_at? + Duration::days(chapter.drip_days?.into());
+    (release > now).then_some(release)
+}
+
+/// Whether the student cannot watch `chapter` at `now`: it is not free and
+/// they have no access, or it has not dripped to them yet.
+pub(crate) fn is_locked(
+    chapter: &chapter::Model,
+    granted_at: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+) -> bool {
+    (!chapter.is_free && granted_at.is_none()) || locked_until(chapter, granted_at, now).is_some()
+}
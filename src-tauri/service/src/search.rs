@@ -0,0 +1,581 @@
+use std::collections::{BTreeMap, HashMap};
+
+use ::entities::{prelude::*, *};
+use sea_orm::{
+    sea_query::{Alias, Expr, Func},
+    *,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+/// Results per page unless [`SearchQuery::per_page`] asks for another size.
+pub const DEFAULT_PER_PAGE: u64 = 12;
+pub const MAX_PER_PAGE: u64 = 50;
+
+// How much a match in each part of a course counts towards its score.
+const TITLE_WEIGHT: f64 = 5.0;
+const CATEGORY_WEIGHT: f64 = 3.0;
+const CHAPTER_WEIGHT: f64 = 2.0;
+const DESCRIPTION_WEIGHT: f64 = 1.0;
+
+// How much each kind of match is worth, before weighting.
+const EXACT_MATCH: f64 = 1.0;
+const PREFIX_MATCH: f64 = 0.7;
+const TYPO_MATCH: f64 = 0.4;
+
+/// Price buckets courses are filtered and counted by, in VND.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum PriceRange {
+    Free,
+    /// Up to 200,000.
+    Under200k,
+    /// From 200,000 up to 500,000.
+    Under500k,
+    /// From 500,000 up to 1,000,000.
+    Under1m,
+    /// 1,000,000 and over.
+    Over1m,
+}
+
+impl PriceRange {
+    pub const ALL: [PriceRange; 5] = [
+        PriceRange::Free,
+        PriceRange::Under200k,
+        PriceRange::Under500k,
+        PriceRange::Under1m,
+        PriceRange::Over1m,
+    ];
+
+    /// Inclusive lower and exclusive upper bound.
+    pub fn bounds(self) -> (i32, Option<i32>) {
+        match self {
+            PriceRange::Free => (0, Some(1)),
+            PriceRange::Under200k => (1, Some(200_000)),
+            PriceRange::Under500k => (200_000, Some(500_000)),
+            PriceRange::Under1m => (500_000, Some(1_000_000)),
+            PriceRange::Over1m => (1_000_000, None),
+        }
+    }
+
+    /// The bucket a course with `price` falls in. Courses without a price
+    /// are free.
+    pub fn of(price: Option<i32>) -> PriceRange {
+        let price = price.unwrap_or(0).max(0);
+        PriceRange::ALL
+            .into_iter()
+            .find(|range| match range.bounds() {
+                (min, Some(max)) => (min..max).contains(&price),
+                (min, None) => price >= min,
+            })
+            .unwrap_or(PriceRange::Free)
+    }
+
+    /// Courses whose price falls in this bucket.
+    pub(crate) fn condition(self) -> Condition {
+        match self.bounds() {
+            (_, Some(1)) => Condition::any()
+                .add(course::Column::Price.is_null())
+                .add(course::Column::Price.lt(1)),
+            (min, Some(max)) => Condition::all()
+                .add(course::Column::Price.gte(min))
+                .add(course::Column::Price.lt(max)),
+            (min, None) => Condition::all().add(course::Column::Price.gte(min)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SearchQuery {
+    /// Words to look for in titles, descriptions, chapter titles and category
+    /// names. Accents and case are ignored.
+    pub text: Option<String>,
+    pub category_id: Option<String>,
+    pub price: Option<PriceRange>,
+    /// 1-based.
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+impl SearchQuery {
+    pub(crate) fn page(&self) -> u64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub(crate) fn per_page(&self) -> u64 {
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryFacet {
+    pub id: String,
+    pub name: String,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceFacet {
+    pub range: PriceRange,
+    pub min: i32,
+    pub max: Option<i32>,
+    pub count: u64,
+}
+
+/// How many matching courses each filter would leave. Category counts ignore
+/// the chosen category and price counts ignore the chosen price, so picking
+/// one never hides the others.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFacets {
+    pub categories: Vec<CategoryFacet>,
+    pub prices: Vec<PriceFacet>,
+}
+
+/// Lowercases `text` and strips accents, so `Lập trình` and `lap trinh`
+/// read the same.
+pub(crate) fn normalize(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .filter_map(fold)
+        .collect()
+}
+
+fn fold(c: char) -> Option<char> {
+    let folded = match c {
+        // Combining marks left over from decomposed input.
+        '\u{300}'..='\u{36f}' => return None,
+        'à' | 'á' | 'ả' | 'ã' | 'ạ' | 'ă' | 'ằ' | 'ắ' | 'ẳ' | 'ẵ' | 'ặ' | 'â' | 'ầ' | 'ấ' | 'ẩ'
+        | 'ẫ' | 'ậ' | 'ä' | 'å' => 'a',
+        'è' | 'é' | 'ẻ' | 'ẽ' | 'ẹ' | 'ê' | 'ề' | 'ế' | 'ể' | 'ễ' | 'ệ' | 'ë' => {
+            'e'
+        }
+        'ì' | 'í' | 'ỉ' | 'ĩ' | 'ị' | 'î' | 'ï' => 'i',
+        'ò' | 'ó' | 'ỏ' | 'õ' | 'ọ' | 'ô' | 'ồ' | 'ố' | 'ổ' | 'ỗ' | 'ộ' | 'ơ' | 'ờ' | 'ớ' | 'ở'
+        | 'ỡ' | 'ợ' | 'ö' => 'o',
+        'ù' | 'ú' | 'ủ' | 'ũ' | 'ụ' | 'ư' | 'ừ' | 'ứ' | 'ử' | 'ữ' | 'ự' | 'û' | 'ü' => {
+            'u'
+        }
+        'ỳ' | 'ý' | 'ỷ' | 'ỹ' | 'ỵ' | 'ÿ' => 'y',
+        'đ' => 'd',
+        'ç' => 'c',
+        'ñ' => 'n',
+        c => c,
+    };
+    Some(folded)
+}
+
+/// Normalized words of `text`.
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    normalize(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Part of a course a word in the index comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Field {
+    Title,
+    Category,
+    Chapter,
+    Description,
+}
+
+impl Field {
+    const ALL: [Field; 4] = [
+        Field::Title,
+        Field::Category,
+        Field::Chapter,
+        Field::Description,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Field::Title => "title",
+            Field::Category => "category",
+            Field::Chapter => "chapter",
+            Field::Description => "description",
+        }
+    }
+
+    fn weight(self) -> f64 {
+        match self {
+            Field::Title => TITLE_WEIGHT,
+            Field::Category => CATEGORY_WEIGHT,
+            Field::Chapter => CHAPTER_WEIGHT,
+            Field::Description => DESCRIPTION_WEIGHT,
+        }
+    }
+}
+
+/// The words of one course that matched some term, by field.
+pub(crate) struct Document {
+    words: [Vec<String>; 4],
+    phrase: String,
+}
+
+impl Document {
+    pub(crate) fn new(title: &str, matched: Vec<(Field, String)>) -> Self {
+        let mut words: [Vec<String>; 4] = Default::default();
+        for (field, word) in matched {
+            words[field as usize].push(word);
+        }
+        Document {
+            words,
+            phrase: tokenize(title).join(" "),
+        }
+    }
+
+    /// How well the document matches `terms`, or `None` if some term is
+    /// nowhere to be found. Every term has to match somewhere; matches in
+    /// more important fields and closer matches score higher, and a title
+    /// containing the whole query gets a bonus.
+    pub(crate) fn score(&self, terms: &[String]) -> Option<f64> {
+        let mut score = 0.0;
+        for term in terms {
+            let term_score: f64 = Field::ALL
+                .iter()
+                .map(|field| best_match(term, &self.words[*field as usize]) * field.weight())
+                .sum();
+            if term_score == 0.0 {
+                return None;
+            }
+            score += term_score;
+        }
+
+        if terms.len() > 1 && self.phrase.contains(&terms.join(" ")) {
+            score += TITLE_WEIGHT;
+        }
+
+        Some(score)
+    }
+}
+
+/// Rewrites the index rows of `course_id` from its title, description,
+/// category and chapter titles. Run after every write that changes one of
+/// them; deleting a course or chapter drops its rows through the foreign
+/// keys. Publishing needs nothing, as [`matching_documents`] checks it.
+pub(crate) async fn index_course<C: ConnectionTrait>(db: &C, course_id: &str) -> Result<(), DbErr> {
+    CourseSearchWord::delete_many()
+        .filter(course_search_word::Column::CourseId.eq(course_id))
+        .exec(db)
+        .await?;
+    let Some((course, category)) = Course::find_by_id(course_id)
+        .find_also_related(Category)
+        .one(db)
+        .await?
+    else {
+        return Ok(());
+    };
+    let chapters = Chapter::find()
+        .filter(chapter::Column::CourseId.eq(course_id))
+        .all(db)
+        .await?;
+
+    let mut rows = Vec::new();
+    let mut add = |field: Field, chapter_id: Option<&String>, text: &str| {
+        let mut words = tokenize(text);
+        words.sort();
+        words.dedup();
+        rows.extend(
+            words
+                .into_iter()
+                .map(|word| course_search_word::ActiveModel {
+                    id: Set(Uuid::new_v4().to_string()),
+                    course_id: Set(course.id.clone()),
+                    chapter_id: Set(chapter_id.cloned()),
+                    field: Set(field.as_str().to_string()),
+                    word: Set(word),
+                }),
+        );
+    };
+    add(Field::Title, None, &course.title);
+    if let Some(category) = &category {
+        add(Field::Category, None, &category.name);
+    }
+    if let Some(description) = &course.description {
+        add(Field::Description, None, description);
+    }
+    for chapter in &chapters {
+        add(Field::Chapter, Some(&chapter.id), &chapter.title);
+    }
+
+    // Keeps each insert well under SQLite's limit on bound parameters.
+    for chunk in rows.chunks(INDEX_INSERT_CHUNK) {
+        CourseSearchWord::insert_many(chunk.to_vec())
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+const INDEX_INSERT_CHUNK: usize = 500;
+
+/// Indexes the courses that have no index rows yet: those written before the
+/// index existed, or by an older version of the app.
+pub(crate) async fn index_missing(db: &DbConn) -> Result<usize, DbErr> {
+    let missing: Vec<String> = Course::find()
+        .select_only()
+        .column(course::Column::Id)
+        .left_join(CourseSearchWord)
+        .filter(course_search_word::Column::Id.is_null())
+        .into_tuple()
+        .all(db)
+        .await?;
+    for course_id in &missing {
+        index_course(db, course_id).await?;
+    }
+    Ok(missing.len())
+}
+
+/// Published courses with a word matching each of `terms`, by id, with the
+/// matching words of published chapters and the course itself. Only the
+/// index is read, and only for words that can match: exact and prefix
+/// matches, and words close enough in length to be a typo.
+pub(crate) async fn matching_documents(
+    db: &DbConn,
+    terms: &[String],
+) -> Result<HashMap<String, Vec<(Field, String)>>, DbErr> {
+    let mut candidates = Condition::any();
+    for term in terms {
+        let len = term.chars().count() as i32;
+        candidates = candidates.add(course_search_word::Column::Word.eq(term.as_str()));
+        if len >= 2 {
+            candidates = candidates.add(course_search_word::Column::Word.starts_with(term));
+        }
+        let allowed = typo_allowance(term);
+        if allowed > 0 {
+            candidates = candidates.add(
+                Expr::expr(Func::cust(Alias::new("length")).arg(Expr::col((
+                    CourseSearchWord,
+                    course_search_word::Column::Word,
+                ))))
+                .between(len - allowed as i32, len + allowed as i32),
+            );
+        }
+    }
+    let words: Vec<String> = CourseSearchWord::find()
+        .select_only()
+        .column(course_search_word::Column::Word)
+        .distinct()
+        .filter(candidates)
+        .into_tuple()
+        .all(db)
+        .await?;
+    let words: Vec<String> = words
+        .into_iter()
+        .filter(|word| terms.iter().any(|term| match_quality(term, word) > 0.0))
+        .collect();
+    if words.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let rows: Vec<(String, String, String)> = CourseSearchWord::find()
+        .select_only()
+        .column(course_search_word::Column::CourseId)
+        .column(course_search_word::Column::Field)
+        .column(course_search_word::Column::Word)
+        .inner_join(Course)
+        .left_join(Chapter)
+        .filter(course::Column::IsPublished.eq(true))
+        .filter(
+            Condition::any()
+                .add(course_search_word::Column::ChapterId.is_null())
+                .add(chapter::Column::IsPublished.eq(true)),
+        )
+        .filter(course_search_word::Column::Word.is_in(words))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut documents: HashMap<String, Vec<(Field, String)>> = HashMap::new();
+    for (course_id, field, word) in rows {
+        let Some(field) = Field::ALL.into_iter().find(|known| known.as_str() == field) else {
+            continue;
+        };
+        documents.entry(course_id).or_default().push((field, word));
+    }
+    Ok(documents)
+}
+
+fn best_match(term: &str, words: &[String]) -> f64 {
+    words
+        .iter()
+        .map(|word| match_quality(term, word))
+        .fold(0.0, f64::max)
+}
+
+// Short terms only match exactly or as a prefix; longer ones forgive one
+// typo, and long ones two.
+fn match_quality(term: &str, word: &str) -> f64 {
+    if term == word {
+        return EXACT_MATCH;
+    }
+    let term_len = term.chars().count();
+    if term_len >= 2 && word.starts_with(term) {
+        return PREFIX_MATCH;
+    }
+    let allowed = typo_allowance(term);
+    if allowed == 0 {
+        return 0.0;
+    }
+    match edit_distance(term, word, allowed) {
+        Some(distance) => TYPO_MATCH / distance as f64,
+        None => 0.0,
+    }
+}
+
+fn typo_allowance(term: &str) -> usize {
+    match term.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Optimal string alignment distance between `a` and `b`, counting a swap of
+/// two neighbouring letters as one edit, or `None` if it is over `max`.
+fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
+
+/// Counts `matches` for [`SearchFacets`]. `matches` are the courses that
+/// matched the text, with their category.
+pub(crate) fn facets(
+    matches: &[(&course::Model, Option<&category::Model>)],
+    query: &SearchQuery,
+) -> SearchFacets {
+    let mut categories: BTreeMap<(String, String), u64> = BTreeMap::new();
+    let mut prices: HashMap<PriceRange, u64> = HashMap::new();
+    for (course, category) in matches {
+        let in_price = query
+            .price
+            .map_or(true, |range| PriceRange::of(course.price) == range);
+        let in_category = query.category_id.as_ref().map_or(true, |category_id| {
+            course.category_id.as_ref() == Some(category_id)
+        });
+
+        if let (Some(category), true) = (category, in_price) {
+            *categories
+                .entry((category.name.clone(), category.id.clone()))
+                .or_default() += 1;
+        }
+        if in_category {
+            *prices.entry(PriceRange::of(course.price)).or_default() += 1;
+        }
+    }
+
+    SearchFacets::new(categories, prices)
+}
+
+/// [`SearchFacets`] of every published course, counted by the database.
+pub(crate) async fn catalogue_facets(
+    db: &DbConn,
+    query: &SearchQuery,
+) -> Result<SearchFacets, DbErr> {
+    let categories: Vec<(String, String, i64)> = Course::find()
+        .select_only()
+        .column(category::Column::Name)
+        .column(category::Column::Id)
+        .column_as(course::Column::Id.count(), "count")
+        .inner_join(Category)
+        .filter(course::Column::IsPublished.eq(true))
+        .apply_if(query.price, |select, range| {
+            select.filter(range.condition())
+        })
+        .group_by(category::Column::Name)
+        .group_by(category::Column::Id)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let prices: Vec<(Option<i32>, i64)> = Course::find()
+        .select_only()
+        .column(course::Column::Price)
+        .column_as(course::Column::Id.count(), "count")
+        .filter(course::Column::IsPublished.eq(true))
+        .apply_if(query.category_id.clone(), |select, category_id| {
+            select.filter(course::Column::CategoryId.eq(category_id))
+        })
+        .group_by(course::Column::Price)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut by_range: HashMap<PriceRange, u64> = HashMap::new();
+    for (price, count) in prices {
+        *by_range.entry(PriceRange::of(price)).or_default() += count as u64;
+    }
+
+    Ok(SearchFacets::new(
+        categories
+            .into_iter()
+            .map(|(name, id, count)| ((name, id), count as u64))
+            .collect(),
+        by_range,
+    ))
+}
+
+impl SearchFacets {
+    fn new(categories: BTreeMap<(String, String), u64>, prices: HashMap<PriceRange, u64>) -> Self {
+        SearchFacets {
+            categories: categories
+                .into_iter()
+                .map(|((name, id), count)| CategoryFacet { id, name, count })
+                .collect(),
+            prices: PriceRange::ALL
+                .into_iter()
+                .map(|range| {
+                    let (min, max) = range.bounds();
+                    PriceFacet {
+                        range,
+                        min,
+                        max,
+                        count: prices.get(&range).copied().unwrap_or(0),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Courses left by the filters of `query`, which is what the chosen price
+    /// bucket counts.
+    pub(crate) fn total(&self, query: &SearchQuery) -> u64 {
+        self.prices
+            .iter()
+            .filter(|facet| query.price.map_or(true, |range| facet.range == range))
+            .map(|facet| facet.count)
+            .sum()
+    }
+}
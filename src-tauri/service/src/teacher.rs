@@ -1,6 +1,11 @@
-use ::entities::{prelude::*, *};
-use sea_orm::*;
-use serde::Serialize;
+use std::collections::BTreeMap;
+
+use ::entities::{prelude::*, sea_orm_active_enums::PurchaseStatus, *};
+use sea_orm::{sea_query::Expr, *};
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::NaiveDateTime;
+
+use crate::revisions;
 
 pub struct Teacher;
 
@@ -11,21 +16,100 @@ pub struct TeacherCourse {
     course: course::Model,
     chapters: Vec<chapter::Model>,
     attachments: Vec<attachment::Model>,
+    has_draft: bool,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TeacherCourseData {
     name: String,
-    total: i32,
+    total: i64,
+    sales: i64,
+}
+
+/// One period of [`TeacherAnalytics::series`], named by its first day
+/// (`2024-12-23`) or, for months, by the month (`2024-12`).
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsPoint {
+    period: String,
+    revenue: i64,
+    sales: i64,
+    refunds: i64,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TeacherAnalytics {
     data: Vec<TeacherCourseData>,
-    total_revenue: i32,
-    total_sales: i32,
+    total_revenue: i64,
+    total_sales: i64,
+    total_refunds: i64,
+    refunded_amount: i64,
+    active_learners: i64,
+    series: Vec<AnalyticsPoint>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AnalyticsInterval {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+/// The stretch of time analytics cover. `from` is inclusive and `to`
+/// exclusive; either can be left open.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AnalyticsRange {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub interval: AnalyticsInterval,
+}
+
+impl AnalyticsInterval {
+    // The period name as text. Weeks start on Monday.
+    fn bucket(self, backend: DbBackend, column: &str) -> String {
+        match (backend, self) {
+            (DbBackend::Postgres, AnalyticsInterval::Day) => {
+                format!("to_char(date_trunc('day', {}), 'YYYY-MM-DD')", column)
+            }
+            (DbBackend::Postgres, AnalyticsInterval::Week) => {
+                format!("to_char(date_trunc('week', {}), 'YYYY-MM-DD')", column)
+            }
+            (DbBackend::Postgres, AnalyticsInterval::Month) => {
+                format!("to_char(date_trunc('month', {}), 'YYYY-MM')", column)
+            }
+            // SQLite keeps timestamps as `YYYY-MM-DD HH:MM:SS` text, so days
+            // and months are prefixes.
+            (_, AnalyticsInterval::Day) => format!("substr({}, 1, 10)", column),
+            (_, AnalyticsInterval::Week) => {
+                format!("date(substr({}, 1, 10), 'weekday 0', '-6 days')", column)
+            }
+            (_, AnalyticsInterval::Month) => format!("substr({}, 1, 7)", column),
+        }
+    }
+}
+
+impl AnalyticsRange {
+    fn condition(&self, column: impl ColumnTrait) -> Condition {
+        Condition::all()
+            .add(column.is_not_null())
+            .add_option(self.from.map(|from| column.gte(from)))
+            .add_option(self.to.map(|to| column.lt(to)))
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct PurchaseTotals {
+    course_id: String,
+    name: String,
+    period: String,
+    amount: i64,
+    count: i64,
 }
 
 impl Teacher {
@@ -47,12 +131,18 @@ impl Teacher {
             None => return Err(DbErr::RecordNotFound("Cannot find course".into())),
         };
 
-        // Step 2: Find related chapters for this course, ordered by position in ascending order
-        let chapters = chapter::Entity::find()
-            .filter(chapter::Column::CourseId.eq(course_id.clone()))
-            .order_by_asc(chapter::Column::Position) // Order by position ASC
-            .all(db)
-            .await?;
+        // Step 2: Show the course and its chapters (ordered by position) with
+        // pending draft edits applied, since that is what the teacher is editing
+        let has_draft = CourseDraft::find_by_id(course_id.clone())
+            .one(db)
+            .await?
+            .is_some()
+            || ChapterDraft::find()
+                .filter(chapter_draft::Column::CourseId.eq(course_id.clone()))
+                .count(db)
+                .await?
+                > 0;
+        let preview = revisions::preview(db, course).await?;
 
         // Step 3: Find related attachments for this course, ordered by createdAt in descending order
         let attachments = attachment::Entity::find()
@@ -63,9 +153,10 @@ impl Teacher {
 
         // Construct the final result
         Ok(TeacherCourse {
-            course,
-            chapters,
+            course: preview.course,
+            chapters: preview.chapters,
             attachments,
+            has_draft,
         })
     }
 
@@ -79,62 +170,232 @@ impl Teacher {
             .one(db)
             .await?;
 
-        let chapter = match chapter {
+        let mut chapter = match chapter {
             Some(chapter) => chapter,
             _ => return Err(DbErr::RecordNotFound("Cannot find chapter".into())),
         };
 
+        if let Some(updates) = revisions::chapter_draft(db, &chapter.id).await? {
+            updates.apply_to(&mut chapter);
+        }
+
         Ok(chapter)
     }
 
-    pub async fn analytics(db: &DbConn, user_id: String) -> Result<TeacherAnalytics, DbErr> {
-        let purchases = Purchase::find()
-            .filter(purchase::Column::UserId.eq(user_id.clone()))
-            .all(db)
-            .await?;
-
-        if purchases.len() == 0 {
-            return Ok(TeacherAnalytics {
-                data: vec![],
-                total_revenue: 0,
-                total_sales: 0,
-            });
+    /// Sales of the teacher's courses within `range`. A sale counts when it
+    /// is paid and a refund when it is refunded, so `total_revenue` is what
+    /// students paid, after coupon discounts, minus what was given back.
+    /// Bundles and subscriptions of the teacher are listed in `data` next to
+    /// the courses, with every subscription renewal counted as a sale.
+    /// Active learners are students who made progress in the range.
+    pub async fn analytics(
+        db: &DbConn,
+        user_id: String,
+        range: AnalyticsRange,
+    ) -> Result<TeacherAnalytics, DbErr> {
+        if let (Some(from), Some(to)) = (range.from, range.to) {
+            if from >= to {
+                return Err(DbErr::Custom(
+                    "Start of the range must be before its end".into(),
+                ));
+            }
         }
 
-        let mut data: Vec<TeacherCourseData> = Vec::new();
-        let mut total_revenue: i32 = 0;
-        let total_sales: i32 = purchases.len() as i32;
-
-        for purchase in purchases {
-            let course = course::Entity::find_by_id(purchase.course_id.clone())
-                .one(db)
-                .await?;
+        let mut sales = purchase_totals(db, &user_id, &range, purchase::Column::PaidAt).await?;
+        let mut refunds =
+            purchase_totals(db, &user_id, &range, purchase::Column::RefundedAt).await?;
+        sales.extend(
+            refunded_sale_totals(db, &user_id, &range, refunded_sale::Column::PaidAt).await?,
+        );
+        refunds.extend(
+            refunded_sale_totals(db, &user_id, &range, refunded_sale::Column::RefundedAt).await?,
+        );
+        sales.extend(
+            product_payment_totals(db, &user_id, &range, product_payment::Column::PaidAt).await?,
+        );
+        refunds.extend(
+            product_payment_totals(db, &user_id, &range, product_payment::Column::RefundedAt)
+                .await?,
+        );
 
-            let course_names: Vec<String> = data.iter().map(|c| c.name.clone()).collect();
+        let mut data: Vec<(String, TeacherCourseData)> = Vec::new();
+        let mut series: BTreeMap<String, AnalyticsPoint> = BTreeMap::new();
+        let mut refunded_amount = 0;
+        for (row, refund) in sales
+            .into_iter()
+            .map(|row| (row, false))
+            .chain(refunds.into_iter().map(|row| (row, true)))
+        {
+            let (amount, sales) = if refund {
+                (-row.amount, 0)
+            } else {
+                (row.amount, row.count)
+            };
 
-            if let Some(course) = course {
-                if !course_names.contains(&course.title) {
-                    data.push(TeacherCourseData {
-                        name: course.title.to_string(),
-                        total: 0,
-                    });
+            let course = match data.iter_mut().find(|(id, _)| *id == row.course_id) {
+                Some((_, course)) => course,
+                None => {
+                    data.push((
+                        row.course_id,
+                        TeacherCourseData {
+                            name: row.name,
+                            total: 0,
+                            sales: 0,
+                        },
+                    ));
+                    &mut data.last_mut().unwrap().1
                 }
-                data.iter_mut()
-                    .map(|d| {
-                        if d.name == course.title.to_string() {
-                            d.total += course.price.unwrap();
-                        }
-                    })
-                    .count();
-
-                total_revenue += course.price.unwrap();
+            };
+            course.total += amount;
+            course.sales += sales;
+
+            let point = series
+                .entry(row.period.clone())
+                .or_insert_with(|| AnalyticsPoint {
+                    period: row.period,
+                    revenue: 0,
+                    sales: 0,
+                    refunds: 0,
+                });
+            point.revenue += amount;
+            point.sales += sales;
+            if refund {
+                point.refunds += row.count;
+                refunded_amount += row.amount;
             }
         }
 
+        let active_learners = UserProgress::find()
+            .select_only()
+            .column_as(
+                Expr::cust("COUNT(DISTINCT \"UserProgress\".\"userId\")"),
+                "count",
+            )
+            .inner_join(Chapter)
+            .join(JoinType::InnerJoin, chapter::Relation::Course.def())
+            .filter(course::Column::UserId.eq(user_id))
+            .apply_if(range.from, |query, from| {
+                query.filter(user_progress::Column::UpdatedAt.gte(from))
+            })
+            .apply_if(range.to, |query, to| {
+                query.filter(user_progress::Column::UpdatedAt.lt(to))
+            })
+            .into_tuple::<i64>()
+            .one(db)
+            .await?
+            .unwrap_or(0);
+
+        let series: Vec<AnalyticsPoint> = series.into_values().collect();
         Ok(TeacherAnalytics {
-            data: data,
-            total_revenue: total_revenue,
-            total_sales: total_sales,
+            total_revenue: series.iter().map(|point| point.revenue).sum(),
+            total_sales: series.iter().map(|point| point.sales).sum(),
+            total_refunds: series.iter().map(|point| point.refunds).sum(),
+            refunded_amount,
+            data: data.into_iter().map(|(_, course)| course).collect(),
+            active_learners,
+            series,
         })
     }
 }
+
+// Purchases of the teacher's courses whose `column` timestamp falls in
+// `range`, summed per course and period.
+async fn purchase_totals(
+    db: &DbConn,
+    user_id: &str,
+    range: &AnalyticsRange,
+    column: purchase::Column,
+) -> Result<Vec<PurchaseTotals>, DbErr> {
+    let period = range.interval.bucket(
+        db.get_database_backend(),
+        &format!("\"Purchase\".\"{}\"", column.as_str()),
+    );
+
+    Purchase::find()
+        .select_only()
+        .column_as(purchase::Column::CourseId, "course_id")
+        .column_as(course::Column::Title, "name")
+        .column_as(Expr::cust(period.clone()), "period")
+        .column_as(purchase::Column::Amount.sum(), "amount")
+        .column_as(purchase::Column::Id.count(), "count")
+        .inner_join(Course)
+        .filter(course::Column::UserId.eq(user_id))
+        .filter(purchase::Column::Status.ne(PurchaseStatus::Pending))
+        .filter(range.condition(column))
+        .group_by(purchase::Column::CourseId)
+        .group_by(course::Column::Title)
+        .group_by(Expr::cust(period))
+        .order_by_asc(course::Column::Title)
+        .into_model::<PurchaseTotals>()
+        .all(db)
+        .await
+}
+
+// Sales that were refunded before the student bought the course again, like
+// `purchase_totals`.
+async fn refunded_sale_totals(
+    db: &DbConn,
+    user_id: &str,
+    range: &AnalyticsRange,
+    column: refunded_sale::Column,
+) -> Result<Vec<PurchaseTotals>, DbErr> {
+    let period = range.interval.bucket(
+        db.get_database_backend(),
+        &format!("\"RefundedSale\".\"{}\"", column.as_str()),
+    );
+
+    RefundedSale::find()
+        .select_only()
+        .column_as(purchase::Column::CourseId, "course_id")
+        .column_as(course::Column::Title, "name")
+        .column_as(Expr::cust(period.clone()), "period")
+        .column_as(refunded_sale::Column::Amount.sum(), "amount")
+        .column_as(refunded_sale::Column::Id.count(), "count")
+        .inner_join(Purchase)
+        .join(JoinType::InnerJoin, purchase::Relation::Course.def())
+        .filter(course::Column::UserId.eq(user_id))
+        .filter(range.condition(column))
+        .group_by(purchase::Column::CourseId)
+        .group_by(course::Column::Title)
+        .group_by(Expr::cust(period))
+        .order_by_asc(course::Column::Title)
+        .into_model::<PurchaseTotals>()
+        .all(db)
+        .await
+}
+
+// Payments for the teacher's bundles and subscriptions, like
+// `purchase_totals` but summed per product.
+async fn product_payment_totals(
+    db: &DbConn,
+    user_id: &str,
+    range: &AnalyticsRange,
+    column: product_payment::Column,
+) -> Result<Vec<PurchaseTotals>, DbErr> {
+    let period = range.interval.bucket(
+        db.get_database_backend(),
+        &format!("\"ProductPayment\".\"{}\"", column.as_str()),
+    );
+
+    ProductPayment::find()
+        .select_only()
+        .column_as(product::Column::Id, "course_id")
+        .column_as(product::Column::Title, "name")
+        .column_as(Expr::cust(period.clone()), "period")
+        .column_as(product_payment::Column::Amount.sum(), "amount")
+        .column_as(product_payment::Column::Id.count(), "count")
+        .inner_join(ProductPurchase)
+        .join(
+            JoinType::InnerJoin,
+            product_purchase::Relation::Product.def(),
+        )
+        .filter(product::Column::UserId.eq(user_id))
+        .filter(range.condition(column))
+        .group_by(product::Column::Id)
+        .group_by(product::Column::Title)
+        .group_by(Expr::cust(period))
+        .order_by_asc(product::Column::Title)
+        .into_model::<PurchaseTotals>()
+        .all(db)
+        .await
+}
@@ -0,0 +1,96 @@
+use sea_orm::DbErr;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+pub const TITLE_MAX_LEN: usize = 200;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum UpdateError {
+    Invalid(Vec<FieldError>),
+    Db(DbErr),
+}
+
+impl From<DbErr> for UpdateError {
+    fn from(err: DbErr) -> Self {
+        UpdateError::Db(err)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub(crate) fn error(&mut self, field: &str, message: &str) {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    pub(crate) fn title(&mut self, field: &str, title: &Option<String>) {
+        if let Some(title) = title {
+            let len = title.trim().chars().count();
+            if len == 0 || len > TITLE_MAX_LEN {
+                self.error(
+                    field,
+                    &format!("Title must be between 1 and {} characters", TITLE_MAX_LEN),
+                );
+            }
+        }
+    }
+
+    /// Takes the fields of an update sent as a JSON object, reporting any key
+    /// outside `known`.
+    pub(crate) fn object(&mut self, value: Value, known: &[&str]) -> Map<String, Value> {
+        let fields = match value {
+            Value::Object(fields) => fields,
+            _ => {
+                self.error("", "Update must be an object");
+                return Map::new();
+            }
+        };
+        for key in fields.keys() {
+            if !known.contains(&key.as_str()) {
+                self.error(key, "Unknown field");
+            }
+        }
+        fields
+    }
+
+    /// Reads one field on its own, so a value of the wrong type is reported
+    /// against that field. Missing and `null` fields are `None`.
+    pub(crate) fn field<T: DeserializeOwned>(
+        &mut self,
+        fields: &mut Map<String, Value>,
+        field: &str,
+        expected: &str,
+    ) -> Option<T> {
+        match fields.remove(field) {
+            None | Some(Value::Null) => None,
+            Some(value) => match serde_json::from_value(value) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    self.error(field, &format!("Must be {}", expected));
+                    None
+                }
+            },
+        }
+    }
+
+    pub(crate) fn finish(self) -> Result<(), UpdateError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(UpdateError::Invalid(self.errors))
+        }
+    }
+}
@@ -0,0 +1,320 @@
+mod common;
+
+use chrono::Duration;
+use common::{cloudinary::*, *};
+use entities::{assignment, course};
+use service::{
+    sea_orm::*, Assignments, Courses, Grade, NewAssignment, NewSubmission, OtherRoutes, UpdateError,
+};
+use sqlx::types::chrono::Utc;
+
+fn homework(title: &str) -> NewAssignment {
+    NewAssignment {
+        title: title.into(),
+        instructions: Some("Write a short essay".into()),
+        due_at: None,
+        max_score: 10,
+    }
+}
+
+fn answer(text: &str) -> NewSubmission {
+    NewSubmission {
+        answer: Some(text.into()),
+        ..Default::default()
+    }
+}
+
+fn invalid_fields(res: Result<impl std::fmt::Debug, UpdateError>) -> Vec<String> {
+    match res {
+        Err(UpdateError::Invalid(fields)) => fields.into_iter().map(|f| f.field).collect(),
+        other => panic!("expected validation errors, got {:?}", other),
+    }
+}
+
+async fn assignment_in(
+    db: &DatabaseConnection,
+    course: &course::Model,
+    chapter_id: &str,
+) -> assignment::Model {
+    Assignments::create(
+        db,
+        TEACHER.into(),
+        course.id.clone(),
+        chapter_id.into(),
+        homework("Essay"),
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn create_validates_fields_and_ownership() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+
+    let res = Assignments::create(
+        &db,
+        OTHER_TEACHER.into(),
+        course.id.clone(),
+        chapters[0].id.clone(),
+        homework("Essay"),
+    )
+    .await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+
+    let res = Assignments::create(
+        &db,
+        TEACHER.into(),
+        course.id.clone(),
+        chapters[0].id.clone(),
+        NewAssignment {
+            title: " ".into(),
+            instructions: None,
+            due_at: Some(Utc::now().naive_utc() - Duration::days(1)),
+            max_score: 0,
+        },
+    )
+    .await;
+    assert_eq!(invalid_fields(res), vec!["title", "maxScore", "dueAt"]);
+}
+
+#[tokio::test]
+async fn teachers_grade_what_students_hand_in() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    let assignment = assignment_in(&db, &course, &chapters[0].id).await;
+    purchase(&db, STUDENT, &course.id).await;
+
+    let res = Assignments::submit(
+        &db,
+        &cloudinary(),
+        STUDENT.into(),
+        assignment.id.clone(),
+        answer("  "),
+    )
+    .await;
+    assert_eq!(invalid_fields(res), vec!["answer"]);
+
+    let submission = Assignments::submit(
+        &db,
+        &cloudinary(),
+        STUDENT.into(),
+        assignment.id.clone(),
+        answer("Ownership moves values"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(submission.score, None);
+
+    let ungraded = Assignments::ungraded(&db, TEACHER.into()).await.unwrap();
+    assert_eq!(ungraded.len(), 1);
+    let json = serde_json::to_value(&ungraded[0]).unwrap();
+    assert_eq!(json["id"], submission.id);
+    assert_eq!(json["answer"], "Ownership moves values");
+    assert_eq!(json["assignmentTitle"], "Essay");
+    assert_eq!(json["courseTitle"], "Rust");
+    assert_eq!(json["maxScore"], 10);
+    assert!(Assignments::ungraded(&db, OTHER_TEACHER.into())
+        .await
+        .unwrap()
+        .is_empty());
+
+    let grade = |score| Grade {
+        score,
+        feedback: Some("Well argued".into()),
+    };
+    let res = Assignments::grade(&db, OTHER_TEACHER.into(), submission.id.clone(), grade(8)).await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+    let res = Assignments::grade(&db, TEACHER.into(), submission.id.clone(), grade(11)).await;
+    assert_eq!(invalid_fields(res), vec!["score"]);
+
+    let graded = Assignments::grade(&db, TEACHER.into(), submission.id.clone(), grade(8))
+        .await
+        .unwrap();
+    assert_eq!(graded.score, Some(8));
+    assert!(graded.graded_at.is_some());
+    assert!(Assignments::ungraded(&db, TEACHER.into())
+        .await
+        .unwrap()
+        .is_empty());
+
+    let grades = Assignments::grades(&db, STUDENT.into(), course.id.clone())
+        .await
+        .unwrap();
+    let json = serde_json::to_value(&grades).unwrap();
+    assert_eq!(json[0]["title"], "Essay");
+    assert_eq!(json[0]["submission"]["score"], 8);
+    assert_eq!(json[0]["submission"]["feedback"], "Well argued");
+    let others = Assignments::grades(&db, OTHER_STUDENT.into(), course.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(others[0].submission, None);
+
+    // Handing in again sends the submission back for grading
+    let resubmitted = Assignments::submit(
+        &db,
+        &cloudinary(),
+        STUDENT.into(),
+        assignment.id.clone(),
+        answer("Ownership moves values, borrows do not"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resubmitted.id, submission.id);
+    assert_eq!(resubmitted.score, None);
+    assert_eq!(resubmitted.feedback, None);
+    assert_eq!(
+        Assignments::ungraded(&db, TEACHER.into())
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn submit_requires_access_before_the_due_date() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    let assignment = assignment_in(&db, &course, &chapters[0].id).await;
+
+    let res = Assignments::submit(
+        &db,
+        &cloudinary(),
+        STUDENT.into(),
+        assignment.id.clone(),
+        answer("Done"),
+    )
+    .await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+
+    purchase(&db, STUDENT, &course.id).await;
+    assignment::ActiveModel {
+        due_at: Set(Some(Utc::now().naive_utc() - Duration::hours(1))),
+        ..assignment.clone().into()
+    }
+    .update(&db)
+    .await
+    .unwrap();
+    let res = Assignments::submit(
+        &db,
+        &cloudinary(),
+        STUDENT.into(),
+        assignment.id.clone(),
+        answer("Done"),
+    )
+    .await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::Custom(message))) if message == "The due date has passed"
+    ));
+}
+
+#[tokio::test]
+async fn replacing_a_file_deletes_the_old_one() {
+    let db = setup().await;
+    let cloudinary = MockCloudinary::start().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    let assignment = assignment_in(&db, &course, &chapters[0].id).await;
+    purchase(&db, STUDENT, &course.id).await;
+
+    let file = |name: &str| NewSubmission {
+        file_url: Some(format!(
+            "https://res.cloudinary.com/demo/image/upload/v1/{}.pdf",
+            name
+        )),
+        ..Default::default()
+    };
+    let first = Assignments::submit(
+        &db,
+        &cloudinary.config(),
+        STUDENT.into(),
+        assignment.id.clone(),
+        file("essay-draft"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(first.file_name.as_deref(), Some("essay-draft.pdf"));
+    assert!(cloudinary.requests().is_empty());
+
+    Assignments::submit(
+        &db,
+        &cloudinary.config(),
+        STUDENT.into(),
+        assignment.id.clone(),
+        file("essay-final"),
+    )
+    .await
+    .unwrap();
+
+    let requests = cloudinary.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        requests[1].field("public_id").as_deref(),
+        Some("essay-draft")
+    );
+}
+
+#[tokio::test]
+async fn graded_assignments_count_towards_progress() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    let assignment = assignment_in(&db, &course, &chapters[0].id).await;
+    purchase(&db, STUDENT, &course.id).await;
+    progress(&db, STUDENT, &chapters[0].id, true).await;
+
+    let percentage = Courses::get_progress_percentage(&db, STUDENT.into(), course.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(percentage, 50);
+    let dashboard = OtherRoutes::dashboard_courses(&db, STUDENT.into())
+        .await
+        .unwrap();
+    let dashboard = serde_json::to_value(dashboard).unwrap();
+    assert_eq!(dashboard["coursesInProgress"][0]["progress"], 50);
+
+    // Handing in is not enough, the grade is what counts
+    let submission = Assignments::submit(
+        &db,
+        &cloudinary(),
+        STUDENT.into(),
+        assignment.id.clone(),
+        answer("Done"),
+    )
+    .await
+    .unwrap();
+    let percentage = Courses::get_progress_percentage(&db, STUDENT.into(), course.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(percentage, 50);
+
+    Assignments::grade(
+        &db,
+        TEACHER.into(),
+        submission.id,
+        Grade {
+            score: 3,
+            feedback: None,
+        },
+    )
+    .await
+    .unwrap();
+    let percentage = Courses::get_progress_percentage(&db, STUDENT.into(), course.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(percentage, 100);
+    let dashboard = OtherRoutes::dashboard_courses(&db, STUDENT.into())
+        .await
+        .unwrap();
+    let dashboard = serde_json::to_value(dashboard).unwrap();
+    assert_eq!(dashboard["completedCourses"][0]["progress"], 100);
+}
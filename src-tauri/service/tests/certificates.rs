@@ -0,0 +1,260 @@
+mod common;
+
+use common::*;
+use entities::certificate;
+use service::{
+    check_certificate_keys, sea_orm::*, CertificateSettings, Certificates, Verification,
+};
+
+// The first test key pair of RFC 8032.
+const SIGNING_KEY: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+// The second one.
+const OTHER_PUBLIC_KEY: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+
+/// The issuer's install, which holds the signing key.
+fn settings() -> CertificateSettings {
+    CertificateSettings {
+        public_key: PUBLIC_KEY.into(),
+        signing_key: Some(SIGNING_KEY.into()),
+        teacher_name: "Trần Thị B".into(),
+    }
+}
+
+/// Any other install.
+fn student_settings() -> CertificateSettings {
+    CertificateSettings {
+        signing_key: None,
+        teacher_name: "Someone Else".into(),
+        ..settings()
+    }
+}
+
+#[tokio::test]
+async fn issue_requires_every_published_chapter() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 2).await;
+    ChapterBuilder::new(&course.id, 3).insert(&db).await;
+    purchase(&db, STUDENT, &course.id).await;
+    progress(&db, STUDENT, &chapters[0].id, true).await;
+
+    let res = Certificates::issue(
+        &db,
+        &settings(),
+        STUDENT.into(),
+        "Nguyễn Văn A".into(),
+        course.id.clone(),
+    )
+    .await;
+    assert_eq!(
+        res,
+        Err(DbErr::Custom(
+            "Finish every chapter and assignment to get a certificate".into()
+        ))
+    );
+
+    progress(&db, STUDENT, &chapters[1].id, true).await;
+    let certificate = Certificates::issue(
+        &db,
+        &settings(),
+        STUDENT.into(),
+        "  Nguyễn Văn A ".into(),
+        course.id.clone(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(certificate.student_name, "Nguyễn Văn A");
+    assert_eq!(certificate.course_title, "Rust");
+    assert_eq!(certificate.teacher_name, "Trần Thị B");
+    assert_eq!(certificate.code.len(), 14);
+    assert!(certificate
+        .code
+        .chars()
+        .all(|c| c == '-' || c.is_ascii_digit() || c.is_ascii_uppercase()));
+
+    // Asking again hands back the same certificate
+    let again = Certificates::issue(
+        &db,
+        &settings(),
+        STUDENT.into(),
+        "Someone Else".into(),
+        course.id.clone(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(again, certificate);
+    assert_eq!(
+        Certificates::list(&db, STUDENT.into()).await.unwrap(),
+        vec![certificate]
+    );
+    assert!(Certificates::list(&db, OTHER_STUDENT.into())
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn issue_requires_access_and_a_name() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    progress(&db, STUDENT, &chapters[0].id, true).await;
+
+    let res = Certificates::issue(
+        &db,
+        &settings(),
+        STUDENT.into(),
+        "Nguyễn Văn A".into(),
+        course.id.clone(),
+    )
+    .await;
+    assert_eq!(res, Err(DbErr::RecordNotFound("Cannot find course".into())));
+
+    purchase(&db, STUDENT, &course.id).await;
+    let res = Certificates::issue(
+        &db,
+        &settings(),
+        STUDENT.into(),
+        " ".into(),
+        course.id.clone(),
+    )
+    .await;
+    assert_eq!(
+        res,
+        Err(DbErr::Custom(
+            "Enter the name to print on the certificate".into()
+        ))
+    );
+}
+
+#[tokio::test]
+async fn verify_checks_the_token_against_the_public_key() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    purchase(&db, STUDENT, &course.id).await;
+    progress(&db, STUDENT, &chapters[0].id, true).await;
+    let certificate = Certificates::issue(
+        &db,
+        &settings(),
+        STUDENT.into(),
+        "Nguyễn Văn A".into(),
+        course.id.clone(),
+    )
+    .await
+    .unwrap();
+    let token = Certificates::token(&certificate).unwrap();
+
+    let verification = Certificates::verify(PUBLIC_KEY, &token).unwrap();
+    let json = serde_json::to_value(&verification).unwrap();
+    assert_eq!(json["status"], "valid");
+    assert_eq!(json["certificate"]["code"], certificate.code.as_str());
+    assert_eq!(json["certificate"]["studentName"], "Nguyễn Văn A");
+    assert_eq!(json["certificate"]["courseTitle"], "Rust");
+
+    assert_eq!(
+        Certificates::verify(OTHER_PUBLIC_KEY, &token).unwrap(),
+        Verification::Tampered
+    );
+    for token in ["", "not a certificate", "abc.def", &token[1..]] {
+        assert_ne!(
+            Certificates::verify(PUBLIC_KEY, token).unwrap(),
+            verification
+        );
+    }
+    assert_eq!(
+        Certificates::verify(PUBLIC_KEY, "abc").unwrap(),
+        Verification::Malformed
+    );
+    assert!(Certificates::verify("not-a-key", &token).is_err());
+
+    // Changing what is stored breaks the printed token
+    let changed = certificate::ActiveModel {
+        student_name: Set("Someone Else".into()),
+        ..certificate.clone().into()
+    }
+    .update(&db)
+    .await
+    .unwrap();
+    assert_eq!(
+        Certificates::verify(PUBLIC_KEY, &Certificates::token(&changed).unwrap()).unwrap(),
+        Verification::Tampered
+    );
+}
+
+#[tokio::test]
+async fn certificates_wait_for_the_issuer_to_sign_them() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    purchase(&db, STUDENT, &course.id).await;
+    progress(&db, STUDENT, &chapters[0].id, true).await;
+
+    let pending = Certificates::issue(
+        &db,
+        &student_settings(),
+        STUDENT.into(),
+        "Nguyễn Văn A".into(),
+        course.id.clone(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(pending.signature, "");
+    assert_eq!(Certificates::token(&pending), None);
+
+    // Installs without the signing key sign nothing
+    assert_eq!(
+        Certificates::sign_pending(&db, &student_settings())
+            .await
+            .unwrap(),
+        0
+    );
+
+    // Someone with database access records a certificate they did not earn
+    certificate::ActiveModel {
+        id: Set("forged".into()),
+        code: Set("0000-0000-0000".into()),
+        user_id: Set(OTHER_STUDENT.into()),
+        course_id: Set(course.id.clone()),
+        student_name: Set("Forger".into()),
+        course_title: Set("Rust".into()),
+        teacher_name: Set("Trần Thị B".into()),
+        issued_at: Set(pending.issued_at),
+        signature: Set("".into()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        Certificates::sign_pending(&db, &settings()).await.unwrap(),
+        1
+    );
+    let signed = Certificates::get(&db, STUDENT.into(), course.id.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(signed.code, pending.code);
+    assert_eq!(signed.teacher_name, "Trần Thị B");
+    let token = Certificates::token(&signed).unwrap();
+    assert!(matches!(
+        Certificates::verify(PUBLIC_KEY, &token).unwrap(),
+        Verification::Valid { .. }
+    ));
+
+    let forged = Certificates::get(&db, OTHER_STUDENT.into(), course.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(Certificates::token(&forged), None);
+}
+
+#[test]
+fn keys_must_pair_up() {
+    assert_eq!(
+        check_certificate_keys(PUBLIC_KEY, Some(SIGNING_KEY)),
+        Ok(())
+    );
+    assert_eq!(check_certificate_keys(PUBLIC_KEY, None), Ok(()));
+    assert!(check_certificate_keys(OTHER_PUBLIC_KEY, Some(SIGNING_KEY)).is_err());
+    assert!(check_certificate_keys("d75a98", None).is_err());
+    assert!(check_certificate_keys(PUBLIC_KEY, Some("certificate-key")).is_err());
+}
@@ -0,0 +1,596 @@
+mod common;
+
+use common::*;
+use entities::{prelude::*, *};
+use serde_json::{json, Value};
+use service::{
+    sea_orm::*, ChapterUpdate, Chapters, Courses, ProgressUpdate, ReorderData, UpdateError,
+    WatchSettings,
+};
+
+#[tokio::test]
+async fn get_locks_paid_chapter_without_purchase() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 2).await;
+    attachment(&db, &course.id, "https://example.com/notes.pdf").await;
+
+    let res = Chapters::get(&db, STUDENT.into(), course.id, chapters[0].id.clone())
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    assert_eq!(res["chapter"]["id"], json!(chapters[0].id));
+    assert_eq!(res["coursePrice"]["price"], json!(10));
+    assert_eq!(res["attachments"], json!([]));
+    assert_eq!(res["nextChapter"], Value::Null);
+    assert_eq!(res["entitlement"], Value::Null);
+}
+
+#[tokio::test]
+async fn get_hides_video_of_paid_dripped_chapter_without_purchase() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let dripped = ChapterBuilder::new(&course.id, 2)
+        .video("video")
+        .drip_days(7)
+        .published()
+        .insert(&db)
+        .await;
+    let free = ChapterBuilder::new(&course.id, 3)
+        .video("video")
+        .free()
+        .published()
+        .insert(&db)
+        .await;
+
+    let res = Chapters::get(&db, STUDENT.into(), course.id.clone(), dripped.id.clone())
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+    assert_eq!(res["chapter"]["videoId"], Value::Null);
+    assert_eq!(res["entitlement"], Value::Null);
+
+    let res = Chapters::get(&db, STUDENT.into(), course.id.clone(), free.id)
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+    assert_eq!(res["chapter"]["videoId"], json!("video"));
+
+    let res = Courses::get_with_chapters_with_progress(&db, STUDENT.into(), course.id)
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+    let videos: Vec<&Value> = res["chapters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|chapter| &chapter["videoId"])
+        .collect();
+    assert_eq!(videos, vec![&Value::Null, &Value::Null, &json!("video")]);
+}
+
+#[tokio::test]
+async fn get_rejects_chapter_of_another_course() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let (free_course, _) = published_course(&db, "Go", 0, 1).await;
+    let (_, chapters) = published_course(&db, "Zig", 10, 1).await;
+    purchase(&db, STUDENT, &course.id).await;
+
+    for course_id in [course.id, free_course.id] {
+        let res = Chapters::get(&db, STUDENT.into(), course_id, chapters[0].id.clone()).await;
+        assert!(matches!(res, Err(DbErr::Custom(_))));
+    }
+}
+
+#[tokio::test]
+async fn get_unlocks_next_chapter_of_free_chapter() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER)
+        .price(10)
+        .published()
+        .insert(&db)
+        .await;
+    let free = ChapterBuilder::new(&course.id, 1)
+        .free()
+        .published()
+        .insert(&db)
+        .await;
+    let next = ChapterBuilder::new(&course.id, 2)
+        .published()
+        .insert(&db)
+        .await;
+
+    let res = Chapters::get(&db, STUDENT.into(), course.id, free.id)
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    assert_eq!(res["nextChapter"]["id"], json!(next.id));
+    assert_eq!(res["attachments"], json!([]));
+}
+
+#[tokio::test]
+async fn get_returns_everything_after_purchase() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 3).await;
+    attachment(&db, &course.id, "https://example.com/notes.pdf").await;
+    purchase(&db, STUDENT, &course.id).await;
+    progress(&db, STUDENT, &chapters[1].id, true).await;
+
+    let res = Chapters::get(&db, STUDENT.into(), course.id, chapters[1].id.clone())
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    assert_eq!(res["attachments"].as_array().unwrap().len(), 1);
+    assert_eq!(res["nextChapter"]["id"], json!(chapters[2].id));
+    assert_eq!(res["userProgress"]["isCompleted"], json!(true));
+    assert_eq!(res["entitlement"]["source"], json!("purchase"));
+}
+
+#[tokio::test]
+async fn get_rejects_unpublished_chapter_or_course() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let draft = ChapterBuilder::new(&course.id, 2).insert(&db).await;
+    let hidden = CourseBuilder::new(TEACHER).insert(&db).await;
+    let hidden_chapter = ChapterBuilder::new(&hidden.id, 1)
+        .published()
+        .insert(&db)
+        .await;
+
+    let draft = Chapters::get(&db, STUDENT.into(), course.id, draft.id).await;
+    let hidden = Chapters::get(&db, STUDENT.into(), hidden.id, hidden_chapter.id).await;
+
+    assert!(matches!(draft, Err(DbErr::Custom(_))));
+    assert!(matches!(hidden, Err(DbErr::Custom(_))));
+}
+
+#[tokio::test]
+async fn delete_unpublishes_course_without_published_chapters() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).published().insert(&db).await;
+    let chapter = ChapterBuilder::new(&course.id, 1)
+        .published()
+        .insert(&db)
+        .await;
+
+    Chapters::delete(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        chapter.id,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(Chapter::find().count(&db).await.unwrap(), 0);
+    let course = Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!course.is_published);
+}
+
+#[tokio::test]
+async fn delete_rejects_non_owner() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let chapter = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+
+    let res = Chapters::delete(
+        &db,
+        &cloudinary(),
+        OTHER_TEACHER.into(),
+        course.id,
+        chapter.id,
+    )
+    .await;
+
+    assert!(matches!(res, Err(DbErr::Custom(_))));
+    assert_eq!(Chapter::find().count(&db).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn publish_requires_description_and_video() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let incomplete = ChapterBuilder::new(&course.id, 1)
+        .description("Description")
+        .insert(&db)
+        .await;
+    let complete = ChapterBuilder::new(&course.id, 2)
+        .description("Description")
+        .video("video")
+        .insert(&db)
+        .await;
+
+    let res = Chapters::publish(&db, TEACHER.into(), course.id.clone(), incomplete.id).await;
+    assert!(matches!(res, Err(DbErr::AttrNotSet(_))));
+
+    Chapters::publish(&db, TEACHER.into(), course.id, complete.id.clone())
+        .await
+        .unwrap();
+    let complete = Chapter::find_by_id(complete.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(complete.is_published);
+}
+
+#[tokio::test]
+async fn publish_rejects_non_owner_and_foreign_chapter() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let other = CourseBuilder::new(TEACHER).insert(&db).await;
+    let chapter = ChapterBuilder::new(&other.id, 1)
+        .description("Description")
+        .video("video")
+        .insert(&db)
+        .await;
+
+    let not_owner =
+        Chapters::publish(&db, OTHER_TEACHER.into(), other.id, chapter.id.clone()).await;
+    let foreign = Chapters::publish(&db, TEACHER.into(), course.id, chapter.id).await;
+
+    assert!(matches!(not_owner, Err(DbErr::RecordNotFound(_))));
+    assert!(matches!(foreign, Err(DbErr::RecordNotFound(_))));
+}
+
+#[tokio::test]
+async fn unpublish_last_chapter_unpublishes_course() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 2).await;
+
+    Chapters::unpublish(
+        &db,
+        TEACHER.into(),
+        course.id.clone(),
+        chapters[0].id.clone(),
+    )
+    .await
+    .unwrap();
+    let still_published = Course::find_by_id(course.id.clone())
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(still_published.is_published);
+
+    Chapters::unpublish(
+        &db,
+        TEACHER.into(),
+        course.id.clone(),
+        chapters[1].id.clone(),
+    )
+    .await
+    .unwrap();
+    let course = Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!course.is_published);
+}
+
+#[tokio::test]
+async fn unpublish_rejects_non_owner() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 1).await;
+
+    let res =
+        Chapters::unpublish(&db, OTHER_TEACHER.into(), course.id, chapters[0].id.clone()).await;
+
+    assert!(matches!(res, Err(DbErr::RecordNotFound(_))));
+}
+
+#[tokio::test]
+async fn update_progress_inserts_then_updates() {
+    let db = setup().await;
+    let (_, chapters) = published_course(&db, "Rust", 10, 1).await;
+    let chapter_id = chapters[0].id.clone();
+
+    for is_completed in [true, false] {
+        Chapters::update_progress(
+            &db,
+            STUDENT.into(),
+            chapter_id.clone(),
+            ProgressUpdate {
+                is_completed: Some(is_completed),
+                heartbeat: None,
+            },
+            &WatchSettings::default(),
+        )
+        .await
+        .unwrap();
+    }
+
+    let progress = UserProgress::find()
+        .filter(user_progress::Column::ChapterId.eq(chapter_id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(progress.len(), 1);
+    assert!(!progress[0].is_completed);
+    assert_eq!(progress[0].completed_at, None);
+}
+
+#[tokio::test]
+async fn reorder_applies_new_positions() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let first = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+    let second = ChapterBuilder::new(&course.id, 2).insert(&db).await;
+
+    let list: Vec<ReorderData> = serde_json::from_value(json!([
+        { "id": first.id, "position": 2 },
+        { "id": second.id, "position": 1 },
+    ]))
+    .unwrap();
+    let chapters = Chapters::reorder(&db, TEACHER.into(), course.id, list)
+        .await
+        .unwrap();
+
+    let ids: Vec<String> = chapters.into_iter().map(|chapter| chapter.id).collect();
+    assert_eq!(ids, vec![second.id, first.id]);
+}
+
+#[tokio::test]
+async fn reorder_normalizes_gaps_and_duplicates() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let first = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+    let second = ChapterBuilder::new(&course.id, 2).insert(&db).await;
+    let third = ChapterBuilder::new(&course.id, 3).insert(&db).await;
+
+    let list: Vec<ReorderData> = serde_json::from_value(json!([
+        { "id": third.id, "position": 10 },
+        { "id": first.id, "position": 10 },
+        { "id": second.id, "position": 0 },
+    ]))
+    .unwrap();
+    let chapters = Chapters::reorder(&db, TEACHER.into(), course.id.clone(), list)
+        .await
+        .unwrap();
+
+    let order: Vec<(String, i32)> = chapters
+        .into_iter()
+        .map(|chapter| (chapter.id, chapter.position))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            (second.id.clone(), 1),
+            (first.id.clone(), 2),
+            (third.id.clone(), 3)
+        ]
+    );
+
+    let stored: Vec<(String, i32)> = Chapter::find()
+        .filter(chapter::Column::CourseId.eq(course.id))
+        .order_by_asc(chapter::Column::Position)
+        .all(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|chapter| (chapter.id, chapter.position))
+        .collect();
+    assert_eq!(stored, order);
+}
+
+#[tokio::test]
+async fn reorder_keeps_unlisted_chapters() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let first = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+    let second = ChapterBuilder::new(&course.id, 2).insert(&db).await;
+    let third = ChapterBuilder::new(&course.id, 3).insert(&db).await;
+
+    let list: Vec<ReorderData> =
+        serde_json::from_value(json!([{ "id": third.id, "position": 1 }])).unwrap();
+    let chapters = Chapters::reorder(&db, TEACHER.into(), course.id, list)
+        .await
+        .unwrap();
+
+    let ids: Vec<String> = chapters.into_iter().map(|chapter| chapter.id).collect();
+    assert_eq!(ids, vec![third.id, first.id, second.id]);
+}
+
+#[tokio::test]
+async fn reorder_rejects_foreign_and_duplicate_ids_atomically() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let other = CourseBuilder::new(TEACHER).insert(&db).await;
+    let first = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+    let second = ChapterBuilder::new(&course.id, 2).insert(&db).await;
+    let foreign = ChapterBuilder::new(&other.id, 1).insert(&db).await;
+
+    let list: Vec<ReorderData> = serde_json::from_value(json!([
+        { "id": second.id, "position": 1 },
+        { "id": foreign.id, "position": 2 },
+    ]))
+    .unwrap();
+    let res = Chapters::reorder(&db, TEACHER.into(), course.id.clone(), list).await;
+    assert!(matches!(res, Err(DbErr::RecordNotFound(_))));
+
+    let list: Vec<ReorderData> = serde_json::from_value(json!([
+        { "id": second.id, "position": 1 },
+        { "id": second.id, "position": 2 },
+    ]))
+    .unwrap();
+    let res = Chapters::reorder(&db, TEACHER.into(), course.id, list).await;
+    assert!(matches!(res, Err(DbErr::Custom(_))));
+
+    let first = Chapter::find_by_id(first.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    let foreign = Chapter::find_by_id(foreign.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.position, 1);
+    assert_eq!(foreign.position, 1);
+}
+
+#[tokio::test]
+async fn reorder_rejects_non_owner() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+
+    let res = Chapters::reorder(&db, OTHER_TEACHER.into(), course.id, vec![]).await;
+
+    assert!(matches!(res, Err(DbErr::RecordNotFound(_))));
+}
+
+#[tokio::test]
+async fn create_appends_chapter() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+
+    Chapters::create(&db, TEACHER.into(), course.id.clone(), "Intro".into())
+        .await
+        .unwrap();
+    let chapters = Chapters::create(&db, TEACHER.into(), course.id.clone(), "Basics".into())
+        .await
+        .unwrap();
+
+    let titles: Vec<(String, i32)> = chapters
+        .into_iter()
+        .map(|chapter| (chapter.title, chapter.position))
+        .collect();
+    assert_eq!(
+        titles,
+        vec![("Intro".to_string(), 1), ("Basics".to_string(), 2)]
+    );
+}
+
+#[tokio::test]
+async fn create_appends_after_last_chapter() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    ChapterBuilder::new(&course.id, 1).insert(&db).await;
+    ChapterBuilder::new(&course.id, 5).insert(&db).await;
+
+    let chapters = Chapters::create(&db, TEACHER.into(), course.id, "Last".into())
+        .await
+        .unwrap();
+
+    let last = chapters.last().unwrap();
+    assert_eq!(last.title, "Last");
+    assert_eq!(last.position, 6);
+}
+
+#[tokio::test]
+async fn create_rejects_non_owner() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+
+    let res = Chapters::create(&db, OTHER_TEACHER.into(), course.id, "Intro".into()).await;
+
+    assert!(matches!(res, Err(DbErr::RecordNotFound(_))));
+    assert_eq!(Chapter::find().count(&db).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn update_sets_given_fields() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let chapter = ChapterBuilder::new(&course.id, 1)
+        .description("Old")
+        .insert(&db)
+        .await;
+
+    let updated = Chapters::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id,
+        chapter.id.clone(),
+        ChapterUpdate {
+            title: Some("Intro".into()),
+            is_free: Some(true),
+            video_id: Some("video".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(updated.title, "Intro");
+    assert_eq!(updated.description.as_deref(), Some("Old"));
+    assert_eq!(updated.video_id.as_deref(), Some("video"));
+    assert!(updated.is_free);
+    assert!(updated.updated_at >= chapter.updated_at);
+    assert_eq!(
+        Chapter::find_by_id(chapter.id).one(&db).await.unwrap(),
+        Some(updated)
+    );
+}
+
+#[tokio::test]
+async fn update_rejects_invalid_title() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let chapter = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+
+    let res = Chapters::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id,
+        chapter.id,
+        ChapterUpdate {
+            title: Some("x".repeat(201)),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    match res {
+        Err(UpdateError::Invalid(fields)) => assert_eq!(fields[0].field, "title"),
+        other => panic!("expected validation errors, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn update_rejects_non_owner_and_foreign_chapter() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let other = CourseBuilder::new(TEACHER).insert(&db).await;
+    let chapter = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+
+    let not_owner = Chapters::update(
+        &db,
+        &cloudinary(),
+        OTHER_TEACHER.into(),
+        course.id,
+        chapter.id.clone(),
+        ChapterUpdate::default(),
+    )
+    .await;
+    let foreign = Chapters::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        other.id,
+        chapter.id,
+        ChapterUpdate::default(),
+    )
+    .await;
+
+    assert!(matches!(
+        not_owner,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+    assert!(matches!(
+        foreign,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+}
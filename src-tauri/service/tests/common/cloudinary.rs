@@ -0,0 +1,100 @@
+//! A local stand-in for Cloudinary and the media signer.
+//!
+//! One server plays both: `/sign` signs destroy requests the way a school's
+//! signer would, and `/v1_1/...` accepts destroy calls whose signature checks
+//! out. Every request is recorded.
+
+use std::sync::{Arc, Mutex};
+
+use service::{CloudinaryConfig, SignRequest, SignResponse};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::http;
+pub use super::http::Request;
+
+pub const API_KEY: &str = "key";
+pub const API_SECRET: &str = "secret";
+pub const SIGNER_TOKEN: &str = "signer-token";
+
+pub struct MockCloudinary {
+    base_url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockCloudinary {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Cannot bind mock Cloudinary server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(respond(socket, recorded.clone()));
+            }
+        });
+
+        MockCloudinary { base_url, requests }
+    }
+
+    pub fn config(&self) -> CloudinaryConfig {
+        CloudinaryConfig {
+            api_base: self.base_url.clone(),
+            api_key: API_KEY.into(),
+            cloud_name: "demo".into(),
+            signer_url: format!("{}/sign", self.base_url),
+            signer_token: SIGNER_TOKEN.into(),
+        }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn respond(mut socket: TcpStream, requests: Arc<Mutex<Vec<Request>>>) {
+    let Some(request) = http::read(&mut socket).await else {
+        return;
+    };
+    requests.lock().unwrap().push(request.clone());
+
+    let (status, body) = if request.path == "/sign" {
+        sign(&request)
+    } else if request.path.starts_with("/v1_1/demo/") && request.path.ends_with("/destroy") {
+        destroy(&request)
+    } else {
+        ("404 Not Found", r#"{"error":{}}"#.to_string())
+    };
+    http::reply(&mut socket, status, &body).await;
+}
+
+fn sign(request: &Request) -> (&'static str, String) {
+    if request.authorization != format!("Bearer {}", SIGNER_TOKEN) {
+        return ("401 Unauthorized", r#"{"error":"unknown token"}"#.into());
+    }
+    let request: SignRequest = serde_json::from_str(&request.body).unwrap();
+    let signed = SignResponse {
+        signature: request.signature(API_SECRET),
+    };
+    ("200 OK", serde_json::to_string(&signed).unwrap())
+}
+
+fn destroy(request: &Request) -> (&'static str, String) {
+    let field = |key: &str| request.field(key).unwrap_or_default();
+    let expected = service::sign_media_request(
+        &[
+            ("public_id", &field("public_id")),
+            ("timestamp", &field("timestamp")),
+        ],
+        API_SECRET,
+    );
+    if field("api_key") != API_KEY || field("signature") != expected {
+        return (
+            "401 Unauthorized",
+            r#"{"error":"invalid signature"}"#.into(),
+        );
+    }
+    ("200 OK", r#"{"result":"ok"}"#.into())
+}
@@ -0,0 +1,77 @@
+//! Just enough HTTP/1.1 for the local stand-ins of outside services.
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub path: String,
+    pub authorization: String,
+    pub body: String,
+}
+
+impl Request {
+    /// Decoded value of form field `key`.
+    pub fn field(&self, key: &str) -> Option<String> {
+        serde_urlencoded::from_str::<Vec<(String, String)>>(&self.body)
+            .unwrap()
+            .into_iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Reads one request, or `None` if the client hung up first.
+pub async fn read(socket: &mut TcpStream) -> Option<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+    let header_end = loop {
+        let n = socket.read(&mut chunk).await.unwrap();
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let header = |name: &str| {
+        head.lines()
+            .find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.eq_ignore_ascii_case(name)
+                    .then(|| value.trim().to_string())
+            })
+            .unwrap_or_default()
+    };
+    let content_length: usize = header("content-length").parse().unwrap_or(0);
+    while buf.len() < header_end + content_length {
+        let n = socket.read(&mut chunk).await.unwrap();
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    Some(Request {
+        path: head
+            .split_whitespace()
+            .nth(1)
+            .unwrap_or_default()
+            .to_string(),
+        authorization: header("authorization"),
+        body: String::from_utf8_lossy(&buf[header_end..]).to_string(),
+    })
+}
+
+/// Answers with a JSON `body` and closes the connection.
+pub async fn reply(socket: &mut TcpStream, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    socket.write_all(response.as_bytes()).await.unwrap();
+}
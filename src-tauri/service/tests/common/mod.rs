@@ -0,0 +1,342 @@
+//! Shared fixtures for the service integration tests.
+//!
+//! Every test gets its own in-memory SQLite database with the schema created
+//! by the `migration` crate, so tests never touch a real server and can run in
+//! parallel. The builders insert rows with sensible defaults and only the
+//! fields a test cares about need to be spelled out.
+#![allow(dead_code)]
+
+pub mod cloudinary;
+pub mod http;
+pub mod stripe;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use entities::{sea_orm_active_enums::PurchaseStatus, *};
+use migration::{Migrator, MigratorTrait};
+use service::{sea_orm::*, CloudinaryConfig, OtherRoutes};
+use sqlx::types::{
+    chrono::{NaiveDateTime, Utc},
+    Uuid,
+};
+
+pub const TEACHER: &str = "teacher";
+pub const OTHER_TEACHER: &str = "other-teacher";
+pub const STUDENT: &str = "student";
+pub const OTHER_STUDENT: &str = "other-student";
+
+pub async fn setup() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:")
+        .await
+        .expect("Cannot open in-memory database");
+    Migrator::up(&db, None)
+        .await
+        .expect("Cannot migrate in-memory database");
+    db
+}
+
+/// Cloudinary settings for calls that may delete media. Nothing listens on
+/// the port, so any deletion fails and is only logged; tests that check
+/// deletions use [`cloudinary::MockCloudinary`].
+pub fn cloudinary() -> CloudinaryConfig {
+    CloudinaryConfig {
+        api_base: "http://127.0.0.1:1".into(),
+        api_key: "key".into(),
+        cloud_name: "demo".into(),
+        signer_url: "http://127.0.0.1:1/sign".into(),
+        signer_token: "token".into(),
+    }
+}
+
+/// Counts the statements run on `db` from now on.
+pub fn count_queries(db: &mut DatabaseConnection) -> Arc<AtomicUsize> {
+    let count = Arc::new(AtomicUsize::new(0));
+    let counter = count.clone();
+    db.set_metric_callback(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    count
+}
+
+fn new_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+pub async fn category(db: &DatabaseConnection, name: &str) -> category::Model {
+    category::ActiveModel {
+        id: Set(new_id()),
+        name: Set(name.to_string()),
+    }
+    .insert(db)
+    .await
+    .expect("Cannot insert category")
+}
+
+pub struct CourseBuilder {
+    model: course::Model,
+}
+
+impl CourseBuilder {
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            model: course::Model {
+                id: new_id(),
+                user_id: user_id.to_string(),
+                title: "Course".to_string(),
+                description: None,
+                image_url: None,
+                price: None,
+                is_published: false,
+                category_id: None,
+                created_at: now(),
+                updated_at: now(),
+                publish_at: None,
+                unpublish_at: None,
+            },
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.model.title = title.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.model.description = Some(description.to_string());
+        self
+    }
+
+    pub fn image_url(mut self, image_url: &str) -> Self {
+        self.model.image_url = Some(image_url.to_string());
+        self
+    }
+
+    pub fn price(mut self, price: i32) -> Self {
+        self.model.price = Some(price);
+        self
+    }
+
+    pub fn category(mut self, category_id: &str) -> Self {
+        self.model.category_id = Some(category_id.to_string());
+        self
+    }
+
+    pub fn published(mut self) -> Self {
+        self.model.is_published = true;
+        self
+    }
+
+    pub fn created_at(mut self, created_at: NaiveDateTime) -> Self {
+        self.model.created_at = created_at;
+        self
+    }
+
+    pub async fn insert(self, db: &DatabaseConnection) -> course::Model {
+        let course = course::ActiveModel::from(self.model)
+            .reset_all()
+            .insert(db)
+            .await
+            .expect("Cannot insert course");
+        OtherRoutes::reindex_course(db, course.id.clone())
+            .await
+            .expect("Cannot index course");
+        course
+    }
+}
+
+pub struct ChapterBuilder {
+    model: chapter::Model,
+}
+
+impl ChapterBuilder {
+    pub fn new(course_id: &str, position: i32) -> Self {
+        Self {
+            model: chapter::Model {
+                id: new_id(),
+                title: format!("Chapter {}", position),
+                description: None,
+                video_id: None,
+                position,
+                is_published: false,
+                is_free: false,
+                course_id: course_id.to_string(),
+                created_at: now(),
+                updated_at: now(),
+                publish_at: None,
+                unpublish_at: None,
+                drip_days: None,
+            },
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.model.title = title.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.model.description = Some(description.to_string());
+        self
+    }
+
+    pub fn video(mut self, video_id: &str) -> Self {
+        self.model.video_id = Some(video_id.to_string());
+        self
+    }
+
+    pub fn published(mut self) -> Self {
+        self.model.is_published = true;
+        self
+    }
+
+    pub fn free(mut self) -> Self {
+        self.model.is_free = true;
+        self
+    }
+
+    pub fn drip_days(mut self, days: i32) -> Self {
+        self.model.drip_days = Some(days);
+        self
+    }
+
+    pub async fn insert(self, db: &DatabaseConnection) -> chapter::Model {
+        let chapter = chapter::ActiveModel::from(self.model)
+            .reset_all()
+            .insert(db)
+            .await
+            .expect("Cannot insert chapter");
+        OtherRoutes::reindex_course(db, chapter.course_id.clone())
+            .await
+            .expect("Cannot index course");
+        chapter
+    }
+}
+
+pub async fn attachment(db: &DatabaseConnection, course_id: &str, url: &str) -> attachment::Model {
+    attachment::ActiveModel {
+        id: Set(new_id()),
+        name: Set(url.rsplit('/').next().unwrap().to_string()),
+        url: Set(url.to_string()),
+        course_id: Set(course_id.to_string()),
+        created_at: Set(now()),
+        updated_at: Set(now()),
+    }
+    .insert(db)
+    .await
+    .expect("Cannot insert attachment")
+}
+
+pub async fn purchase(db: &DatabaseConnection, user_id: &str, course_id: &str) -> purchase::Model {
+    purchase_at(db, user_id, course_id, now()).await
+}
+
+pub async fn purchase_at(
+    db: &DatabaseConnection,
+    user_id: &str,
+    course_id: &str,
+    created_at: NaiveDateTime,
+) -> purchase::Model {
+    purchase::ActiveModel {
+        id: Set(new_id()),
+        user_id: Set(user_id.to_string()),
+        course_id: Set(course_id.to_string()),
+        created_at: Set(created_at),
+        updated_at: Set(created_at),
+        status: Set(PurchaseStatus::Paid),
+        stripe_session_id: Set(None),
+        amount: Set(0),
+        currency: Set("vnd".into()),
+        paid_at: Set(Some(created_at)),
+        refunded_at: Set(None),
+        revoked_at: Set(None),
+        stripe_payment_intent_id: Set(None),
+        discount: Set(0),
+        coupon_id: Set(None),
+    }
+    .insert(db)
+    .await
+    .expect("Cannot insert purchase")
+}
+
+pub async fn progress(
+    db: &DatabaseConnection,
+    user_id: &str,
+    chapter_id: &str,
+    is_completed: bool,
+) -> user_progress::Model {
+    user_progress::ActiveModel {
+        id: Set(new_id()),
+        user_id: Set(user_id.to_string()),
+        chapter_id: Set(chapter_id.to_string()),
+        is_completed: Set(is_completed),
+        created_at: Set(now()),
+        updated_at: Set(now()),
+        watched_seconds: Set(0),
+        last_position: Set(0),
+        duration: Set(None),
+        watched_ranges: Set("[]".into()),
+        completed_at: Set(is_completed.then(now)),
+    }
+    .insert(db)
+    .await
+    .expect("Cannot insert progress")
+}
+
+pub async fn watch_event(
+    db: &DatabaseConnection,
+    user_id: &str,
+    chapter_id: &str,
+    seconds: std::ops::Range<i32>,
+    created_at: NaiveDateTime,
+) -> watch_event::Model {
+    watch_event::ActiveModel {
+        id: Set(new_id()),
+        user_id: Set(user_id.to_string()),
+        chapter_id: Set(chapter_id.to_string()),
+        start_second: Set(seconds.start),
+        end_second: Set(seconds.end),
+        created_at: Set(created_at),
+    }
+    .insert(db)
+    .await
+    .expect("Cannot insert watch event")
+}
+
+/// A published course owned by [`TEACHER`] with `chapters` published chapters,
+/// ready to be searched, bought and watched.
+pub async fn published_course(
+    db: &DatabaseConnection,
+    title: &str,
+    price: i32,
+    chapters: i32,
+) -> (course::Model, Vec<chapter::Model>) {
+    let course = CourseBuilder::new(TEACHER)
+        .title(title)
+        .description("Description")
+        .image_url("https://res.cloudinary.com/demo/image/upload/cover.png")
+        .price(price)
+        .published()
+        .insert(db)
+        .await;
+
+    let mut published = Vec::new();
+    for position in 1..=chapters {
+        published.push(
+            ChapterBuilder::new(&course.id, position)
+                .description("Description")
+                .video("video")
+                .published()
+                .insert(db)
+                .await,
+        );
+    }
+
+    (course, published)
+}
@@ -0,0 +1,150 @@
+//! A local stand-in for the Stripe API.
+//!
+//! It records every request and answers customer, checkout session and refund
+//! creation and session expiry with canned objects, which is all the payments
+//! code talks to Stripe for.
+
+use std::sync::{Arc, Mutex};
+
+use hmac::{Hmac, Mac};
+use service::sea_orm::sqlx::types::chrono::Utc;
+use service::StripeConfig;
+use sha2::Sha256;
+use tokio::net::{TcpListener, TcpStream};
+
+use super::http;
+pub use super::http::Request;
+
+pub const WEBHOOK_SECRET: &str = "whsec_test";
+pub const RELAY_TOKEN: &str = "relay_test";
+
+pub struct MockStripe {
+    base_url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockStripe {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Cannot bind mock Stripe server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(respond(socket, recorded.clone()));
+            }
+        });
+
+        MockStripe { base_url, requests }
+    }
+
+    pub fn config(&self) -> StripeConfig {
+        StripeConfig {
+            relay_url: self.base_url.clone(),
+            relay_token: RELAY_TOKEN.into(),
+            webhook_secret: WEBHOOK_SECRET.into(),
+            app_url: "http://localhost:3000".into(),
+        }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn respond(mut socket: TcpStream, requests: Arc<Mutex<Vec<Request>>>) {
+    let Some(request) = http::read(&mut socket).await else {
+        return;
+    };
+    let path = request.path.clone();
+    let count = {
+        let mut requests = requests.lock().unwrap();
+        requests.push(request);
+        requests.len()
+    };
+
+    let (status, body) = match path.as_str() {
+        "/v1/customers" => ("200 OK", format!(r#"{{"id":"cus_{}"}}"#, count)),
+        "/v1/checkout/sessions" => (
+            "200 OK",
+            format!(
+                r#"{{"id":"cs_test_{0}","url":"https://checkout.stripe.com/c/pay/cs_test_{0}"}}"#,
+                count
+            ),
+        ),
+        "/v1/refunds" => ("200 OK", format!(r#"{{"id":"re_{}"}}"#, count)),
+        path if path.starts_with("/v1/checkout/sessions/") && path.ends_with("/expire") => {
+            let id = &path["/v1/checkout/sessions/".len()..path.len() - "/expire".len()];
+            ("200 OK", format!(r#"{{"id":"{}","url":null}}"#, id))
+        }
+        _ => ("404 Not Found", r#"{"error":{}}"#.to_string()),
+    };
+    http::reply(&mut socket, status, &body).await;
+}
+
+/// A `Stripe-Signature` header for `payload` signed `age` seconds ago.
+pub fn signature(payload: &str, secret: &str, age: i64) -> String {
+    let timestamp = Utc::now().timestamp() - age;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(format!("{}.{}", timestamp, payload).as_bytes());
+    format!(
+        "t={},v1={}",
+        timestamp,
+        hex::encode(mac.finalize().into_bytes())
+    )
+}
+
+/// The payment intent [`session_event`] reports for `session_id`.
+pub fn payment_intent(session_id: &str) -> String {
+    format!("pi_{}", session_id)
+}
+
+/// A webhook payload for a checkout session event.
+pub fn session_event(kind: &str, session_id: &str, payment_status: &str) -> String {
+    session_event_with_metadata(kind, session_id, payment_status, serde_json::json!({}))
+}
+
+/// A webhook payload for a checkout session event carrying the `metadata`
+/// the session was opened with.
+pub fn session_event_with_metadata(
+    kind: &str,
+    session_id: &str,
+    payment_status: &str,
+    metadata: serde_json::Value,
+) -> String {
+    serde_json::json!({
+        "id": "evt_test",
+        "type": kind,
+        "data": {
+            "object": {
+                "id": session_id,
+                "object": "checkout.session",
+                "payment_status": payment_status,
+                "payment_intent": payment_intent(session_id),
+                "metadata": metadata,
+            }
+        }
+    })
+    .to_string()
+}
+
+/// A `charge.refunded` webhook payload. `refunded` is false for partial
+/// refunds.
+pub fn refund_event(payment_intent: &str, refunded: bool) -> String {
+    serde_json::json!({
+        "id": "evt_test",
+        "type": "charge.refunded",
+        "data": {
+            "object": {
+                "id": "ch_test",
+                "object": "charge",
+                "payment_intent": payment_intent,
+                "refunded": refunded,
+            }
+        }
+    })
+    .to_string()
+}
@@ -0,0 +1,324 @@
+mod common;
+
+use chrono::Duration;
+use common::{stripe::*, *};
+use entities::{
+    coupon,
+    prelude::*,
+    sea_orm_active_enums::{CouponKind, PurchaseStatus},
+};
+use service::{sea_orm::*, Coupons, NewCoupon, OtherRoutes, Payments, UpdateError};
+use sqlx::types::chrono::Utc;
+
+fn percent(code: &str, value: i32) -> NewCoupon {
+    NewCoupon {
+        code: code.into(),
+        course_id: None,
+        kind: CouponKind::Percent,
+        value,
+        max_redemptions: None,
+        expires_at: None,
+    }
+}
+
+async fn redemptions(db: &DatabaseConnection, coupon: &coupon::Model) -> i32 {
+    Coupon::find_by_id(coupon.id.clone())
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap()
+        .redemptions
+}
+
+#[tokio::test]
+async fn create_validates_fields() {
+    let db = setup().await;
+
+    let res = Coupons::create(
+        &db,
+        TEACHER.into(),
+        NewCoupon {
+            code: "spring sale!".into(),
+            course_id: None,
+            kind: CouponKind::Percent,
+            value: 120,
+            max_redemptions: Some(0),
+            expires_at: Some(Utc::now().naive_utc() - Duration::days(1)),
+        },
+    )
+    .await;
+
+    match res {
+        Err(UpdateError::Invalid(fields)) => assert_eq!(
+            fields.into_iter().map(|f| f.field).collect::<Vec<_>>(),
+            vec!["code", "value", "maxRedemptions", "expiresAt"]
+        ),
+        other => panic!("expected validation errors, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn create_rejects_taken_code_and_foreign_course() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let created = Coupons::create(&db, TEACHER.into(), percent(" spring25 ", 25))
+        .await
+        .unwrap();
+
+    let taken = Coupons::create(&db, OTHER_TEACHER.into(), percent("SPRING25", 10)).await;
+    let foreign = Coupons::create(
+        &db,
+        OTHER_TEACHER.into(),
+        NewCoupon {
+            course_id: Some(course.id),
+            ..percent("OTHER", 10)
+        },
+    )
+    .await;
+
+    assert_eq!(created.code, "SPRING25");
+    assert!(matches!(taken, Err(UpdateError::Invalid(_))));
+    assert!(matches!(
+        foreign,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+}
+
+#[tokio::test]
+async fn checkout_charges_discounted_price() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 200000, 1).await;
+    let coupon = Coupons::create(&db, TEACHER.into(), percent("SPRING25", 25))
+        .await
+        .unwrap();
+
+    let session = OtherRoutes::checkout(
+        &db,
+        &stripe.config(),
+        STUDENT.into(),
+        course.id,
+        Some("spring25".into()),
+    )
+    .await
+    .unwrap();
+
+    let request = stripe.requests().pop().unwrap();
+    assert_eq!(
+        request
+            .field("line_items[0][price_data][unit_amount]")
+            .as_deref(),
+        Some("150000")
+    );
+    assert_eq!(session.purchase.amount, 150000);
+    assert_eq!(session.purchase.discount, 50000);
+    assert_eq!(session.purchase.coupon_id, Some(coupon.id.clone()));
+    // Only paid purchases count against the coupon.
+    assert_eq!(redemptions(&db, &coupon).await, 0);
+
+    let payload = session_event(
+        "checkout.session.completed",
+        session.purchase.stripe_session_id.as_ref().unwrap(),
+        "paid",
+    );
+    Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &payload,
+        &signature(&payload, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+    assert_eq!(redemptions(&db, &coupon).await, 1);
+}
+
+#[tokio::test]
+async fn full_discount_grants_course_until_redeemed() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 200000, 1).await;
+    let coupon = Coupons::create(
+        &db,
+        TEACHER.into(),
+        NewCoupon {
+            kind: CouponKind::Fixed,
+            value: 500000,
+            max_redemptions: Some(1),
+            course_id: Some(course.id.clone()),
+            ..percent("FREE", 0)
+        },
+    )
+    .await
+    .unwrap();
+
+    let granted = OtherRoutes::checkout(
+        &db,
+        &stripe.config(),
+        STUDENT.into(),
+        course.id.clone(),
+        Some("FREE".into()),
+    )
+    .await
+    .unwrap();
+    let exhausted = OtherRoutes::checkout(
+        &db,
+        &stripe.config(),
+        OTHER_STUDENT.into(),
+        course.id,
+        Some("FREE".into()),
+    )
+    .await;
+
+    assert_eq!(granted.url, None);
+    assert_eq!(granted.purchase.status, PurchaseStatus::Paid);
+    assert_eq!(granted.purchase.amount, 0);
+    assert_eq!(granted.purchase.discount, 200000);
+    assert!(stripe.requests().is_empty());
+    assert_eq!(redemptions(&db, &coupon).await, 1);
+    assert!(
+        matches!(exhausted, Err(DbErr::Custom(err)) if err == "Coupon has been fully redeemed")
+    );
+}
+
+#[tokio::test]
+async fn payment_after_the_last_redemption_is_refunded() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 200000, 1).await;
+    let coupon = Coupons::create(
+        &db,
+        TEACHER.into(),
+        NewCoupon {
+            max_redemptions: Some(1),
+            ..percent("LAST", 25)
+        },
+    )
+    .await
+    .unwrap();
+
+    // Both students are quoted the discount before either has paid.
+    let mut sessions = Vec::new();
+    for user_id in [STUDENT, OTHER_STUDENT] {
+        let session = OtherRoutes::checkout(
+            &db,
+            &stripe.config(),
+            user_id.into(),
+            course.id.clone(),
+            Some("LAST".into()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(session.purchase.amount, 150000);
+        sessions.push(session.purchase.stripe_session_id.unwrap());
+    }
+    let mut paid = Vec::new();
+    for session_id in &sessions {
+        let payload = session_event("checkout.session.completed", session_id, "paid");
+        paid.push(
+            Payments::handle_webhook(
+                &db,
+                &stripe.config(),
+                &payload,
+                &signature(&payload, WEBHOOK_SECRET, 0),
+            )
+            .await
+            .unwrap()
+            .unwrap(),
+        );
+    }
+
+    assert_eq!(redemptions(&db, &coupon).await, 1);
+    assert_eq!(paid[0].status, PurchaseStatus::Paid);
+    assert_eq!(paid[1].status, PurchaseStatus::Refunded);
+    assert!(paid[1].paid_at.is_some());
+    assert!(paid[1].refunded_at.is_some());
+    let request = stripe.requests().pop().unwrap();
+    assert_eq!(request.path, "/v1/refunds");
+    assert_eq!(
+        request.field("payment_intent"),
+        Some(payment_intent(&sessions[1]))
+    );
+    assert_eq!(
+        OtherRoutes::entitlement(&db, OTHER_STUDENT.into(), course.id.clone())
+            .await
+            .unwrap(),
+        None
+    );
+    let exhausted = OtherRoutes::checkout(
+        &db,
+        &stripe.config(),
+        OTHER_STUDENT.into(),
+        course.id,
+        Some("LAST".into()),
+    )
+    .await;
+    assert!(
+        matches!(exhausted, Err(DbErr::Custom(err)) if err == "Coupon has been fully redeemed")
+    );
+}
+
+#[tokio::test]
+async fn checkout_rejects_coupons_that_do_not_apply() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let (other_course, _) = published_course(&db, "Go", 10, 1).await;
+    Coupons::create(&db, OTHER_TEACHER.into(), percent("FOREIGN", 50))
+        .await
+        .unwrap();
+    Coupons::create(
+        &db,
+        TEACHER.into(),
+        NewCoupon {
+            course_id: Some(other_course.id),
+            ..percent("GO-ONLY", 50)
+        },
+    )
+    .await
+    .unwrap();
+    let expired = Coupons::create(&db, TEACHER.into(), percent("EXPIRED", 50))
+        .await
+        .unwrap();
+    let mut expired: coupon::ActiveModel = expired.into();
+    expired.expires_at = Set(Some(Utc::now().naive_utc() - Duration::minutes(1)));
+    expired.update(&db).await.unwrap();
+
+    for (code, message) in [
+        ("FOREIGN", "Invalid coupon code"),
+        ("GO-ONLY", "Invalid coupon code"),
+        ("MISSING", "Invalid coupon code"),
+        ("EXPIRED", "Coupon has expired"),
+    ] {
+        let res = OtherRoutes::checkout(
+            &db,
+            &stripe.config(),
+            STUDENT.into(),
+            course.id.clone(),
+            Some(code.into()),
+        )
+        .await;
+        assert!(
+            matches!(&res, Err(DbErr::Custom(err)) if err == message),
+            "{}: {:?}",
+            code,
+            res
+        );
+    }
+    assert_eq!(Purchase::find().count(&db).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn delete_only_removes_own_coupons() {
+    let db = setup().await;
+    let coupon = Coupons::create(&db, TEACHER.into(), percent("SPRING25", 25))
+        .await
+        .unwrap();
+
+    let foreign = Coupons::delete(&db, OTHER_TEACHER.into(), coupon.id.clone()).await;
+    Coupons::delete(&db, TEACHER.into(), coupon.id)
+        .await
+        .unwrap();
+
+    assert!(matches!(foreign, Err(DbErr::RecordNotFound(_))));
+    assert!(Coupons::list(&db, TEACHER.into()).await.unwrap().is_empty());
+}
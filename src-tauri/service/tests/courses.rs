@@ -0,0 +1,415 @@
+mod common;
+
+use common::*;
+use entities::{prelude::*, *};
+use serde_json::json;
+use service::{sea_orm::*, ChapterUpdate, CourseUpdate, Courses, UpdateError};
+
+#[tokio::test]
+async fn create_assigns_owner() {
+    let db = setup().await;
+
+    let course = Courses::create(&db, TEACHER.into(), "Rust".into())
+        .await
+        .unwrap();
+
+    assert_eq!(course.user_id, TEACHER);
+    assert_eq!(course.title, "Rust");
+    assert!(!course.is_published);
+    assert!(Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[tokio::test]
+async fn update_sets_given_fields() {
+    let db = setup().await;
+    let category = category(&db, "Math").await;
+    let course = CourseBuilder::new(TEACHER)
+        .description("Old")
+        .insert(&db)
+        .await;
+
+    let updated = Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        CourseUpdate {
+            title: Some(" Algebra ".into()),
+            price: Some(20),
+            category_id: Some(category.id.clone()),
+            image_url: Some("https://example.com/cover.png".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(updated.title, "Algebra");
+    assert_eq!(updated.description.as_deref(), Some("Old"));
+    assert_eq!(updated.price, Some(20));
+    assert_eq!(updated.category_id, Some(category.id));
+    assert_eq!(
+        updated.image_url.as_deref(),
+        Some("https://example.com/cover.png")
+    );
+    assert!(updated.updated_at >= course.updated_at);
+    assert_eq!(
+        Course::find_by_id(course.id).one(&db).await.unwrap(),
+        Some(updated)
+    );
+}
+
+#[tokio::test]
+async fn update_reports_every_invalid_field() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).title("Rust").insert(&db).await;
+
+    let res = Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        CourseUpdate {
+            title: Some("   ".into()),
+            price: Some(-1),
+            category_id: Some("missing".into()),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    let fields: Vec<String> = match res {
+        Err(UpdateError::Invalid(fields)) => fields.into_iter().map(|f| f.field).collect(),
+        other => panic!("expected validation errors, got {:?}", other),
+    };
+    assert_eq!(fields, vec!["title", "price", "categoryId"]);
+    let course = Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(course.title, "Rust");
+}
+
+#[tokio::test]
+async fn update_payload_rejects_unknown_keys_and_wrong_types() {
+    let unknown = serde_json::from_value::<CourseUpdate>(json!({ "unknown": true }));
+    let wrong_type = serde_json::from_value::<CourseUpdate>(json!({ "price": "20" }));
+    let camel_case = serde_json::from_value::<CourseUpdate>(json!({ "categoryId": "id" }));
+
+    assert!(unknown.is_err());
+    assert!(wrong_type.is_err());
+    assert_eq!(camel_case.unwrap().category_id.as_deref(), Some("id"));
+}
+
+#[tokio::test]
+async fn update_rejects_unknown_course() {
+    let db = setup().await;
+
+    let res = Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        "missing".into(),
+        CourseUpdate::default(),
+    )
+    .await;
+
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+}
+
+#[tokio::test]
+async fn list_returns_only_owned_courses() {
+    let db = setup().await;
+    let owned = CourseBuilder::new(TEACHER).insert(&db).await;
+    CourseBuilder::new(OTHER_TEACHER).insert(&db).await;
+
+    let courses = Courses::list(&db, TEACHER.into()).await.unwrap();
+
+    assert_eq!(courses, vec![owned]);
+}
+
+#[tokio::test]
+async fn add_attachment_names_file_from_url() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+
+    let attachments = Courses::add_attachment(
+        &db,
+        TEACHER.into(),
+        course.id.clone(),
+        "https://example.com/files/notes.pdf".into(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(attachments[0].name, "notes.pdf");
+    assert_eq!(attachments[0].course_id, course.id);
+}
+
+#[tokio::test]
+async fn add_attachment_rejects_non_owner() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+
+    let res = Courses::add_attachment(
+        &db,
+        OTHER_TEACHER.into(),
+        course.id,
+        "https://example.com/files/notes.pdf".into(),
+    )
+    .await;
+
+    assert!(matches!(res, Err(DbErr::Custom(_))));
+    assert_eq!(Attachment::find().count(&db).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn remove_attachment_rejects_non_owner() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let attachment = attachment(&db, &course.id, "https://example.com/notes.pdf").await;
+
+    let res = Courses::remove_attachment(
+        &db,
+        &cloudinary(),
+        OTHER_TEACHER.into(),
+        attachment.id,
+        course.id,
+    )
+    .await;
+
+    assert!(matches!(res, Err(DbErr::Custom(_))));
+    assert_eq!(Attachment::find().count(&db).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn delete_removes_course_and_cascades() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let chapter = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+    progress(&db, STUDENT, &chapter.id, true).await;
+    purchase(&db, STUDENT, &course.id).await;
+    attachment(&db, &course.id, "https://example.com/notes.pdf").await;
+
+    Courses::delete(&db, &cloudinary(), TEACHER.into(), course.id)
+        .await
+        .unwrap();
+
+    assert_eq!(Course::find().count(&db).await.unwrap(), 0);
+    assert_eq!(Chapter::find().count(&db).await.unwrap(), 0);
+    assert_eq!(UserProgress::find().count(&db).await.unwrap(), 0);
+    assert_eq!(Purchase::find().count(&db).await.unwrap(), 0);
+    assert_eq!(Attachment::find().count(&db).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn delete_rejects_non_owner() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+
+    let res = Courses::delete(&db, &cloudinary(), OTHER_TEACHER.into(), course.id).await;
+
+    assert!(matches!(res, Err(DbErr::Custom(_))));
+    assert_eq!(Course::find().count(&db).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn publish_requires_fields_and_published_chapter() {
+    let db = setup().await;
+    let category = category(&db, "Math").await;
+    let course = CourseBuilder::new(TEACHER)
+        .description("Description")
+        .image_url("https://example.com/cover.png")
+        .category(&category.id)
+        .insert(&db)
+        .await;
+    let chapter = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+
+    let res = Courses::publish(&db, TEACHER.into(), course.id.clone()).await;
+    assert!(matches!(res, Err(DbErr::Custom(_))));
+
+    let mut chapter: chapter::ActiveModel = chapter.into();
+    chapter.is_published = Set(true);
+    chapter.update(&db).await.unwrap();
+
+    Courses::publish(&db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+    let course = Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(course.is_published);
+}
+
+#[tokio::test]
+async fn publish_rejects_incomplete_course() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    ChapterBuilder::new(&course.id, 1)
+        .published()
+        .insert(&db)
+        .await;
+
+    let res = Courses::publish(&db, TEACHER.into(), course.id).await;
+
+    assert!(matches!(res, Err(DbErr::Custom(_))));
+}
+
+#[tokio::test]
+async fn publish_and_unpublish_reject_non_owner() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+
+    let publish = Courses::publish(&db, OTHER_TEACHER.into(), course.id.clone()).await;
+    let unpublish = Courses::unpublish(&db, OTHER_TEACHER.into(), course.id.clone()).await;
+
+    assert!(matches!(publish, Err(DbErr::Custom(_))));
+    assert!(matches!(unpublish, Err(DbErr::Custom(_))));
+    let course = Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(course.is_published);
+}
+
+#[tokio::test]
+async fn unpublish_hides_course() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+
+    Courses::unpublish(&db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+
+    let course = Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!course.is_published);
+}
+
+#[tokio::test]
+async fn get_returns_course_with_all_chapters() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    ChapterBuilder::new(&course.id, 1)
+        .published()
+        .insert(&db)
+        .await;
+    ChapterBuilder::new(&course.id, 2).insert(&db).await;
+
+    let res = serde_json::to_value(Courses::get(&db, course.id.clone()).await.unwrap()).unwrap();
+
+    assert_eq!(res["id"], json!(course.id));
+    assert_eq!(res["chapters"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn get_with_chapters_with_progress_lists_published_chapters_in_order() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 2).await;
+    ChapterBuilder::new(&course.id, 3).insert(&db).await;
+    progress(&db, STUDENT, &chapters[1].id, true).await;
+
+    let res = Courses::get_with_chapters_with_progress(&db, STUDENT.into(), course.id.clone())
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    let listed = res["chapters"].as_array().unwrap();
+    assert_eq!(listed.len(), 2);
+    assert_eq!(listed[0]["id"], json!(chapters[0].id));
+    assert_eq!(listed[0]["userProgress"], json!([]));
+    assert_eq!(listed[1]["id"], json!(chapters[1].id));
+    assert_eq!(listed[1]["userProgress"][0]["isCompleted"], json!(true));
+}
+
+#[tokio::test]
+async fn get_with_chapters_with_progress_rejects_unknown_course() {
+    let db = setup().await;
+
+    let res = Courses::get_with_chapters_with_progress(&db, STUDENT.into(), "missing".into()).await;
+
+    assert!(matches!(res, Err(DbErr::Custom(_))));
+}
+
+#[tokio::test]
+async fn progress_percentage_counts_completed_published_chapters() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 3).await;
+    let draft = ChapterBuilder::new(&course.id, 4).insert(&db).await;
+    progress(&db, STUDENT, &chapters[0].id, true).await;
+    progress(&db, STUDENT, &chapters[1].id, false).await;
+    progress(&db, STUDENT, &draft.id, true).await;
+    progress(&db, OTHER_STUDENT, &chapters[2].id, true).await;
+
+    let percentage = Courses::get_progress_percentage(&db, STUDENT.into(), course.id)
+        .await
+        .unwrap();
+
+    assert_eq!(percentage, 33);
+}
+
+#[tokio::test]
+async fn update_payload_reports_each_bad_field() {
+    let res = CourseUpdate::from_json(json!({ "price": "20", "title": 3, "unknown": true }));
+
+    let fields: Vec<String> = match res {
+        Err(UpdateError::Invalid(fields)) => fields.into_iter().map(|f| f.field).collect(),
+        other => panic!("expected validation errors, got {:?}", other),
+    };
+    assert_eq!(fields, vec!["unknown", "title", "price"]);
+
+    let updates = CourseUpdate::from_json(json!({ "price": 20, "imageUrl": null })).unwrap();
+    assert_eq!(
+        updates,
+        CourseUpdate {
+            price: Some(20),
+            ..Default::default()
+        }
+    );
+    let updates = ChapterUpdate::from_json(json!({ "isFree": "yes" }));
+    assert!(matches!(updates, Err(UpdateError::Invalid(fields)) if fields[0].field == "isFree"));
+}
+
+#[tokio::test]
+async fn update_is_limited_to_the_owner() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).title("Rust").insert(&db).await;
+
+    let res = Courses::update(
+        &db,
+        &cloudinary(),
+        OTHER_TEACHER.into(),
+        course.id.clone(),
+        CourseUpdate {
+            title: Some("Mine".into()),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+    let course = Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(course.title, "Rust");
+}
@@ -0,0 +1,323 @@
+mod common;
+
+use common::*;
+use service::{sea_orm::*, Discussions, NewComment, UpdateError};
+
+fn question(body: &str) -> NewComment {
+    NewComment {
+        body: body.into(),
+        parent_id: None,
+    }
+}
+
+fn reply(body: &str, parent_id: &str) -> NewComment {
+    NewComment {
+        body: body.into(),
+        parent_id: Some(parent_id.into()),
+    }
+}
+
+#[tokio::test]
+async fn threads_need_access_to_the_chapter() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    let chapter_id = chapters[0].id.clone();
+
+    let res = Discussions::post(&db, STUDENT.into(), chapter_id.clone(), question("Why?")).await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+    assert!(Discussions::list(&db, STUDENT.into(), chapter_id.clone())
+        .await
+        .is_err());
+
+    purchase(&db, STUDENT, &course.id).await;
+    let res = Discussions::post(&db, STUDENT.into(), chapter_id.clone(), question("  ")).await;
+    assert!(matches!(res, Err(UpdateError::Invalid(fields)) if fields[0].field == "body"));
+
+    let posted = Discussions::post(&db, STUDENT.into(), chapter_id.clone(), question(" Why? "))
+        .await
+        .unwrap();
+    assert_eq!(posted.body, "Why?");
+
+    // The teacher takes part without buying their own course
+    let threads = Discussions::list(&db, TEACHER.into(), chapter_id.clone())
+        .await
+        .unwrap();
+    assert_eq!(threads.len(), 1);
+    assert!(!threads[0].comment.by_teacher);
+}
+
+#[tokio::test]
+async fn replies_edits_and_deletes() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    let chapter_id = chapters[0].id.clone();
+    purchase(&db, STUDENT, &course.id).await;
+    purchase(&db, OTHER_STUDENT, &course.id).await;
+
+    let first = Discussions::post(&db, STUDENT.into(), chapter_id.clone(), question("First"))
+        .await
+        .unwrap();
+    let answer = Discussions::post(
+        &db,
+        TEACHER.into(),
+        chapter_id.clone(),
+        reply("Answer", &first.id),
+    )
+    .await
+    .unwrap();
+    // Replying to a reply stays in the same thread
+    let follow_up = Discussions::post(
+        &db,
+        STUDENT.into(),
+        chapter_id.clone(),
+        reply("Thanks", &answer.id),
+    )
+    .await
+    .unwrap();
+    assert_eq!(follow_up.parent_id.as_deref(), Some(first.id.as_str()));
+    let second = Discussions::post(
+        &db,
+        OTHER_STUDENT.into(),
+        chapter_id.clone(),
+        question("Second"),
+    )
+    .await
+    .unwrap();
+
+    let threads = Discussions::list(&db, STUDENT.into(), chapter_id.clone())
+        .await
+        .unwrap();
+    let json = serde_json::to_value(&threads).unwrap();
+    assert_eq!(json[0]["body"], "Second");
+    assert_eq!(json[1]["body"], "First");
+    assert_eq!(json[1]["replies"][0]["body"], "Answer");
+    assert_eq!(json[1]["replies"][0]["byTeacher"], true);
+    assert_eq!(json[1]["replies"][1]["body"], "Thanks");
+
+    // Only the author edits
+    let res = Discussions::edit(&db, OTHER_STUDENT.into(), first.id.clone(), "Mine".into()).await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+    let edited = Discussions::edit(
+        &db,
+        STUDENT.into(),
+        first.id.clone(),
+        "First, edited".into(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(edited.body, "First, edited");
+    assert!(edited.edited_at.is_some());
+
+    // Other students cannot delete, the author and the teacher can
+    assert!(Discussions::delete(&db, STUDENT.into(), second.id.clone())
+        .await
+        .is_err());
+    Discussions::delete(&db, TEACHER.into(), second.id.clone())
+        .await
+        .unwrap();
+    Discussions::delete(&db, STUDENT.into(), first.id.clone())
+        .await
+        .unwrap();
+
+    let threads = Discussions::list(&db, STUDENT.into(), chapter_id.clone())
+        .await
+        .unwrap();
+    // The unanswered question is gone, the answered one keeps its replies
+    assert_eq!(threads.len(), 1);
+    assert_eq!(threads[0].comment.comment.id, first.id);
+    assert_eq!(threads[0].comment.comment.body, "");
+    assert!(threads[0].comment.comment.deleted_at.is_some());
+    assert_eq!(threads[0].replies.len(), 2);
+
+    let res = Discussions::edit(&db, STUDENT.into(), first.id.clone(), "Back".into()).await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn teachers_pin_answers() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    let chapter_id = chapters[0].id.clone();
+    purchase(&db, STUDENT, &course.id).await;
+
+    let older = Discussions::post(&db, STUDENT.into(), chapter_id.clone(), question("Older"))
+        .await
+        .unwrap();
+    Discussions::post(&db, STUDENT.into(), chapter_id.clone(), question("Newer"))
+        .await
+        .unwrap();
+    let guess = Discussions::post(
+        &db,
+        STUDENT.into(),
+        chapter_id.clone(),
+        reply("Guess", &older.id),
+    )
+    .await
+    .unwrap();
+    let answer = Discussions::post(
+        &db,
+        TEACHER.into(),
+        chapter_id.clone(),
+        reply("Answer", &older.id),
+    )
+    .await
+    .unwrap();
+
+    assert!(
+        Discussions::pin(&db, STUDENT.into(), older.id.clone(), true)
+            .await
+            .is_err()
+    );
+    assert!(
+        Discussions::pin(&db, OTHER_TEACHER.into(), older.id.clone(), true)
+            .await
+            .is_err()
+    );
+    Discussions::pin(&db, TEACHER.into(), older.id.clone(), true)
+        .await
+        .unwrap();
+    Discussions::pin(&db, TEACHER.into(), guess.id.clone(), true)
+        .await
+        .unwrap();
+    // One accepted answer per thread
+    Discussions::pin(&db, TEACHER.into(), answer.id.clone(), true)
+        .await
+        .unwrap();
+
+    let threads = Discussions::list(&db, STUDENT.into(), chapter_id.clone())
+        .await
+        .unwrap();
+    assert_eq!(threads[0].comment.comment.body, "Older");
+    assert!(threads[0].comment.comment.is_pinned);
+    let replies: Vec<(&str, bool)> = threads[0]
+        .replies
+        .iter()
+        .map(|reply| (reply.comment.body.as_str(), reply.comment.is_pinned))
+        .collect();
+    assert_eq!(replies, vec![("Answer", true), ("Guess", false)]);
+}
+
+#[tokio::test]
+async fn unread_counts_reset_when_the_teacher_reads() {
+    let db = setup().await;
+    let (rust, rust_chapters) = published_course(&db, "Rust", 100_000, 2).await;
+    let (go, go_chapters) = published_course(&db, "Go", 100_000, 1).await;
+    purchase(&db, STUDENT, &rust.id).await;
+    purchase(&db, STUDENT, &go.id).await;
+
+    for chapter in &rust_chapters {
+        Discussions::post(&db, STUDENT.into(), chapter.id.clone(), question("?"))
+            .await
+            .unwrap();
+    }
+    let go_question = Discussions::post(
+        &db,
+        STUDENT.into(),
+        go_chapters[0].id.clone(),
+        question("?"),
+    )
+    .await
+    .unwrap();
+    // The teacher's own comments and deleted ones do not count
+    Discussions::post(
+        &db,
+        TEACHER.into(),
+        go_chapters[0].id.clone(),
+        reply("!", &go_question.id),
+    )
+    .await
+    .unwrap();
+    let deleted = Discussions::post(
+        &db,
+        STUDENT.into(),
+        go_chapters[0].id.clone(),
+        question("Never mind"),
+    )
+    .await
+    .unwrap();
+    Discussions::delete(&db, STUDENT.into(), deleted.id)
+        .await
+        .unwrap();
+
+    let unread = Discussions::unread(&db, TEACHER.into()).await.unwrap();
+    let counts: Vec<(&str, i64)> = unread
+        .iter()
+        .map(|course| (course.course_title.as_str(), course.unread))
+        .collect();
+    assert_eq!(counts, vec![("Go", 1), ("Rust", 2)]);
+    assert!(Discussions::unread(&db, OTHER_TEACHER.into())
+        .await
+        .unwrap()
+        .is_empty());
+
+    assert!(
+        Discussions::mark_read(&db, OTHER_TEACHER.into(), rust.id.clone())
+            .await
+            .is_err()
+    );
+    Discussions::mark_read(&db, TEACHER.into(), rust.id.clone())
+        .await
+        .unwrap();
+    let unread = Discussions::unread(&db, TEACHER.into()).await.unwrap();
+    assert_eq!(unread.len(), 1);
+    assert_eq!(unread[0].course_id, go.id);
+
+    Discussions::post(
+        &db,
+        STUDENT.into(),
+        rust_chapters[0].id.clone(),
+        question("Again"),
+    )
+    .await
+    .unwrap();
+    // Reading twice keeps one marker
+    Discussions::mark_read(&db, TEACHER.into(), go.id.clone())
+        .await
+        .unwrap();
+    let unread = Discussions::unread(&db, TEACHER.into()).await.unwrap();
+    assert_eq!(unread.len(), 1);
+    assert_eq!(unread[0].course_id, rust.id);
+    assert_eq!(unread[0].unread, 1);
+
+    let threads = Discussions::for_course(&db, TEACHER.into(), rust.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(threads.len(), 2);
+    assert_eq!(threads[0].chapter_id, rust_chapters[0].id);
+    assert_eq!(threads[0].threads.len(), 2);
+    assert!(
+        Discussions::for_course(&db, OTHER_TEACHER.into(), rust.id.clone())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn tutor_prompt_carries_the_chapter() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 100_000, 0).await;
+    let chapter = ChapterBuilder::new(&course.id, 1)
+        .published()
+        .free()
+        .description("<p>Ownership &amp; borrowing</p><p>Each value has   one owner.</p>")
+        .insert(&db)
+        .await;
+
+    let prompt = Discussions::tutor_prompt(
+        &db,
+        STUDENT.into(),
+        chapter.id.clone(),
+        " What is a borrow? ".into(),
+    )
+    .await
+    .unwrap();
+    assert!(prompt.contains("\"Rust\""));
+    assert!(prompt.contains("Ownership & borrowing Each value has one owner."));
+    assert!(prompt.ends_with("What is a borrow?"));
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::*;
+use entities::chapter;
+use serde_json::json;
+use service::{sea_orm::*, Teacher};
+
+#[tokio::test]
+async fn engagement_follows_students_through_chapters() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 3).await;
+    let ten_minutes_ago = Utc::now().naive_utc() - Duration::minutes(10);
+    // Finished the first chapter ten minutes after starting it, stopped in
+    // the second.
+    watch_event(&db, STUDENT, &chapters[0].id, 0..300, ten_minutes_ago).await;
+    watch_event(&db, STUDENT, &chapters[0].id, 300..600, ten_minutes_ago).await;
+    progress(&db, STUDENT, &chapters[0].id, true).await;
+    watch_event(&db, STUDENT, &chapters[1].id, 0..60, ten_minutes_ago).await;
+    // Gave up during the first chapter.
+    watch_event(&db, OTHER_STUDENT, &chapters[0].id, 0..30, ten_minutes_ago).await;
+    // Went all the way.
+    for chapter in &chapters {
+        progress(&db, "finisher", &chapter.id, true).await;
+    }
+
+    let res = Teacher::course_engagement(&db, TEACHER.into(), course.id)
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    assert_eq!(res["learners"], json!(3));
+    let chapters = res["chapters"].as_array().unwrap();
+    let column = |key: &str| chapters.iter().map(|c| c[key].clone()).collect::<Vec<_>>();
+    assert_eq!(column("reached"), vec![json!(3), json!(2), json!(1)]);
+    assert_eq!(column("completed"), vec![json!(2), json!(1), json!(1)]);
+    assert_eq!(
+        column("dropOffRate"),
+        vec![json!(1.0 / 3.0), json!(0.5), json!(null)]
+    );
+    assert_eq!(
+        column("watchSeconds"),
+        vec![json!(630), json!(60), json!(0)]
+    );
+    // Ten minutes for the student, nothing for the finisher who never watched.
+    let median = chapters[0]["medianSecondsToComplete"].as_i64().unwrap();
+    assert!((299..=301).contains(&median), "{}", median);
+}
+
+#[tokio::test]
+async fn engagement_is_only_for_the_owner() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+
+    let res = Teacher::course_engagement(&db, OTHER_TEACHER.into(), course.id.clone()).await;
+    let csv = Teacher::course_engagement_csv(&db, OTHER_TEACHER.into(), course.id).await;
+
+    assert!(matches!(res, Err(DbErr::RecordNotFound(_))));
+    assert!(matches!(csv, Err(DbErr::RecordNotFound(_))));
+}
+
+#[tokio::test]
+async fn engagement_exports_csv() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 2).await;
+    let mut first: chapter::ActiveModel = chapters[0].clone().into();
+    first.title = Set("Hello, \"world\"".into());
+    first.update(&db).await.unwrap();
+    progress(&db, STUDENT, &chapters[0].id, true).await;
+
+    let csv = Teacher::course_engagement_csv(&db, TEACHER.into(), course.id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        csv,
+        "position,chapter,reached,completed,completionRate,dropOffRate,medianSecondsToComplete,watchSeconds\n\
+         1,\"Hello, \"\"world\"\"\",1,1,1.0000,1.0000,0,0\n\
+         2,Chapter 2,0,0,0.0000,,,0\n"
+    );
+}
@@ -0,0 +1,360 @@
+mod common;
+
+use common::*;
+use entities::{flashcard, prelude::*};
+use service::{
+    sea_orm::*, CardUpdate, Flashcards, NewCard, NewNote, Notes, UpdateError,
+    MAX_REVIEW_INTERVAL_DAYS,
+};
+use sqlx::types::chrono::Utc;
+
+fn card(front: &str, back: &str) -> NewCard {
+    NewCard {
+        front: front.into(),
+        back: back.into(),
+        chapter_id: None,
+    }
+}
+
+/// Moves the card's next review to now, as if its interval had passed.
+async fn make_due(db: &DatabaseConnection, card_id: &str) {
+    let card = Flashcard::find_by_id(card_id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut card: flashcard::ActiveModel = card.into();
+    card.due_at = Set(Utc::now().naive_utc());
+    card.update(db).await.unwrap();
+}
+
+#[tokio::test]
+async fn decks_need_access_to_the_course() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    let (other, other_chapters) = published_course(&db, "Go", 100_000, 1).await;
+
+    let res = Flashcards::create_deck(&db, STUDENT.into(), course.id.clone(), "Rust".into()).await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+    purchase(&db, STUDENT, &course.id).await;
+    purchase(&db, STUDENT, &other.id).await;
+    let res = Flashcards::create_deck(&db, STUDENT.into(), course.id.clone(), " ".into()).await;
+    assert!(matches!(res, Err(UpdateError::Invalid(fields)) if fields[0].field == "title"));
+    let deck = Flashcards::create_deck(&db, STUDENT.into(), course.id.clone(), "Rust".into())
+        .await
+        .unwrap();
+
+    let res = Flashcards::create_card(&db, STUDENT.into(), deck.id.clone(), card("", "Back")).await;
+    assert!(matches!(res, Err(UpdateError::Invalid(fields)) if fields[0].field == "front"));
+    // Cards can only point at chapters of the deck's course
+    let res = Flashcards::create_card(
+        &db,
+        STUDENT.into(),
+        deck.id.clone(),
+        NewCard {
+            chapter_id: Some(other_chapters[0].id.clone()),
+            ..card("Goroutine?", "A green thread")
+        },
+    )
+    .await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+    let owner = Flashcards::create_card(
+        &db,
+        STUDENT.into(),
+        deck.id.clone(),
+        NewCard {
+            chapter_id: Some(chapters[0].id.clone()),
+            ..card(" Owner? ", " One per value ")
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(owner.front, "Owner?");
+    assert!(!owner.is_generated);
+
+    // Decks are private
+    let res = Flashcards::create_card(
+        &db,
+        OTHER_STUDENT.into(),
+        deck.id.clone(),
+        card("Mine?", "No"),
+    )
+    .await;
+    assert!(res.is_err());
+    assert!(
+        Flashcards::cards(&db, OTHER_STUDENT.into(), deck.id.clone())
+            .await
+            .is_err()
+    );
+    assert!(Flashcards::decks(&db, OTHER_STUDENT.into())
+        .await
+        .unwrap()
+        .is_empty());
+
+    let updated = Flashcards::update_card(
+        &db,
+        STUDENT.into(),
+        owner.id.clone(),
+        CardUpdate {
+            front: "Owners?".into(),
+            back: "Exactly one".into(),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.back, "Exactly one");
+    Flashcards::create_card(&db, STUDENT.into(), deck.id.clone(), card("Borrow?", "&T"))
+        .await
+        .unwrap();
+
+    let decks = Flashcards::decks(&db, STUDENT.into()).await.unwrap();
+    assert_eq!(decks.len(), 1);
+    assert_eq!(decks[0].course_title, "Rust");
+    assert_eq!((decks[0].cards, decks[0].due), (2, 2));
+
+    Flashcards::delete_card(&db, STUDENT.into(), owner.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        Flashcards::cards(&db, STUDENT.into(), deck.id.clone())
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    Flashcards::delete_deck(&db, STUDENT.into(), deck.id.clone())
+        .await
+        .unwrap();
+    assert!(Flashcards::decks(&db, STUDENT.into())
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn reviews_follow_sm2() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 100_000, 0).await;
+    purchase(&db, STUDENT, &course.id).await;
+    let deck = Flashcards::create_deck(&db, STUDENT.into(), course.id.clone(), "Rust".into())
+        .await
+        .unwrap();
+    let owner =
+        Flashcards::create_card(&db, STUDENT.into(), deck.id.clone(), card("Owner?", "One"))
+            .await
+            .unwrap();
+    Flashcards::create_card(&db, STUDENT.into(), deck.id.clone(), card("Borrow?", "&T"))
+        .await
+        .unwrap();
+
+    // New cards are due right away
+    let due = Flashcards::due(&db, STUDENT.into(), Some(deck.id.clone()))
+        .await
+        .unwrap();
+    assert_eq!(due.len(), 2);
+    assert!(Flashcards::due(&db, OTHER_STUDENT.into(), None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let res = Flashcards::review(&db, STUDENT.into(), owner.id.clone(), 6).await;
+    assert!(matches!(res, Err(UpdateError::Invalid(fields)) if fields[0].field == "grade"));
+    assert!(
+        Flashcards::review(&db, OTHER_STUDENT.into(), owner.id.clone(), 5)
+            .await
+            .is_err()
+    );
+
+    let mut schedule = Vec::new();
+    for grade in [5, 4, 4, 1, 3] {
+        make_due(&db, &owner.id).await;
+        let card = Flashcards::review(&db, STUDENT.into(), owner.id.clone(), grade)
+            .await
+            .unwrap();
+        assert_eq!(
+            card.due_at.date(),
+            (card.last_reviewed_at.unwrap() + chrono::Duration::days(card.interval.into())).date()
+        );
+        schedule.push((card.ease, card.interval, card.repetitions));
+    }
+    assert_eq!(
+        schedule,
+        vec![
+            (2600, 1, 1),
+            (2600, 6, 2),
+            (2600, 16, 3),
+            // Forgetting starts over and keeps the ease
+            (2600, 1, 0),
+            (2460, 1, 1),
+        ]
+    );
+
+    let due = Flashcards::due(&db, STUDENT.into(), None).await.unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].front, "Borrow?");
+
+    let stats = Flashcards::review_stats(&db, STUDENT.into()).await.unwrap();
+    assert_eq!(stats.due, 1);
+    assert_eq!(stats.reviewed_today, 5);
+    assert_eq!(stats.daily.len(), 7);
+    assert_eq!(stats.daily.last().unwrap().reviews, 5);
+    assert_eq!(stats.daily[0].reviews, 0);
+}
+
+#[tokio::test]
+async fn early_reviews_keep_the_schedule() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 100_000, 0).await;
+    purchase(&db, STUDENT, &course.id).await;
+    let deck = Flashcards::create_deck(&db, STUDENT.into(), course.id, "Rust".into())
+        .await
+        .unwrap();
+    let card = Flashcards::create_card(&db, STUDENT.into(), deck.id, card("Owner?", "One"))
+        .await
+        .unwrap();
+
+    let reviewed = Flashcards::review(&db, STUDENT.into(), card.id.clone(), 5)
+        .await
+        .unwrap();
+    let early = Flashcards::review(&db, STUDENT.into(), card.id, 5)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        (early.ease, early.interval, early.repetitions, early.due_at),
+        (
+            reviewed.ease,
+            reviewed.interval,
+            reviewed.repetitions,
+            reviewed.due_at
+        )
+    );
+    assert!(early.last_reviewed_at > reviewed.last_reviewed_at);
+    let stats = Flashcards::review_stats(&db, STUDENT.into()).await.unwrap();
+    assert_eq!(stats.reviewed_today, 2);
+}
+
+#[tokio::test]
+async fn easy_reviews_stop_at_the_longest_interval() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 100_000, 0).await;
+    purchase(&db, STUDENT, &course.id).await;
+    let deck = Flashcards::create_deck(&db, STUDENT.into(), course.id, "Rust".into())
+        .await
+        .unwrap();
+    let card = Flashcards::create_card(&db, STUDENT.into(), deck.id, card("Owner?", "One"))
+        .await
+        .unwrap();
+
+    let mut intervals = Vec::new();
+    for _ in 0..60 {
+        make_due(&db, &card.id).await;
+        let card = Flashcards::review(&db, STUDENT.into(), card.id.clone(), 5)
+            .await
+            .unwrap();
+        intervals.push(card.interval);
+    }
+
+    assert!(intervals.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(*intervals.last().unwrap(), MAX_REVIEW_INTERVAL_DAYS);
+    let card = Flashcard::find_by_id(card.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(card.ease, 5000);
+    assert_eq!(card.repetitions, 60);
+}
+
+#[tokio::test]
+async fn generated_cards_come_from_the_chapter_and_notes() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 100_000, 0).await;
+    let chapter = ChapterBuilder::new(&course.id, 1)
+        .title("Ownership")
+        .published()
+        .description("<p>Each value has <b>one</b> owner.</p>")
+        .insert(&db)
+        .await;
+    purchase(&db, STUDENT, &course.id).await;
+    Notes::create(
+        &db,
+        STUDENT.into(),
+        NewNote {
+            chapter_id: Some(chapter.id.clone()),
+            attachment_id: None,
+            page: None,
+            highlight: Some("Moves transfer ownership".into()),
+            body: Some("Clone to keep a copy".into()),
+        },
+    )
+    .await
+    .unwrap();
+    let deck = Flashcards::create_deck(&db, STUDENT.into(), course.id.clone(), "Rust".into())
+        .await
+        .unwrap();
+    Flashcards::create_card(&db, STUDENT.into(), deck.id.clone(), card("Owner?", "One"))
+        .await
+        .unwrap();
+
+    let prompt =
+        Flashcards::generation_prompt(&db, STUDENT.into(), deck.id.clone(), chapter.id.clone())
+            .await
+            .unwrap();
+    assert!(prompt.contains("\"Ownership\""));
+    assert!(prompt.contains("Each value has one owner."));
+    assert!(prompt.contains("- Moves transfer ownership\n- Clone to keep a copy"));
+
+    let answer = "Đây là các thẻ:\n\
+                  1. Hỏi: Ai sở hữu một giá trị?\n\
+                  Đáp: Đúng một biến.\n\
+                  \n\
+                  2. Hỏi: Owner?\n\
+                  Đáp: Duplicate of a card in the deck\n\
+                  - Hỏi: Move là gì?\n\
+                  - Đáp: Chuyển quyền sở hữu,\n\
+                  biến cũ không dùng được nữa.\n\
+                  Hỏi: Câu hỏi không có đáp án\n";
+    let added = Flashcards::add_generated(
+        &db,
+        STUDENT.into(),
+        deck.id.clone(),
+        chapter.id.clone(),
+        answer.into(),
+    )
+    .await
+    .unwrap();
+    let pairs: Vec<(&str, &str)> = added
+        .iter()
+        .map(|card| (card.front.as_str(), card.back.as_str()))
+        .collect();
+    assert_eq!(
+        pairs,
+        vec![
+            ("Ai sở hữu một giá trị?", "Đúng một biến."),
+            (
+                "Move là gì?",
+                "Chuyển quyền sở hữu,\nbiến cũ không dùng được nữa."
+            ),
+        ]
+    );
+    assert!(added.iter().all(|card| card.is_generated));
+    assert_eq!(added[0].chapter_id.as_deref(), Some(chapter.id.as_str()));
+
+    // Asking again gives nothing new
+    let res = Flashcards::add_generated(
+        &db,
+        STUDENT.into(),
+        deck.id.clone(),
+        chapter.id.clone(),
+        answer.into(),
+    )
+    .await;
+    assert!(matches!(res, Err(UpdateError::Invalid(fields)) if fields[0].field == "text"));
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{cloudinary::*, *};
+use service::{
+    sign_media_request, ChapterUpdate, Chapters, CloudinaryConfig, CourseUpdate, Courses,
+};
+
+#[tokio::test]
+async fn replacing_a_draft_course_image_deletes_the_old_one() {
+    let db = setup().await;
+    let cloudinary = MockCloudinary::start().await;
+    let course = CourseBuilder::new(TEACHER)
+        .image_url("https://res.cloudinary.com/demo/image/upload/v1/old-cover.png")
+        .insert(&db)
+        .await;
+
+    Courses::update(
+        &db,
+        &cloudinary.config(),
+        TEACHER.into(),
+        course.id,
+        CourseUpdate {
+            image_url: Some("https://res.cloudinary.com/demo/image/upload/v2/new.png".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let requests = cloudinary.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].path, "/sign");
+    assert_eq!(
+        requests[0].authorization,
+        format!("Bearer {}", SIGNER_TOKEN)
+    );
+    let signed: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+    assert_eq!(signed["publicId"], "old-cover");
+    assert_eq!(signed["resourceType"], "image");
+
+    let destroy = &requests[1];
+    assert_eq!(destroy.path, "/v1_1/demo/image/destroy");
+    assert_eq!(destroy.field("public_id").as_deref(), Some("old-cover"));
+    assert_eq!(destroy.field("api_key").as_deref(), Some(API_KEY));
+    assert_eq!(destroy.field("api_secret"), None);
+    assert!(!destroy.body.contains(API_SECRET));
+}
+
+#[tokio::test]
+async fn deleting_a_chapter_deletes_its_video() {
+    let db = setup().await;
+    let cloudinary = MockCloudinary::start().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let chapter = ChapterBuilder::new(&course.id, 1)
+        .video("lesson-1")
+        .insert(&db)
+        .await;
+
+    Chapters::delete(
+        &db,
+        &cloudinary.config(),
+        TEACHER.into(),
+        course.id,
+        chapter.id,
+    )
+    .await
+    .unwrap();
+
+    let requests = cloudinary.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].path, "/v1_1/demo/video/destroy");
+    assert_eq!(requests[1].field("public_id").as_deref(), Some("lesson-1"));
+}
+
+#[tokio::test]
+async fn refused_signature_is_logged_and_the_update_still_applies() {
+    let db = setup().await;
+    let cloudinary = MockCloudinary::start().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let chapter = ChapterBuilder::new(&course.id, 1)
+        .video("lesson-1")
+        .insert(&db)
+        .await;
+
+    let updated = Chapters::update(
+        &db,
+        &CloudinaryConfig {
+            signer_token: "revoked".into(),
+            ..cloudinary.config()
+        },
+        TEACHER.into(),
+        course.id,
+        chapter.id,
+        ChapterUpdate {
+            video_id: Some("lesson-1-v2".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(updated.video_id.as_deref(), Some("lesson-1-v2"));
+    let requests = cloudinary.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/sign");
+}
+
+#[test]
+fn signature_does_not_depend_on_parameter_order() {
+    let signature = sign_media_request(&[("timestamp", "1"), ("public_id", "a")], "s");
+
+    assert_eq!(
+        signature,
+        sign_media_request(&[("public_id", "a"), ("timestamp", "1")], "s")
+    );
+    assert_eq!(signature.len(), 40);
+    assert_ne!(
+        signature,
+        sign_media_request(&[("public_id", "a"), ("timestamp", "1")], "other")
+    );
+}
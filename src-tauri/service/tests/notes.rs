@@ -0,0 +1,289 @@
+mod common;
+
+use common::*;
+use service::{sea_orm::*, NewNote, NoteUpdate, Notes, UpdateError};
+
+fn chapter_note(chapter_id: &str, highlight: Option<&str>, body: Option<&str>) -> NewNote {
+    NewNote {
+        chapter_id: Some(chapter_id.into()),
+        attachment_id: None,
+        page: None,
+        highlight: highlight.map(Into::into),
+        body: body.map(Into::into),
+    }
+}
+
+fn page_note(attachment_id: &str, page: i32, body: &str) -> NewNote {
+    NewNote {
+        chapter_id: None,
+        attachment_id: Some(attachment_id.into()),
+        page: Some(page),
+        highlight: None,
+        body: Some(body.into()),
+    }
+}
+
+#[tokio::test]
+async fn notes_need_one_source_and_access() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    let chapter_id = chapters[0].id.clone();
+    let pdf = attachment(&db, &course.id, "https://example.com/book.pdf").await;
+
+    let res = Notes::create(
+        &db,
+        STUDENT.into(),
+        chapter_note(&chapter_id, None, Some("Hi")),
+    )
+    .await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+    let res = Notes::create(&db, STUDENT.into(), page_note(&pdf.id, 1, "Hi")).await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+
+    purchase(&db, STUDENT, &course.id).await;
+    let both = NewNote {
+        attachment_id: Some(pdf.id.clone()),
+        ..chapter_note(&chapter_id, None, Some("Hi"))
+    };
+    let res = Notes::create(&db, STUDENT.into(), both).await;
+    assert!(matches!(res, Err(UpdateError::Invalid(fields)) if fields[0].field == "chapterId"));
+    let paged_chapter = NewNote {
+        page: Some(2),
+        ..chapter_note(&chapter_id, None, Some("Hi"))
+    };
+    let res = Notes::create(&db, STUDENT.into(), paged_chapter).await;
+    assert!(matches!(res, Err(UpdateError::Invalid(fields)) if fields[0].field == "page"));
+    let res = Notes::create(&db, STUDENT.into(), page_note(&pdf.id, 0, "Hi")).await;
+    assert!(matches!(res, Err(UpdateError::Invalid(fields)) if fields[0].field == "page"));
+    let res = Notes::create(
+        &db,
+        STUDENT.into(),
+        chapter_note(&chapter_id, Some(" "), Some("")),
+    )
+    .await;
+    assert!(matches!(res, Err(UpdateError::Invalid(fields)) if fields[0].field == "body"));
+
+    let note = Notes::create(
+        &db,
+        STUDENT.into(),
+        chapter_note(&chapter_id, Some(" Each value has one owner "), None),
+    )
+    .await
+    .unwrap();
+    assert_eq!(note.highlight.as_deref(), Some("Each value has one owner"));
+    assert_eq!(note.body, None);
+
+    // The teacher takes notes on their own course without buying it
+    Notes::create(&db, TEACHER.into(), page_note(&pdf.id, 3, "Fix typo"))
+        .await
+        .unwrap();
+    // Notes are private
+    let notes = Notes::list_for_chapter(&db, STUDENT.into(), chapter_id.clone())
+        .await
+        .unwrap();
+    assert_eq!(notes.len(), 1);
+    assert!(
+        Notes::list_for_attachment(&db, STUDENT.into(), pdf.id.clone())
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert!(
+        Notes::list_for_chapter(&db, OTHER_STUDENT.into(), chapter_id.clone())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn attachment_notes_by_page_edits_and_deletes() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 100_000, 0).await;
+    let pdf = attachment(&db, &course.id, "https://example.com/book.pdf").await;
+    purchase(&db, STUDENT, &course.id).await;
+    purchase(&db, OTHER_STUDENT, &course.id).await;
+
+    let later = Notes::create(&db, STUDENT.into(), page_note(&pdf.id, 12, "Later"))
+        .await
+        .unwrap();
+    Notes::create(&db, STUDENT.into(), page_note(&pdf.id, 2, "Early"))
+        .await
+        .unwrap();
+
+    let notes = Notes::list_for_attachment(&db, STUDENT.into(), pdf.id.clone())
+        .await
+        .unwrap();
+    let pages: Vec<Option<i32>> = notes.iter().map(|note| note.page).collect();
+    assert_eq!(pages, vec![Some(2), Some(12)]);
+
+    let update = NoteUpdate {
+        highlight: Some("Quoted".into()),
+        body: Some("Mine".into()),
+    };
+    let res = Notes::update(&db, OTHER_STUDENT.into(), later.id.clone(), update.clone()).await;
+    assert!(matches!(
+        res,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+    let res = Notes::update(
+        &db,
+        STUDENT.into(),
+        later.id.clone(),
+        NoteUpdate {
+            highlight: None,
+            body: None,
+        },
+    )
+    .await;
+    assert!(matches!(res, Err(UpdateError::Invalid(_))));
+    let updated = Notes::update(&db, STUDENT.into(), later.id.clone(), update)
+        .await
+        .unwrap();
+    assert_eq!(updated.highlight.as_deref(), Some("Quoted"));
+    assert_eq!(updated.page, Some(12));
+
+    assert!(Notes::delete(&db, OTHER_STUDENT.into(), later.id.clone())
+        .await
+        .is_err());
+    Notes::delete(&db, STUDENT.into(), later.id.clone())
+        .await
+        .unwrap();
+    let notes = Notes::list_for_attachment(&db, STUDENT.into(), pdf.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(notes.len(), 1);
+}
+
+#[tokio::test]
+async fn search_ignores_accents_and_filters_by_course() {
+    let db = setup().await;
+    let (rust, rust_chapters) = published_course(&db, "Rust", 100_000, 1).await;
+    let (go, go_chapters) = published_course(&db, "Go", 100_000, 1).await;
+    let pdf = attachment(&db, &go.id, "https://example.com/go.pdf").await;
+    purchase(&db, STUDENT, &rust.id).await;
+    purchase(&db, STUDENT, &go.id).await;
+    purchase(&db, OTHER_STUDENT, &rust.id).await;
+
+    Notes::create(
+        &db,
+        STUDENT.into(),
+        chapter_note(
+            &rust_chapters[0].id,
+            Some("Quyền sở hữu"),
+            Some("Mượn giá trị"),
+        ),
+    )
+    .await
+    .unwrap();
+    Notes::create(
+        &db,
+        STUDENT.into(),
+        chapter_note(&go_chapters[0].id, None, Some("Goroutine và kênh")),
+    )
+    .await
+    .unwrap();
+    Notes::create(&db, STUDENT.into(), page_note(&pdf.id, 4, "Kênh có bộ đệm"))
+        .await
+        .unwrap();
+    Notes::create(
+        &db,
+        OTHER_STUDENT.into(),
+        chapter_note(&rust_chapters[0].id, None, Some("quyen so huu")),
+    )
+    .await
+    .unwrap();
+
+    let found = Notes::search(&db, STUDENT.into(), "quyen muon".into(), None)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].course_title, "Rust");
+    assert_eq!(found[0].chapter_title.as_deref(), Some("Chapter 1"));
+
+    // Words match from their start
+    let found = Notes::search(&db, STUDENT.into(), "kên".into(), None)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].attachment_name.as_deref(), Some("go.pdf"));
+    assert!(Notes::search(&db, STUDENT.into(), "enh".into(), None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let found = Notes::search(&db, STUDENT.into(), "".into(), Some(rust.id.clone()))
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].course_id, rust.id);
+    let found = Notes::search(&db, STUDENT.into(), "kenh".into(), Some(go.id.clone()))
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 2);
+    assert!(
+        Notes::search(&db, STUDENT.into(), "kenh".into(), Some(rust.id))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn markdown_export_follows_the_course() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 100_000, 2).await;
+    let pdf = attachment(&db, &course.id, "https://example.com/book.pdf").await;
+    purchase(&db, STUDENT, &course.id).await;
+    purchase(&db, OTHER_STUDENT, &course.id).await;
+
+    Notes::create(&db, STUDENT.into(), page_note(&pdf.id, 5, "Page five"))
+        .await
+        .unwrap();
+    Notes::create(
+        &db,
+        STUDENT.into(),
+        chapter_note(&chapters[1].id, None, Some("Second")),
+    )
+    .await
+    .unwrap();
+    Notes::create(
+        &db,
+        STUDENT.into(),
+        chapter_note(&chapters[0].id, Some("Line one\nLine two"), Some("First")),
+    )
+    .await
+    .unwrap();
+    Notes::create(&db, STUDENT.into(), page_note(&pdf.id, 1, "Page one"))
+        .await
+        .unwrap();
+    Notes::create(
+        &db,
+        OTHER_STUDENT.into(),
+        chapter_note(&chapters[0].id, None, Some("Not mine")),
+    )
+    .await
+    .unwrap();
+
+    let markdown = Notes::export_markdown(&db, STUDENT.into(), course.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        markdown,
+        "# Ghi chú: Rust\n\
+         \n## Chapter 1\n\
+         \n> Line one\n> Line two\n\nFirst\n\
+         \n## Chapter 2\n\
+         \nSecond\n\
+         \n## Tài liệu: book.pdf\n\
+         \n### Trang 1\n\
+         \nPage one\n\
+         \n### Trang 5\n\
+         \nPage five\n"
+    );
+}
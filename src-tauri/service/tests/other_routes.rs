@@ -0,0 +1,163 @@
+mod common;
+
+use common::*;
+use serde_json::{json, Value};
+use service::{sea_orm::*, OtherRoutes, SearchQuery};
+use sqlx::types::chrono::NaiveDate;
+
+#[tokio::test]
+async fn dashboard_splits_completed_and_in_progress_courses() {
+    let db = setup().await;
+    let (finished, finished_chapters) = published_course(&db, "Finished", 10, 2).await;
+    let (started, started_chapters) = published_course(&db, "Started", 10, 2).await;
+    published_course(&db, "Not bought", 10, 1).await;
+    purchase(&db, STUDENT, &finished.id).await;
+    purchase(&db, STUDENT, &started.id).await;
+    for chapter in finished_chapters.iter() {
+        progress(&db, STUDENT, &chapter.id, true).await;
+    }
+    progress(&db, STUDENT, &started_chapters[0].id, true).await;
+
+    let res = OtherRoutes::dashboard_courses(&db, STUDENT.into())
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    assert_eq!(res["completedCourses"].as_array().unwrap().len(), 1);
+    assert_eq!(res["completedCourses"][0]["id"], json!(finished.id));
+    assert_eq!(res["completedCourses"][0]["progress"], json!(100));
+    assert_eq!(res["coursesInProgress"].as_array().unwrap().len(), 1);
+    assert_eq!(res["coursesInProgress"][0]["id"], json!(started.id));
+    assert_eq!(res["coursesInProgress"][0]["progress"], json!(50));
+}
+
+#[tokio::test]
+async fn purchase_returns_only_own_purchase() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    purchase(&db, STUDENT, &course.id).await;
+
+    let own = OtherRoutes::purchase(&db, STUDENT.into(), course.id.clone())
+        .await
+        .unwrap();
+    let other = OtherRoutes::purchase(&db, OTHER_STUDENT.into(), course.id)
+        .await
+        .unwrap();
+
+    assert_eq!(own.unwrap().user_id, STUDENT);
+    assert!(other.is_none());
+}
+
+#[tokio::test]
+async fn categories_are_sorted_by_name() {
+    let db = setup().await;
+    category(&db, "Physics").await;
+    category(&db, "Math").await;
+
+    let names: Vec<String> = OtherRoutes::categories(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|category| category.name)
+        .collect();
+
+    assert_eq!(names, vec!["Math", "Physics"]);
+}
+
+#[tokio::test]
+async fn search_lists_published_courses_newest_first() {
+    let db = setup().await;
+    let older = CourseBuilder::new(TEACHER)
+        .title("Older")
+        .published()
+        .created_at(
+            NaiveDate::from_ymd_opt(2024, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+        )
+        .insert(&db)
+        .await;
+    let newer = CourseBuilder::new(TEACHER)
+        .title("Newer")
+        .published()
+        .insert(&db)
+        .await;
+    CourseBuilder::new(TEACHER).title("Draft").insert(&db).await;
+
+    let res = OtherRoutes::search(&db, STUDENT.into(), SearchQuery::default())
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    let ids: Vec<&Value> = res["courses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| &c["id"])
+        .collect();
+    assert_eq!(ids, vec![&json!(newer.id), &json!(older.id)]);
+}
+
+#[tokio::test]
+async fn search_filters_by_title_and_category() {
+    let db = setup().await;
+    let math = category(&db, "Math").await;
+    let algebra = CourseBuilder::new(TEACHER)
+        .title("Algebra")
+        .category(&math.id)
+        .published()
+        .insert(&db)
+        .await;
+    CourseBuilder::new(TEACHER)
+        .title("Algebra for physics")
+        .published()
+        .insert(&db)
+        .await;
+    CourseBuilder::new(TEACHER)
+        .title("Geometry")
+        .category(&math.id)
+        .published()
+        .insert(&db)
+        .await;
+
+    let res = OtherRoutes::search(
+        &db,
+        STUDENT.into(),
+        SearchQuery {
+            text: Some("Algebra".into()),
+            category_id: Some(math.id),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    assert_eq!(res["total"], json!(1));
+    assert_eq!(res["courses"][0]["id"], json!(algebra.id));
+    assert_eq!(res["courses"][0]["category"]["name"], json!("Math"));
+}
+
+#[tokio::test]
+async fn search_reports_progress_only_for_purchased_courses() {
+    let db = setup().await;
+    let (bought, chapters) = published_course(&db, "Bought", 10, 2).await;
+    published_course(&db, "Browsing", 10, 2).await;
+    purchase(&db, STUDENT, &bought.id).await;
+    progress(&db, STUDENT, &chapters[0].id, true).await;
+
+    let res = OtherRoutes::search(&db, STUDENT.into(), SearchQuery::default())
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    for course in res["courses"].as_array().unwrap() {
+        assert_eq!(course["chapters"].as_array().unwrap().len(), 2);
+        if course["id"] == json!(bought.id) {
+            assert_eq!(course["progress"], json!(50));
+        } else {
+            assert_eq!(course["progress"], Value::Null);
+        }
+    }
+}
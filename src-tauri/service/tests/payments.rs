@@ -0,0 +1,354 @@
+mod common;
+
+use common::{stripe::*, *};
+use entities::{prelude::*, sea_orm_active_enums::PurchaseStatus};
+use service::{is_relayed_stripe_call, sea_orm::*, Chapters, OtherRoutes, Payments, WebhookError};
+
+async fn pending_checkout(db: &DatabaseConnection, stripe: &MockStripe) -> (String, String) {
+    let (course, _) = published_course(db, "Rust", 250000, 1).await;
+    let session = OtherRoutes::checkout(
+        db,
+        &stripe.config(),
+        STUDENT.into(),
+        course.id.clone(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    (course.id, session.purchase.stripe_session_id.unwrap())
+}
+
+#[tokio::test]
+async fn checkout_opens_session_and_records_pending_purchase() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, chapters) = published_course(&db, "Rust", 250000, 1).await;
+
+    let session = OtherRoutes::checkout(
+        &db,
+        &stripe.config(),
+        STUDENT.into(),
+        course.id.clone(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let requests = stripe.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].path, "/v1/customers");
+    assert_eq!(requests[0].authorization, format!("Bearer {}", RELAY_TOKEN));
+    assert_eq!(requests[1].path, "/v1/checkout/sessions");
+    assert_eq!(requests[1].field("customer").as_deref(), Some("cus_1"));
+    assert_eq!(
+        requests[1]
+            .field("line_items[0][price_data][unit_amount]")
+            .as_deref(),
+        Some("250000")
+    );
+    assert_eq!(
+        requests[1].field("metadata[courseId]").as_deref(),
+        Some(course.id.as_str())
+    );
+
+    assert_eq!(
+        session.url.as_deref(),
+        Some("https://checkout.stripe.com/c/pay/cs_test_2")
+    );
+    assert_eq!(session.purchase.status, PurchaseStatus::Pending);
+    assert_eq!(
+        session.purchase.stripe_session_id.as_deref(),
+        Some("cs_test_2")
+    );
+    let customer = StripeCustomer::find().one(&db).await.unwrap().unwrap();
+    assert_eq!(customer.stripe_customer_id, "cus_1");
+
+    // A pending purchase does not unlock anything yet.
+    let purchase = OtherRoutes::purchase(&db, STUDENT.into(), course.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(purchase, None);
+    let details = Chapters::get(&db, STUDENT.into(), course.id, chapters[0].id.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(details).unwrap()["entitlement"],
+        serde_json::Value::Null
+    );
+}
+
+#[tokio::test]
+async fn retrying_checkout_reuses_customer_and_purchase() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course_id, first_session) = pending_checkout(&db, &stripe).await;
+
+    let again = OtherRoutes::checkout(&db, &stripe.config(), STUDENT.into(), course_id, None)
+        .await
+        .unwrap();
+
+    let paths: Vec<String> = stripe.requests().into_iter().map(|r| r.path).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/v1/customers".to_string(),
+            "/v1/checkout/sessions".to_string(),
+            format!("/v1/checkout/sessions/{}/expire", first_session),
+            "/v1/checkout/sessions".to_string(),
+        ]
+    );
+    assert_ne!(again.purchase.stripe_session_id, Some(first_session));
+    assert_eq!(Purchase::find().count(&db).await.unwrap(), 1);
+    assert_eq!(StripeCustomer::find().count(&db).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn relay_allows_the_calls_checkout_makes_and_nothing_else() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course_id, _) = pending_checkout(&db, &stripe).await;
+    OtherRoutes::checkout(&db, &stripe.config(), STUDENT.into(), course_id, None)
+        .await
+        .unwrap();
+
+    for request in stripe.requests() {
+        let path = request.path.strip_prefix("/v1/").unwrap();
+        assert!(is_relayed_stripe_call(path), "{} is not relayed", path);
+    }
+    for path in [
+        "charges",
+        "payouts",
+        "customers/cus_1",
+        "checkout/sessions/cs_1",
+        "checkout/sessions//expire",
+        "refunds/re_1/cancel",
+    ] {
+        assert!(!is_relayed_stripe_call(path), "{} is relayed", path);
+    }
+}
+
+#[tokio::test]
+async fn paying_an_earlier_session_still_grants_the_course() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course_id, first_session) = pending_checkout(&db, &stripe).await;
+    let again = OtherRoutes::checkout(
+        &db,
+        &stripe.config(),
+        STUDENT.into(),
+        course_id.clone(),
+        None,
+    )
+    .await
+    .unwrap();
+    let second_session = again.purchase.stripe_session_id.unwrap();
+    let metadata = serde_json::json!({ "userId": STUDENT, "courseId": course_id });
+
+    // The first session was paid before Stripe expired it
+    let paid = session_event_with_metadata(
+        "checkout.session.completed",
+        &first_session,
+        "paid",
+        metadata.clone(),
+    );
+    let purchase = Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &paid,
+        &signature(&paid, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(purchase.status, PurchaseStatus::Paid);
+    assert_eq!(
+        purchase.stripe_session_id.as_deref(),
+        Some(first_session.as_str())
+    );
+    assert_eq!(
+        purchase.stripe_payment_intent_id,
+        Some(payment_intent(&first_session))
+    );
+    // The newer session can no longer take a second payment
+    let last = stripe.requests().pop().unwrap();
+    assert_eq!(
+        last.path,
+        format!("/v1/checkout/sessions/{}/expire", second_session)
+    );
+
+    // Its expiry leaves the paid purchase alone
+    let expired = session_event_with_metadata(
+        "checkout.session.expired",
+        &second_session,
+        "unpaid",
+        metadata,
+    );
+    let res = Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &expired,
+        &signature(&expired, WEBHOOK_SECRET, 0),
+    )
+    .await;
+    assert!(matches!(res, Ok(None)));
+    let purchase = Purchase::find().one(&db).await.unwrap().unwrap();
+    assert_eq!(purchase.status, PurchaseStatus::Paid);
+}
+
+#[tokio::test]
+async fn checkout_grants_free_course_without_stripe() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Free", 0, 1).await;
+
+    let session = OtherRoutes::checkout(&db, &stripe.config(), STUDENT.into(), course.id, None)
+        .await
+        .unwrap();
+
+    assert_eq!(session.url, None);
+    assert_eq!(session.purchase.status, PurchaseStatus::Paid);
+    assert!(stripe.requests().is_empty());
+}
+
+#[tokio::test]
+async fn checkout_rejects_paid_and_unpublished_courses() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    purchase(&db, STUDENT, &course.id).await;
+    let draft = CourseBuilder::new(TEACHER).price(10).insert(&db).await;
+
+    let paid = OtherRoutes::checkout(&db, &stripe.config(), STUDENT.into(), course.id, None).await;
+    let unpublished =
+        OtherRoutes::checkout(&db, &stripe.config(), STUDENT.into(), draft.id, None).await;
+
+    assert!(matches!(paid, Err(DbErr::Custom(_))));
+    assert!(matches!(unpublished, Err(DbErr::Custom(_))));
+    assert!(stripe.requests().is_empty());
+}
+
+#[tokio::test]
+async fn completed_webhook_confirms_purchase() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course_id, session_id) = pending_checkout(&db, &stripe).await;
+    let payload = session_event("checkout.session.completed", &session_id, "paid");
+
+    let confirmed = Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &payload,
+        &signature(&payload, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(confirmed.status, PurchaseStatus::Paid);
+    let purchase = OtherRoutes::purchase(&db, STUDENT.into(), course_id)
+        .await
+        .unwrap();
+    assert_eq!(purchase, Some(confirmed));
+}
+
+#[tokio::test]
+async fn completed_webhook_waits_for_delayed_payment() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (_, session_id) = pending_checkout(&db, &stripe).await;
+    let completed = session_event("checkout.session.completed", &session_id, "unpaid");
+    let succeeded = session_event(
+        "checkout.session.async_payment_succeeded",
+        &session_id,
+        "paid",
+    );
+
+    let first = Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &completed,
+        &signature(&completed, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+    let second = Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &succeeded,
+        &signature(&succeeded, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(first, None);
+    assert_eq!(second.unwrap().status, PurchaseStatus::Paid);
+}
+
+#[tokio::test]
+async fn webhook_rejects_bad_signatures() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (_, session_id) = pending_checkout(&db, &stripe).await;
+    let payload = session_event("checkout.session.completed", &session_id, "paid");
+    let tampered = session_event("checkout.session.completed", "cs_other", "paid");
+
+    for header in [
+        signature(&payload, "whsec_wrong", 0),
+        signature(&tampered, WEBHOOK_SECRET, 0),
+        signature(&payload, WEBHOOK_SECRET, 600),
+        "v1=deadbeef".to_string(),
+    ] {
+        let res = Payments::handle_webhook(&db, &stripe.config(), &payload, &header).await;
+        assert!(matches!(res, Err(WebhookError::Signature)));
+    }
+
+    let purchase = Purchase::find().one(&db).await.unwrap().unwrap();
+    assert_eq!(purchase.status, PurchaseStatus::Pending);
+}
+
+#[tokio::test]
+async fn expired_webhook_drops_pending_purchase() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (_, session_id) = pending_checkout(&db, &stripe).await;
+    let payload = session_event("checkout.session.expired", &session_id, "unpaid");
+
+    Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &payload,
+        &signature(&payload, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(Purchase::find().count(&db).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn webhook_ignores_other_events_and_rejects_unknown_sessions() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let other = session_event("customer.created", "cus_1", "");
+    let unknown = session_event("checkout.session.completed", "cs_unknown", "paid");
+
+    let ignored = Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &other,
+        &signature(&other, WEBHOOK_SECRET, 0),
+    )
+    .await;
+    let rejected = Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &unknown,
+        &signature(&unknown, WEBHOOK_SECRET, 0),
+    )
+    .await;
+
+    assert!(matches!(ignored, Ok(None)));
+    assert!(matches!(rejected, Err(WebhookError::Payload(_))));
+}
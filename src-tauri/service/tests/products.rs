@@ -0,0 +1,416 @@
+mod common;
+
+use chrono::Duration;
+use common::{stripe::*, *};
+use entities::{
+    prelude::*,
+    product,
+    sea_orm_active_enums::{ProductKind, PurchaseStatus},
+};
+use serde_json::json;
+use service::{
+    sea_orm::*, AnalyticsRange, Chapters, EntitlementSource, NewProduct, OtherRoutes, Payments,
+    Products, SearchQuery, Teacher, UpdateError,
+};
+use sqlx::types::chrono::Utc;
+
+fn bundle(course_ids: Vec<String>, price: i32) -> NewProduct {
+    NewProduct {
+        kind: ProductKind::Bundle,
+        title: "Bundle".into(),
+        description: None,
+        price,
+        duration_days: None,
+        course_ids,
+    }
+}
+
+fn subscription(price: i32, days: i32) -> NewProduct {
+    NewProduct {
+        kind: ProductKind::Subscription,
+        title: "Monthly".into(),
+        description: None,
+        price,
+        duration_days: Some(days),
+        course_ids: vec![],
+    }
+}
+
+async fn published(db: &DatabaseConnection, product: NewProduct) -> product::Model {
+    let created = Products::create(db, TEACHER.into(), product).await.unwrap();
+    Products::set_published(db, TEACHER.into(), created.product.id, true)
+        .await
+        .unwrap()
+}
+
+async fn pay(db: &DatabaseConnection, stripe: &MockStripe, session_id: &str) {
+    let payload = session_event("checkout.session.completed", session_id, "paid");
+    Payments::handle_webhook(
+        db,
+        &stripe.config(),
+        &payload,
+        &signature(&payload, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn create_validates_kind_and_course_ownership() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let foreign = CourseBuilder::new(OTHER_TEACHER).insert(&db).await;
+
+    let invalid = Products::create(
+        &db,
+        TEACHER.into(),
+        NewProduct {
+            duration_days: Some(30),
+            ..bundle(vec![course.id.clone()], -1)
+        },
+    )
+    .await;
+    let endless = Products::create(
+        &db,
+        TEACHER.into(),
+        NewProduct {
+            duration_days: None,
+            ..subscription(10, 30)
+        },
+    )
+    .await;
+    let not_owned =
+        Products::create(&db, TEACHER.into(), bundle(vec![course.id, foreign.id], 10)).await;
+
+    match invalid {
+        Err(UpdateError::Invalid(fields)) => assert_eq!(
+            fields.into_iter().map(|f| f.field).collect::<Vec<_>>(),
+            vec!["price", "durationDays", "courseIds"]
+        ),
+        other => panic!("expected validation errors, got {:?}", other),
+    }
+    assert!(matches!(endless, Err(UpdateError::Invalid(_))));
+    assert!(matches!(
+        not_owned,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+}
+
+#[tokio::test]
+async fn bundle_grants_its_courses_once_paid() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (rust, rust_chapters) = published_course(&db, "Rust", 200000, 1).await;
+    let (go, _) = published_course(&db, "Go", 200000, 1).await;
+    let (elixir, _) = published_course(&db, "Elixir", 200000, 1).await;
+    let product = published(&db, bundle(vec![rust.id.clone(), go.id.clone()], 300000)).await;
+
+    let checkout = Products::checkout(&db, &stripe.config(), STUDENT.into(), product.id.clone())
+        .await
+        .unwrap();
+    let request = stripe.requests().pop().unwrap();
+    assert_eq!(
+        request.field("metadata[productId]").as_deref(),
+        Some(product.id.as_str())
+    );
+    assert_eq!(
+        request
+            .field("line_items[0][price_data][unit_amount]")
+            .as_deref(),
+        Some("300000")
+    );
+    assert_eq!(checkout.purchase.status, PurchaseStatus::Pending);
+    assert_eq!(
+        OtherRoutes::entitlement(&db, STUDENT.into(), rust.id.clone())
+            .await
+            .unwrap(),
+        None
+    );
+
+    pay(
+        &db,
+        &stripe,
+        checkout.purchase.stripe_session_id.as_ref().unwrap(),
+    )
+    .await;
+
+    let entitlement = OtherRoutes::entitlement(&db, STUDENT.into(), rust.id.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(entitlement.source, EntitlementSource::Bundle);
+    assert_eq!(entitlement.expires_at, None);
+    let details = Chapters::get(&db, STUDENT.into(), rust.id, rust_chapters[0].id.clone())
+        .await
+        .unwrap();
+    let details = serde_json::to_value(details).unwrap();
+    assert_eq!(details["entitlement"]["source"], json!("bundle"));
+    assert_eq!(details["attachments"], json!([]));
+
+    let search = OtherRoutes::search(&db, STUDENT.into(), SearchQuery::default())
+        .await
+        .unwrap();
+    let search = serde_json::to_value(search).unwrap();
+    let progress = |id: &str| {
+        search["courses"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|course| course["id"] == json!(id))
+            .unwrap()["progress"]
+            .clone()
+    };
+    assert_eq!(progress(&go.id), json!(0));
+    assert_eq!(progress(&elixir.id), json!(null));
+
+    let again = Products::checkout(&db, &stripe.config(), STUDENT.into(), product.id).await;
+    assert!(matches!(again, Err(DbErr::Custom(err)) if err == "Already Purchased"));
+}
+
+#[tokio::test]
+async fn subscription_grants_teacher_courses_until_it_expires() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 200000, 1).await;
+    let foreign = CourseBuilder::new(OTHER_TEACHER)
+        .price(10)
+        .published()
+        .insert(&db)
+        .await;
+    let product = published(&db, subscription(100000, 30)).await;
+
+    let checkout = Products::checkout(&db, &stripe.config(), STUDENT.into(), product.id.clone())
+        .await
+        .unwrap();
+    pay(
+        &db,
+        &stripe,
+        checkout.purchase.stripe_session_id.as_ref().unwrap(),
+    )
+    .await;
+
+    let entitlement = OtherRoutes::entitlement(&db, STUDENT.into(), course.id.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(entitlement.source, EntitlementSource::Subscription);
+    let expires_at = entitlement.expires_at.unwrap();
+    assert!(expires_at > Utc::now().naive_utc() + Duration::days(29));
+    assert_eq!(
+        OtherRoutes::entitlement(&db, STUDENT.into(), foreign.id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    let purchase = ProductPurchase::find().one(&db).await.unwrap().unwrap();
+    let mut purchase: entities::product_purchase::ActiveModel = purchase.into();
+    purchase.expires_at = Set(Some(Utc::now().naive_utc() - Duration::minutes(1)));
+    purchase.update(&db).await.unwrap();
+
+    assert_eq!(
+        OtherRoutes::entitlement(&db, STUDENT.into(), course.id)
+            .await
+            .unwrap(),
+        None
+    );
+    let dashboard = OtherRoutes::dashboard_courses(&db, STUDENT.into())
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(dashboard).unwrap()["coursesInProgress"],
+        json!([])
+    );
+}
+
+#[tokio::test]
+async fn renewing_subscription_extends_current_period_once() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    published_course(&db, "Rust", 200000, 1).await;
+    let product = published(&db, subscription(100000, 30)).await;
+
+    let first = Products::checkout(&db, &stripe.config(), STUDENT.into(), product.id.clone())
+        .await
+        .unwrap();
+    pay(
+        &db,
+        &stripe,
+        first.purchase.stripe_session_id.as_ref().unwrap(),
+    )
+    .await;
+    let paid = ProductPurchase::find().one(&db).await.unwrap().unwrap();
+
+    let renewal = Products::checkout(&db, &stripe.config(), STUDENT.into(), product.id)
+        .await
+        .unwrap();
+    // Access carries on while the renewal is being paid.
+    assert_eq!(renewal.purchase.status, PurchaseStatus::Paid);
+    let session_id = renewal.purchase.stripe_session_id.unwrap();
+    pay(&db, &stripe, &session_id).await;
+    pay(&db, &stripe, &session_id).await;
+
+    let renewed = ProductPurchase::find().one(&db).await.unwrap().unwrap();
+    assert_eq!(
+        renewed.expires_at,
+        paid.expires_at
+            .map(|expires_at| expires_at + Duration::days(30))
+    );
+}
+
+#[tokio::test]
+async fn renewing_subscription_keeps_dripped_chapters_released() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 200000, 1).await;
+    let dripped = ChapterBuilder::new(&course.id, 2)
+        .video("video")
+        .published()
+        .drip_days(7)
+        .insert(&db)
+        .await;
+    let product = published(&db, subscription(100000, 30)).await;
+
+    let first = Products::checkout(&db, &stripe.config(), STUDENT.into(), product.id.clone())
+        .await
+        .unwrap();
+    pay(
+        &db,
+        &stripe,
+        first.purchase.stripe_session_id.as_ref().unwrap(),
+    )
+    .await;
+    let started_at = Utc::now().naive_utc() - Duration::days(10);
+    let paid = ProductPurchase::find().one(&db).await.unwrap().unwrap();
+    let mut paid: entities::product_purchase::ActiveModel = paid.into();
+    paid.paid_at = Set(Some(started_at));
+    paid.update(&db).await.unwrap();
+
+    let renewal = Products::checkout(&db, &stripe.config(), STUDENT.into(), product.id)
+        .await
+        .unwrap();
+    pay(
+        &db,
+        &stripe,
+        renewal.purchase.stripe_session_id.as_ref().unwrap(),
+    )
+    .await;
+
+    let renewed = ProductPurchase::find().one(&db).await.unwrap().unwrap();
+    assert_eq!(renewed.paid_at, Some(started_at));
+    let details = Chapters::get(&db, STUDENT.into(), course.id, dripped.id)
+        .await
+        .unwrap();
+    let details = serde_json::to_value(details).unwrap();
+    assert_eq!(details["availableAt"], json!(null));
+    assert_eq!(details["chapter"]["videoId"], json!("video"));
+}
+
+#[tokio::test]
+async fn refunded_product_no_longer_grants_access() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (rust, _) = published_course(&db, "Rust", 200000, 1).await;
+    let (go, _) = published_course(&db, "Go", 200000, 1).await;
+    let product = published(&db, bundle(vec![rust.id.clone(), go.id], 300000)).await;
+    let checkout = Products::checkout(&db, &stripe.config(), STUDENT.into(), product.id)
+        .await
+        .unwrap();
+    let session_id = checkout.purchase.stripe_session_id.unwrap();
+    pay(&db, &stripe, &session_id).await;
+
+    let payload = refund_event(&payment_intent(&session_id), true);
+    Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &payload,
+        &signature(&payload, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+
+    let purchase = ProductPurchase::find().one(&db).await.unwrap().unwrap();
+    assert_eq!(purchase.status, PurchaseStatus::Refunded);
+    assert_eq!(
+        OtherRoutes::entitlement(&db, STUDENT.into(), rust.id)
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn analytics_counts_every_product_payment_and_refund() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    published_course(&db, "Rust", 200000, 1).await;
+    let product = published(&db, subscription(100000, 30)).await;
+
+    let mut session_ids = Vec::new();
+    for _ in 0..2 {
+        let checkout =
+            Products::checkout(&db, &stripe.config(), STUDENT.into(), product.id.clone())
+                .await
+                .unwrap();
+        let session_id = checkout.purchase.stripe_session_id.unwrap();
+        pay(&db, &stripe, &session_id).await;
+        session_ids.push(session_id);
+    }
+    let payload = refund_event(&payment_intent(&session_ids[1]), true);
+    Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &payload,
+        &signature(&payload, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+
+    let payments = ProductPayment::find().all(&db).await.unwrap();
+    assert_eq!(payments.len(), 2);
+    assert_eq!(
+        payments
+            .iter()
+            .filter(|payment| payment.refunded_at.is_some())
+            .count(),
+        1
+    );
+    let analytics = Teacher::analytics(&db, TEACHER.into(), AnalyticsRange::default())
+        .await
+        .unwrap();
+    let analytics = serde_json::to_value(analytics).unwrap();
+    assert_eq!(
+        analytics["data"],
+        json!([{ "name": "Monthly", "total": 100000, "sales": 2 }])
+    );
+    assert_eq!(analytics["totalRevenue"], json!(100000));
+    assert_eq!(analytics["totalSales"], json!(2));
+    assert_eq!(analytics["totalRefunds"], json!(1));
+    assert_eq!(analytics["refundedAmount"], json!(100000));
+    let foreign = Teacher::analytics(&db, OTHER_TEACHER.into(), AnalyticsRange::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(foreign).unwrap()["totalSales"],
+        json!(0)
+    );
+}
+
+#[tokio::test]
+async fn only_published_products_are_offered() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (rust, _) = published_course(&db, "Rust", 200000, 1).await;
+    let (go, _) = published_course(&db, "Go", 200000, 1).await;
+    let offered = published(&db, subscription(100000, 30)).await;
+    let draft = Products::create(&db, TEACHER.into(), bundle(vec![rust.id.clone(), go.id], 1))
+        .await
+        .unwrap();
+
+    let products = Products::for_course(&db, rust.id).await.unwrap();
+    let checkout =
+        Products::checkout(&db, &stripe.config(), STUDENT.into(), draft.product.id).await;
+
+    assert_eq!(products, vec![offered]);
+    assert!(matches!(checkout, Err(DbErr::Custom(err)) if err == "Cannot find product"));
+}
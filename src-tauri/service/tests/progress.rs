@@ -0,0 +1,274 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::*;
+use entities::{prelude::*, user_progress};
+use service::{
+    sea_orm::*, Chapters, ProgressUpdate, WatchHeartbeat, WatchSettings, WatchThrottle,
+    DEFAULT_HEARTBEAT_INTERVAL_SECS,
+};
+
+fn heartbeat(seconds: std::ops::Range<i32>, duration: i32) -> WatchHeartbeat {
+    WatchHeartbeat {
+        start_second: seconds.start,
+        end_second: seconds.end,
+        position: seconds.end,
+        duration: Some(duration),
+    }
+}
+
+async fn report(
+    db: &DatabaseConnection,
+    course_id: &str,
+    chapter_id: &str,
+    heartbeat: WatchHeartbeat,
+) -> Result<user_progress::Model, DbErr> {
+    Chapters::report_watch(
+        db,
+        &WatchThrottle::new(Duration::zero()),
+        STUDENT.into(),
+        course_id.into(),
+        chapter_id.into(),
+        heartbeat,
+        &WatchSettings {
+            complete_at_percent: 80,
+        },
+    )
+    .await
+    .map(|progress| progress.expect("Heartbeat was written"))
+}
+
+#[tokio::test]
+async fn heartbeats_merge_into_watched_ranges() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 1).await;
+    purchase(&db, STUDENT, &course.id).await;
+    let chapter_id = &chapters[0].id;
+
+    report(&db, &course.id, chapter_id, heartbeat(0..10, 100))
+        .await
+        .unwrap();
+    report(&db, &course.id, chapter_id, heartbeat(30..40, 100))
+        .await
+        .unwrap();
+    // Rewatching a stretch does not count twice.
+    report(&db, &course.id, chapter_id, heartbeat(5..35, 100))
+        .await
+        .unwrap();
+    let progress = report(
+        &db,
+        &course.id,
+        chapter_id,
+        WatchHeartbeat {
+            position: 12,
+            ..heartbeat(50..60, 100)
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(progress.watched_ranges, "[[0,40],[50,60]]");
+    assert_eq!(progress.watched_seconds, 50);
+    assert_eq!(progress.last_position, 12);
+    assert_eq!(progress.duration, Some(100));
+    assert!(!progress.is_completed);
+    assert_eq!(UserProgress::find().count(&db).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn watching_enough_completes_the_chapter() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 1).await;
+    purchase(&db, STUDENT, &course.id).await;
+    let chapter_id = &chapters[0].id;
+
+    let before = report(&db, &course.id, chapter_id, heartbeat(0..79, 100))
+        .await
+        .unwrap();
+    // The end of the range is clamped to the length of the video.
+    let after = report(&db, &course.id, chapter_id, heartbeat(79..500, 100))
+        .await
+        .unwrap();
+
+    assert!(!before.is_completed);
+    assert!(after.is_completed);
+    assert_eq!(after.watched_seconds, 100);
+    assert!(after.completed_at.is_some());
+
+    // Marking the chapter as not done again is still up to the student.
+    let undone = Chapters::update_progress(
+        &db,
+        STUDENT.into(),
+        chapter_id.clone(),
+        ProgressUpdate {
+            is_completed: Some(false),
+            heartbeat: None,
+        },
+        &WatchSettings::default(),
+    )
+    .await
+    .unwrap();
+    assert!(!undone.is_completed);
+    assert_eq!(undone.completed_at, None);
+    assert_eq!(undone.watched_seconds, 100);
+}
+
+#[tokio::test]
+async fn continuous_heartbeats_share_one_watch_event() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 1).await;
+    purchase(&db, STUDENT, &course.id).await;
+    let chapter_id = &chapters[0].id;
+
+    for start in (0..30).step_by(10) {
+        report(
+            &db,
+            &course.id,
+            chapter_id,
+            heartbeat(start..start + 10, 600),
+        )
+        .await
+        .unwrap();
+    }
+    // Skipping ahead starts a new one.
+    report(&db, &course.id, chapter_id, heartbeat(300..310, 600))
+        .await
+        .unwrap();
+
+    let events = WatchEvent::find()
+        .order_by_asc(entities::watch_event::Column::StartSecond)
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(
+        events
+            .iter()
+            .map(|event| (event.start_second, event.end_second))
+            .collect::<Vec<_>>(),
+        vec![(0, 30), (300, 310)]
+    );
+}
+
+#[tokio::test]
+async fn report_watch_requires_access_to_the_chapter() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 1).await;
+    let free = ChapterBuilder::new(&course.id, 2)
+        .published()
+        .free()
+        .insert(&db)
+        .await;
+
+    let locked = report(&db, &course.id, &chapters[0].id, heartbeat(0..10, 100)).await;
+    let backwards = report(
+        &db,
+        &course.id,
+        &free.id,
+        WatchHeartbeat {
+            start_second: 10,
+            ..heartbeat(0..5, 100)
+        },
+    )
+    .await;
+    let watched = report(&db, &course.id, &free.id, heartbeat(5..10, 100))
+        .await
+        .unwrap();
+
+    assert!(matches!(locked, Err(DbErr::Custom(err)) if err == "Chapter is locked"));
+    assert!(matches!(backwards, Err(DbErr::Custom(err)) if err == "Invalid watch range"));
+    assert_eq!(watched.watched_seconds, 5);
+    assert_eq!(WatchEvent::find().count(&db).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn rapid_heartbeats_are_coalesced() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 1).await;
+    purchase(&db, STUDENT, &course.id).await;
+    let chapter_id = &chapters[0].id;
+    let throttle = WatchThrottle::new(Duration::minutes(1));
+    let settings = WatchSettings::default();
+
+    let mut written = Vec::new();
+    for start in (0..50).step_by(10) {
+        written.push(
+            Chapters::report_watch(
+                &db,
+                &throttle,
+                STUDENT.into(),
+                course.id.clone(),
+                chapter_id.clone(),
+                heartbeat(start..start + 10, 600),
+                &settings,
+            )
+            .await
+            .unwrap(),
+        );
+    }
+
+    assert!(written[0].is_some());
+    assert!(written[1..].iter().all(Option::is_none));
+    assert_eq!(WatchEvent::find().count(&db).await.unwrap(), 1);
+
+    // Nothing is due before the interval has passed.
+    let now = Utc::now().naive_utc();
+    assert_eq!(
+        Chapters::flush_watch(&db, &throttle, &settings, now)
+            .await
+            .unwrap(),
+        0
+    );
+    let flushed = Chapters::flush_watch(&db, &throttle, &settings, now + Duration::minutes(2))
+        .await
+        .unwrap();
+
+    let progress = UserProgress::find().one(&db).await.unwrap().unwrap();
+    assert_eq!(flushed, 1);
+    assert_eq!(progress.watched_ranges, "[[0,50]]");
+    assert_eq!(progress.last_position, 50);
+}
+
+#[tokio::test]
+async fn default_throttle_holds_heartbeats_between_player_reports() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 1).await;
+    purchase(&db, STUDENT, &course.id).await;
+    let chapter_id = &chapters[0].id;
+    let throttle = WatchThrottle::default();
+    let settings = WatchSettings::default();
+    // The video player reports every 10 seconds.
+    let player_interval = Duration::seconds(10);
+    assert_eq!(DEFAULT_HEARTBEAT_INTERVAL_SECS, 30);
+
+    let now = Utc::now().naive_utc();
+    for start in [0, 10] {
+        Chapters::report_watch(
+            &db,
+            &throttle,
+            STUDENT.into(),
+            course.id.clone(),
+            chapter_id.clone(),
+            heartbeat(start..start + 10, 600),
+            &settings,
+        )
+        .await
+        .unwrap();
+    }
+
+    let early = Chapters::flush_watch(&db, &throttle, &settings, now + player_interval * 2)
+        .await
+        .unwrap();
+    let due = Chapters::flush_watch(
+        &db,
+        &throttle,
+        &settings,
+        now + Duration::seconds(DEFAULT_HEARTBEAT_INTERVAL_SECS + 1),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(early, 0);
+    assert_eq!(due, 1);
+    let progress = UserProgress::find().one(&db).await.unwrap().unwrap();
+    assert_eq!(progress.watched_ranges, "[[0,20]]");
+}
@@ -0,0 +1,317 @@
+mod common;
+
+use common::{stripe::*, *};
+use entities::{prelude::*, sea_orm_active_enums::PurchaseStatus};
+use serde_json::json;
+use service::{
+    sea_orm::*, AnalyticsRange, Chapters, OtherRoutes, Payments, Purchases, SearchQuery, Teacher,
+};
+
+/// A course bought by [`STUDENT`] through Stripe, confirmed by the webhook.
+async fn paid_checkout(db: &DatabaseConnection, stripe: &MockStripe) -> (String, String) {
+    let (course, _) = published_course(db, "Rust", 250000, 1).await;
+    let session = OtherRoutes::checkout(
+        db,
+        &stripe.config(),
+        STUDENT.into(),
+        course.id.clone(),
+        None,
+    )
+    .await
+    .unwrap();
+    let session_id = session.purchase.stripe_session_id.unwrap();
+    let payload = session_event("checkout.session.completed", &session_id, "paid");
+    Payments::handle_webhook(
+        db,
+        &stripe.config(),
+        &payload,
+        &signature(&payload, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+
+    (course.id, session.purchase.id)
+}
+
+#[tokio::test]
+async fn confirmed_checkout_records_amount_and_payment() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (_, purchase_id) = paid_checkout(&db, &stripe).await;
+
+    let purchase = Purchase::find_by_id(purchase_id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(purchase.status, PurchaseStatus::Paid);
+    assert_eq!(purchase.amount, 250000);
+    assert_eq!(purchase.currency, "vnd");
+    assert!(purchase.paid_at.is_some());
+    assert_eq!(
+        purchase.stripe_payment_intent_id,
+        Some(payment_intent(&purchase.stripe_session_id.unwrap()))
+    );
+}
+
+#[tokio::test]
+async fn refund_goes_through_stripe_and_removes_access() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course_id, purchase_id) = paid_checkout(&db, &stripe).await;
+    let chapter = Chapter::find().one(&db).await.unwrap().unwrap();
+
+    let refunded = Purchases::refund(&db, &stripe.config(), TEACHER.into(), purchase_id.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(refunded.status, PurchaseStatus::Refunded);
+    assert!(refunded.refunded_at.is_some());
+    let request = stripe.requests().pop().unwrap();
+    assert_eq!(request.path, "/v1/refunds");
+    assert!(service::is_relayed_stripe_call("refunds"));
+    assert_eq!(
+        request.field("payment_intent"),
+        refunded.stripe_payment_intent_id
+    );
+    assert_eq!(request.field("metadata[purchaseId]"), Some(purchase_id));
+
+    let details = Chapters::get(&db, STUDENT.into(), course_id.clone(), chapter.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(details).unwrap()["entitlement"],
+        json!(null)
+    );
+    let dashboard = OtherRoutes::dashboard_courses(&db, STUDENT.into())
+        .await
+        .unwrap();
+    let dashboard = serde_json::to_value(dashboard).unwrap();
+    assert_eq!(dashboard["coursesInProgress"], json!([]));
+    let search = OtherRoutes::search(&db, STUDENT.into(), SearchQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(search).unwrap()["courses"][0]["progress"],
+        json!(null)
+    );
+}
+
+#[tokio::test]
+async fn refund_and_revoke_need_owner_and_paid_purchase() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let purchase = purchase(&db, STUDENT, &course.id).await;
+
+    let foreign = Purchases::revoke(&db, OTHER_TEACHER.into(), purchase.id.clone()).await;
+    Purchases::revoke(&db, TEACHER.into(), purchase.id.clone())
+        .await
+        .unwrap();
+    let refund =
+        Purchases::refund(&db, &stripe.config(), TEACHER.into(), purchase.id.clone()).await;
+    let again = Purchases::revoke(&db, TEACHER.into(), purchase.id).await;
+
+    assert!(matches!(foreign, Err(DbErr::RecordNotFound(_))));
+    assert!(matches!(refund, Err(DbErr::Custom(_))));
+    assert!(matches!(again, Err(DbErr::Custom(_))));
+    assert!(stripe.requests().is_empty());
+}
+
+#[tokio::test]
+async fn revoked_student_cannot_buy_again() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let purchase = purchase(&db, STUDENT, &course.id).await;
+
+    let revoked = Purchases::revoke(&db, TEACHER.into(), purchase.id)
+        .await
+        .unwrap();
+    let checkout = OtherRoutes::checkout(
+        &db,
+        &stripe.config(),
+        STUDENT.into(),
+        course.id.clone(),
+        None,
+    )
+    .await;
+
+    assert_eq!(revoked.status, PurchaseStatus::Revoked);
+    assert!(revoked.revoked_at.is_some());
+    assert!(matches!(checkout, Err(DbErr::Custom(_))));
+    let purchase = OtherRoutes::purchase(&db, STUDENT.into(), course.id)
+        .await
+        .unwrap();
+    assert_eq!(purchase, None);
+}
+
+#[tokio::test]
+async fn refunded_student_can_buy_again() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let purchase = purchase(&db, STUDENT, &course.id).await;
+    // Granted without payment, so there is nothing to refund on Stripe.
+    let refunded = Purchases::refund(&db, &stripe.config(), TEACHER.into(), purchase.id.clone())
+        .await
+        .unwrap();
+    assert!(stripe.requests().is_empty());
+
+    let session = OtherRoutes::checkout(&db, &stripe.config(), STUDENT.into(), course.id, None)
+        .await
+        .unwrap();
+    let payload = session_event(
+        "checkout.session.expired",
+        session.purchase.stripe_session_id.as_ref().unwrap(),
+        "unpaid",
+    );
+    Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &payload,
+        &signature(&payload, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(session.purchase.id, purchase.id);
+    assert_eq!(session.purchase.status, PurchaseStatus::Pending);
+    assert_eq!(session.purchase.amount, 10);
+    assert_eq!(session.purchase.refunded_at, None);
+    let kept = Purchase::find_by_id(purchase.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(kept.status, PurchaseStatus::Refunded);
+    assert_eq!(kept.stripe_session_id, None);
+    assert_eq!(kept.paid_at, refunded.paid_at);
+    assert_eq!(kept.refunded_at, refunded.refunded_at);
+    assert_eq!(RefundedSale::find().count(&db).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn buying_again_after_a_refund_keeps_the_refunded_sale() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course_id, purchase_id) = paid_checkout(&db, &stripe).await;
+    let refunded = Purchases::refund(&db, &stripe.config(), TEACHER.into(), purchase_id.clone())
+        .await
+        .unwrap();
+
+    let session = OtherRoutes::checkout(&db, &stripe.config(), STUDENT.into(), course_id, None)
+        .await
+        .unwrap();
+    assert_eq!(session.purchase.id, purchase_id);
+    assert_eq!(session.purchase.status, PurchaseStatus::Pending);
+    assert_eq!(session.purchase.paid_at, None);
+    assert_eq!(session.purchase.refunded_at, None);
+    let session_id = session.purchase.stripe_session_id.unwrap();
+    let payload = session_event("checkout.session.completed", &session_id, "paid");
+    Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &payload,
+        &signature(&payload, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+
+    let rebought = Purchase::find_by_id(purchase_id.clone())
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(rebought.status, PurchaseStatus::Paid);
+    assert!(rebought.paid_at.is_some());
+    assert_eq!(rebought.refunded_at, None);
+    assert_eq!(
+        rebought.stripe_payment_intent_id,
+        Some(payment_intent(&session_id))
+    );
+
+    let sales = RefundedSale::find().all(&db).await.unwrap();
+    assert_eq!(sales.len(), 1);
+    assert_eq!(sales[0].purchase_id, purchase_id);
+    assert_eq!(sales[0].amount, 250000);
+    assert_eq!(Some(sales[0].paid_at), refunded.paid_at);
+    assert_eq!(Some(sales[0].refunded_at), refunded.refunded_at);
+    assert_eq!(
+        sales[0].stripe_payment_intent_id,
+        refunded.stripe_payment_intent_id
+    );
+
+    let analytics = Teacher::analytics(&db, TEACHER.into(), AnalyticsRange::default())
+        .await
+        .unwrap();
+    let analytics = serde_json::to_value(analytics).unwrap();
+    assert_eq!(
+        analytics["data"],
+        json!([{ "name": "Rust", "total": 250000, "sales": 2 }])
+    );
+    assert_eq!(analytics["totalRevenue"], json!(250000));
+    assert_eq!(analytics["totalSales"], json!(2));
+    assert_eq!(analytics["totalRefunds"], json!(1));
+    assert_eq!(analytics["refundedAmount"], json!(250000));
+}
+
+#[tokio::test]
+async fn charge_refunded_webhook_refunds_full_refunds_only() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (_, purchase_id) = paid_checkout(&db, &stripe).await;
+    let purchase = Purchase::find_by_id(purchase_id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    let payment_intent = purchase.stripe_payment_intent_id.unwrap();
+    let partial = refund_event(&payment_intent, false);
+    let full = refund_event(&payment_intent, true);
+
+    let ignored = Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &partial,
+        &signature(&partial, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap();
+    let refunded = Payments::handle_webhook(
+        &db,
+        &stripe.config(),
+        &full,
+        &signature(&full, WEBHOOK_SECRET, 0),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(ignored, None);
+    assert_eq!(refunded.status, PurchaseStatus::Refunded);
+}
+
+#[tokio::test]
+async fn list_shows_every_purchase_to_the_owner() {
+    let db = setup().await;
+    let stripe = MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let refunded = purchase(&db, STUDENT, &course.id).await;
+    purchase(&db, OTHER_STUDENT, &course.id).await;
+    Purchases::refund(&db, &stripe.config(), TEACHER.into(), refunded.id)
+        .await
+        .unwrap();
+
+    let purchases = Purchases::list(&db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+    let foreign = Purchases::list(&db, OTHER_TEACHER.into(), course.id).await;
+
+    assert_eq!(purchases.len(), 2);
+    assert!(purchases
+        .iter()
+        .any(|purchase| purchase.status == PurchaseStatus::Refunded));
+    assert!(matches!(foreign, Err(DbErr::RecordNotFound(_))));
+}
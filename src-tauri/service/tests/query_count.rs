@@ -0,0 +1,104 @@
+//! Query budgets for the pages that list many courses. Each scenario is
+//! seeded at two sizes and has to cost the same number of queries at both, so
+//! adding courses never adds round trips.
+
+mod common;
+
+use std::{sync::atomic::Ordering, time::Instant};
+
+use common::*;
+use service::{sea_orm::*, OtherRoutes, SearchQuery};
+
+const SMALL: i32 = 2;
+const LARGE: i32 = 50;
+
+// `courses` bought courses with three chapters each, the first one completed,
+// next to as many courses the student has not bought.
+async fn seed(courses: i32) -> DatabaseConnection {
+    let db = setup().await;
+    let category = category(&db, "Programming").await;
+    for i in 0..courses {
+        let bought = CourseBuilder::new(TEACHER)
+            .title(&format!("Bought {}", i))
+            .category(&category.id)
+            .price(10)
+            .published()
+            .insert(&db)
+            .await;
+        let mut chapters = Vec::new();
+        for position in 1..=3 {
+            chapters.push(
+                ChapterBuilder::new(&bought.id, position)
+                    .published()
+                    .insert(&db)
+                    .await,
+            );
+        }
+        purchase(&db, STUDENT, &bought.id).await;
+        progress(&db, STUDENT, &chapters[0].id, true).await;
+        published_course(&db, &format!("Browsing {}", i), 10, 3).await;
+    }
+    db
+}
+
+async fn measure<F, Fut>(courses: i32, run: F) -> usize
+where
+    F: FnOnce(DatabaseConnection) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let mut db = seed(courses).await;
+    let count = count_queries(&mut db);
+    let started = Instant::now();
+    run(db).await;
+    let queries = count.load(Ordering::SeqCst);
+    println!(
+        "{} courses: {} queries in {:?}",
+        courses,
+        queries,
+        started.elapsed()
+    );
+    queries
+}
+
+#[tokio::test]
+async fn dashboard_runs_a_constant_number_of_queries() {
+    let run = |db: DatabaseConnection| async move {
+        let res = OtherRoutes::dashboard_courses(&db, STUDENT.into())
+            .await
+            .unwrap();
+        let res = serde_json::to_value(res).unwrap();
+        assert!(!res["coursesInProgress"].as_array().unwrap().is_empty());
+    };
+
+    let small = measure(SMALL, run).await;
+    let large = measure(LARGE, run).await;
+
+    assert_eq!(small, large);
+    assert!(large <= 6, "dashboard ran {} queries", large);
+}
+
+#[tokio::test]
+async fn search_runs_a_constant_number_of_queries() {
+    let run = |db: DatabaseConnection| async move {
+        let res = OtherRoutes::search(
+            &db,
+            STUDENT.into(),
+            SearchQuery {
+                text: Some("bought".into()),
+                per_page: Some(50),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let res = serde_json::to_value(res).unwrap();
+        assert!(!res["courses"].as_array().unwrap().is_empty());
+    };
+
+    let small = measure(SMALL, run).await;
+    let large = measure(LARGE, run).await;
+
+    // Two of them look the text up in the search index.
+    assert_eq!(small, large);
+    assert!(large <= 8, "search ran {} queries", large);
+}
@@ -0,0 +1,372 @@
+mod common;
+
+use common::*;
+use entities::prelude::*;
+use serde_json::json;
+use service::{sea_orm::*, ChapterUpdate, Chapters, CourseUpdate, Courses, Revisions, Teacher};
+
+/// A complete course that goes live through `Courses::publish`, which records
+/// version 1.
+async fn live_course(db: &DatabaseConnection) -> (entities::course::Model, String) {
+    let category = category(db, "Math").await;
+    let course = CourseBuilder::new(TEACHER)
+        .title("Algebra")
+        .description("Description")
+        .image_url("https://example.com/cover.png")
+        .category(&category.id)
+        .price(10)
+        .insert(db)
+        .await;
+    let chapter = ChapterBuilder::new(&course.id, 1)
+        .title("Intro")
+        .description("Description")
+        .video("video")
+        .free()
+        .published()
+        .insert(db)
+        .await;
+    Courses::publish(db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+
+    (course, chapter.id)
+}
+
+fn retitle(title: &str) -> CourseUpdate {
+    CourseUpdate {
+        title: Some(title.into()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn edits_to_live_course_are_kept_as_draft() {
+    let db = setup().await;
+    let (course, chapter_id) = live_course(&db).await;
+
+    let preview = Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        retitle("Algebra II"),
+    )
+    .await
+    .unwrap();
+    Chapters::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        chapter_id.clone(),
+        ChapterUpdate {
+            title: Some("Welcome".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(preview.title, "Algebra II");
+    let live = Course::find_by_id(course.id.clone())
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(live.title, "Algebra");
+    let student = Chapters::get(&db, STUDENT.into(), course.id.clone(), chapter_id.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(student).unwrap()["chapter"]["title"],
+        json!("Intro")
+    );
+
+    let teacher = Teacher::course(&db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+    let teacher = serde_json::to_value(teacher).unwrap();
+    assert_eq!(teacher["title"], json!("Algebra II"));
+    assert_eq!(teacher["chapters"][0]["title"], json!("Welcome"));
+    assert_eq!(teacher["hasDraft"], json!(true));
+    let chapter = Teacher::chapter(&db, course.id, chapter_id).await.unwrap();
+    assert_eq!(chapter.title, "Welcome");
+}
+
+#[tokio::test]
+async fn draft_edits_accumulate() {
+    let db = setup().await;
+    let (course, _) = live_course(&db).await;
+
+    Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        retitle("Algebra II"),
+    )
+    .await
+    .unwrap();
+    let preview = Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        CourseUpdate {
+            price: Some(20),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(preview.title, "Algebra II");
+    assert_eq!(preview.price, Some(20));
+}
+
+#[tokio::test]
+async fn unpublished_course_is_edited_in_place() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).title("Draft").insert(&db).await;
+
+    Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        retitle("Renamed"),
+    )
+    .await
+    .unwrap();
+
+    let live = Course::find_by_id(course.id.clone())
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(live.title, "Renamed");
+    assert_eq!(CourseDraft::find().count(&db).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn publish_draft_applies_edits_and_records_revision() {
+    let db = setup().await;
+    let (course, chapter_id) = live_course(&db).await;
+    Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        retitle("Algebra II"),
+    )
+    .await
+    .unwrap();
+    Chapters::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        chapter_id.clone(),
+        ChapterUpdate {
+            is_free: Some(false),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let revision = Revisions::publish_draft(&db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(revision.version, 2);
+    let live = Course::find_by_id(course.id.clone())
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(live.title, "Algebra II");
+    let chapter = Chapter::find_by_id(chapter_id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!chapter.is_free);
+    assert_eq!(CourseDraft::find().count(&db).await.unwrap(), 0);
+    assert_eq!(ChapterDraft::find().count(&db).await.unwrap(), 0);
+
+    let history = Revisions::history(&db, TEACHER.into(), course.id)
+        .await
+        .unwrap();
+    let versions: Vec<serde_json::Value> = history
+        .into_iter()
+        .map(|revision| serde_json::to_value(revision).unwrap()["version"].clone())
+        .collect();
+    assert_eq!(versions, vec![json!(2), json!(1)]);
+}
+
+#[tokio::test]
+async fn publish_draft_requires_pending_changes() {
+    let db = setup().await;
+    let (course, _) = live_course(&db).await;
+
+    let res = Revisions::publish_draft(&db, TEACHER.into(), course.id).await;
+
+    assert!(matches!(res, Err(DbErr::Custom(_))));
+}
+
+#[tokio::test]
+async fn discard_draft_keeps_live_course() {
+    let db = setup().await;
+    let (course, _) = live_course(&db).await;
+    Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        retitle("Algebra II"),
+    )
+    .await
+    .unwrap();
+
+    Revisions::discard_draft(&db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+
+    let teacher = Teacher::course(&db, TEACHER.into(), course.id)
+        .await
+        .unwrap();
+    let teacher = serde_json::to_value(teacher).unwrap();
+    assert_eq!(teacher["title"], json!("Algebra"));
+    assert_eq!(teacher["hasDraft"], json!(false));
+}
+
+#[tokio::test]
+async fn diff_lists_changed_fields() {
+    let db = setup().await;
+    let (course, chapter_id) = live_course(&db).await;
+    Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        retitle("Algebra II"),
+    )
+    .await
+    .unwrap();
+
+    let pending = Revisions::diff(&db, TEACHER.into(), course.id.clone(), 1, None)
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].field, "title");
+    assert_eq!(pending[0].before, json!("Algebra"));
+    assert_eq!(pending[0].after, json!("Algebra II"));
+
+    Chapters::create(&db, TEACHER.into(), course.id.clone(), "Extra".into())
+        .await
+        .unwrap();
+    Chapters::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        chapter_id.clone(),
+        ChapterUpdate {
+            description: Some("New".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    Revisions::publish_draft(&db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+
+    let changes = Revisions::diff(&db, TEACHER.into(), course.id, 1, Some(2))
+        .await
+        .unwrap();
+    let fields: Vec<String> = changes.iter().map(|change| change.field.clone()).collect();
+    assert_eq!(fields.len(), 3);
+    assert_eq!(fields[0], "title");
+    assert_eq!(fields[1], format!("chapters.{}.description", chapter_id));
+    assert!(fields[2].starts_with("chapters."));
+    assert_eq!(changes[2].after, json!("Extra"));
+}
+
+#[tokio::test]
+async fn rollback_restores_version_as_new_revision() {
+    let db = setup().await;
+    let (course, chapter_id) = live_course(&db).await;
+    Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        retitle("Algebra II"),
+    )
+    .await
+    .unwrap();
+    Chapters::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        chapter_id.clone(),
+        ChapterUpdate {
+            title: Some("Welcome".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    Revisions::publish_draft(&db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+    Courses::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        retitle("Pending"),
+    )
+    .await
+    .unwrap();
+
+    let revision = Revisions::rollback(&db, TEACHER.into(), course.id.clone(), 1)
+        .await
+        .unwrap();
+
+    assert_eq!(revision.version, 3);
+    let live = Course::find_by_id(course.id.clone())
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(live.title, "Algebra");
+    let chapter = Chapter::find_by_id(chapter_id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(chapter.title, "Intro");
+    assert_eq!(CourseDraft::find().count(&db).await.unwrap(), 0);
+    let changes = Revisions::diff(&db, TEACHER.into(), course.id, 1, Some(3))
+        .await
+        .unwrap();
+    assert!(changes.is_empty());
+}
+
+#[tokio::test]
+async fn revisions_are_owner_only() {
+    let db = setup().await;
+    let (course, _) = live_course(&db).await;
+
+    let history = Revisions::history(&db, OTHER_TEACHER.into(), course.id.clone()).await;
+    let rollback = Revisions::rollback(&db, OTHER_TEACHER.into(), course.id.clone(), 1).await;
+    let discard = Revisions::discard_draft(&db, OTHER_TEACHER.into(), course.id).await;
+
+    assert!(matches!(history, Err(DbErr::RecordNotFound(_))));
+    assert!(matches!(rollback, Err(DbErr::RecordNotFound(_))));
+    assert!(matches!(discard, Err(DbErr::RecordNotFound(_))));
+}
@@ -0,0 +1,278 @@
+mod common;
+
+use chrono::Duration;
+use common::*;
+use entities::prelude::*;
+use serde_json::json;
+use service::{
+    sea_orm::*, ChapterSchedule, Chapters, CourseSchedule, Courses, Schedule, UpdateError,
+};
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+fn invalid_fields(res: Result<impl std::fmt::Debug, UpdateError>) -> Vec<String> {
+    match res {
+        Err(UpdateError::Invalid(fields)) => fields.into_iter().map(|f| f.field).collect(),
+        other => panic!("expected validation errors, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn set_course_rejects_inverted_times() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+
+    let res = Schedule::set_course(
+        &db,
+        TEACHER.into(),
+        course.id,
+        CourseSchedule {
+            publish_at: Some(now() + Duration::days(2)),
+            unpublish_at: Some(now() + Duration::days(1)),
+        },
+    )
+    .await;
+
+    assert_eq!(invalid_fields(res), vec!["unpublishAt"]);
+}
+
+#[tokio::test]
+async fn set_chapter_validates_and_checks_ownership() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 1).await;
+
+    let invalid = Schedule::set_chapter(
+        &db,
+        TEACHER.into(),
+        course.id.clone(),
+        chapters[0].id.clone(),
+        ChapterSchedule {
+            publish_at: Some(now()),
+            drip_days: Some(-1),
+            ..Default::default()
+        },
+    )
+    .await;
+    let foreign = Schedule::set_chapter(
+        &db,
+        OTHER_TEACHER.into(),
+        course.id,
+        chapters[0].id.clone(),
+        ChapterSchedule::default(),
+    )
+    .await;
+
+    assert_eq!(invalid_fields(invalid), vec!["publishAt", "dripDays"]);
+    assert!(matches!(
+        foreign,
+        Err(UpdateError::Db(DbErr::RecordNotFound(_)))
+    ));
+}
+
+#[tokio::test]
+async fn apply_due_publishes_chapters_then_course() {
+    let db = setup().await;
+    let category = category(&db, "Math").await;
+    let course = CourseBuilder::new(TEACHER)
+        .description("Description")
+        .image_url("https://example.com/cover.png")
+        .category(&category.id)
+        .insert(&db)
+        .await;
+    let chapter = ChapterBuilder::new(&course.id, 1)
+        .description("Description")
+        .video("video")
+        .insert(&db)
+        .await;
+    let later = ChapterBuilder::new(&course.id, 2)
+        .description("Description")
+        .video("video")
+        .insert(&db)
+        .await;
+    let due = CourseSchedule {
+        publish_at: Some(now() - Duration::minutes(1)),
+        unpublish_at: None,
+    };
+    Schedule::set_course(&db, TEACHER.into(), course.id.clone(), due.clone())
+        .await
+        .unwrap();
+    Schedule::set_chapter(
+        &db,
+        TEACHER.into(),
+        course.id.clone(),
+        chapter.id.clone(),
+        ChapterSchedule {
+            publish_at: due.publish_at,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    Schedule::set_chapter(
+        &db,
+        TEACHER.into(),
+        course.id.clone(),
+        later.id.clone(),
+        ChapterSchedule {
+            publish_at: Some(now() + Duration::days(1)),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let applied = Schedule::apply_due(&db, now()).await.unwrap();
+
+    assert_eq!(applied, 2);
+    let course = Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(course.is_published);
+    assert_eq!(course.publish_at, None);
+    let chapter = Chapter::find_by_id(chapter.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(chapter.is_published);
+    let later = Chapter::find_by_id(later.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!later.is_published);
+    assert!(later.publish_at.is_some());
+    assert_eq!(CourseRevision::find().count(&db).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn apply_due_keeps_incomplete_course_scheduled() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let publish_at = now() - Duration::minutes(1);
+    Schedule::set_course(
+        &db,
+        TEACHER.into(),
+        course.id.clone(),
+        CourseSchedule {
+            publish_at: Some(publish_at),
+            unpublish_at: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let applied = Schedule::apply_due(&db, now()).await.unwrap();
+
+    assert_eq!(applied, 0);
+    let course = Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!course.is_published);
+    assert_eq!(course.publish_at, Some(publish_at));
+}
+
+#[tokio::test]
+async fn apply_due_unpublishes_course() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    Schedule::set_course(
+        &db,
+        TEACHER.into(),
+        course.id.clone(),
+        CourseSchedule {
+            publish_at: None,
+            unpublish_at: Some(now() + Duration::hours(1)),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(Schedule::apply_due(&db, now()).await.unwrap(), 0);
+    let applied = Schedule::apply_due(&db, now() + Duration::hours(2))
+        .await
+        .unwrap();
+
+    assert_eq!(applied, 1);
+    let course = Course::find_by_id(course.id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!course.is_published);
+    assert_eq!(course.unpublish_at, None);
+}
+
+#[tokio::test]
+async fn get_withholds_dripped_chapter_until_release() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    let dripped = ChapterBuilder::new(&course.id, 2)
+        .description("Description")
+        .video("video")
+        .published()
+        .drip_days(7)
+        .insert(&db)
+        .await;
+    let purchased_at = now() - Duration::days(2);
+    purchase_at(&db, STUDENT, &course.id, purchased_at).await;
+    purchase_at(&db, OTHER_STUDENT, &course.id, now() - Duration::days(8)).await;
+
+    let locked = Chapters::get(&db, STUDENT.into(), course.id.clone(), dripped.id.clone())
+        .await
+        .unwrap();
+    let released = Chapters::get(&db, OTHER_STUDENT.into(), course.id, dripped.id)
+        .await
+        .unwrap();
+
+    let locked = serde_json::to_value(locked).unwrap();
+    assert_eq!(
+        locked["availableAt"],
+        json!(purchased_at + Duration::days(7))
+    );
+    assert_eq!(locked["chapter"]["videoId"], json!(null));
+    let released = serde_json::to_value(released).unwrap();
+    assert_eq!(released["availableAt"], json!(null));
+    assert_eq!(released["chapter"]["videoId"], json!("video"));
+}
+
+#[tokio::test]
+async fn course_outline_marks_dripped_chapters() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 10, 1).await;
+    ChapterBuilder::new(&course.id, 2)
+        .description("Description")
+        .video("video")
+        .published()
+        .drip_days(3)
+        .insert(&db)
+        .await;
+    ChapterBuilder::new(&course.id, 3)
+        .description("Description")
+        .video("video")
+        .published()
+        .free()
+        .drip_days(3)
+        .insert(&db)
+        .await;
+    purchase(&db, STUDENT, &course.id).await;
+
+    let res = Courses::get_with_chapters_with_progress(&db, STUDENT.into(), course.id)
+        .await
+        .unwrap();
+
+    let res = serde_json::to_value(res).unwrap();
+    let chapters = res["chapters"].as_array().unwrap();
+    assert_eq!(chapters[0]["availableAt"], json!(null));
+    assert!(chapters[1]["availableAt"].is_string());
+    assert_eq!(chapters[1]["videoId"], json!(null));
+    assert_eq!(chapters[2]["availableAt"], json!(null));
+    assert_eq!(chapters[2]["videoId"], json!("video"));
+}
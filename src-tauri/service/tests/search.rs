@@ -0,0 +1,287 @@
+mod common;
+
+use common::*;
+use entities::prelude::*;
+use serde_json::{json, Value};
+use service::{
+    sea_orm::*, ChapterUpdate, Chapters, OtherRoutes, PriceRange, Revisions, SearchQuery,
+};
+
+async fn search(db: &DatabaseConnection, query: SearchQuery) -> Value {
+    let res = OtherRoutes::search(db, STUDENT.into(), query)
+        .await
+        .unwrap();
+    serde_json::to_value(res).unwrap()
+}
+
+fn text(text: &str) -> SearchQuery {
+    SearchQuery {
+        text: Some(text.into()),
+        ..Default::default()
+    }
+}
+
+fn titles(res: &Value) -> Vec<&str> {
+    res["courses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|course| course["title"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn matching_ignores_case_and_vietnamese_accents() {
+    let db = setup().await;
+    published_course(&db, "Lập trình Rust cơ bản", 0, 1).await;
+    published_course(&db, "Đại số tuyến tính", 0, 1).await;
+
+    assert_eq!(
+        titles(&search(&db, text("lap TRINH")).await),
+        vec!["Lập trình Rust cơ bản"]
+    );
+    assert_eq!(
+        titles(&search(&db, text("dai so")).await),
+        vec!["Đại số tuyến tính"]
+    );
+}
+
+#[tokio::test]
+async fn matching_tolerates_prefixes_and_typos() {
+    let db = setup().await;
+    published_course(&db, "Programming in Rust", 0, 1).await;
+    published_course(&db, "Photography", 0, 1).await;
+
+    assert_eq!(
+        titles(&search(&db, text("progr")).await),
+        vec!["Programming in Rust"]
+    );
+    assert_eq!(
+        titles(&search(&db, text("programing")).await),
+        vec!["Programming in Rust"]
+    );
+    assert_eq!(
+        titles(&search(&db, text("photgoraphy")).await),
+        vec!["Photography"]
+    );
+    // Short words have to be typed right.
+    assert!(titles(&search(&db, text("rst")).await).is_empty());
+}
+
+#[tokio::test]
+async fn every_word_has_to_match_somewhere() {
+    let db = setup().await;
+    published_course(&db, "Rust for beginners", 0, 1).await;
+    published_course(&db, "Python for beginners", 0, 1).await;
+
+    assert_eq!(
+        titles(&search(&db, text("beginners rust")).await),
+        vec!["Rust for beginners"]
+    );
+}
+
+#[tokio::test]
+async fn matches_descriptions_chapters_and_categories() {
+    let db = setup().await;
+    let music = category(&db, "Music").await;
+    CourseBuilder::new(TEACHER)
+        .title("Guitar")
+        .category(&music.id)
+        .published()
+        .insert(&db)
+        .await;
+    CourseBuilder::new(TEACHER)
+        .title("Databases")
+        .description("Learn about indexing and transactions")
+        .published()
+        .insert(&db)
+        .await;
+    let (course, _) = published_course(&db, "Frontend", 0, 0).await;
+    ChapterBuilder::new(&course.id, 1)
+        .title("Hooks in React")
+        .published()
+        .insert(&db)
+        .await;
+    ChapterBuilder::new(&course.id, 2)
+        .title("Unpublished Svelte chapter")
+        .insert(&db)
+        .await;
+
+    assert_eq!(titles(&search(&db, text("music")).await), vec!["Guitar"]);
+    assert_eq!(
+        titles(&search(&db, text("transactions")).await),
+        vec!["Databases"]
+    );
+    assert_eq!(titles(&search(&db, text("react")).await), vec!["Frontend"]);
+    assert!(titles(&search(&db, text("svelte")).await).is_empty());
+}
+
+#[tokio::test]
+async fn title_matches_rank_above_other_fields() {
+    let db = setup().await;
+    CourseBuilder::new(TEACHER)
+        .title("Web design")
+        .description("Everything about Rust")
+        .published()
+        .insert(&db)
+        .await;
+    CourseBuilder::new(TEACHER)
+        .title("Rust")
+        .published()
+        .insert(&db)
+        .await;
+    CourseBuilder::new(TEACHER)
+        .title("Rustic furniture")
+        .published()
+        .insert(&db)
+        .await;
+
+    assert_eq!(
+        titles(&search(&db, text("rust")).await),
+        vec!["Rust", "Rustic furniture", "Web design"]
+    );
+}
+
+#[tokio::test]
+async fn results_are_paginated() {
+    let db = setup().await;
+    for title in ["One", "Two", "Three", "Four", "Five"] {
+        published_course(&db, title, 0, 1).await;
+    }
+
+    let first = search(
+        &db,
+        SearchQuery {
+            per_page: Some(2),
+            ..Default::default()
+        },
+    )
+    .await;
+    let last = search(
+        &db,
+        SearchQuery {
+            page: Some(3),
+            per_page: Some(2),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(first["total"], json!(5));
+    assert_eq!(first["page"], json!(1));
+    assert_eq!(first["perPage"], json!(2));
+    assert_eq!(first["courses"].as_array().unwrap().len(), 2);
+    assert_eq!(last["page"], json!(3));
+    assert_eq!(last["courses"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn facets_count_categories_and_price_ranges() {
+    let db = setup().await;
+    let math = category(&db, "Math").await;
+    let music = category(&db, "Music").await;
+    for (category_id, price) in [(&math.id, 0), (&math.id, 300_000), (&music.id, 300_000)] {
+        CourseBuilder::new(TEACHER)
+            .title("Course")
+            .category(category_id)
+            .price(price)
+            .published()
+            .insert(&db)
+            .await;
+    }
+
+    let res = search(
+        &db,
+        SearchQuery {
+            category_id: Some(math.id.clone()),
+            price: Some(PriceRange::Under500k),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(res["total"], json!(1));
+    // Category counts respect the price filter but not the category one.
+    assert_eq!(
+        res["facets"]["categories"],
+        json!([
+            { "id": math.id, "name": "Math", "count": 1 },
+            { "id": music.id, "name": "Music", "count": 1 },
+        ])
+    );
+    // Price counts respect the category filter but not the price one.
+    let prices: Vec<(&Value, &Value)> = res["facets"]["prices"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|facet| (&facet["range"], &facet["count"]))
+        .collect();
+    assert_eq!(
+        prices,
+        vec![
+            (&json!("free"), &json!(1)),
+            (&json!("under200k"), &json!(0)),
+            (&json!("under500k"), &json!(1)),
+            (&json!("under1m"), &json!(0)),
+            (&json!("over1m"), &json!(0)),
+        ]
+    );
+
+    // Ranked results are counted the same way.
+    let matched = search(
+        &db,
+        SearchQuery {
+            text: Some("course".into()),
+            category_id: Some(math.id.clone()),
+            price: Some(PriceRange::Under500k),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(matched["total"], res["total"]);
+    assert_eq!(matched["facets"], res["facets"]);
+}
+
+#[tokio::test]
+async fn index_follows_published_edits() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 0, 1).await;
+    assert!(titles(&search(&db, text("ownership")).await).is_empty());
+
+    Chapters::update(
+        &db,
+        &cloudinary(),
+        TEACHER.into(),
+        course.id.clone(),
+        chapters[0].id.clone(),
+        ChapterUpdate {
+            title: Some("Ownership".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    // Drafts are not searchable until they are published.
+    assert!(titles(&search(&db, text("ownership")).await).is_empty());
+
+    Revisions::publish_draft(&db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(titles(&search(&db, text("ownership")).await), vec!["Rust"]);
+}
+
+#[tokio::test]
+async fn update_search_index_catches_up_on_unindexed_courses() {
+    let db = setup().await;
+    let (course, _) = published_course(&db, "Rust", 0, 1).await;
+    CourseSearchWord::delete_many().exec(&db).await.unwrap();
+    assert!(titles(&search(&db, text("rust")).await).is_empty());
+
+    let indexed = OtherRoutes::update_search_index(&db).await.unwrap();
+
+    assert_eq!(indexed, 1);
+    assert_eq!(
+        titles(&search(&db, text("rust")).await),
+        vec![course.title.as_str()]
+    );
+}
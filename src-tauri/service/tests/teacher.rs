@@ -0,0 +1,254 @@
+mod common;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use common::*;
+use entities::{
+    purchase,
+    sea_orm_active_enums::{CouponKind, PurchaseStatus},
+};
+use serde_json::json;
+use service::{
+    sea_orm::*, AnalyticsInterval, AnalyticsRange, Coupons, NewCoupon, OtherRoutes, Teacher,
+};
+
+fn day(month: u32, day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, month, day)
+        .unwrap()
+        .and_hms_opt(10, 0, 0)
+        .unwrap()
+}
+
+async fn paid(
+    db: &DatabaseConnection,
+    user_id: &str,
+    course_id: &str,
+    amount: i32,
+    at: NaiveDateTime,
+) -> purchase::Model {
+    let mut purchase: purchase::ActiveModel = purchase_at(db, user_id, course_id, at).await.into();
+    purchase.amount = Set(amount);
+    purchase.update(db).await.unwrap()
+}
+
+#[tokio::test]
+async fn course_returns_sorted_chapters_and_attachments() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let second = ChapterBuilder::new(&course.id, 2).insert(&db).await;
+    let first = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+    attachment(&db, &course.id, "https://example.com/notes.pdf").await;
+
+    let res = Teacher::course(&db, TEACHER.into(), course.id.clone())
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    assert_eq!(res["id"], json!(course.id));
+    assert_eq!(res["chapters"][0]["id"], json!(first.id));
+    assert_eq!(res["chapters"][1]["id"], json!(second.id));
+    assert_eq!(res["attachments"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn course_rejects_non_owner() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+
+    let res = Teacher::course(&db, OTHER_TEACHER.into(), course.id).await;
+
+    assert!(matches!(res, Err(DbErr::RecordNotFound(_))));
+}
+
+#[tokio::test]
+async fn chapter_must_belong_to_course() {
+    let db = setup().await;
+    let course = CourseBuilder::new(TEACHER).insert(&db).await;
+    let other = CourseBuilder::new(TEACHER).insert(&db).await;
+    let chapter = ChapterBuilder::new(&course.id, 1).insert(&db).await;
+
+    let found = Teacher::chapter(&db, course.id, chapter.id.clone())
+        .await
+        .unwrap();
+    let foreign = Teacher::chapter(&db, other.id, chapter.id.clone()).await;
+
+    assert_eq!(found, chapter);
+    assert!(matches!(foreign, Err(DbErr::RecordNotFound(_))));
+}
+
+#[tokio::test]
+async fn analytics_without_sales_is_empty() {
+    let db = setup().await;
+    published_course(&db, "Rust", 10, 1).await;
+
+    let res = Teacher::analytics(&db, TEACHER.into(), AnalyticsRange::default())
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    assert_eq!(
+        res,
+        json!({
+            "data": [],
+            "totalRevenue": 0,
+            "totalSales": 0,
+            "totalRefunds": 0,
+            "refundedAmount": 0,
+            "activeLearners": 0,
+            "series": []
+        })
+    );
+}
+
+#[tokio::test]
+async fn analytics_reports_net_revenue_of_own_courses() {
+    let db = setup().await;
+    let stripe = stripe::MockStripe::start().await;
+    let (course, _) = published_course(&db, "Rust", 200000, 1).await;
+    let foreign = CourseBuilder::new(OTHER_TEACHER)
+        .title("Go")
+        .price(100000)
+        .published()
+        .insert(&db)
+        .await;
+    purchase(&db, STUDENT, &foreign.id).await;
+    Coupons::create(
+        &db,
+        TEACHER.into(),
+        NewCoupon {
+            code: "FREE".into(),
+            course_id: None,
+            kind: CouponKind::Percent,
+            value: 100,
+            max_redemptions: None,
+            expires_at: None,
+        },
+    )
+    .await
+    .unwrap();
+    OtherRoutes::checkout(
+        &db,
+        &stripe.config(),
+        STUDENT.into(),
+        course.id.clone(),
+        Some("FREE".into()),
+    )
+    .await
+    .unwrap();
+    let mut paid: purchase::ActiveModel = purchase(&db, OTHER_STUDENT, &course.id).await.into();
+    paid.amount = Set(200000);
+    paid.update(&db).await.unwrap();
+
+    let res = Teacher::analytics(&db, TEACHER.into(), AnalyticsRange::default())
+        .await
+        .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    assert_eq!(
+        res["data"],
+        json!([{ "name": "Rust", "total": 200000, "sales": 2 }])
+    );
+    assert_eq!(res["totalRevenue"], json!(200000));
+    assert_eq!(res["totalSales"], json!(2));
+}
+
+#[tokio::test]
+async fn analytics_groups_sales_and_refunds_by_period() {
+    let db = setup().await;
+    let (rust, _) = published_course(&db, "Rust", 100000, 1).await;
+    let (go, _) = published_course(&db, "Go", 50000, 1).await;
+    // Monday and Sunday of the same week, then the next Monday.
+    paid(&db, STUDENT, &rust.id, 100000, day(12, 2)).await;
+    paid(&db, STUDENT, &go.id, 50000, day(12, 8)).await;
+    let refunded = paid(&db, OTHER_STUDENT, &rust.id, 100000, day(12, 9)).await;
+    let mut refunded: purchase::ActiveModel = refunded.into();
+    refunded.status = Set(PurchaseStatus::Refunded);
+    refunded.refunded_at = Set(Some(day(12, 10)));
+    refunded.update(&db).await.unwrap();
+    // Outside the range.
+    paid(&db, OTHER_STUDENT, &go.id, 50000, day(11, 30)).await;
+
+    let res = Teacher::analytics(
+        &db,
+        TEACHER.into(),
+        AnalyticsRange {
+            from: Some(day(12, 1)),
+            to: Some(day(12, 31)),
+            interval: AnalyticsInterval::Week,
+        },
+    )
+    .await
+    .unwrap();
+    let res = serde_json::to_value(res).unwrap();
+
+    assert_eq!(
+        res["series"],
+        json!([
+            { "period": "2024-12-02", "revenue": 150000, "sales": 2, "refunds": 0 },
+            { "period": "2024-12-09", "revenue": 0, "sales": 1, "refunds": 1 },
+        ])
+    );
+    assert_eq!(
+        res["data"],
+        json!([
+            { "name": "Go", "total": 50000, "sales": 1 },
+            { "name": "Rust", "total": 100000, "sales": 2 },
+        ])
+    );
+    assert_eq!(res["totalRevenue"], json!(150000));
+    assert_eq!(res["totalSales"], json!(3));
+    assert_eq!(res["totalRefunds"], json!(1));
+    assert_eq!(res["refundedAmount"], json!(100000));
+}
+
+#[tokio::test]
+async fn analytics_counts_learners_active_in_range() {
+    let db = setup().await;
+    let (course, chapters) = published_course(&db, "Rust", 10, 2).await;
+    let foreign = CourseBuilder::new(OTHER_TEACHER).insert(&db).await;
+    let foreign_chapter = ChapterBuilder::new(&foreign.id, 1).insert(&db).await;
+    purchase(&db, STUDENT, &course.id).await;
+    progress(&db, STUDENT, &chapters[0].id, true).await;
+    progress(&db, STUDENT, &chapters[1].id, false).await;
+    progress(&db, OTHER_STUDENT, &foreign_chapter.id, true).await;
+
+    let now = Teacher::analytics(&db, TEACHER.into(), AnalyticsRange::default())
+        .await
+        .unwrap();
+    let later = Teacher::analytics(
+        &db,
+        TEACHER.into(),
+        AnalyticsRange {
+            from: Some(chrono::Utc::now().naive_utc() + Duration::days(1)),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        serde_json::to_value(now).unwrap()["activeLearners"],
+        json!(1)
+    );
+    assert_eq!(
+        serde_json::to_value(later).unwrap()["activeLearners"],
+        json!(0)
+    );
+}
+
+#[tokio::test]
+async fn analytics_rejects_inverted_range() {
+    let db = setup().await;
+
+    let res = Teacher::analytics(
+        &db,
+        TEACHER.into(),
+        AnalyticsRange {
+            from: Some(day(12, 31)),
+            to: Some(day(12, 1)),
+            interval: AnalyticsInterval::Month,
+        },
+    )
+    .await;
+
+    assert!(matches!(res, Err(DbErr::Custom(_))));
+}
@@ -1,23 +1,199 @@
 use std::sync::Arc;
 
-use entities::{attachment, category, chapter, course, purchase};
+use entities::{
+    assignment, attachment, category, certificate, chapter, comment, coupon, course,
+    course_revision, deck, flashcard, note, product, purchase, submission, user_progress,
+};
 use sea_orm::DbErr;
-use serde_json::Value;
+use serde::Serialize;
 use service::{
-    ChapterDetails, Chapters, CourseWithChapters, CourseWithChaptersAndProgress, Courses,
-    DashboardCourses, OtherRoutes, ReorderData, SearchCourseWithProgressWithCategory, Teacher,
-    TeacherAnalytics, TeacherCourse,
+    AnalyticsRange, AssignmentWithSubmission, Assignments, CardUpdate, CertificateSettings,
+    Certificates, ChapterDetails, ChapterSchedule, ChapterThreads, ChapterUpdate, Chapters,
+    CheckoutSession, Coupons, CourseEngagement, CourseSchedule, CourseUpdate, CourseWithChapters,
+    CourseWithChaptersAndProgress, Courses, DashboardCourses, DeckSummary, Discussions,
+    Entitlement, FieldError, Flashcards, Grade, NewAssignment, NewCard, NewComment, NewCoupon,
+    NewNote, NewProduct, NewSubmission, NoteUpdate, NoteWithSource, Notes, OtherRoutes,
+    ProductCheckout, ProductWithCourses, Products, ProgressUpdate, Purchases, ReorderData,
+    ReviewStats, RevisionChange, RevisionSummary, Revisions, Schedule, SearchQuery, SearchResults,
+    StripeConfig, SubmissionToGrade, Teacher, TeacherAnalytics, TeacherCourse, Thread,
+    UnreadComments, UpdateError, Verification, WatchHeartbeat,
+};
+use tauri::Manager;
+use tracing::instrument;
+
+use crate::{
+    config::{ConfigProblem, Settings},
+    database::{self, DbHealth},
+    diagnostics,
+    keystore::{self, Secrets},
+    logging,
+    startup::{self, Stage, Startup, StartupStatus},
+    AppState,
 };
 
-use crate::AppState;
+// Error returned by the update commands. `message` is always displayable on
+// its own; `fields` lists the individual validation failures, if any.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFailure {
+    message: String,
+    fields: Vec<FieldError>,
+}
+
+// Payments only work once Stripe is configured.
+async fn stripe(state: &AppState) -> Result<StripeConfig, String> {
+    state
+        .config
+        .read()
+        .await
+        .stripe()
+        .ok_or("Payments are not configured".into())
+}
+
+// Certificates are only issued once the public key is configured.
+async fn certificates(state: &AppState) -> Result<CertificateSettings, String> {
+    state
+        .config
+        .read()
+        .await
+        .certificates()
+        .ok_or("Certificates are not configured".into())
+}
+
+fn update_failure(err: UpdateError, fallback: &str) -> UpdateFailure {
+    let message = match &err {
+        UpdateError::Invalid(fields) => fields
+            .iter()
+            .map(|field| field.message.clone())
+            .collect::<Vec<String>>()
+            .join(", "),
+        UpdateError::Db(DbErr::RecordNotFound(err)) => err.clone(),
+        UpdateError::Db(DbErr::Custom(err)) => err.clone(),
+        UpdateError::Db(_) => fallback.to_string(),
+    };
+    let fields = match err {
+        UpdateError::Invalid(fields) => fields,
+        UpdateError::Db(_) => vec![],
+    };
+
+    UpdateFailure { message, fields }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn db_health(state: tauri::State<'_, Arc<AppState>>) -> Result<DbHealth, String> {
+    Ok(database::health(&state.conn, &state.db_config).await)
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_settings(state: tauri::State<'_, Arc<AppState>>) -> Result<Settings, String> {
+    Ok(state.config.read().await.settings())
+}
+
+// Pool settings and the number of log files take effect the next time the app
+// starts; everything else right away.
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn update_settings(
+    state: tauri::State<'_, Arc<AppState>>,
+    settings: Settings,
+) -> Result<Settings, Vec<ConfigProblem>> {
+    let config = state.config.read().await.update(settings)?;
+    let settings = config.settings();
+    if let Err(err) = logging::set_level(&config.log.level) {
+        tracing::warn!("Cannot change the log level: {}", err);
+    }
+    *state.config.write().await = config;
+    Ok(settings)
+}
+
+#[tauri::command]
+#[instrument(skip(startup), err(Debug))]
+pub async fn get_startup_status(
+    startup: tauri::State<'_, Startup>,
+) -> Result<StartupStatus, String> {
+    Ok(startup.status.lock().await.clone())
+}
+
+#[tauri::command]
+#[instrument(skip_all, err(Debug))]
+pub async fn unlock_keystore(
+    app: tauri::AppHandle,
+    startup: tauri::State<'_, Startup>,
+    passphrase: String,
+) -> Result<StartupStatus, Vec<ConfigProblem>> {
+    let mut status = startup.status.lock().await;
+    if status.stage != Stage::Locked {
+        return Ok(status.clone());
+    }
+
+    let secrets = keystore::open(&passphrase).map_err(|problem| vec![problem])?;
+    let state = open_with(&startup, &secrets).await?;
+    *status = serve(&app, &startup, state).await;
+    Ok(status.clone())
+}
+
+// The keystore is only written once its secrets have opened the database.
+#[tauri::command]
+#[instrument(skip_all, err(Debug))]
+pub async fn create_keystore(
+    app: tauri::AppHandle,
+    startup: tauri::State<'_, Startup>,
+    passphrase: String,
+    secrets: Secrets,
+) -> Result<StartupStatus, Vec<ConfigProblem>> {
+    let mut status = startup.status.lock().await;
+    if status.stage != Stage::NoKeystore {
+        return Ok(status.clone());
+    }
+
+    keystore::check_passphrase(&passphrase).map_err(|problem| vec![problem])?;
+    let state = open_with(&startup, &secrets).await?;
+    keystore::create(&passphrase, &secrets).map_err(|problem| vec![problem])?;
+    *status = serve(&app, &startup, state).await;
+    Ok(status.clone())
+}
+
+// Works before the app has started too, which is when support needs it most.
+#[tauri::command]
+#[instrument(skip_all, err(Debug))]
+pub async fn export_diagnostics(
+    app: tauri::AppHandle,
+    startup: tauri::State<'_, Startup>,
+) -> Result<String, String> {
+    let settings = match app.try_state::<Arc<AppState>>() {
+        Some(state) => Some(state.config.read().await.settings()),
+        None => None,
+    };
+    let status = startup.status.lock().await.clone();
+    let path = diagnostics::export(&status, settings)?;
+    tracing::info!("Exported diagnostics to {}", path.display());
+    Ok(path.display().to_string())
+}
+
+async fn open_with(
+    startup: &Startup,
+    secrets: &Secrets,
+) -> Result<Arc<AppState>, Vec<ConfigProblem>> {
+    let config = startup.pending.lock().await.clone().unwrap_or_default();
+    startup::open(config.with_secrets(secrets)?).await
+}
+
+async fn serve(app: &tauri::AppHandle, startup: &Startup, state: Arc<AppState>) -> StartupStatus {
+    startup::serve(app, state).await;
+    *startup.pending.lock().await = None;
+    StartupStatus::new(Stage::Ready, Vec::new())
+}
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_dashboard_courses(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
 ) -> Result<DashboardCourses, String> {
-    let db = state.conn.lock().await;
-    if let Ok(res) = OtherRoutes::dashboard_courses(&db, user_id).await {
+    let db = &state.conn;
+    if let Ok(res) = OtherRoutes::dashboard_courses(db, user_id).await {
         Ok(res)
     } else {
         Err("Cannot get dashboard courses".into())
@@ -25,13 +201,14 @@ pub async fn get_dashboard_courses(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_purchase(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
 ) -> Result<Option<purchase::Model>, String> {
-    let db = state.conn.lock().await;
-    if let Ok(res) = OtherRoutes::purchase(&db, user_id, course_id).await {
+    let db = &state.conn;
+    if let Ok(res) = OtherRoutes::purchase(db, user_id, course_id).await {
         Ok(res)
     } else {
         Err("Cannot get course purchase".into())
@@ -39,11 +216,27 @@ pub async fn get_purchase(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_entitlement(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<Option<Entitlement>, String> {
+    let db = &state.conn;
+    if let Ok(res) = OtherRoutes::entitlement(db, user_id, course_id).await {
+        Ok(res)
+    } else {
+        Err("Cannot get course access".into())
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_categories(
     state: tauri::State<'_, Arc<AppState>>,
 ) -> Result<Vec<category::Model>, String> {
-    let db = state.conn.lock().await;
-    if let Ok(res) = OtherRoutes::categories(&db).await {
+    let db = &state.conn;
+    if let Ok(res) = OtherRoutes::categories(db).await {
         Ok(res)
     } else {
         Err("Cannot get categories".into())
@@ -51,14 +244,14 @@ pub async fn get_categories(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_search(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
-    title: Option<String>,
-    category_id: Option<String>,
-) -> Result<Vec<SearchCourseWithProgressWithCategory>, String> {
-    let db = state.conn.lock().await;
-    if let Ok(res) = OtherRoutes::search(&db, user_id, title, category_id).await {
+    query: Option<SearchQuery>,
+) -> Result<SearchResults, String> {
+    let db = &state.conn;
+    if let Ok(res) = OtherRoutes::search(db, user_id, query.unwrap_or_default()).await {
         Ok(res)
     } else {
         Err("Cannot search".into())
@@ -66,27 +259,211 @@ pub async fn get_search(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn course_checkout(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
+    coupon_code: Option<String>,
+) -> Result<CheckoutSession, String> {
+    let db = &state.conn;
+    let stripe = stripe(&state).await?;
+    match OtherRoutes::checkout(db, &stripe, user_id, course_id, coupon_code).await {
+        Ok(session) => Ok(session),
+        Err(DbErr::Custom(err)) => Err(err),
+        _ => Err("Cannot purchase course".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn create_coupon(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    coupon: NewCoupon,
+) -> Result<coupon::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Coupons::create(db, user_id, coupon).await {
+        Ok(coupon) => Ok(coupon),
+        Err(err) => Err(update_failure(err, "Cannot create coupon")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn list_coupons(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<coupon::Model>, String> {
+    let db = &state.conn;
+    if let Ok(res) = Coupons::list(db, user_id).await {
+        Ok(res)
+    } else {
+        Err("Cannot get coupons".into())
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn delete_coupon(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    coupon_id: String,
+) -> Result<(), String> {
+    let db = &state.conn;
+    match Coupons::delete(db, user_id, coupon_id).await {
+        Ok(()) => Ok(()),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot delete coupon".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn create_product(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    product: NewProduct,
+) -> Result<ProductWithCourses, UpdateFailure> {
+    let db = &state.conn;
+    match Products::create(db, user_id, product).await {
+        Ok(product) => Ok(product),
+        Err(err) => Err(update_failure(err, "Cannot create product")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn list_products(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<ProductWithCourses>, String> {
+    let db = &state.conn;
+    if let Ok(res) = Products::list(db, user_id).await {
+        Ok(res)
+    } else {
+        Err("Cannot get products".into())
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_course_products(
+    state: tauri::State<'_, Arc<AppState>>,
+    course_id: String,
+) -> Result<Vec<product::Model>, String> {
+    let db = &state.conn;
+    match Products::for_course(db, course_id).await {
+        Ok(res) => Ok(res),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get products".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn set_product_published(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    product_id: String,
+    is_published: bool,
+) -> Result<product::Model, String> {
+    let db = &state.conn;
+    match Products::set_published(db, user_id, product_id, is_published).await {
+        Ok(product) => Ok(product),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot update product".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn delete_product(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    product_id: String,
+) -> Result<(), String> {
+    let db = &state.conn;
+    match Products::delete(db, user_id, product_id).await {
+        Ok(()) => Ok(()),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot delete product".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn product_checkout(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    product_id: String,
+) -> Result<ProductCheckout, String> {
+    let db = &state.conn;
+    let stripe = stripe(&state).await?;
+    match Products::checkout(db, &stripe, user_id, product_id).await {
+        Ok(checkout) => Ok(checkout),
+        Err(DbErr::Custom(err)) => Err(err),
+        _ => Err("Cannot purchase product".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_course_purchases(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<Vec<purchase::Model>, String> {
+    let db = &state.conn;
+    match Purchases::list(db, user_id, course_id).await {
+        Ok(purchases) => Ok(purchases),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get course purchases".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn refund_purchase(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    purchase_id: String,
 ) -> Result<purchase::Model, String> {
-    let db = state.conn.lock().await;
-    match OtherRoutes::checkout(&db, user_id, course_id).await {
+    let db = &state.conn;
+    let stripe = stripe(&state).await?;
+    match Purchases::refund(db, &stripe, user_id, purchase_id).await {
         Ok(purchase) => Ok(purchase),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
         Err(DbErr::Custom(err)) => Err(err),
-        _ => Err("Cannot purchase course".into()),
+        _ => Err("Cannot refund purchase".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn revoke_purchase(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    purchase_id: String,
+) -> Result<purchase::Model, String> {
+    let db = &state.conn;
+    match Purchases::revoke(db, user_id, purchase_id).await {
+        Ok(purchase) => Ok(purchase),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        Err(DbErr::Custom(err)) => Err(err),
+        _ => Err("Cannot revoke purchase".into()),
     }
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn create_course(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     title: String,
 ) -> Result<course::Model, String> {
-    let db = state.conn.lock().await;
-    if let Ok(res) = Courses::create(&db, user_id, title).await {
+    let db = &state.conn;
+    if let Ok(res) = Courses::create(db, user_id, title).await {
         Ok(res)
     } else {
         Err("Cannot create course".into())
@@ -94,26 +471,31 @@ pub async fn create_course(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn update_course(
     state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
     course_id: String,
-    updates: std::collections::HashMap<String, Value>,
-) -> Result<(), String> {
-    let db = state.conn.lock().await;
-    if let Ok(_) = Courses::update(&db, course_id, updates).await {
-        Ok(())
-    } else {
-        Err("Cannot update course".into())
+    updates: serde_json::Value,
+) -> Result<(), UpdateFailure> {
+    let db = &state.conn;
+    let cloudinary = state.config.read().await.cloudinary();
+    let updates = CourseUpdate::from_json(updates)
+        .map_err(|err| update_failure(err, "Cannot update course"))?;
+    match Courses::update(db, &cloudinary, user_id, course_id, updates).await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(update_failure(err, "Cannot update course")),
     }
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn list_courses(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
 ) -> Result<Vec<course::Model>, String> {
-    let db = state.conn.lock().await;
-    if let Ok(courses) = Courses::list(&db, user_id).await {
+    let db = &state.conn;
+    if let Ok(courses) = Courses::list(db, user_id).await {
         Ok(courses)
     } else {
         Err("Cannot list course".into())
@@ -121,14 +503,15 @@ pub async fn list_courses(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn add_attachment(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
     url: String,
 ) -> Result<Vec<attachment::Model>, String> {
-    let db = state.conn.lock().await;
-    if let Ok(res) = Courses::add_attachment(&db, user_id, course_id, url).await {
+    let db = &state.conn;
+    if let Ok(res) = Courses::add_attachment(db, user_id, course_id, url).await {
         Ok(res)
     } else {
         Err("Cannot add attachment".into())
@@ -136,14 +519,18 @@ pub async fn add_attachment(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn remove_attachment(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     attachment_id: String,
     course_id: String,
 ) -> Result<Vec<attachment::Model>, String> {
-    let db = state.conn.lock().await;
-    if let Ok(res) = Courses::remove_attachment(&db, user_id, attachment_id, course_id).await {
+    let db = &state.conn;
+    let cloudinary = state.config.read().await.cloudinary();
+    if let Ok(res) =
+        Courses::remove_attachment(db, &cloudinary, user_id, attachment_id, course_id).await
+    {
         Ok(res)
     } else {
         Err("Cannot remove attachment".into())
@@ -151,13 +538,15 @@ pub async fn remove_attachment(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn delete_course(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
 ) -> Result<(), String> {
-    let db = state.conn.lock().await;
-    if let Ok(_) = Courses::delete(&db, user_id, course_id).await {
+    let db = &state.conn;
+    let cloudinary = state.config.read().await.cloudinary();
+    if let Ok(_) = Courses::delete(db, &cloudinary, user_id, course_id).await {
         Ok(())
     } else {
         Err("Cannot delete course".into())
@@ -165,13 +554,14 @@ pub async fn delete_course(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn publish_course(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
 ) -> Result<(), String> {
-    let db = state.conn.lock().await;
-    if let Ok(_) = Courses::publish(&db, user_id, course_id).await {
+    let db = &state.conn;
+    if let Ok(_) = Courses::publish(db, user_id, course_id).await {
         Ok(())
     } else {
         Err("Cannot publish course".into())
@@ -179,13 +569,14 @@ pub async fn publish_course(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn unpublish_course(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
 ) -> Result<(), String> {
-    let db = state.conn.lock().await;
-    if let Ok(_) = Courses::unpublish(&db, user_id, course_id).await {
+    let db = &state.conn;
+    if let Ok(_) = Courses::unpublish(db, user_id, course_id).await {
         Ok(())
     } else {
         Err("Cannot unpublish course".into())
@@ -193,12 +584,13 @@ pub async fn unpublish_course(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_course(
     state: tauri::State<'_, Arc<AppState>>,
     course_id: String,
 ) -> Result<CourseWithChapters, String> {
-    let db = state.conn.lock().await;
-    if let Ok(course) = Courses::get(&db, course_id).await {
+    let db = &state.conn;
+    if let Ok(course) = Courses::get(db, course_id).await {
         Ok(course)
     } else {
         Err("Cannot get course".into())
@@ -206,13 +598,14 @@ pub async fn get_course(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_course_with_chapters_with_progress(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
 ) -> Result<CourseWithChaptersAndProgress, String> {
-    let db = state.conn.lock().await;
-    if let Ok(course) = Courses::get_with_chapters_with_progress(&db, user_id, course_id).await {
+    let db = &state.conn;
+    if let Ok(course) = Courses::get_with_chapters_with_progress(db, user_id, course_id).await {
         Ok(course)
     } else {
         Err("Cannot get course with chapters with progress".into())
@@ -220,13 +613,14 @@ pub async fn get_course_with_chapters_with_progress(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_progress_percentage(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
 ) -> Result<u8, String> {
-    let db = state.conn.lock().await;
-    if let Ok(progress_percentage) = Courses::get_progress_percentage(&db, user_id, course_id).await
+    let db = &state.conn;
+    if let Ok(progress_percentage) = Courses::get_progress_percentage(db, user_id, course_id).await
     {
         Ok(progress_percentage)
     } else {
@@ -235,14 +629,15 @@ pub async fn get_progress_percentage(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_chapter(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
     chapter_id: String,
 ) -> Result<ChapterDetails, String> {
-    let db = state.conn.lock().await;
-    match Chapters::get(&db, user_id, course_id, chapter_id).await {
+    let db = &state.conn;
+    match Chapters::get(db, user_id, course_id, chapter_id).await {
         Ok(chapter) => Ok(chapter),
         Err(DbErr::RecordNotFound(err)) => Err(err),
         Err(DbErr::AttrNotSet(err)) => Err(err),
@@ -252,14 +647,16 @@ pub async fn get_chapter(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn delete_chapter(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
     chapter_id: String,
 ) -> Result<(), String> {
-    let db = state.conn.lock().await;
-    if let Ok(_) = Chapters::delete(&db, user_id, course_id, chapter_id).await {
+    let db = &state.conn;
+    let cloudinary = state.config.read().await.cloudinary();
+    if let Ok(_) = Chapters::delete(db, &cloudinary, user_id, course_id, chapter_id).await {
         Ok(())
     } else {
         Err("Cannot delete chapter".into())
@@ -267,14 +664,15 @@ pub async fn delete_chapter(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn publish_chapter(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
     chapter_id: String,
 ) -> Result<(), String> {
-    let db = state.conn.lock().await;
-    match Chapters::publish(&db, user_id, course_id, chapter_id).await {
+    let db = &state.conn;
+    match Chapters::publish(db, user_id, course_id, chapter_id).await {
         Ok(_) => Ok(()),
         Err(DbErr::RecordNotFound(err)) => Err(err),
         Err(DbErr::AttrNotSet(err)) => Err(err),
@@ -283,14 +681,15 @@ pub async fn publish_chapter(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn unpublish_chapter(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
     chapter_id: String,
 ) -> Result<(), String> {
-    let db = state.conn.lock().await;
-    if let Ok(_) = Chapters::unpublish(&db, user_id, course_id, chapter_id).await {
+    let db = &state.conn;
+    if let Ok(_) = Chapters::unpublish(db, user_id, course_id, chapter_id).await {
         Ok(())
     } else {
         Err("Cannot publish chapter".into())
@@ -298,14 +697,20 @@ pub async fn unpublish_chapter(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn update_chapter_progress(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     chapter_id: String,
     is_completed: bool,
 ) -> Result<(), String> {
-    let db = state.conn.lock().await;
-    if let Ok(_) = Chapters::update_progress(&db, user_id, chapter_id, is_completed).await {
+    let db = &state.conn;
+    let update = ProgressUpdate {
+        is_completed: Some(is_completed),
+        heartbeat: None,
+    };
+    let settings = state.config.read().await.watch();
+    if let Ok(_) = Chapters::update_progress(db, user_id, chapter_id, update, &settings).await {
         Ok(())
     } else {
         Err("Cannot update chapter progress".into())
@@ -313,29 +718,56 @@ pub async fn update_chapter_progress(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn report_watch_progress(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    chapter_id: String,
+    heartbeat: WatchHeartbeat,
+) -> Result<Option<user_progress::Model>, String> {
+    let db = &state.conn;
+    let throttle = &state.watch_throttle;
+    let settings = state.config.read().await.watch();
+    match Chapters::report_watch(
+        db, throttle, user_id, course_id, chapter_id, heartbeat, &settings,
+    )
+    .await
+    {
+        Ok(progress) => Ok(progress),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        Err(DbErr::Custom(err)) => Err(err),
+        _ => Err("Cannot update watch progress".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn reorder_chapters(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
     list: Vec<ReorderData>,
 ) -> Result<Vec<chapter::Model>, String> {
-    let db = state.conn.lock().await;
-    if let Ok(chapter) = Chapters::reorder(&db, user_id, course_id, list).await {
-        Ok(chapter)
-    } else {
-        Err("Cannot reorder chapters".into())
+    let db = &state.conn;
+    match Chapters::reorder(db, user_id, course_id, list).await {
+        Ok(chapters) => Ok(chapters),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        Err(DbErr::Custom(err)) => Err(err),
+        _ => Err("Cannot reorder chapters".into()),
     }
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn create_chapter(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
     title: String,
 ) -> Result<Vec<chapter::Model>, String> {
-    let db = state.conn.lock().await;
-    if let Ok(chapter) = Chapters::create(&db, user_id, course_id, title).await {
+    let db = &state.conn;
+    if let Ok(chapter) = Chapters::create(db, user_id, course_id, title).await {
         Ok(chapter)
     } else {
         Err("Cannot create chapter".into())
@@ -343,31 +775,33 @@ pub async fn create_chapter(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn update_chapter(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
     chapter_id: String,
-    updates: std::collections::HashMap<String, Value>,
-) -> Result<(), String> {
-    let db = state.conn.lock().await;
-    match Chapters::update(&db, user_id, course_id, chapter_id, updates).await {
+    updates: serde_json::Value,
+) -> Result<(), UpdateFailure> {
+    let db = &state.conn;
+    let cloudinary = state.config.read().await.cloudinary();
+    let updates = ChapterUpdate::from_json(updates)
+        .map_err(|err| update_failure(err, "Cannot update chapter"))?;
+    match Chapters::update(db, &cloudinary, user_id, course_id, chapter_id, updates).await {
         Ok(_) => Ok(()),
-        Err(DbErr::RecordNotFound(err)) => Err(err),
-        Err(DbErr::AttrNotSet(err)) => Err(err),
-        Err(DbErr::Custom(err)) => Err(err),
-        _ => Err("Cannot update chapter".into()),
+        Err(err) => Err(update_failure(err, "Cannot update chapter")),
     }
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_teacher_course(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
     course_id: String,
 ) -> Result<TeacherCourse, String> {
-    let db = state.conn.lock().await;
-    match Teacher::course(&db, user_id, course_id).await {
+    let db = &state.conn;
+    match Teacher::course(db, user_id, course_id).await {
         Ok(coruse) => Ok(coruse),
         Err(DbErr::RecordNotFound(err)) => Err(err),
         Err(DbErr::AttrNotSet(err)) => Err(err),
@@ -377,13 +811,14 @@ pub async fn get_teacher_course(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_teacher_chapter(
     state: tauri::State<'_, Arc<AppState>>,
     course_id: String,
     chapter_id: String,
 ) -> Result<chapter::Model, String> {
-    let db = state.conn.lock().await;
-    match Teacher::chapter(&db, course_id, chapter_id).await {
+    let db = &state.conn;
+    match Teacher::chapter(db, course_id, chapter_id).await {
         Ok(coruse) => Ok(coruse),
         Err(DbErr::RecordNotFound(err)) => Err(err),
         _ => Err("Cannot get teacher course".into()),
@@ -391,13 +826,784 @@ pub async fn get_teacher_chapter(
 }
 
 #[tauri::command]
+#[instrument(skip(state), err(Debug))]
 pub async fn get_teacher_analytics(
     state: tauri::State<'_, Arc<AppState>>,
     user_id: String,
+    range: Option<AnalyticsRange>,
 ) -> Result<TeacherAnalytics, String> {
-    let db = state.conn.lock().await;
-    match Teacher::analytics(&db, user_id).await {
+    let db = &state.conn;
+    match Teacher::analytics(db, user_id, range.unwrap_or_default()).await {
         Ok(analytics) => Ok(analytics),
+        Err(DbErr::Custom(err)) => Err(err),
         _ => Err("Cannot get teacher analytics".into()),
     }
 }
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_course_engagement(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<CourseEngagement, String> {
+    let db = &state.conn;
+    match Teacher::course_engagement(db, user_id, course_id).await {
+        Ok(engagement) => Ok(engagement),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get course engagement".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn export_course_engagement(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<String, String> {
+    let db = &state.conn;
+    match Teacher::course_engagement_csv(db, user_id, course_id).await {
+        Ok(csv) => Ok(csv),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot export course engagement".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn publish_course_draft(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<course_revision::Model, String> {
+    let db = &state.conn;
+    match Revisions::publish_draft(db, user_id, course_id).await {
+        Ok(revision) => Ok(revision),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        Err(DbErr::Custom(err)) => Err(err),
+        _ => Err("Cannot publish course draft".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn discard_course_draft(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<(), String> {
+    let db = &state.conn;
+    match Revisions::discard_draft(db, user_id, course_id).await {
+        Ok(_) => Ok(()),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot discard course draft".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_course_revisions(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<Vec<RevisionSummary>, String> {
+    let db = &state.conn;
+    match Revisions::history(db, user_id, course_id).await {
+        Ok(revisions) => Ok(revisions),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get course revisions".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn diff_course_revisions(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    from: i32,
+    to: Option<i32>,
+) -> Result<Vec<RevisionChange>, String> {
+    let db = &state.conn;
+    match Revisions::diff(db, user_id, course_id, from, to).await {
+        Ok(changes) => Ok(changes),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot diff course revisions".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn rollback_course_revision(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    version: i32,
+) -> Result<course_revision::Model, String> {
+    let db = &state.conn;
+    match Revisions::rollback(db, user_id, course_id, version).await {
+        Ok(revision) => Ok(revision),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot roll back course".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn schedule_course(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    schedule: CourseSchedule,
+) -> Result<(), UpdateFailure> {
+    let db = &state.conn;
+    match Schedule::set_course(db, user_id, course_id, schedule).await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(update_failure(err, "Cannot schedule course")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn schedule_chapter(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    chapter_id: String,
+    schedule: ChapterSchedule,
+) -> Result<(), UpdateFailure> {
+    let db = &state.conn;
+    match Schedule::set_chapter(db, user_id, course_id, chapter_id, schedule).await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(update_failure(err, "Cannot schedule chapter")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn issue_certificate(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    student_name: String,
+) -> Result<certificate::Model, String> {
+    let settings = certificates(&state).await?;
+    let db = &state.conn;
+    match Certificates::issue(db, &settings, user_id, student_name, course_id).await {
+        Ok(certificate) => Ok(certificate),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        Err(DbErr::Custom(err)) => Err(err),
+        _ => Err("Cannot issue certificate".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_certificates(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<certificate::Model>, String> {
+    let db = &state.conn;
+    if let Ok(res) = Certificates::list(db, user_id).await {
+        Ok(res)
+    } else {
+        Err("Cannot get certificates".into())
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn verify_certificate(
+    state: tauri::State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<Verification, String> {
+    let settings = certificates(&state).await?;
+    if let Ok(res) = Certificates::verify(&settings.public_key, &token) {
+        Ok(res)
+    } else {
+        Err("Cannot verify certificate".into())
+    }
+}
+
+// Saves the certificate as a PDF in the downloads folder and returns where.
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn export_certificate(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<String, String> {
+    let db = &state.conn;
+    let certificate = match Certificates::get(db, user_id, course_id).await {
+        Ok(Some(certificate)) => certificate,
+        Ok(None) => return Err("Cannot find certificate".into()),
+        Err(_) => return Err("Cannot get certificate".into()),
+    };
+    let token = Certificates::token(&certificate)
+        .ok_or("The certificate is waiting for the teacher's signature")?;
+    let path = crate::certificate::export(&certificate, &token)?;
+    Ok(path.display().to_string())
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn create_assignment(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    chapter_id: String,
+    assignment: NewAssignment,
+) -> Result<assignment::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Assignments::create(db, user_id, course_id, chapter_id, assignment).await {
+        Ok(assignment) => Ok(assignment),
+        Err(err) => Err(update_failure(err, "Cannot create assignment")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn update_assignment(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    assignment_id: String,
+    updates: NewAssignment,
+) -> Result<assignment::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Assignments::update(db, user_id, course_id, assignment_id, updates).await {
+        Ok(assignment) => Ok(assignment),
+        Err(err) => Err(update_failure(err, "Cannot update assignment")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn delete_assignment(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    assignment_id: String,
+) -> Result<(), String> {
+    let db = &state.conn;
+    let cloudinary = state.config.read().await.cloudinary();
+    match Assignments::delete(db, &cloudinary, user_id, course_id, assignment_id).await {
+        Ok(()) => Ok(()),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot delete assignment".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_teacher_assignments(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    chapter_id: String,
+) -> Result<Vec<assignment::Model>, String> {
+    let db = &state.conn;
+    match Assignments::list_for_teacher(db, user_id, course_id, chapter_id).await {
+        Ok(res) => Ok(res),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get assignments".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_chapter_assignments(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    chapter_id: String,
+) -> Result<Vec<AssignmentWithSubmission>, String> {
+    let db = &state.conn;
+    match Assignments::list(db, user_id, chapter_id).await {
+        Ok(res) => Ok(res),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get assignments".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn submit_assignment(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    assignment_id: String,
+    submission: NewSubmission,
+) -> Result<submission::Model, UpdateFailure> {
+    let db = &state.conn;
+    let cloudinary = state.config.read().await.cloudinary();
+    match Assignments::submit(db, &cloudinary, user_id, assignment_id, submission).await {
+        Ok(submission) => Ok(submission),
+        Err(err) => Err(update_failure(err, "Cannot submit assignment")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_ungraded_submissions(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<SubmissionToGrade>, String> {
+    let db = &state.conn;
+    if let Ok(res) = Assignments::ungraded(db, user_id).await {
+        Ok(res)
+    } else {
+        Err("Cannot get submissions".into())
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn grade_submission(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    submission_id: String,
+    grade: Grade,
+) -> Result<submission::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Assignments::grade(db, user_id, submission_id, grade).await {
+        Ok(submission) => Ok(submission),
+        Err(err) => Err(update_failure(err, "Cannot grade submission")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_course_grades(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<Vec<AssignmentWithSubmission>, String> {
+    let db = &state.conn;
+    if let Ok(res) = Assignments::grades(db, user_id, course_id).await {
+        Ok(res)
+    } else {
+        Err("Cannot get grades".into())
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_chapter_comments(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    chapter_id: String,
+) -> Result<Vec<Thread>, String> {
+    let db = &state.conn;
+    match Discussions::list(db, user_id, chapter_id).await {
+        Ok(res) => Ok(res),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get comments".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state, comment), err(Debug))]
+pub async fn post_comment(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    chapter_id: String,
+    comment: NewComment,
+) -> Result<comment::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Discussions::post(db, user_id, chapter_id, comment).await {
+        Ok(comment) => Ok(comment),
+        Err(err) => Err(update_failure(err, "Cannot post comment")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state, body), err(Debug))]
+pub async fn edit_comment(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    comment_id: String,
+    body: String,
+) -> Result<comment::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Discussions::edit(db, user_id, comment_id, body).await {
+        Ok(comment) => Ok(comment),
+        Err(err) => Err(update_failure(err, "Cannot edit comment")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn delete_comment(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    comment_id: String,
+) -> Result<(), String> {
+    let db = &state.conn;
+    match Discussions::delete(db, user_id, comment_id).await {
+        Ok(()) => Ok(()),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot delete comment".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn pin_comment(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    comment_id: String,
+    pinned: bool,
+) -> Result<comment::Model, String> {
+    let db = &state.conn;
+    match Discussions::pin(db, user_id, comment_id, pinned).await {
+        Ok(comment) => Ok(comment),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot pin comment".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_course_discussions(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<Vec<ChapterThreads>, String> {
+    let db = &state.conn;
+    match Discussions::for_course(db, user_id, course_id).await {
+        Ok(res) => Ok(res),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get discussions".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_unread_comments(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<UnreadComments>, String> {
+    let db = &state.conn;
+    if let Ok(res) = Discussions::unread(db, user_id).await {
+        Ok(res)
+    } else {
+        Err("Cannot get unread comments".into())
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn mark_discussions_read(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<(), String> {
+    let db = &state.conn;
+    match Discussions::mark_read(db, user_id, course_id).await {
+        Ok(()) => Ok(()),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot mark discussions as read".into()),
+    }
+}
+
+// Builds the prompt the "ask the AI tutor first" button sends to
+// generate_text, with the chapter as context.
+#[tauri::command]
+#[instrument(skip(state, question), err(Debug))]
+pub async fn get_tutor_prompt(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    chapter_id: String,
+    question: String,
+) -> Result<String, String> {
+    let db = &state.conn;
+    match Discussions::tutor_prompt(db, user_id, chapter_id, question).await {
+        Ok(prompt) => Ok(prompt),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot ask the tutor".into()),
+    }
+}
+
+// Note bodies and search queries stay out of the log, like tutor questions.
+#[tauri::command]
+#[instrument(skip(state, note), err(Debug))]
+pub async fn create_note(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    note: NewNote,
+) -> Result<note::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Notes::create(db, user_id, note).await {
+        Ok(note) => Ok(note),
+        Err(err) => Err(update_failure(err, "Cannot create note")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state, note), err(Debug))]
+pub async fn update_note(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    note_id: String,
+    note: NoteUpdate,
+) -> Result<note::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Notes::update(db, user_id, note_id, note).await {
+        Ok(note) => Ok(note),
+        Err(err) => Err(update_failure(err, "Cannot update note")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn delete_note(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    note_id: String,
+) -> Result<(), String> {
+    let db = &state.conn;
+    match Notes::delete(db, user_id, note_id).await {
+        Ok(()) => Ok(()),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot delete note".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_chapter_notes(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    chapter_id: String,
+) -> Result<Vec<note::Model>, String> {
+    let db = &state.conn;
+    match Notes::list_for_chapter(db, user_id, chapter_id).await {
+        Ok(notes) => Ok(notes),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get chapter notes".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_attachment_notes(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    attachment_id: String,
+) -> Result<Vec<note::Model>, String> {
+    let db = &state.conn;
+    match Notes::list_for_attachment(db, user_id, attachment_id).await {
+        Ok(notes) => Ok(notes),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get attachment notes".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state, query), err(Debug))]
+pub async fn search_notes(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    query: String,
+    course_id: Option<String>,
+) -> Result<Vec<NoteWithSource>, String> {
+    let db = &state.conn;
+    match Notes::search(db, user_id, query, course_id).await {
+        Ok(notes) => Ok(notes),
+        _ => Err("Cannot search notes".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn export_course_notes(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+) -> Result<String, String> {
+    let db = &state.conn;
+    match Notes::export_markdown(db, user_id, course_id).await {
+        Ok(markdown) => Ok(markdown),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot export notes".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn create_deck(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    course_id: String,
+    title: String,
+) -> Result<deck::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Flashcards::create_deck(db, user_id, course_id, title).await {
+        Ok(deck) => Ok(deck),
+        Err(err) => Err(update_failure(err, "Cannot create deck")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_decks(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<DeckSummary>, String> {
+    let db = &state.conn;
+    match Flashcards::decks(db, user_id).await {
+        Ok(decks) => Ok(decks),
+        _ => Err("Cannot get decks".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn delete_deck(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    deck_id: String,
+) -> Result<(), String> {
+    let db = &state.conn;
+    match Flashcards::delete_deck(db, user_id, deck_id).await {
+        Ok(()) => Ok(()),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot delete deck".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_deck_cards(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    deck_id: String,
+) -> Result<Vec<flashcard::Model>, String> {
+    let db = &state.conn;
+    match Flashcards::cards(db, user_id, deck_id).await {
+        Ok(cards) => Ok(cards),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot get cards".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state, card), err(Debug))]
+pub async fn create_card(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    deck_id: String,
+    card: NewCard,
+) -> Result<flashcard::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Flashcards::create_card(db, user_id, deck_id, card).await {
+        Ok(card) => Ok(card),
+        Err(err) => Err(update_failure(err, "Cannot create card")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state, card), err(Debug))]
+pub async fn update_card(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    card_id: String,
+    card: CardUpdate,
+) -> Result<flashcard::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Flashcards::update_card(db, user_id, card_id, card).await {
+        Ok(card) => Ok(card),
+        Err(err) => Err(update_failure(err, "Cannot update card")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn delete_card(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    card_id: String,
+) -> Result<(), String> {
+    let db = &state.conn;
+    match Flashcards::delete_card(db, user_id, card_id).await {
+        Ok(()) => Ok(()),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot delete card".into()),
+    }
+}
+
+// Builds the prompt the frontend sends to generate_text; the answer comes
+// back through add_generated_cards.
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_flashcard_prompt(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    deck_id: String,
+    chapter_id: String,
+) -> Result<String, String> {
+    let db = &state.conn;
+    match Flashcards::generation_prompt(db, user_id, deck_id, chapter_id).await {
+        Ok(prompt) => Ok(prompt),
+        Err(DbErr::RecordNotFound(err)) => Err(err),
+        _ => Err("Cannot generate cards".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state, text), err(Debug))]
+pub async fn add_generated_cards(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    deck_id: String,
+    chapter_id: String,
+    text: String,
+) -> Result<Vec<flashcard::Model>, UpdateFailure> {
+    let db = &state.conn;
+    match Flashcards::add_generated(db, user_id, deck_id, chapter_id, text).await {
+        Ok(cards) => Ok(cards),
+        Err(err) => Err(update_failure(err, "Cannot add generated cards")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_due_cards(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    deck_id: Option<String>,
+) -> Result<Vec<flashcard::Model>, String> {
+    let db = &state.conn;
+    match Flashcards::due(db, user_id, deck_id).await {
+        Ok(cards) => Ok(cards),
+        _ => Err("Cannot get due cards".into()),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn review_card(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+    card_id: String,
+    grade: i32,
+) -> Result<flashcard::Model, UpdateFailure> {
+    let db = &state.conn;
+    match Flashcards::review(db, user_id, card_id, grade).await {
+        Ok(card) => Ok(card),
+        Err(err) => Err(update_failure(err, "Cannot review card")),
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(state), err(Debug))]
+pub async fn get_review_stats(
+    state: tauri::State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<ReviewStats, String> {
+    let db = &state.conn;
+    match Flashcards::review_stats(db, user_id).await {
+        Ok(stats) => Ok(stats),
+        _ => Err("Cannot get review stats".into()),
+    }
+}
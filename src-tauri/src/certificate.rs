@@ -0,0 +1,165 @@
+use std::{
+    fs,
+    io::{BufWriter, Cursor},
+    path::PathBuf,
+};
+
+use entities::certificate;
+use owned_ttf_parser::Face;
+use printpdf::{
+    Color, IndirectFontRef, Line, Mm, PdfDocument, PdfLayerReference, Point, Pt, Rect, Rgb,
+};
+use qrcode::QrCode;
+
+use crate::config;
+
+// Arimo covers Vietnamese, which the PDF base fonts do not. It is embedded in
+// every certificate so they print the same everywhere.
+const FONT: &[u8] = include_bytes!("../../public/lib/ui/assets/fonts/webfonts/Arimo.ttf");
+
+// A4 landscape.
+const WIDTH: f32 = 297.0;
+const HEIGHT: f32 = 210.0;
+
+// The verification QR code, in the bottom right corner inside the frame.
+const QR_SIZE: f32 = 45.0;
+const QR_X: f32 = WIDTH - 20.0 - QR_SIZE;
+const QR_Y: f32 = 24.0;
+
+struct Page<'a> {
+    layer: PdfLayerReference,
+    font: IndirectFontRef,
+    face: Face<'a>,
+}
+
+impl Page<'_> {
+    // Draws `text` centered on the page with its baseline at `y` mm.
+    fn centered(&self, text: &str, size: f32, y: f32) {
+        let x = (WIDTH - Mm::from(self.width(text, size)).0) / 2.0;
+        self.layer
+            .use_text(text, size, Mm(x.max(0.0)), Mm(y), &self.font);
+    }
+
+    fn width(&self, text: &str, size: f32) -> Pt {
+        let units: u32 = text
+            .chars()
+            .filter_map(|c| self.face.glyph_index(c))
+            .filter_map(|glyph| self.face.glyph_hor_advance(glyph))
+            .map(u32::from)
+            .sum();
+        Pt(units as f32 / self.face.units_per_em() as f32 * size)
+    }
+
+    // Draws `data` as a QR code `size` mm wide with its bottom left corner at
+    // `x`, `y` mm. Dark modules next to each other in a row are drawn as one
+    // rectangle.
+    fn qr(&self, data: &str, x: f32, y: f32, size: f32) -> Result<(), String> {
+        let code = QrCode::new(data.as_bytes()).map_err(|err| err.to_string())?;
+        let width = code.width();
+        let module = size / width as f32;
+        let colors = code.to_colors();
+        for (row, colors) in colors.chunks(width).enumerate() {
+            let top = y + size - row as f32 * module;
+            let mut column = 0;
+            while column < width {
+                if colors[column] != qrcode::Color::Dark {
+                    column += 1;
+                    continue;
+                }
+                let start = column;
+                while column < width && colors[column] == qrcode::Color::Dark {
+                    column += 1;
+                }
+                self.layer.add_rect(Rect::new(
+                    Mm(x + start as f32 * module),
+                    Mm(top - module),
+                    Mm(x + column as f32 * module),
+                    Mm(top),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn frame(&self, inset: f32, thickness: f32) {
+        let corners = [
+            (inset, inset),
+            (WIDTH - inset, inset),
+            (WIDTH - inset, HEIGHT - inset),
+            (inset, HEIGHT - inset),
+        ];
+        self.layer.set_outline_thickness(thickness);
+        self.layer.add_line(Line {
+            points: corners
+                .into_iter()
+                .map(|(x, y)| (Point::new(Mm(x), Mm(y)), false))
+                .collect(),
+            is_closed: true,
+        });
+    }
+}
+
+// Renders the certificate as a one page PDF. `token` is printed as a QR code,
+// so anyone can check the certificate against the issuer's public key.
+pub fn render(certificate: &certificate::Model, token: &str) -> Result<Vec<u8>, String> {
+    let title = format!("Chứng nhận - {}", certificate.course_title);
+    let (doc, page, layer) = PdfDocument::new(&title, Mm(WIDTH), Mm(HEIGHT), "Certificate");
+    let font = doc
+        .add_external_font(Cursor::new(FONT))
+        .map_err(|err| err.to_string())?;
+    let face = Face::parse(FONT, 0).map_err(|err| err.to_string())?;
+    let page = Page {
+        layer: doc.get_page(page).get_layer(layer),
+        font,
+        face,
+    };
+
+    let accent = Color::Rgb(Rgb::new(0.09, 0.27, 0.55, None));
+    let text = Color::Rgb(Rgb::new(0.15, 0.15, 0.15, None));
+    page.layer.set_outline_color(accent.clone());
+    page.frame(10.0, 3.0);
+    page.frame(14.0, 0.75);
+
+    page.layer.set_fill_color(accent);
+    page.centered("CHỨNG NHẬN HOÀN THÀNH", 30.0, 160.0);
+    page.centered(&certificate.student_name, 34.0, 118.0);
+
+    page.layer.set_fill_color(text);
+    page.centered("Chứng nhận", 14.0, 138.0);
+    page.centered("đã hoàn thành khóa học", 14.0, 100.0);
+    page.centered(&certificate.course_title, 20.0, 86.0);
+    page.centered(
+        &format!("Giảng viên: {}", certificate.teacher_name),
+        13.0,
+        62.0,
+    );
+    page.centered(
+        &format!("Ngày cấp: {}", certificate.issued_at.format("%d/%m/%Y")),
+        13.0,
+        54.0,
+    );
+    page.centered(&format!("Mã xác minh: {}", certificate.code), 11.0, 28.0);
+    page.qr(token, QR_X, QR_Y, QR_SIZE)?;
+    let caption = "Quét mã để xác minh";
+    let x = QR_X + (QR_SIZE - Mm::from(page.width(caption, 8.0)).0) / 2.0;
+    page.layer
+        .use_text(caption, 8.0, Mm(x), Mm(QR_Y - 5.0), &page.font);
+
+    let mut bytes = BufWriter::new(Vec::new());
+    doc.save(&mut bytes).map_err(|err| err.to_string())?;
+    bytes.into_inner().map_err(|err| err.to_string())
+}
+
+// Saves the PDF to the downloads folder, like diagnostics, and returns where.
+pub fn export(certificate: &certificate::Model, token: &str) -> Result<PathBuf, String> {
+    let dir = dirs::download_dir()
+        .or_else(|| dirs::data_dir().map(|dir| dir.join(config::APP_DIR)))
+        .ok_or("Cannot find a folder to save the certificate in")?;
+    let path = dir.join(format!("capi-certificate-{}.pdf", certificate.code));
+
+    let pdf = render(certificate, token)?;
+    fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(&path, pdf))
+        .map_err(|err| format!("Cannot save {}: {}", path.display(), err))?;
+    Ok(path)
+}
@@ -0,0 +1,622 @@
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use service::{
+    CertificateSettings, CloudinaryConfig, StripeConfig, WatchSettings,
+    DEFAULT_CLOUDINARY_API_BASE, DEFAULT_COMPLETE_AT_PERCENT,
+};
+use tracing_subscriber::EnvFilter;
+
+use crate::keystore::Secrets;
+
+// Settings live in `<config dir>/tauri.lms.com/config.toml`, next to where
+// Tauri keeps the app's other files.
+pub const APP_DIR: &str = "tauri.lms.com";
+const FILE_NAME: &str = "config.toml";
+
+const DEFAULT_WEBHOOK_ADDR: &str = "127.0.0.1:4242";
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+// Everything the app can be configured with. Values come from the config file
+// and any of them can be overridden with the environment variable named next
+// to it, which is how `.env` files and CI set them. Secrets are never read
+// from or written to the file: they come from the environment or, in installed
+// apps, from the keystore.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub database: DatabaseConfig,
+    pub model: ModelConfig,
+    pub cloudinary: CloudinarySection,
+    pub stripe: StripeSection,
+    pub watch: WatchSection,
+    pub certificates: CertificateSection,
+    pub log: LogConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    // DATABASE_URL, or the keystore
+    #[serde(skip)]
+    pub url: Option<String>,
+    // DATABASE_MAX_CONNECTIONS
+    pub max_connections: u32,
+    // DATABASE_MIN_CONNECTIONS
+    pub min_connections: u32,
+    // DATABASE_CONNECT_TIMEOUT_SECS
+    pub connect_timeout_secs: u64,
+    // DATABASE_CONNECT_RETRIES
+    pub connect_retries: u32,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: None,
+            max_connections: 10,
+            min_connections: 1,
+            connect_timeout_secs: 8,
+            connect_retries: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelConfig {
+    // MODEL_PATH
+    pub model_path: Option<PathBuf>,
+    // TOKENIZER_PATH
+    pub tokenizer_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CloudinarySection {
+    // CLOUDINARY_API_BASE
+    pub api_base: String,
+    // NEXT_PUBLIC_CLOUDINARY_API_KEY
+    pub api_key: Option<String>,
+    // NEXT_PUBLIC_CLOUDINARY_CLOUD_NAME
+    pub cloud_name: Option<String>,
+    // CLOUDINARY_SIGNER_URL
+    pub signer_url: Option<String>,
+    // CLOUDINARY_SIGNER_TOKEN, or the keystore
+    #[serde(skip)]
+    pub signer_token: Option<String>,
+}
+
+impl Default for CloudinarySection {
+    fn default() -> Self {
+        CloudinarySection {
+            api_base: DEFAULT_CLOUDINARY_API_BASE.into(),
+            api_key: None,
+            cloud_name: None,
+            signer_url: None,
+            signer_token: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StripeSection {
+    // STRIPE_RELAY_URL. The app never holds the Stripe secret key, see
+    // `service::payments`.
+    pub relay_url: Option<String>,
+    // STRIPE_RELAY_TOKEN, or the keystore
+    #[serde(skip)]
+    pub relay_token: Option<String>,
+    // STRIPE_WEBHOOK_SECRET, or the keystore
+    #[serde(skip)]
+    pub webhook_secret: Option<String>,
+    // STRIPE_WEBHOOK_ADDR
+    pub webhook_addr: String,
+    // NEXT_PUBLIC_APP_URL
+    pub app_url: Option<String>,
+    // Written by versions that called Stripe directly; ignored so their
+    // config files still load.
+    #[serde(rename = "api_base", skip_serializing)]
+    pub legacy_api_base: Option<String>,
+}
+
+impl Default for StripeSection {
+    fn default() -> Self {
+        StripeSection {
+            relay_url: None,
+            relay_token: None,
+            webhook_secret: None,
+            webhook_addr: DEFAULT_WEBHOOK_ADDR.into(),
+            app_url: None,
+            legacy_api_base: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchSection {
+    // WATCH_COMPLETE_PERCENT
+    pub complete_at_percent: u8,
+}
+
+impl Default for WatchSection {
+    fn default() -> Self {
+        WatchSection {
+            complete_at_percent: DEFAULT_COMPLETE_AT_PERCENT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CertificateSection {
+    // CERTIFICATE_TEACHER_NAME, printed on every certificate
+    pub teacher_name: Option<String>,
+    // CERTIFICATE_PUBLIC_KEY, the hex ed25519 key certificates are checked
+    // against. Not a secret, every install has it
+    pub public_key: Option<String>,
+    // CERTIFICATE_SIGNING_KEY, or the keystore. Only on the issuer's install;
+    // the key pair can be made with
+    // `openssl genpkey -algorithm ed25519 -outform DER | tail -c 32 | xxd -p -c 32`
+    // and the public half with `openssl pkey -pubout -outform DER | tail -c 32`
+    #[serde(skip)]
+    pub signing_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    // LOG_LEVEL, a level or filter directives such as `info,service=debug`
+    pub level: String,
+    // LOG_MAX_FILES, one file per day
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: DEFAULT_LOG_LEVEL.into(),
+            max_files: 7,
+        }
+    }
+}
+
+// One thing wrong with the configuration, named by its key in the file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigProblem {
+    pub key: String,
+    pub message: String,
+}
+
+impl ConfigProblem {
+    fn new(key: &str, message: impl Into<String>) -> Self {
+        ConfigProblem {
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+}
+
+// The values `get_settings` shows and `update_settings` changes. Secrets and
+// the database URL are left out; they only come from the environment or the
+// keystore.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Settings {
+    pub database_max_connections: u32,
+    pub database_min_connections: u32,
+    pub database_connect_timeout_secs: u64,
+    pub database_connect_retries: u32,
+    pub model_path: Option<PathBuf>,
+    pub tokenizer_path: Option<PathBuf>,
+    pub cloudinary_api_key: Option<String>,
+    pub cloudinary_cloud_name: Option<String>,
+    pub cloudinary_signer_url: Option<String>,
+    pub stripe_relay_url: Option<String>,
+    pub stripe_webhook_addr: String,
+    pub app_url: Option<String>,
+    pub watch_complete_at_percent: u8,
+    pub certificate_teacher_name: Option<String>,
+    pub certificate_public_key: Option<String>,
+    pub log_level: String,
+    pub log_max_files: usize,
+}
+
+pub fn dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR))
+}
+
+pub fn path() -> Option<PathBuf> {
+    dir().map(|dir| dir.join(FILE_NAME))
+}
+
+impl AppConfig {
+    // Reads the config file, if there is one, and applies the environment on
+    // top. The result is only checked once the secrets are in, see
+    // `with_secrets`.
+    pub fn load() -> Result<AppConfig, Vec<ConfigProblem>> {
+        let mut config = match path() {
+            Some(path) => Self::read(&path)?,
+            None => AppConfig::default(),
+        };
+        let problems = config.apply_env();
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(problems)
+        }
+    }
+
+    // Whether the environment left secrets for the keystore to provide. The
+    // database URL is the one the app cannot start without.
+    pub fn needs_keystore(&self) -> bool {
+        self.database.url.is_none()
+    }
+
+    // Fills in the secrets the environment did not set and checks the result.
+    pub fn with_secrets(mut self, secrets: &Secrets) -> Result<AppConfig, Vec<ConfigProblem>> {
+        let fill = |value: &mut Option<String>, secret: &Option<String>| {
+            if value.is_none() {
+                *value = secret.clone().filter(|secret| !secret.trim().is_empty());
+            }
+        };
+        fill(&mut self.database.url, &Some(secrets.database_url.clone()));
+        fill(
+            &mut self.cloudinary.signer_token,
+            &secrets.cloudinary_signer_token,
+        );
+        fill(&mut self.stripe.relay_token, &secrets.stripe_relay_token);
+        fill(
+            &mut self.stripe.webhook_secret,
+            &secrets.stripe_webhook_secret,
+        );
+        fill(
+            &mut self.certificates.signing_key,
+            &secrets.certificate_signing_key,
+        );
+        self.validate()?;
+        Ok(self)
+    }
+
+    fn secrets(&self) -> Secrets {
+        Secrets {
+            database_url: self.database.url.clone().unwrap_or_default(),
+            cloudinary_signer_token: self.cloudinary.signer_token.clone(),
+            stripe_relay_token: self.stripe.relay_token.clone(),
+            stripe_secret_key: None,
+            stripe_webhook_secret: self.stripe.webhook_secret.clone(),
+            certificate_signing_key: self.certificates.signing_key.clone(),
+        }
+    }
+
+    fn read(path: &Path) -> Result<AppConfig, Vec<ConfigProblem>> {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|err| {
+                vec![ConfigProblem::new(
+                    "file",
+                    format!("Cannot read {}: {}", path.display(), err.message()),
+                )]
+            }),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(AppConfig::default()),
+            Err(err) => Err(vec![ConfigProblem::new(
+                "file",
+                format!("Cannot read {}: {}", path.display(), err),
+            )]),
+        }
+    }
+
+    // Returns the variables that are set but cannot be used.
+    fn apply_env(&mut self) -> Vec<ConfigProblem> {
+        let database = &mut self.database;
+        override_with(&mut database.url, "DATABASE_URL");
+        if let Some(path) = env_value("MODEL_PATH") {
+            self.model.model_path = Some(path.into());
+        }
+        if let Some(path) = env_value("TOKENIZER_PATH") {
+            self.model.tokenizer_path = Some(path.into());
+        }
+        let cloudinary = &mut self.cloudinary;
+        override_with(&mut cloudinary.api_key, "NEXT_PUBLIC_CLOUDINARY_API_KEY");
+        override_with(
+            &mut cloudinary.cloud_name,
+            "NEXT_PUBLIC_CLOUDINARY_CLOUD_NAME",
+        );
+        override_with(&mut cloudinary.signer_url, "CLOUDINARY_SIGNER_URL");
+        override_with(&mut cloudinary.signer_token, "CLOUDINARY_SIGNER_TOKEN");
+        let stripe = &mut self.stripe;
+        override_with(&mut stripe.relay_url, "STRIPE_RELAY_URL");
+        override_with(&mut stripe.relay_token, "STRIPE_RELAY_TOKEN");
+        override_with(&mut stripe.webhook_secret, "STRIPE_WEBHOOK_SECRET");
+        override_with(&mut stripe.app_url, "NEXT_PUBLIC_APP_URL");
+        let certificates = &mut self.certificates;
+        override_with(&mut certificates.teacher_name, "CERTIFICATE_TEACHER_NAME");
+        override_with(&mut certificates.public_key, "CERTIFICATE_PUBLIC_KEY");
+        override_with(&mut certificates.signing_key, "CERTIFICATE_SIGNING_KEY");
+        if let Some(level) = env_value("LOG_LEVEL") {
+            self.log.level = level;
+        }
+
+        [
+            override_parsed(&mut database.max_connections, "DATABASE_MAX_CONNECTIONS"),
+            override_parsed(&mut database.min_connections, "DATABASE_MIN_CONNECTIONS"),
+            override_parsed(
+                &mut database.connect_timeout_secs,
+                "DATABASE_CONNECT_TIMEOUT_SECS",
+            ),
+            override_parsed(&mut database.connect_retries, "DATABASE_CONNECT_RETRIES"),
+            override_parsed(&mut cloudinary.api_base, "CLOUDINARY_API_BASE"),
+            override_parsed(&mut stripe.webhook_addr, "STRIPE_WEBHOOK_ADDR"),
+            override_parsed(
+                &mut self.watch.complete_at_percent,
+                "WATCH_COMPLETE_PERCENT",
+            ),
+            override_parsed(&mut self.log.max_files, "LOG_MAX_FILES"),
+        ]
+        .into_iter()
+        .filter_map(Result::err)
+        .collect()
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<ConfigProblem>> {
+        let mut problems = Vec::new();
+
+        if self.database.url.is_none() {
+            problems.push(ConfigProblem::new(
+                "database.url",
+                "Set the database URL (DATABASE_URL or the keystore)",
+            ));
+        }
+        if self.database.max_connections == 0 {
+            problems.push(ConfigProblem::new(
+                "database.max_connections",
+                "Allow at least one database connection",
+            ));
+        }
+        if self.database.min_connections > self.database.max_connections {
+            problems.push(ConfigProblem::new(
+                "database.min_connections",
+                "Keep at most max_connections connections open",
+            ));
+        }
+        if self.database.connect_timeout_secs == 0 {
+            problems.push(ConfigProblem::new(
+                "database.connect_timeout_secs",
+                "Wait at least one second for the database",
+            ));
+        }
+
+        for (key, path) in [
+            ("model.model_path", &self.model.model_path),
+            ("model.tokenizer_path", &self.model.tokenizer_path),
+        ] {
+            if let Some(path) = path.as_ref().filter(|path| !path.is_file()) {
+                problems.push(ConfigProblem::new(
+                    key,
+                    format!("{} does not exist", path.display()),
+                ));
+            }
+        }
+
+        for (key, value) in [
+            ("cloudinary.api_key", &self.cloudinary.api_key),
+            ("cloudinary.cloud_name", &self.cloudinary.cloud_name),
+            ("cloudinary.signer_url", &self.cloudinary.signer_url),
+            ("cloudinary.signer_token", &self.cloudinary.signer_token),
+        ] {
+            if value.is_none() {
+                problems.push(ConfigProblem::new(key, "Set the Cloudinary credentials"));
+            }
+        }
+
+        if self.stripe.relay_token.is_some() {
+            if self.stripe.relay_url.is_none() {
+                problems.push(ConfigProblem::new(
+                    "stripe.relay_url",
+                    "Set the payments relay the app sends Stripe calls to (STRIPE_RELAY_URL)",
+                ));
+            }
+            if self.stripe.app_url.is_none() {
+                problems.push(ConfigProblem::new(
+                    "stripe.app_url",
+                    "Set where Stripe sends students back after checkout (NEXT_PUBLIC_APP_URL)",
+                ));
+            }
+        }
+
+        let certificates = &self.certificates;
+        if certificates.public_key.is_some() || certificates.signing_key.is_some() {
+            if certificates.teacher_name.is_none() {
+                problems.push(ConfigProblem::new(
+                    "certificates.teacher_name",
+                    "Set the teacher name printed on certificates (CERTIFICATE_TEACHER_NAME)",
+                ));
+            }
+            match &certificates.public_key {
+                Some(public_key) => {
+                    if let Err(err) = service::check_certificate_keys(
+                        public_key,
+                        certificates.signing_key.as_deref(),
+                    ) {
+                        problems.push(ConfigProblem::new("certificates.public_key", err));
+                    }
+                }
+                None => problems.push(ConfigProblem::new(
+                    "certificates.public_key",
+                    "Set the key certificates are checked against (CERTIFICATE_PUBLIC_KEY)",
+                )),
+            }
+        }
+
+        if !(1..=100).contains(&self.watch.complete_at_percent) {
+            problems.push(ConfigProblem::new(
+                "watch.complete_at_percent",
+                "Use a percentage from 1 to 100",
+            ));
+        }
+
+        if let Err(err) = EnvFilter::try_new(&self.log.level) {
+            problems.push(ConfigProblem::new(
+                "log.level",
+                format!("{} is not a valid level: {}", self.log.level, err),
+            ));
+        }
+        if self.log.max_files == 0 {
+            problems.push(ConfigProblem::new(
+                "log.max_files",
+                "Keep at least one log file",
+            ));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    pub fn database_url(&self) -> &str {
+        self.database.url.as_deref().unwrap_or_default()
+    }
+
+    pub fn cloudinary(&self) -> CloudinaryConfig {
+        CloudinaryConfig {
+            api_base: self.cloudinary.api_base.clone(),
+            api_key: self.cloudinary.api_key.clone().unwrap_or_default(),
+            cloud_name: self.cloudinary.cloud_name.clone().unwrap_or_default(),
+            signer_url: self.cloudinary.signer_url.clone().unwrap_or_default(),
+            signer_token: self.cloudinary.signer_token.clone().unwrap_or_default(),
+        }
+    }
+
+    // `None` when payments are not set up.
+    pub fn stripe(&self) -> Option<StripeConfig> {
+        Some(StripeConfig {
+            relay_url: self.stripe.relay_url.clone()?,
+            relay_token: self.stripe.relay_token.clone()?,
+            webhook_secret: self.stripe.webhook_secret.clone().unwrap_or_default(),
+            app_url: self.stripe.app_url.clone()?,
+        })
+    }
+
+    pub fn watch(&self) -> WatchSettings {
+        WatchSettings {
+            complete_at_percent: self.watch.complete_at_percent,
+        }
+    }
+
+    // `None` when certificates are not set up.
+    pub fn certificates(&self) -> Option<CertificateSettings> {
+        Some(CertificateSettings {
+            public_key: self.certificates.public_key.clone()?,
+            signing_key: self.certificates.signing_key.clone(),
+            teacher_name: self.certificates.teacher_name.clone()?,
+        })
+    }
+
+    pub fn settings(&self) -> Settings {
+        Settings {
+            database_max_connections: self.database.max_connections,
+            database_min_connections: self.database.min_connections,
+            database_connect_timeout_secs: self.database.connect_timeout_secs,
+            database_connect_retries: self.database.connect_retries,
+            model_path: self.model.model_path.clone(),
+            tokenizer_path: self.model.tokenizer_path.clone(),
+            cloudinary_api_key: self.cloudinary.api_key.clone(),
+            cloudinary_cloud_name: self.cloudinary.cloud_name.clone(),
+            cloudinary_signer_url: self.cloudinary.signer_url.clone(),
+            stripe_relay_url: self.stripe.relay_url.clone(),
+            stripe_webhook_addr: self.stripe.webhook_addr.clone(),
+            app_url: self.stripe.app_url.clone(),
+            watch_complete_at_percent: self.watch.complete_at_percent,
+            certificate_teacher_name: self.certificates.teacher_name.clone(),
+            certificate_public_key: self.certificates.public_key.clone(),
+            log_level: self.log.level.clone(),
+            log_max_files: self.log.max_files,
+        }
+    }
+
+    // Saves `settings` to the config file, keeping whatever else the file
+    // holds, and returns the configuration the app runs with from now on.
+    // Environment variables still win over the file, and the secrets stay the
+    // ones the app is running with.
+    pub fn update(&self, settings: Settings) -> Result<AppConfig, Vec<ConfigProblem>> {
+        let path = path().ok_or_else(|| {
+            vec![ConfigProblem::new(
+                "file",
+                "Cannot find a config directory on this system",
+            )]
+        })?;
+
+        let mut file = Self::read(&path)?;
+        file.database.max_connections = settings.database_max_connections;
+        file.database.min_connections = settings.database_min_connections;
+        file.database.connect_timeout_secs = settings.database_connect_timeout_secs;
+        file.database.connect_retries = settings.database_connect_retries;
+        file.model.model_path = settings.model_path;
+        file.model.tokenizer_path = settings.tokenizer_path;
+        file.cloudinary.api_key = settings.cloudinary_api_key;
+        file.cloudinary.cloud_name = settings.cloudinary_cloud_name;
+        file.cloudinary.signer_url = settings.cloudinary_signer_url;
+        file.stripe.relay_url = settings.stripe_relay_url;
+        file.stripe.webhook_addr = settings.stripe_webhook_addr;
+        file.stripe.app_url = settings.app_url;
+        file.watch.complete_at_percent = settings.watch_complete_at_percent;
+        file.certificates.teacher_name = settings.certificate_teacher_name;
+        file.certificates.public_key = settings.certificate_public_key;
+        file.log.level = settings.log_level;
+        file.log.max_files = settings.log_max_files;
+
+        let mut config = file.clone();
+        let problems = config.apply_env();
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        let config = config.with_secrets(&self.secrets())?;
+
+        let write = |text: String| -> std::io::Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, text)
+        };
+        toml::to_string_pretty(&file)
+            .map_err(|err| err.to_string())
+            .and_then(|text| write(text).map_err(|err| err.to_string()))
+            .map_err(|err| {
+                vec![ConfigProblem::new(
+                    "file",
+                    format!("Cannot save {}: {}", path.display(), err),
+                )]
+            })?;
+
+        Ok(config)
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+fn override_with(value: &mut Option<String>, name: &str) {
+    if let Some(from_env) = env_value(name) {
+        *value = Some(from_env);
+    }
+}
+
+fn override_parsed<T: FromStr>(value: &mut T, name: &str) -> Result<(), ConfigProblem> {
+    if let Some(from_env) = env_value(name) {
+        *value = from_env
+            .trim()
+            .parse()
+            .map_err(|_| ConfigProblem::new(name, format!("{} is not a valid value", from_env)))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbBackend, DbErr};
+use serde::Serialize;
+
+use crate::config::DatabaseConfig;
+
+// Waited between connection attempts, doubling after each failure.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+fn options(config: &DatabaseConfig) -> ConnectOptions {
+    let timeout = Duration::from_secs(config.connect_timeout_secs);
+    let mut options = ConnectOptions::new(config.url.clone().unwrap_or_default());
+    options
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .connect_timeout(timeout)
+        .acquire_timeout(timeout);
+    options
+}
+
+// `DatabaseConnection` is already a pool, so commands share it directly and
+// run side by side. Connecting is retried a few times with a growing delay,
+// e.g. while a local server is still starting.
+pub async fn connect(config: &DatabaseConfig) -> Result<DatabaseConnection, DbErr> {
+    let mut delay = RETRY_DELAY;
+    let mut attempt = 0;
+    loop {
+        match Database::connect(options(config)).await {
+            Ok(db) => return Ok(db),
+            Err(err) if attempt < config.connect_retries => {
+                attempt += 1;
+                tracing::warn!(
+                    "Database connection failed ({:?}), retrying in {:?} ({}/{})",
+                    err,
+                    delay,
+                    attempt,
+                    config.connect_retries
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbHealth {
+    pub ok: bool,
+    // Round trip of a ping, in milliseconds.
+    pub latency_ms: u128,
+    // Connections open in the pool, and how many of those are idle.
+    pub connections: u32,
+    pub idle: u32,
+    pub max_connections: u32,
+    pub error: Option<String>,
+}
+
+pub async fn health(db: &DatabaseConnection, config: &DatabaseConfig) -> DbHealth {
+    let started = Instant::now();
+    let ping = db.ping().await;
+    let latency_ms = started.elapsed().as_millis();
+
+    let (connections, idle) = match db.get_database_backend() {
+        DbBackend::Postgres => {
+            let pool = db.get_postgres_connection_pool();
+            (pool.size(), pool.num_idle() as u32)
+        }
+        DbBackend::Sqlite => {
+            let pool = db.get_sqlite_connection_pool();
+            (pool.size(), pool.num_idle() as u32)
+        }
+        _ => (0, 0),
+    };
+
+    DbHealth {
+        ok: ping.is_ok(),
+        latency_ms,
+        connections,
+        idle,
+        max_connections: config.max_connections,
+        error: ping.err().map(|err| err.to_string()),
+    }
+}
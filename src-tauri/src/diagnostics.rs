@@ -0,0 +1,87 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use sea_orm::sqlx::types::chrono::Utc;
+use serde::Serialize;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    config::{self, Settings},
+    logging,
+    startup::StartupStatus,
+};
+
+// What the app looked like when the diagnostics were exported.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary<'a> {
+    version: &'a str,
+    os: &'a str,
+    arch: &'a str,
+    exported_at: String,
+    startup: &'a StartupStatus,
+    // Missing while the app has not started.
+    settings: Option<Settings>,
+}
+
+// Bundles what a support ticket needs into one zip in the downloads folder:
+// the log files, the config file and a summary of the running app. Secrets
+// never end up in it, since the config file holds none and the keystore is
+// left out.
+pub fn export(startup: &StartupStatus, settings: Option<Settings>) -> Result<PathBuf, String> {
+    let dir = dirs::download_dir()
+        .or_else(|| dirs::data_dir().map(|dir| dir.join(config::APP_DIR)))
+        .ok_or("Cannot find a folder to save diagnostics in")?;
+    let now = Utc::now();
+    let path = dir.join(format!(
+        "capi-diagnostics-{}.zip",
+        now.format("%Y%m%d-%H%M%S")
+    ));
+
+    let summary = Summary {
+        version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        exported_at: now.to_rfc3339(),
+        startup,
+        settings,
+    };
+    write(&path, &summary).map_err(|err| format!("Cannot save {}: {}", path.display(), err))?;
+    Ok(path)
+}
+
+fn write(path: &Path, summary: &Summary) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("summary.json", options)?;
+    zip.write_all(&serde_json::to_vec_pretty(summary)?)?;
+
+    if let Some(config) = config::path().filter(|path| path.is_file()) {
+        zip.start_file("config.toml", options)?;
+        io::copy(&mut File::open(config)?, &mut zip)?;
+    }
+
+    if let Some(logs) = logging::dir().filter(|dir| dir.is_dir()) {
+        for entry in fs::read_dir(logs)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            zip.start_file(
+                format!("logs/{}", entry.file_name().to_string_lossy()),
+                options,
+            )?;
+            io::copy(&mut File::open(entry.path())?, &mut zip)?;
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
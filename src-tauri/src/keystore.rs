@@ -0,0 +1,174 @@
+use std::{fmt, fs, path::PathBuf};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, ConfigProblem};
+
+// Secrets live in `<config dir>/tauri.lms.com/secrets.enc`, encrypted with a
+// key derived from a passphrase the user picks when setting the app up. None
+// of them ship with the app, and the Cloudinary API secret and the Stripe
+// secret key are not among them at all: the app only holds tokens for the
+// media signer and the payments relay.
+//
+// What the keystore does hold still reaches every install it is set up on:
+// - The database URL connects straight to the database with whatever rights
+//   its role has. Give installs a role limited to the app's tables, never the
+//   owner of the database.
+// - The relay token can start checkouts and refund purchases through the
+//   payments relay, see `service::payments`.
+// - The Stripe webhook secret lets whoever holds it forge Stripe events to
+//   the app's webhook listener.
+// The certificate signing key is the exception: only the issuer's install
+// should be given it, every other install signs nothing.
+const FILE_NAME: &str = "secrets.enc";
+const VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+// What the keystore holds. Optional values can be left empty when the
+// feature they unlock is not used.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Secrets {
+    pub database_url: String,
+    #[serde(default)]
+    pub cloudinary_signer_token: Option<String>,
+    #[serde(default)]
+    pub stripe_relay_token: Option<String>,
+    // Written by versions that held the Stripe secret key; dropped the next
+    // time the keystore is saved.
+    #[serde(default, skip_serializing)]
+    pub stripe_secret_key: Option<String>,
+    #[serde(default)]
+    pub stripe_webhook_secret: Option<String>,
+    #[serde(default)]
+    pub certificate_signing_key: Option<String>,
+}
+
+// Keeps secrets out of logs.
+impl fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secrets { .. }")
+    }
+}
+
+// The file as written to disk. Everything but the version is hex.
+#[derive(Serialize, Deserialize)]
+struct Sealed {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub fn path() -> Option<PathBuf> {
+    config::dir().map(|dir| dir.join(FILE_NAME))
+}
+
+pub fn exists() -> bool {
+    path().is_some_and(|path| path.is_file())
+}
+
+// Decrypts the keystore. A wrong passphrase and a tampered file look the same.
+pub fn open(passphrase: &str) -> Result<Secrets, ConfigProblem> {
+    let path = path().ok_or_else(|| problem("Cannot find a config directory on this system"))?;
+    let text = fs::read_to_string(&path)
+        .map_err(|err| problem(format!("Cannot read {}: {}", path.display(), err)))?;
+    let sealed: Sealed = serde_json::from_str(&text)
+        .map_err(|_| problem(format!("{} is not a keystore", path.display())))?;
+    if sealed.version != VERSION {
+        return Err(problem(format!(
+            "Keystore version {} is not supported",
+            sealed.version
+        )));
+    }
+
+    let decode = |value: &str| hex::decode(value).map_err(|_| problem("The keystore is damaged"));
+    let salt = decode(&sealed.salt)?;
+    let nonce = decode(&sealed.nonce)?;
+    let ciphertext = decode(&sealed.ciphertext)?;
+    if nonce.len() != XNonce::default().len() {
+        return Err(problem("The keystore is damaged"));
+    }
+
+    let plaintext = cipher(passphrase, &salt)?
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| problem("Wrong passphrase"))?;
+    serde_json::from_slice(&plaintext).map_err(|_| problem("The keystore is damaged"))
+}
+
+// Writes a new keystore. An existing one is never replaced, so a mistyped
+// setup cannot lock the user out of secrets they already saved.
+pub fn create(passphrase: &str, secrets: &Secrets) -> Result<(), ConfigProblem> {
+    let path = path().ok_or_else(|| problem("Cannot find a config directory on this system"))?;
+    if path.exists() {
+        return Err(problem(format!("{} already exists", path.display())));
+    }
+    check_passphrase(passphrase)?;
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(secrets).map_err(|err| problem(err.to_string()))?;
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(&nonce, plaintext.as_ref())
+        .map_err(|_| problem("Cannot encrypt the keystore"))?;
+    let sealed = Sealed {
+        version: VERSION,
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    };
+
+    let write = || -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&sealed)?)?;
+        restrict(&path)
+    };
+    write().map_err(|err| problem(format!("Cannot save {}: {}", path.display(), err)))
+}
+
+pub fn check_passphrase(passphrase: &str) -> Result<(), ConfigProblem> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(problem(format!(
+            "Use a passphrase of at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+    Ok(())
+}
+
+// Argon2id makes guessing the passphrase from a copied file slow.
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305, ConfigProblem> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| problem(format!("Cannot derive the keystore key: {}", err)))?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+// Only the user who set the app up can read the file.
+#[cfg(unix)]
+fn restrict(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn restrict(_path: &std::path::Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+fn problem(message: impl Into<String>) -> ConfigProblem {
+    ConfigProblem {
+        key: "keystore".into(),
+        message: message.into(),
+    }
+}
@@ -1,13 +1,21 @@
-use sea_orm::{Database, DatabaseConnection};
-use std::collections::VecDeque;
+use sea_orm::DatabaseConnection;
 use std::sync::Arc;
 use tauri::State;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
 
 use dotenv::dotenv;
-use std::env;
+mod certificate;
+mod config;
+mod database;
+mod diagnostics;
+mod keystore;
 mod loader;
+mod logging;
+mod scheduler;
+mod session;
+mod startup;
 mod textgen;
+mod webhook;
 
 mod api;
 use api::*;
@@ -25,15 +33,29 @@ const MAX_HISTORY: usize = 20; // Giới hạn lịch sử hội thoại
 // Cấu trúc AppState
 pub struct AppState {
     pub textgen: Mutex<Option<textgen::TextGeneration>>, // Chứa mô hình nếu đã tải
-    pub history: Mutex<VecDeque<(String, String)>>,      // Lịch sử hội thoại
-    pub conn: Mutex<DatabaseConnection>,
-    pub model_loaded: Mutex<bool>, // Đánh dấu mô hình đã tải
+    pub session: Mutex<session::ChatSession>,            // Lịch sử hội thoại và bản tóm tắt
+    pub conn: DatabaseConnection,                        // Pool dùng chung, không cần khóa
+    pub db_config: config::DatabaseConfig,               // Cấu hình pool lúc khởi động
+    pub config: RwLock<config::AppConfig>,               // Cấu hình hiện tại
+    pub watch_throttle: service::WatchThrottle,          // Gộp heartbeat xem video dồn dập
+    pub model_loaded: Mutex<bool>,                       // Đánh dấu mô hình đã tải
 }
 
-// Hàm định dạng prompt với CHAT_TEMPLATE và history
-fn format_prompt(new_prompt: &str, history: &VecDeque<(String, String)>, is_chat: bool) -> String {
+// Hàm định dạng prompt với CHAT_TEMPLATE, bản tóm tắt và history
+fn format_prompt(new_prompt: &str, session: &session::ChatSession, is_chat: bool) -> String {
+    // Bản tóm tắt các lượt đã bị cắt đứng ngay sau template
+    let summary_str = if session.summary.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "[INST] Tóm tắt phần trò chuyện trước: {} [/INST] </s>",
+            session.summary
+        )
+    };
+
     // Xây dựng chuỗi lịch sử từ VecDeque
-    let history_str = history
+    let history_str = session
+        .history
         .iter()
         .map(|(user_prompt, model_response)| {
             format!("[INST] {} [/INST] {} </s>", user_prompt, model_response)
@@ -45,36 +67,31 @@ fn format_prompt(new_prompt: &str, history: &VecDeque<(String, String)>, is_chat
     if is_chat {
         // Trò chuyện: Sử dụng CHAT_TEMPLATE_CHAT_BOT
         format!(
-            "{} {} [INST] {} [/INST]",
-            CHAT_TEMPLATE_CHAT_BOT, history_str, new_prompt
+            "{} {} {} [INST] {} [/INST]",
+            CHAT_TEMPLATE_CHAT_BOT, summary_str, history_str, new_prompt
         )
     } else {
         // Truy vấn PDF: Sử dụng CHAT_TEMPLATE_PDF
         format!(
-            "{} {} [INST] {} [/INST]",
-            CHAT_TEMPLATE_PDF, history_str, new_prompt
+            "{} {} {} [INST] {} [/INST]",
+            CHAT_TEMPLATE_PDF, summary_str, history_str, new_prompt
         )
     }
 }
 
-#[tauri::command]
-async fn generate_text(
-    prompt: String,
-    sample_len: usize,
-    state: State<'_, Arc<AppState>>,
-    style: bool,
-) -> Result<String, String> {
-    println!("Received prompt: {}", prompt);
-
+// Tải mô hình ở lần dùng đầu tiên
+async fn load_model(state: &AppState) -> Result<(), String> {
     // Kiểm tra xem mô hình đã được tải chưa
     let mut model_loaded = state.model_loaded.lock().await;
 
     // Nếu mô hình chưa tải, tải mô hình và khởi tạo TextGeneration
     if !*model_loaded {
-        println!("Loading model...");
+        tracing::info!("Loading model");
 
         // Tải mô hình tại thời điểm này
-        let (model, tokenizer, device) = loader::model_loader().expect("Không thể tải mô hình");
+        let model_config = state.config.read().await.model.clone();
+        let (model, tokenizer, device) =
+            loader::model_loader(&model_config).map_err(|e| e.to_string())?;
 
         // Khởi tạo đối tượng TextGeneration
         let textgen = textgen::TextGeneration::new(
@@ -95,9 +112,22 @@ async fn generate_text(
 
         // Đánh dấu mô hình đã tải
         *model_loaded = true;
-        println!("Model loaded successfully");
+        tracing::info!("Model loaded");
     }
 
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state, prompt), fields(prompt_len = prompt.len()), err(Debug))]
+async fn generate_text(
+    prompt: String,
+    sample_len: usize,
+    state: State<'_, Arc<AppState>>,
+    style: bool,
+) -> Result<String, String> {
+    load_model(&state).await?;
+
     // Lấy đối tượng TextGeneration đã tải
     let _textgen = state
         .textgen
@@ -112,12 +142,12 @@ async fn generate_text(
         .as_mut()
         .ok_or("Model not loaded")?;
 
-    // Acquire history lock
-    let mut history = state.history.lock().await;
+    // Acquire session lock
+    let mut session = state.session.lock().await;
 
     // Format the prompt
-    let formatted_prompt = format_prompt(&prompt, &history, style);
-    println!("Formatted prompt: {}", formatted_prompt);
+    let formatted_prompt = format_prompt(&prompt, &session, style);
+    tracing::debug!("Formatted prompt: {}", formatted_prompt);
 
     // Create a channel for token communication
     let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(1000);
@@ -160,66 +190,181 @@ async fn generate_text(
 
     match infer_result {
         Ok(_) => {
-            println!("Generated text successfully: {}", generated_text);
+            tracing::debug!("Generated text: {}", generated_text);
 
-            // Update history
-            history.push_back((prompt.clone(), generated_text.clone()));
-            if history.len() > MAX_HISTORY {
-                history.pop_front();
+            // Update history, các lượt bị cắt được gộp vào bản tóm tắt
+            session
+                .history
+                .push_back((prompt.clone(), generated_text.clone()));
+            let overflow = session.history.len().saturating_sub(MAX_HISTORY);
+            let dropped: Vec<_> = session.history.drain(..overflow).collect();
+            session::save(&session);
+            if !dropped.is_empty() {
+                spawn_summary(Arc::clone(&state), dropped);
             }
-
-            println!("Returning generated text to frontend: {}", generated_text);
             Ok(generated_text)
         }
-        Err(e) => {
-            println!("Error generating text: {:?}", e);
-            Err(e.to_string())
-        }
+        Err(e) => Err(e.to_string()),
     }
 }
 
+// Sinh văn bản từ đúng prompt được gửi, không kèm template, lịch sử hay bản
+// tóm tắt của hội thoại và cũng không ghi gì vào đó. Dùng để tạo thẻ ghi nhớ
+#[tauri::command]
+#[tracing::instrument(skip(state, prompt), fields(prompt_len = prompt.len()), err(Debug))]
+async fn generate_flashcard_text(
+    prompt: String,
+    sample_len: usize,
+    state: State<'_, Arc<AppState>>,
+) -> Result<String, String> {
+    load_model(&state).await?;
+
+    // infer gửi từng token qua kênh nên phải đủ chỗ và giữ đầu nhận
+    let (tx, _rx) = tokio::sync::mpsc::channel::<String>(sample_len + 1);
+    let mut textgen = state.textgen.lock().await;
+    let textgen = textgen.as_mut().ok_or("Model not loaded")?;
+    textgen
+        .infer(&prompt, sample_len, &tx)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Tóm tắt các lượt bị cắt trong nền bằng chính mô hình đang dùng, để câu trả
+// lời không phải chờ. Không giữ khóa session khi chờ mô hình (generate_text khóa
+// theo thứ tự ngược lại). Nếu lịch sử bị xóa trong lúc đó thì bỏ kết quả
+fn spawn_summary(state: Arc<AppState>, dropped: Vec<(String, String)>) {
+    tokio::spawn(async move {
+        let (id, prompt) = {
+            let session = state.session.lock().await;
+            (
+                session.id.clone(),
+                session::summary_prompt(&session.summary, &dropped),
+            )
+        };
+
+        // infer gửi từng token qua kênh nên phải đủ chỗ và giữ đầu nhận
+        let (tx, _rx) = tokio::sync::mpsc::channel::<String>(session::SUMMARY_LEN + 1);
+        let summary = {
+            let mut textgen = state.textgen.lock().await;
+            let Some(textgen) = textgen.as_mut() else {
+                return;
+            };
+            textgen.infer(&prompt, session::SUMMARY_LEN, &tx).await
+        };
+
+        match summary {
+            Ok(summary) => {
+                let mut session = state.session.lock().await;
+                if session.id == id {
+                    session.summary = summary.trim().to_string();
+                    session::save(&session);
+                }
+            }
+            Err(e) => tracing::warn!("Cannot summarize history: {}", e),
+        }
+    });
+}
+
 // Lệnh để xóa lịch sử hội thoại
 #[tauri::command]
+#[tracing::instrument(skip(state), err(Debug))]
 async fn clear_history(state: State<'_, Arc<AppState>>) -> Result<(), String> {
-    let mut history = state.history.lock().await;
-    history.clear();
-    println!("Chat history cleared");
+    let mut session = state.session.lock().await;
+    *session = session::ChatSession::default();
+    session::save(&session);
     Ok(())
 }
 
 #[tauri::command]
 fn greet(name: &str, email: &str) -> String {
-    println!("Inside RUST Code");
     format!("Hello, {}! you are logged in with email {}", name, email)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub async fn run() {
     dotenv().ok();
-    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
-    let db = Database::connect(db_url)
-        .await
-        .expect("Database connection failed");
-
-    // Đưa TextGeneration và lịch sử vào State của Tauri
-    let state = Arc::new(AppState {
-        textgen: Mutex::new(None), // TextGeneration không có mô hình ban đầu
-        history: Mutex::new(VecDeque::new()), // Khởi tạo `history` trống
-        conn: db.into(),
-        model_loaded: Mutex::new(false), // Đánh dấu mô hình chưa được tải
+    let loaded = config::AppConfig::load();
+
+    // Ghi log theo cấu hình nếu đọc được, không thì theo mặc định. Giữ guard
+    // đến khi ứng dụng đóng để log được ghi hết ra tệp
+    let log_config = match &loaded {
+        Ok(app_config) => app_config.log.clone(),
+        Err(_) => config::LogConfig::default(),
+    };
+    let _log_guard = logging::init(&log_config);
+
+    // Khi môi trường đã có đủ bí mật (lúc phát triển) thì mở ngay, còn không
+    // thì chờ người dùng mở khóa kho bí mật
+    let mut state = None;
+    let mut pending = None;
+    let status = match loaded {
+        Err(problems) => startup::StartupStatus::new(startup::Stage::Failed, problems),
+        Ok(app_config) if app_config.needs_keystore() => {
+            pending = Some(app_config);
+            let stage = if keystore::exists() {
+                startup::Stage::Locked
+            } else {
+                startup::Stage::NoKeystore
+            };
+            startup::StartupStatus::new(stage, Vec::new())
+        }
+        Ok(app_config) => {
+            let opened = match app_config.with_secrets(&keystore::Secrets::default()) {
+                Ok(app_config) => startup::open(app_config).await,
+                Err(problems) => Err(problems),
+            };
+            match opened {
+                Ok(opened) => {
+                    startup::spawn_tasks(&opened).await;
+                    state = Some(opened);
+                    startup::StartupStatus::new(startup::Stage::Ready, Vec::new())
+                }
+                Err(problems) => startup::StartupStatus::new(startup::Stage::Failed, problems),
+            }
+        }
+    };
+    if status.stage == startup::Stage::Failed {
+        tracing::error!("Cannot start: {:?}", status.problems);
+    }
+
+    let mut builder = tauri::Builder::default().manage(startup::Startup {
+        status: Mutex::new(status),
+        pending: Mutex::new(pending),
     });
+    if let Some(state) = state {
+        builder = builder.manage(state);
+    }
 
-    tauri::Builder::default()
-        .manage(state)
+    builder
         .invoke_handler(tauri::generate_handler![
             greet,
+            get_startup_status,
+            unlock_keystore,
+            create_keystore,
+            get_settings,
+            update_settings,
             generate_text,
+            generate_flashcard_text,
             clear_history,
+            db_health,
             get_dashboard_courses,
             get_purchase,
+            get_entitlement,
             get_categories,
             get_search,
             course_checkout,
+            create_coupon,
+            list_coupons,
+            delete_coupon,
+            create_product,
+            list_products,
+            get_course_products,
+            set_product_published,
+            delete_product,
+            product_checkout,
+            get_course_purchases,
+            refund_purchase,
+            revoke_purchase,
             create_course,
             update_course,
             list_courses,
@@ -236,23 +381,65 @@ pub async fn run() {
             publish_chapter,
             unpublish_chapter,
             update_chapter_progress,
+            report_watch_progress,
             reorder_chapters,
             create_chapter,
             update_chapter,
             get_teacher_course,
             get_teacher_chapter,
-            get_teacher_analytics
+            get_teacher_analytics,
+            get_course_engagement,
+            export_course_engagement,
+            publish_course_draft,
+            discard_course_draft,
+            get_course_revisions,
+            diff_course_revisions,
+            rollback_course_revision,
+            schedule_course,
+            schedule_chapter,
+            issue_certificate,
+            get_certificates,
+            verify_certificate,
+            export_certificate,
+            create_assignment,
+            update_assignment,
+            delete_assignment,
+            get_teacher_assignments,
+            get_chapter_assignments,
+            submit_assignment,
+            get_ungraded_submissions,
+            grade_submission,
+            get_course_grades,
+            get_chapter_comments,
+            post_comment,
+            edit_comment,
+            delete_comment,
+            pin_comment,
+            get_course_discussions,
+            get_unread_comments,
+            mark_discussions_read,
+            get_tutor_prompt,
+            create_note,
+            update_note,
+            delete_note,
+            get_chapter_notes,
+            get_attachment_notes,
+            search_notes,
+            export_course_notes,
+            create_deck,
+            get_decks,
+            delete_deck,
+            get_deck_cards,
+            create_card,
+            update_card,
+            delete_card,
+            get_flashcard_prompt,
+            add_generated_cards,
+            get_due_cards,
+            review_card,
+            get_review_stats,
+            export_diagnostics
         ])
-        .setup(|app| {
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
-            Ok(())
-        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -1,15 +1,16 @@
-//It is used to load the model and tokenizer from the file system. The model is loaded from the MODEL_PATH environment variable, and the tokenizer is loaded from the TOKENIZER_PATH environment variable. The model is loaded using the gguf_file crate, and the tokenizer is loaded using the tokenizers crate. The model is loaded using the ModelWeights struct from the candle_transformers crate, and the tokenizer is loaded using the Tokenizer struct from the tokenizers crate. The device is created using the Device struct from the candle_core crate. The model_loader function returns a Result containing the model, tokenizer, and device, or an error if loading the model or tokenizer fails. The format_size function is used to format the size of the model in bytes.
+//It is used to load the model and tokenizer from the file system. The model is loaded from `model.model_path` in the app configuration, and the tokenizer is loaded from `model.tokenizer_path`. The model is loaded using the gguf_file crate, and the tokenizer is loaded using the tokenizers crate. The model is loaded using the ModelWeights struct from the candle_transformers crate, and the tokenizer is loaded using the Tokenizer struct from the tokenizers crate. The device is created using the Device struct from the candle_core crate. The model_loader function returns a Result containing the model, tokenizer, and device, or an error if loading the model or tokenizer fails. The format_size function is used to format the size of the model in bytes.
 use anyhow::Error as E;
 use candle_core::{quantized::gguf_file, utils::metal_is_available, Device};
 use candle_transformers::models::quantized_llama::ModelWeights;
-use dotenv::{dotenv, var};
 use tokenizers::Tokenizer;
 
-pub fn model_loader() -> Result<(ModelWeights, Tokenizer, Device), E> {
-    // Đảm bảo biến môi trường đã được tải
-    dotenv().ok();
+use crate::config::ModelConfig;
 
-    let model_path = var("MODEL_PATH").expect("MODEL_PATH is not set");
+pub fn model_loader(config: &ModelConfig) -> Result<(ModelWeights, Tokenizer, Device), E> {
+    let model_path = config
+        .model_path
+        .clone()
+        .ok_or_else(|| E::msg("model.model_path is not set"))?;
     let mut file = std::fs::File::open(&model_path)?;
     let model = gguf_file::Content::read(&mut file).map_err(|e| e.with_path(model_path))?;
 
@@ -26,20 +27,20 @@ pub fn model_loader() -> Result<(ModelWeights, Tokenizer, Device), E> {
         Device::cuda_if_available(0)?
     };
 
-    println!(
-        "\n> Loading {:?} tensors - {}",
+    tracing::info!(
+        "Loading {} tensors ({}) on {:?}",
         model.tensor_infos.len(),
-        &format_size(total_size_in_bytes),
+        format_size(total_size_in_bytes),
+        device
     );
     let model = ModelWeights::from_gguf(model, &mut file, &device)?;
-    println!("> Successfully loaded tensors ✓");
 
-    let tokenizer_path = var("TOKENIZER_PATH").expect("TOKENIZER_PATH is not set");
-    let tokenizer_filename = std::path::PathBuf::from(tokenizer_path);
+    let tokenizer_filename = config
+        .tokenizer_path
+        .clone()
+        .ok_or_else(|| E::msg("model.tokenizer_path is not set"))?;
 
-    println!("\n> Loading tokenizer");
     let tokenizer = Tokenizer::from_file(tokenizer_filename).map_err(E::msg)?;
-    println!("> Successfully loaded tokenizer ✓\n");
 
     Ok((model, tokenizer, device))
 }
@@ -0,0 +1,84 @@
+use std::{fs, path::PathBuf, sync::OnceLock};
+
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    fmt::{self, format::FmtSpan},
+    layer::SubscriberExt,
+    reload,
+    util::SubscriberInitExt,
+    EnvFilter, Registry,
+};
+
+use crate::config::{self, LogConfig};
+
+// Log files are `capi.<date>.log` in `<data dir>/tauri.lms.com/logs`, next to
+// the rest of the app's data.
+const LOG_DIR: &str = "logs";
+const FILE_PREFIX: &str = "capi";
+const FILE_SUFFIX: &str = "log";
+
+// Lets `set_level` change the filter while the app runs.
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+pub fn dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(config::APP_DIR).join(LOG_DIR))
+}
+
+// Sends everything traced, including `log` records from dependencies, to
+// stdout and to a log file rotated daily. Every span is reported when it
+// closes, with how long it took, so each command shows up once with its
+// arguments and timing.
+//
+// The returned guard flushes the file when dropped, so it has to be kept for
+// as long as the app runs.
+pub fn init(config: &LogConfig) -> Option<WorkerGuard> {
+    let filter = EnvFilter::try_new(&config.level)
+        .unwrap_or_else(|_| EnvFilter::new(config::DEFAULT_LOG_LEVEL));
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let appender = dir().and_then(|dir| {
+        fs::create_dir_all(&dir).ok()?;
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(FILE_PREFIX)
+            .filename_suffix(FILE_SUFFIX)
+            .max_log_files(config.max_files)
+            .build(dir)
+            .ok()
+    });
+    let (file, guard) = match appender {
+        Some(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (Some(writer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    let initialized = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_span_events(FmtSpan::CLOSE))
+        .with(file.map(|writer| {
+            fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .with_span_events(FmtSpan::CLOSE)
+        }))
+        .try_init();
+    if initialized.is_ok() {
+        let _ = FILTER.set(handle);
+    }
+    guard
+}
+
+// Applies a new level, e.g. after the settings changed. The number of files
+// kept only changes on the next start.
+pub fn set_level(level: &str) -> Result<(), String> {
+    let filter = EnvFilter::try_new(level).map_err(|err| err.to_string())?;
+    if let Some(handle) = FILTER.get() {
+        handle.reload(filter).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::{sync::Arc, time::Duration};
+
+use sea_orm::sqlx::types::chrono::Utc;
+use service::{Certificates, Chapters, Schedule};
+
+use crate::AppState;
+
+// How often scheduled publish and unpublish times, and certificates waiting
+// for a signature, are checked.
+const INTERVAL: Duration = Duration::from_secs(60);
+
+// How often watch heartbeats held back by the throttle are written.
+const WATCH_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+// Applies scheduled publishing in the background for as long as the app runs,
+// and on the issuer's install signs the certificates other installs recorded.
+// The first run happens right away so anything that came due while the app was
+// closed is caught up on startup.
+pub fn spawn(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            match Schedule::apply_due(&state.conn, Utc::now().naive_utc()).await {
+                Ok(0) => (),
+                Ok(applied) => tracing::info!("Applied {} scheduled publish changes", applied),
+                Err(err) => tracing::error!("Cannot apply publish schedule: {:?}", err),
+            }
+            let certificates = state.config.read().await.certificates();
+            if let Some(settings) = certificates {
+                match Certificates::sign_pending(&state.conn, &settings).await {
+                    Ok(0) => (),
+                    Ok(signed) => tracing::info!("Signed {} certificates", signed),
+                    Err(err) => tracing::error!("Cannot sign certificates: {:?}", err),
+                }
+            }
+            tokio::time::sleep(INTERVAL).await;
+        }
+    });
+}
+
+// Writes watch heartbeats that `Chapters::report_watch` held back, so the last
+// seconds before a student stops watching still count.
+pub fn spawn_watch_flush(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(WATCH_FLUSH_INTERVAL).await;
+            let settings = state.config.read().await.watch();
+            let now = Utc::now().naive_utc();
+            if let Err(err) =
+                Chapters::flush_watch(&state.conn, &state.watch_throttle, &settings, now).await
+            {
+                tracing::error!("Cannot write watch progress: {:?}", err);
+            }
+        }
+    });
+}
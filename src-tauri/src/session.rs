@@ -0,0 +1,94 @@
+use std::{collections::VecDeque, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+// The tutoring conversation lives in `<data dir>/tauri.lms.com/session.json`,
+// so the tutor still knows what was said after the app restarts.
+const FILE_NAME: &str = "session.json";
+
+// The model's context window is small, so the summary is kept short.
+pub const SUMMARY_LEN: usize = 200;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatSession {
+    // Changes when the history is cleared, so a summary of the old
+    // conversation that finishes late is thrown away.
+    pub id: String,
+    // Turns that no longer fit in `history`, folded into a few sentences by
+    // the model.
+    #[serde(default)]
+    pub summary: String,
+    pub history: VecDeque<(String, String)>,
+}
+
+impl Default for ChatSession {
+    fn default() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            summary: String::new(),
+            history: VecDeque::new(),
+        }
+    }
+}
+
+pub fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(config::APP_DIR).join(FILE_NAME))
+}
+
+// The saved session, or a new one when there is none or it cannot be read.
+pub fn load() -> ChatSession {
+    let Some(path) = path().filter(|path| path.is_file()) else {
+        return ChatSession::default();
+    };
+    match fs::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|err| err.to_string()))
+    {
+        Ok(session) => session,
+        Err(err) => {
+            tracing::warn!("Cannot read {}, starting over: {}", path.display(), err);
+            ChatSession::default()
+        }
+    }
+}
+
+// Losing the session only costs the tutor its memory, so failures are logged
+// rather than shown to the student.
+pub fn save(session: &ChatSession) {
+    let Some(path) = path() else {
+        return;
+    };
+    let saved = serde_json::to_string(session)
+        .map_err(|err| err.to_string())
+        .and_then(|json| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+            }
+            fs::write(&path, json).map_err(|err| err.to_string())
+        });
+    if let Err(err) = saved {
+        tracing::warn!("Cannot save {}: {}", path.display(), err);
+    }
+}
+
+// Asks the model to fold `dropped` turns into the running summary.
+pub fn summary_prompt(summary: &str, dropped: &[(String, String)]) -> String {
+    let mut prompt = String::from(
+        "[INST] Hãy tóm tắt ngắn gọn cuộc trò chuyện dưới đây giữa học viên và gia sư, \
+         giữ lại các thông tin quan trọng về học viên và những gì đã giải thích.",
+    );
+    if !summary.is_empty() {
+        prompt.push_str(&format!("\nTóm tắt trước đó: {}", summary));
+    }
+    for (user_prompt, model_response) in dropped {
+        prompt.push_str(&format!(
+            "\nHọc viên: {}\nGia sư: {}",
+            user_prompt, model_response
+        ));
+    }
+    prompt.push_str(" [/INST]");
+    prompt
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use migration::{Migrator, MigratorTrait};
+use serde::Serialize;
+use service::{OtherRoutes, WatchThrottle};
+use tauri::{AppHandle, Manager};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::{
+    config::{self, AppConfig, ConfigProblem},
+    database, keystore, scheduler, session, webhook, AppState,
+};
+
+// Where startup got to. The window only shows the app once it is `Ready`;
+// until then it asks for the keystore passphrase, or for the secrets when
+// there is no keystore yet, or lists what is wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    Ready,
+    Locked,
+    NoKeystore,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupStatus {
+    pub stage: Stage,
+    pub config_path: Option<String>,
+    pub keystore_path: Option<String>,
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl StartupStatus {
+    pub fn new(stage: Stage, problems: Vec<ConfigProblem>) -> Self {
+        StartupStatus {
+            stage,
+            config_path: config::path().map(|path| path.display().to_string()),
+            keystore_path: keystore::path().map(|path| path.display().to_string()),
+            problems,
+        }
+    }
+}
+
+// Managed from the start, unlike `AppState`, which only exists once the
+// database is open.
+pub struct Startup {
+    pub status: Mutex<StartupStatus>,
+    // The configuration still waiting for its secrets
+    pub pending: Mutex<Option<AppConfig>>,
+}
+
+// Connects to the database and gets the schema up to date.
+pub async fn open(config: AppConfig) -> Result<Arc<AppState>, Vec<ConfigProblem>> {
+    let problem = |message: String| {
+        vec![ConfigProblem {
+            key: "database.url".into(),
+            message,
+        }]
+    };
+    let db = database::connect(&config.database)
+        .await
+        .map_err(|err| problem(format!("Cannot connect to the database: {}", err)))?;
+    Migrator::up(&db, None)
+        .await
+        .map_err(|err| problem(format!("Cannot migrate the database: {}", err)))?;
+
+    Ok(Arc::new(AppState {
+        textgen: Mutex::new(None),
+        session: Mutex::new(session::load()),
+        conn: db,
+        db_config: config.database.clone(),
+        config: RwLock::new(config),
+        watch_throttle: WatchThrottle::default(),
+        model_loaded: Mutex::new(false),
+    }))
+}
+
+// Starts the background work that needs the database.
+pub async fn spawn_tasks(state: &Arc<AppState>) {
+    let config = state.config.read().await.clone();
+    scheduler::spawn(Arc::clone(state));
+    scheduler::spawn_watch_flush(Arc::clone(state));
+    webhook::spawn(Arc::clone(state), &config);
+
+    // Courses and notes written before search had an index, or by an older
+    // version of the app, only become searchable once they are indexed.
+    let state = Arc::clone(state);
+    tokio::spawn(async move {
+        match OtherRoutes::update_search_index(&state.conn).await {
+            Ok(0) => (),
+            Ok(indexed) => tracing::info!("Indexed {} courses and notes for search", indexed),
+            Err(err) => tracing::error!("Cannot update the search index: {:?}", err),
+        }
+    });
+}
+
+// Makes `state` available to the commands of a window that started without
+// it.
+pub async fn serve(app: &AppHandle, state: Arc<AppState>) {
+    spawn_tasks(&state).await;
+    app.manage(state);
+}
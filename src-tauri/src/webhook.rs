@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use service::{Payments, StripeConfig, WebhookError};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{config::AppConfig, AppState};
+
+// Stripe events are forwarded here, e.g. with
+// `stripe listen --forward-to localhost:4242/api/webhook`.
+const PATH: &str = "/api/webhook";
+
+// Stripe event payloads are far smaller than this.
+const MAX_BODY: usize = 1024 * 1024;
+
+// Listens for Stripe webhook deliveries and confirms purchases. Only started
+// when a webhook secret is configured, since events cannot be verified without
+// it.
+pub fn spawn(state: Arc<AppState>, config: &AppConfig) {
+    let stripe = match config.stripe() {
+        Some(stripe) if !stripe.webhook_secret.is_empty() => stripe,
+        _ => {
+            tracing::info!("Stripe webhook secret is not set, Stripe webhook disabled");
+            return;
+        }
+    };
+    let addr = config.stripe.webhook_addr.clone();
+
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(&addr).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                tracing::error!("Cannot listen for Stripe webhooks on {}: {}", addr, err);
+                return;
+            }
+        };
+        tracing::info!("Listening for Stripe webhooks on {}{}", addr, PATH);
+
+        while let Ok((socket, _)) = listener.accept().await {
+            let state = Arc::clone(&state);
+            let stripe = stripe.clone();
+            tokio::spawn(async move {
+                if let Err(err) = handle(socket, &state, &stripe).await {
+                    tracing::warn!("Stripe webhook connection failed: {}", err);
+                }
+            });
+        }
+    });
+}
+
+async fn handle(
+    mut socket: TcpStream,
+    state: &AppState,
+    stripe: &StripeConfig,
+) -> std::io::Result<()> {
+    let (status, message) = match read_request(&mut socket).await? {
+        Some((path, signature, body)) if path == PATH => {
+            match Payments::handle_webhook(&state.conn, stripe, &body, &signature).await {
+                Ok(_) => ("200 OK", String::new()),
+                Err(WebhookError::Signature) => {
+                    ("400 Bad Request", "Webhook Error: Invalid signature".into())
+                }
+                Err(WebhookError::Payload(err)) => {
+                    ("400 Bad Request", format!("Webhook Error: {}", err))
+                }
+                Err(WebhookError::Db(err)) => {
+                    tracing::error!("Cannot apply Stripe webhook: {:?}", err);
+                    ("500 Internal Server Error", String::new())
+                }
+            }
+        }
+        Some(_) => ("404 Not Found", String::new()),
+        None => ("400 Bad Request", String::new()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        message.len(),
+        message
+    );
+    socket.write_all(response.as_bytes()).await
+}
+
+// Reads a POST request and returns its path, `Stripe-Signature` header and
+// body, or `None` if it is not a request we can handle.
+async fn read_request(socket: &mut TcpStream) -> std::io::Result<Option<(String, String, String)>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+    let header_end = loop {
+        let n = socket.read(&mut chunk).await?;
+        if n == 0 || buf.len() > MAX_BODY {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    if request_line.next() != Some("POST") {
+        return Ok(None);
+    }
+    let path = request_line.next().unwrap_or_default().to_string();
+
+    let header = |name: &str| {
+        head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    };
+    let content_length: usize = match header("content-length").and_then(|v| v.parse().ok()) {
+        Some(len) if len <= MAX_BODY => len,
+        _ => return Ok(None),
+    };
+    let signature = header("stripe-signature").unwrap_or_default();
+
+    while buf.len() < header_end + content_length {
+        let n = socket.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    // The signature covers the exact bytes Stripe sent, so the body is passed
+    // on untouched.
+    match String::from_utf8(buf[header_end..header_end + content_length].to_vec()) {
+        Ok(body) => Ok(Some((path, signature, body))),
+        Err(_) => Ok(None),
+    }
+}